@@ -0,0 +1,153 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::output::Format;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "todoem",
+    version,
+    about = "Manage todoem tasks from the terminal"
+)]
+pub struct Cli {
+    /// Server to talk to [default: the one logged in to, or http://localhost:8080]
+    #[arg(long, global = true, env = "TODOEM_SERVER")]
+    pub server: Option<String>,
+    /// Where `login` keeps credentials [default: ~/.config/todoem/credentials.toml]
+    #[arg(long, global = true, env = "TODOEM_CREDENTIALS")]
+    pub credentials: Option<PathBuf>,
+    /// How to print results
+    #[arg(short, long, global = true, value_enum, default_value_t)]
+    pub output: Format,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Log in and keep the session for the commands after
+    Login(LoginArgs),
+    /// Sign out and forget the stored credentials
+    Logout,
+    /// Show the account logged in to
+    Whoami,
+    /// Add a task
+    Add(AddArgs),
+    /// List tasks, the open ones unless told otherwise
+    #[command(alias = "ls")]
+    Tasks(TasksArgs),
+    /// Complete tasks
+    Done {
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Reopen completed tasks
+    Undo {
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Manage connections with other users
+    #[command(subcommand, alias = "conn")]
+    Connections(ConnectionCommand),
+    /// Make lists of tasks and send them to connections
+    #[command(subcommand)]
+    Lists(ListCommand),
+    /// Write the account's profile, tasks, filters and connections as JSON
+    Export {
+        /// File to write to instead of standard output
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct LoginArgs {
+    /// Username or email address; asked for when missing
+    pub login: Option<String>,
+    /// Asked for without echo when missing
+    #[arg(long, env = "TODOEM_PASSWORD", hide_env_values = true)]
+    pub password: Option<String>,
+    /// Code of the authenticator app or a recovery code, for accounts with
+    /// two-factor authentication; asked for when needed
+    #[arg(long)]
+    pub code: Option<String>,
+    /// Use a personal access token instead of a password
+    #[arg(long, env = "TODOEM_TOKEN", hide_env_values = true, conflicts_with_all = ["login", "password", "code"])]
+    pub token: Option<String>,
+    /// Name of the session in the account's session list
+    #[arg(long, default_value = "todoem CLI")]
+    pub device_name: String,
+}
+
+#[derive(Debug, Args)]
+pub struct AddArgs {
+    /// What to do
+    #[arg(required = true)]
+    pub task: Vec<String>,
+    /// When, like `tomorrow 5pm`, `fri`, `2024-12-24 18:00` or `in 2 hours`
+    #[arg(short, long)]
+    pub due: Option<String>,
+    #[arg(short = 'm', long, default_value = "")]
+    pub description: String,
+    #[arg(short, long, value_enum)]
+    pub repeat: Option<Repeat>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Repeat {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Args)]
+pub struct TasksArgs {
+    /// Only completed tasks
+    #[arg(long, conflicts_with = "all")]
+    pub done: bool,
+    /// Open and completed tasks
+    #[arg(long)]
+    pub all: bool,
+}
+
+/// Users are given by username or id.
+#[derive(Debug, Subcommand)]
+pub enum ConnectionCommand {
+    /// List connected users
+    Ls,
+    /// Find users to connect with
+    Search { query: String },
+    /// List pending requests, received and sent
+    Requests,
+    /// Ask a user to connect
+    Request { user: String },
+    /// Withdraw a request
+    Cancel { user: String },
+    /// Accept a received request
+    Accept { user: String },
+    /// Reject a received request
+    Reject { user: String },
+    /// End a connection
+    Disconnect { user: String },
+}
+
+/// Users are given by username or id.
+#[derive(Debug, Subcommand)]
+pub enum ListCommand {
+    /// List the lists made and received
+    Ls,
+    /// Create a list
+    Create {
+        name: String,
+        #[arg(short = 'm', long)]
+        description: Option<String>,
+        /// A task of the list, in order; repeat for more
+        #[arg(short, long = "task")]
+        tasks: Vec<String>,
+    },
+    /// Show a list with its tasks
+    Show { id: Uuid },
+    /// Send a copy of a list to a connection
+    Send { id: Uuid, user: String },
+}
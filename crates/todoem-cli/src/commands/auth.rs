@@ -0,0 +1,99 @@
+use todoem_client::models::user::Profile;
+use todoem_client::types::auth::{LoginRequest, SecondFactorRequest};
+use todoem_client::{Auth, Client, Login, StatusCode};
+
+use super::prompt;
+use crate::cli::LoginArgs;
+use crate::credentials::Credentials;
+use crate::output::{self, Table};
+use crate::{Context, Error};
+
+pub async fn login(ctx: &mut Context<'_>, args: LoginArgs) -> Result<(), Error> {
+    let server = ctx.client.base_url().to_string();
+    let client = Client::new(&server);
+
+    let msg = match args.token {
+        Some(token) => {
+            client.set_auth(Auth::Token(token));
+            format!("Using the token for {}", server)
+        }
+        None => {
+            let profile = sign_in(&client, args).await?;
+            format!("Logged in to {} as {}", server, profile.username)
+        }
+    };
+
+    Credentials {
+        server,
+        auth: client.auth(),
+    }
+    .save(&ctx.credentials)?;
+    output::message(ctx.out, ctx.format, &msg)?;
+    Ok(())
+}
+
+async fn sign_in(client: &Client, args: LoginArgs) -> Result<Profile, Error> {
+    let login = match args.login {
+        Some(login) => login,
+        None => prompt("Username or email: ")?,
+    };
+    let password = match args.password {
+        Some(password) => password,
+        None => rpassword::prompt_password("Password: ")?,
+    };
+    let req = LoginRequest {
+        login,
+        password,
+        device_name: Some(args.device_name.clone()),
+    };
+
+    match client.login(&req).await? {
+        Login::SignedIn(profile) => Ok(profile),
+        Login::SecondFactor(challenge) => {
+            let code = match args.code {
+                Some(code) => code,
+                None => prompt("Two-factor code: ")?,
+            };
+            let req = SecondFactorRequest {
+                challenge: Some(challenge.challenge),
+                code,
+                device_name: Some(args.device_name),
+            };
+            Ok(client.second_factor(&req).await?)
+        }
+    }
+}
+
+/// Ends the session on the server too; a token stays valid, as it may be in
+/// use elsewhere.
+pub async fn logout(ctx: &mut Context<'_>) -> Result<(), Error> {
+    if let Auth::Session(_) = ctx.client.auth() {
+        let current = match ctx.client.get_sessions().await {
+            Ok(sessions) => sessions.into_iter().find(|s| s.current),
+            // expired already
+            Err(e) if e.status() == Some(StatusCode::UNAUTHORIZED) => None,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(session) = current {
+            ctx.client.revoke_session(session.id).await?;
+        }
+    }
+
+    Credentials::remove(&ctx.credentials)?;
+    output::message(ctx.out, ctx.format, "Logged out")?;
+    Ok(())
+}
+
+pub async fn whoami(ctx: &mut Context<'_>) -> Result<(), Error> {
+    let profile = ctx.logged_in()?.get_me().await?;
+    output::print(ctx.out, ctx.format, &profile, |p| {
+        Table::new(&["id", "username", "name", "email", "timezone"]).row(vec![
+            p.id.to_string(),
+            p.username.clone(),
+            output::opt(&p.name),
+            p.email.clone(),
+            p.timezone.clone(),
+        ])
+    })?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use serde::Serialize;
+use todoem_client::models::user::User;
+use todoem_client::Client;
+use uuid::Uuid;
+
+use crate::cli::ConnectionCommand;
+use crate::output::{self, Table};
+use crate::{Context, Error};
+
+fn table(users: &[User]) -> Table {
+    Table::new(&["id", "username", "name"]).rows(users, |u| {
+        vec![u.id.to_string(), u.username.clone(), output::opt(&u.name)]
+    })
+}
+
+#[derive(Serialize)]
+struct Requests {
+    received: Vec<User>,
+    sent: Vec<User>,
+}
+
+/// `user` as an id, or the username of one of `candidates`.
+pub(crate) fn resolve(user: &str, candidates: &[User], among: &str) -> Result<Uuid, Error> {
+    if let Ok(id) = user.parse() {
+        return Ok(id);
+    }
+    candidates
+        .iter()
+        .find(|u| u.username.eq_ignore_ascii_case(user))
+        .map(|u| u.id)
+        .ok_or_else(|| Error::Invalid(format!("no user {:?} among {}", user, among)))
+}
+
+pub async fn run(ctx: &mut Context<'_>, cmd: ConnectionCommand) -> Result<(), Error> {
+    let client: Client = ctx.logged_in()?.clone();
+    let res = match cmd {
+        ConnectionCommand::Ls => {
+            let users = client.get_listers().all().await?;
+            output::print(ctx.out, ctx.format, &users, |u| table(u))?;
+            return Ok(());
+        }
+        ConnectionCommand::Search { query } => {
+            let users = client.search(&query).all().await?;
+            output::print(ctx.out, ctx.format, &users, |u| table(u))?;
+            return Ok(());
+        }
+        ConnectionCommand::Requests => {
+            let requests = Requests {
+                received: client.get_received_requests().await?,
+                sent: client.get_sent_requests().await?,
+            };
+            output::print(ctx.out, ctx.format, &requests, |r| {
+                let rows = r
+                    .received
+                    .iter()
+                    .map(|u| ("received", u))
+                    .chain(r.sent.iter().map(|u| ("sent", u)));
+                rows.fold(
+                    Table::new(&["", "id", "username", "name"]),
+                    |t, (way, u)| {
+                        t.row(vec![
+                            way.to_string(),
+                            u.id.to_string(),
+                            u.username.clone(),
+                            output::opt(&u.name),
+                        ])
+                    },
+                )
+            })?;
+            return Ok(());
+        }
+        ConnectionCommand::Request { user } => {
+            let found = match user.parse::<Uuid>() {
+                Ok(_) => Vec::new(),
+                Err(_) => client.search(&user).all().await?,
+            };
+            let id = resolve(&user, &found, "the search results")?;
+            client.request_connection(id).await?
+        }
+        ConnectionCommand::Cancel { user } => {
+            let sent = client.get_sent_requests().await?;
+            let id = resolve(&user, &sent, "the requests sent")?;
+            client.delete_request_connection(id).await?
+        }
+        ConnectionCommand::Accept { user } => {
+            let received = client.get_received_requests().await?;
+            let id = resolve(&user, &received, "the requests received")?;
+            client.accept_connection(id).await?
+        }
+        ConnectionCommand::Reject { user } => {
+            let received = client.get_received_requests().await?;
+            let id = resolve(&user, &received, "the requests received")?;
+            client.reject_connection(id).await?
+        }
+        ConnectionCommand::Disconnect { user } => {
+            let listers = client.get_listers().all().await?;
+            let id = resolve(&user, &listers, "the connections")?;
+            client.disconnect_lister(id).await?
+        }
+    };
+    output::message(ctx.out, ctx.format, &res.msg)?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use todoem_client::models::filter::SavedFilter;
+use todoem_client::models::{task::Task, user::Profile, user::User};
+
+use crate::output;
+use crate::{Context, Error};
+
+#[derive(Serialize)]
+struct Export {
+    server: String,
+    exported_at: DateTime<Utc>,
+    profile: Profile,
+    tasks: Vec<Task>,
+    filters: Vec<SavedFilter>,
+    connections: Vec<User>,
+}
+
+/// Always JSON, whatever `--output` says: it is meant to be read back.
+pub async fn export(ctx: &mut Context<'_>, file: Option<PathBuf>) -> Result<(), Error> {
+    let client = ctx.logged_in()?;
+    let export = Export {
+        server: client.base_url().to_string(),
+        exported_at: Utc::now(),
+        profile: client.get_me().await?,
+        filters: client.get_filters().await?,
+        tasks: client.get_task_pages().all().await?,
+        connections: client.get_listers().all().await?,
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(std::io::Error::from)?;
+
+    match file {
+        Some(path) => {
+            fs::write(&path, json)?;
+            let msg = format!(
+                "Exported {} tasks to {}",
+                export.tasks.len(),
+                path.display()
+            );
+            output::message(ctx.out, ctx.format, &msg)?;
+        }
+        None => writeln!(ctx.out, "{}", json)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use todoem_client::models::list::List;
+use todoem_client::types::list::CreateListRequest;
+
+use crate::cli::ListCommand;
+use crate::commands::connection::resolve;
+use crate::output::{self, Table};
+use crate::{Context, Error};
+
+fn table(lists: &[List]) -> Table {
+    Table::new(&["id", "name", "description", "tasks", "sent by"]).rows(lists, |l| {
+        vec![
+            l.id.to_string(),
+            l.name.clone(),
+            l.description.clone(),
+            l.task_count.to_string(),
+            output::opt(&l.sent_by_id),
+        ]
+    })
+}
+
+pub async fn run(ctx: &mut Context<'_>, cmd: ListCommand) -> Result<(), Error> {
+    let client = ctx.logged_in()?.clone();
+    match cmd {
+        ListCommand::Ls => {
+            let lists = client.get_lists().all().await?;
+            output::print(ctx.out, ctx.format, &lists, |l| table(l))?;
+        }
+        ListCommand::Create {
+            name,
+            description,
+            tasks,
+        } => {
+            let list = client
+                .create_list(&CreateListRequest {
+                    name,
+                    description,
+                    tasks,
+                })
+                .await?;
+            output::print(ctx.out, ctx.format, &list, |l| {
+                table(std::slice::from_ref(l))
+            })?;
+        }
+        ListCommand::Show { id } => {
+            let view = client.get_list(id).await?;
+            output::print(ctx.out, ctx.format, &view, |v| {
+                Table::new(&["id", "task", "done"]).rows(&v.tasks, |t| {
+                    vec![t.id.to_string(), t.task.clone(), t.done.to_string()]
+                })
+            })?;
+        }
+        ListCommand::Send { id, user } => {
+            let listers = client.get_listers().all().await?;
+            let user_id = resolve(&user, &listers, "the connections")?;
+            let res = client.send_list(id, user_id).await?;
+            output::message(ctx.out, ctx.format, &res.msg)?;
+        }
+    }
+    Ok(())
+}
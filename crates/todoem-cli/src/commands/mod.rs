@@ -0,0 +1,30 @@
+pub mod auth;
+pub mod connection;
+pub mod export;
+pub mod list;
+pub mod task;
+
+use chrono_tz::Tz;
+use std::io::{self, BufRead, Write};
+use todoem_client::{Client, StatusCode};
+
+use crate::Error;
+
+/// The zone of the account, for due dates typed and shown in it. Tokens
+/// without `read:profile` cannot see it, and get UTC.
+async fn timezone(client: &Client) -> Result<Tz, Error> {
+    match client.get_me().await {
+        Ok(profile) => Ok(profile.timezone.parse().unwrap_or(Tz::UTC)),
+        Err(e) if e.status() == Some(StatusCode::FORBIDDEN) => Ok(Tz::UTC),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Asks on the terminal, leaving standard output to the results.
+fn prompt(question: &str) -> Result<String, Error> {
+    eprint!("{}", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
@@ -0,0 +1,93 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use todoem_client::models::task::{Frequency, Task};
+use todoem_client::types::task::CreateTaskRequest;
+
+use super::timezone;
+use crate::cli::{AddArgs, Repeat, TasksArgs};
+use crate::due::{self, Due};
+use crate::output::{self, Table};
+use crate::{Context, Error};
+
+fn table(tasks: &[Task], tz: Tz) -> Table {
+    Table::new(&["id", "done", "task", "due", "repeat"]).rows(tasks, |t| {
+        let due = match (t.due_on, t.due_date) {
+            (Some(on), _) => on.to_string(),
+            (None, Some(at)) => at.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string(),
+            (None, None) => String::new(),
+        };
+        vec![
+            t.id.to_string(),
+            if t.done { "x" } else { "" }.to_string(),
+            t.task.clone(),
+            due,
+            t.repeat_frequency
+                .as_ref()
+                .map(|f| format!("{:?}", f).to_lowercase())
+                .unwrap_or_default(),
+        ]
+    })
+}
+
+pub async fn add(ctx: &mut Context<'_>, args: AddArgs) -> Result<(), Error> {
+    let client = ctx.logged_in()?;
+    let tz = timezone(client).await?;
+    let due = match &args.due {
+        Some(input) => {
+            Some(due::parse(input, Utc::now().with_timezone(&tz)).map_err(Error::Invalid)?)
+        }
+        None => None,
+    };
+
+    let req = CreateTaskRequest {
+        task: args.task.join(" "),
+        description: args.description,
+        due_date: match due {
+            Some(Due::At(at)) => Some(at),
+            _ => None,
+        },
+        due_on: match due {
+            Some(Due::On(on)) => Some(on),
+            _ => None,
+        },
+        repeat_frequency: args.repeat.map(|r| match r {
+            Repeat::Daily => Frequency::Daily,
+            Repeat::Weekly => Frequency::Weekly,
+            Repeat::Monthly => Frequency::Monthly,
+        }),
+    };
+    let task = client.create_task(&req).await?;
+    output::print(ctx.out, ctx.format, &task, |t| {
+        table(std::slice::from_ref(t), tz)
+    })?;
+    Ok(())
+}
+
+pub async fn tasks(ctx: &mut Context<'_>, args: TasksArgs) -> Result<(), Error> {
+    let client = ctx.logged_in()?;
+    let tz = timezone(client).await?;
+    let tasks = match (args.done, args.all) {
+        (true, _) => client.get_all_done_tasks().await?,
+        (_, true) => client.get_all_tasks().await?,
+        _ => client.get_all_undone_tasks().await?,
+    };
+    output::print(ctx.out, ctx.format, &tasks, |t| table(t, tz))?;
+    Ok(())
+}
+
+/// Completes the tasks `ids`, or reopens them when `done` is false.
+pub async fn done(ctx: &mut Context<'_>, ids: Vec<i64>, done: bool) -> Result<(), Error> {
+    let client = ctx.logged_in()?.clone();
+    for id in ids {
+        match done {
+            true => client.done_task(id).await?,
+            false => client.undone_task(id).await?,
+        }
+        let msg = match done {
+            true => format!("Completed task {}", id),
+            false => format!("Reopened task {}", id),
+        };
+        output::message(ctx.out, ctx.format, &msg)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use todoem_client::Auth;
+
+use crate::Error;
+
+/// What `todoem login` remembers for the commands after it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Credentials {
+    pub server: String,
+    pub auth: Auth,
+}
+
+/// `$XDG_CONFIG_HOME/todoem/credentials.toml`, or under `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let config = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home::home_dir()?.join(".config"),
+    };
+    Some(config.join("todoem").join("credentials.toml"))
+}
+
+impl Credentials {
+    /// `None` before the first login.
+    pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::credentials(path, e)),
+        };
+        toml::from_str(&text)
+            .map(Some)
+            .map_err(|e| Error::credentials(path, e))
+    }
+
+    /// Written readable by the owner only, as it holds a session or a token.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let text = toml::to_string(self).map_err(|e| Error::credentials(path, e))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::credentials(path, e))?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|e| Error::credentials(path, e))
+    }
+
+    pub fn remove(path: &Path) -> Result<(), Error> {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Error::credentials(path, e)),
+            _ => Ok(()),
+        }
+    }
+}
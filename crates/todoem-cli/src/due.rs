@@ -0,0 +1,202 @@
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+
+use todoem_core::due::resolve_local;
+pub use todoem_core::due::Due;
+
+/// Reads due dates the way people type them, relative to `now`: `tomorrow
+/// 5pm`, `fri`, `next monday at 9:30`, `2024-12-24 18:00`, `in 3 days` or
+/// `in 2 hours`. A day alone is due all day; a time alone is due next time
+/// the clock reads it.
+pub fn parse(input: &str, now: DateTime<Tz>) -> Result<Due, String> {
+    let words: Vec<String> = input
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .filter(|w| !matches!(w.as_str(), "at" | "on" | "next"))
+        .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    if let ["in", n, unit] = words[..] {
+        return relative(n, unit, now);
+    }
+
+    let today = now.date_naive();
+    let (mut date, mut time) = (None, None);
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        // `5 pm`, written apart
+        let meridiem = words.get(i + 1).filter(|w| matches!(**w, "am" | "pm"));
+        let (found_date, found_time) = match meridiem {
+            Some(m) => (None, parse_time(&format!("{}{}", word, m))),
+            None => (parse_day(word, today), parse_time(word)),
+        };
+        match (found_date, found_time) {
+            (Some(d), _) if date.is_none() => date = Some(d),
+            (None, Some(t)) if time.is_none() => time = Some(t),
+            (None, None) => return Err(format!("cannot read {:?} as a day or a time", word)),
+            _ => return Err(format!("{:?} has more than one day or time", input)),
+        }
+        i += if meridiem.is_some() { 2 } else { 1 };
+    }
+
+    match (date, time) {
+        (None, None) => Err("a due date needs a day or a time".to_string()),
+        (Some(date), None) => Ok(Due::On(date)),
+        (Some(date), Some(time)) => Ok(Due::At(resolve_local(now.timezone(), date.and_time(time)))),
+        (None, Some(time)) => {
+            let at = resolve_local(now.timezone(), today.and_time(time));
+            match at > now {
+                true => Ok(Due::At(at)),
+                false => Ok(Due::At(resolve_local(
+                    now.timezone(),
+                    (today + Days::new(1)).and_time(time),
+                ))),
+            }
+        }
+    }
+}
+
+/// `in <n> <unit>`: minutes and hours from now, or days and weeks from today.
+fn relative(n: &str, unit: &str, now: DateTime<Tz>) -> Result<Due, String> {
+    let n: u32 = n.parse().map_err(|_| format!("{:?} is not a number", n))?;
+    let unit = unit.strip_suffix('s').unwrap_or(unit);
+    let now_utc = now.with_timezone(&Utc);
+    let today = now.date_naive();
+    let due = match unit {
+        "min" | "minute" => Duration::try_minutes(n.into())
+            .and_then(|d| now_utc.checked_add_signed(d))
+            .map(Due::At),
+        "h" | "hr" | "hour" => Duration::try_hours(n.into())
+            .and_then(|d| now_utc.checked_add_signed(d))
+            .map(Due::At),
+        "day" => today.checked_add_days(Days::new(n.into())).map(Due::On),
+        "week" => u64::from(n)
+            .checked_mul(7)
+            .and_then(|days| today.checked_add_days(Days::new(days)))
+            .map(Due::On),
+        _ => return Err(format!("unknown unit {:?}", unit)),
+    };
+    due.ok_or_else(|| format!("in {} {} is too far ahead", n, unit))
+}
+
+fn parse_day(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match word {
+        "today" | "tonight" => return Some(today),
+        "tomorrow" | "tmr" => return today.succ_opt(),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+        return Some(date);
+    }
+    // a weekday is the next one to come, a week ahead on the day itself
+    let weekday: Weekday = word.parse().ok()?;
+    let ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    Some(today + Days::new(if ahead == 0 { 7 } else { ahead.into() }))
+}
+
+/// `5pm`, `5:30pm`, `17:00`, `noon` or `midnight`.
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    match word {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return Some(NaiveTime::MIN),
+        _ => {}
+    }
+    let (clock, offset) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(0)),
+        (_, Some(clock)) => (clock, Some(12)),
+        _ => (word, None),
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h.parse::<u32>().ok()?, m.parse().ok()?),
+        None if offset.is_some() => (clock.parse().ok()?, 0),
+        _ => return None,
+    };
+    let hour = match offset {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Berlin;
+
+    /// Wednesday 2024-03-06, 10:00 in Berlin.
+    fn now() -> DateTime<Tz> {
+        Berlin.with_ymd_and_hms(2024, 3, 6, 10, 0, 0).unwrap()
+    }
+
+    fn at(s: &str) -> Due {
+        Due::At(s.parse().unwrap())
+    }
+
+    fn on(y: i32, m: u32, d: u32) -> Due {
+        Due::On(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+    }
+
+    #[test]
+    fn days_and_times_combine_in_the_zone() {
+        assert_eq!(parse("tomorrow 5pm", now()), Ok(at("2024-03-07T16:00:00Z")));
+        assert_eq!(
+            parse("5 PM tomorrow", now()),
+            Ok(at("2024-03-07T16:00:00Z"))
+        );
+        assert_eq!(
+            parse("fri at 9:30am", now()),
+            Ok(at("2024-03-08T08:30:00Z"))
+        );
+        assert_eq!(
+            parse("2024-12-24 18:00", now()),
+            Ok(at("2024-12-24T17:00:00Z"))
+        );
+        assert_eq!(parse("today noon", now()), Ok(at("2024-03-06T11:00:00Z")));
+    }
+
+    #[test]
+    fn a_day_alone_is_due_all_day() {
+        assert_eq!(parse("today", now()), Ok(on(2024, 3, 6)));
+        assert_eq!(parse("next monday", now()), Ok(on(2024, 3, 11)));
+        assert_eq!(parse("wednesday", now()), Ok(on(2024, 3, 13)));
+        assert_eq!(parse("in 2 weeks", now()), Ok(on(2024, 3, 20)));
+    }
+
+    #[test]
+    fn a_time_alone_is_the_next_one() {
+        assert_eq!(parse("5pm", now()), Ok(at("2024-03-06T16:00:00Z")));
+        assert_eq!(parse("9am", now()), Ok(at("2024-03-07T08:00:00Z")));
+        assert_eq!(
+            parse("in 90 minutes", now()),
+            Ok(at("2024-03-06T10:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn skipped_times_move_past_the_gap() {
+        // clocks go from 02:00 to 03:00 in Berlin that night
+        assert_eq!(
+            parse("2024-03-31 2:30", now()),
+            Ok(at("2024-03-31T01:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn nonsense_is_refused() {
+        assert!(parse("someday", now()).is_err());
+        assert!(parse("13pm", now()).is_err());
+        assert!(parse("today tomorrow", now()).is_err());
+        assert!(parse("in 3 fortnights", now()).is_err());
+        assert!(parse("", now()).is_err());
+    }
+
+    #[test]
+    fn too_far_ahead_is_refused() {
+        assert!(parse("in 4294967295 days", now()).is_err());
+        assert!(parse("in 4294967295 weeks", now()).is_err());
+        assert!(parse("in 4294967295 hours", now()).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! The `todoem` command line client, over `todoem-client`. [`run`] carries
+//! out a parsed command line and prints to the writer it is given, so the
+//! commands can be driven in process.
+
+pub mod cli;
+mod commands;
+mod credentials;
+pub mod due;
+mod output;
+
+pub use cli::Cli;
+pub use output::Format;
+
+use std::{fmt, io, io::Write, path::Path, path::PathBuf};
+use todoem_client::{Auth, Client, StatusCode};
+
+use cli::Command;
+use credentials::Credentials;
+
+/// Where to log in when neither `--server` nor stored credentials say.
+const DEFAULT_SERVER: &str = "http://localhost:8080";
+
+#[derive(Debug)]
+pub enum Error {
+    /// The server refused a request, or could not be reached.
+    Client(todoem_client::Error),
+    /// The credentials file is unreadable, unwritable or not valid.
+    Credentials {
+        path: PathBuf,
+        reason: String,
+    },
+    NotLoggedIn,
+    /// Arguments that parse but make no sense, like an unreadable due date.
+    Invalid(String),
+    Io(io::Error),
+}
+
+impl Error {
+    fn credentials(path: &Path, reason: impl fmt::Display) -> Self {
+        Self::Credentials {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Client(e) if e.status() == Some(StatusCode::UNAUTHORIZED) => {
+                write!(f, "{} (run `todoem login` again)", e)
+            }
+            Self::Client(e) => write!(f, "{}", e),
+            Self::Credentials { path, reason } => {
+                write!(f, "cannot use credentials {}: {}", path.display(), reason)
+            }
+            Self::NotLoggedIn => write!(f, "not logged in (run `todoem login` first)"),
+            Self::Invalid(msg) => write!(f, "{}", msg),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Client(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<todoem_client::Error> for Error {
+    fn from(e: todoem_client::Error) -> Self {
+        Self::Client(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// What every command gets: a client of the server and where to print.
+pub(crate) struct Context<'a> {
+    pub client: Client,
+    pub format: Format,
+    pub out: &'a mut dyn Write,
+    pub credentials: PathBuf,
+}
+
+impl Context<'_> {
+    /// Fails before sending anything when there are no credentials to send.
+    pub fn logged_in(&self) -> Result<&Client, Error> {
+        match self.client.auth() {
+            Auth::None => Err(Error::NotLoggedIn),
+            _ => Ok(&self.client),
+        }
+    }
+}
+
+/// Carries out `cli`, printing results to `out`.
+pub async fn run(cli: Cli, out: &mut dyn Write) -> Result<(), Error> {
+    let path = match cli.credentials {
+        Some(path) => path,
+        None => credentials::default_path()
+            .ok_or_else(|| Error::Invalid("no home directory; pass --credentials".to_string()))?,
+    };
+    let stored = Credentials::load(&path)?;
+
+    // credentials only go to the server they were issued by
+    let (server, auth) = match (cli.server, stored) {
+        (Some(server), Some(c)) if same_server(&server, &c.server) => (server, c.auth),
+        (Some(server), _) => (server, Auth::None),
+        (None, Some(c)) => (c.server, c.auth),
+        (None, None) => (DEFAULT_SERVER.to_string(), Auth::None),
+    };
+    let mut ctx = Context {
+        client: Client::new(&server).with_auth(auth),
+        format: cli.output,
+        out,
+        credentials: path,
+    };
+
+    match cli.command {
+        Command::Login(args) => commands::auth::login(&mut ctx, args).await,
+        Command::Logout => commands::auth::logout(&mut ctx).await,
+        Command::Whoami => commands::auth::whoami(&mut ctx).await,
+        Command::Add(args) => commands::task::add(&mut ctx, args).await,
+        Command::Tasks(args) => commands::task::tasks(&mut ctx, args).await,
+        Command::Done { ids } => commands::task::done(&mut ctx, ids, true).await,
+        Command::Undo { ids } => commands::task::done(&mut ctx, ids, false).await,
+        Command::Connections(cmd) => commands::connection::run(&mut ctx, cmd).await,
+        Command::Lists(cmd) => commands::list::run(&mut ctx, cmd).await,
+        Command::Export { file } => commands::export::export(&mut ctx, file).await,
+    }
+}
+
+fn same_server(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
@@ -0,0 +1,12 @@
+use clap::Parser;
+use std::process;
+use todoem_cli::Cli;
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = todoem_cli::run(cli, &mut std::io::stdout()).await {
+        eprintln!("todoem: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,89 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns, for people
+    #[default]
+    Table,
+    /// The API's own JSON, for scripts
+    Json,
+}
+
+/// Columns of text, each as wide as its widest cell.
+pub struct Table {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(header: &[&'static str]) -> Self {
+        Self {
+            header: header.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn row(mut self, cells: Vec<String>) -> Self {
+        self.rows.push(cells);
+        self
+    }
+
+    pub fn rows<T>(self, items: &[T], cells: impl Fn(&T) -> Vec<String>) -> Self {
+        items
+            .iter()
+            .fold(self, |table, item| table.row(cells(item)))
+    }
+
+    fn write(&self, out: &mut dyn Write) -> io::Result<()> {
+        let header: Vec<String> = self.header.iter().map(|h| h.to_uppercase()).collect();
+        let lines: Vec<&Vec<String>> = std::iter::once(&header).chain(&self.rows).collect();
+        let widths: Vec<usize> = (0..header.len())
+            .map(|i| {
+                lines
+                    .iter()
+                    .map(|cells| cells.get(i).map_or(0, |c| c.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for cells in lines {
+            let mut line = String::new();
+            for (cell, width) in cells.iter().zip(&widths) {
+                line.push_str(&format!("{:<width$}  ", cell, width = width));
+            }
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints `value` as JSON, or as the table `table` makes of it.
+pub fn print<T: Serialize>(
+    out: &mut dyn Write,
+    format: Format,
+    value: &T,
+    table: impl FnOnce(&T) -> Table,
+) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, value)?;
+            writeln!(out)
+        }
+        Format::Table => table(value).write(out),
+    }
+}
+
+/// Prints a one-line outcome, or `{"msg": ...}` for scripts.
+pub fn message(out: &mut dyn Write, format: Format, msg: &str) -> io::Result<()> {
+    match format {
+        Format::Json => writeln!(out, "{}", serde_json::json!({ "msg": msg })),
+        Format::Table => writeln!(out, "{}", msg),
+    }
+}
+
+pub fn opt<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(T::to_string).unwrap_or_default()
+}
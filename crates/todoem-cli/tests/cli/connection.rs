@@ -0,0 +1,63 @@
+use sqlx::PgPool;
+
+use crate::Todoem;
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn connections_are_made_by_username(pool: PgPool) {
+    let luffy = Todoem::spawn(pool).await.logged_in("luffy").await;
+    let zoro = luffy.other().logged_in("zoro").await;
+
+    luffy
+        .run(&["connections", "request", "zoro"])
+        .await
+        .unwrap();
+    let out = zoro.run(&["conn", "requests"]).await.unwrap();
+    assert!(
+        out.lines().nth(1).unwrap().starts_with("received"),
+        "{}",
+        out
+    );
+    assert!(out.contains("luffy"), "{}", out);
+
+    zoro.run(&["conn", "accept", "luffy"]).await.unwrap();
+    let listers = luffy.json(&["conn", "ls"]).await;
+    assert_eq!(listers[0]["username"], "zoro");
+
+    let e = luffy
+        .run(&["conn", "disconnect", "nami"])
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("no user \"nami\""), "{}", e);
+    luffy.run(&["conn", "disconnect", "zoro"]).await.unwrap();
+    assert_eq!(luffy.json(&["conn", "ls"]).await, serde_json::json!([]));
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn export_holds_every_task(pool: PgPool) {
+    let luffy = Todoem::spawn(pool).await.logged_in("luffy").await;
+    for i in 0..12 {
+        luffy.run(&["add", &format!("task {}", i)]).await.unwrap();
+    }
+
+    let export = luffy.json(&["export"]).await;
+    assert_eq!(export["profile"]["username"], "luffy");
+    assert_eq!(export["tasks"].as_array().unwrap().len(), 12);
+    // read without saving anything along the way
+    assert_eq!(export["filters"], serde_json::json!([]));
+
+    let file = luffy.dir.path().join("export.json");
+    let out = luffy
+        .run(&["export", "--file", file.to_str().unwrap()])
+        .await
+        .unwrap();
+    assert!(out.starts_with("Exported 12 tasks"), "{}", out);
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(file).unwrap()).unwrap();
+    assert_eq!(written["tasks"], export["tasks"]);
+}
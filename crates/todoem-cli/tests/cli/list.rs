@@ -0,0 +1,39 @@
+use sqlx::PgPool;
+
+use crate::Todoem;
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn lists_are_sent_to_connections(pool: PgPool) {
+    let luffy = Todoem::spawn(pool).await.logged_in("luffy").await;
+    let zoro = luffy.other().logged_in("zoro").await;
+
+    let list = luffy
+        .json(&["lists", "create", "crew", "-t", "meat", "--task", "sake"])
+        .await;
+    assert_eq!(list["task_count"], 2);
+    let id = list["id"].as_str().unwrap();
+    let view = luffy.json(&["lists", "show", id]).await;
+    assert_eq!(view["tasks"][0]["task"], "meat");
+    assert_eq!(view["tasks"][1]["task"], "sake");
+
+    let e = luffy.run(&["lists", "send", id, "zoro"]).await.unwrap_err();
+    assert!(e.to_string().contains("no user \"zoro\""), "{}", e);
+
+    luffy.run(&["conn", "request", "zoro"]).await.unwrap();
+    zoro.run(&["conn", "accept", "luffy"]).await.unwrap();
+    let out = luffy.run(&["lists", "send", id, "zoro"]).await.unwrap();
+    assert!(out.contains("List sent"), "{}", out);
+    let e = luffy.run(&["lists", "send", id, "zoro"]).await.unwrap_err();
+    assert!(e.to_string().contains("409"), "{}", e);
+
+    let received = zoro.json(&["lists", "ls"]).await;
+    assert_eq!(received.as_array().unwrap().len(), 1);
+    assert_eq!(received[0]["name"], "crew");
+    assert_eq!(received[0]["sent_by_id"], list["user_id"]);
+    let copy = received[0]["id"].as_str().unwrap();
+    let view = zoro.json(&["lists", "show", copy]).await;
+    assert_eq!(view["tasks"].as_array().unwrap().len(), 2);
+}
@@ -0,0 +1,162 @@
+//! Runs command lines against a server spawned in process on a free port,
+//! over a fresh database per test (see the server's own tests). Each
+//! [`Todoem`] keeps its own credentials file, like a user on their machine.
+
+mod connection;
+mod list;
+mod task;
+
+use clap::Parser;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::{future::IntoFuture, net::SocketAddr, sync::Arc};
+use tempfile::TempDir;
+use todoem_cli::{Cli, Error};
+use todoem_client::models::token::Scope;
+use todoem_client::types::{auth::LoginRequest, token::CreateTokenRequest};
+use todoem_client::Client;
+use todoem_server::config::{Config, Settings};
+use todoem_server::routes;
+use todoem_server::services::{
+    metrics::Metrics, shutdown::Shutdown, storage::LocalStorage, storage::SharedStorage,
+};
+use uuid::Uuid;
+
+pub struct Todoem {
+    server: String,
+    dir: TempDir,
+}
+
+impl Todoem {
+    /// Serves the API over `pool`, for a user not logged in yet.
+    pub async fn spawn(pool: PgPool) -> Self {
+        let mut settings = Settings::default();
+        settings.jwt.secret_key = "secret".to_string();
+        // as many as the fixture passwords are hashed with, so none is rehashed
+        settings.accounts.password_iterations = 1_000;
+        let config = Config::new(settings, pool).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("todoem-test-{}", Uuid::new_v4()));
+        let storage: SharedStorage = Arc::new(LocalStorage::new(dir));
+        let router = routes::init(config, storage, Shutdown::new(), Metrics::new());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        );
+        tokio::spawn(server.into_future());
+
+        Self {
+            server: format!("http://{}", addr),
+            dir: TempDir::new().unwrap(),
+        }
+    }
+
+    /// Another user of the same server.
+    pub fn other(&self) -> Self {
+        Self {
+            server: self.server.clone(),
+            dir: TempDir::new().unwrap(),
+        }
+    }
+
+    /// Logs in as a fixture user, whose password is their username.
+    pub async fn logged_in(self, username: &str) -> Self {
+        self.run(&["login", username, "--password", username])
+            .await
+            .unwrap();
+        self
+    }
+
+    /// Runs `todoem <args>` and returns what it printed.
+    pub async fn run(&self, args: &[&str]) -> Result<String, Error> {
+        let credentials = self.dir.path().join("credentials.toml");
+        let global = ["todoem", "--server", &self.server, "--credentials"];
+        let argv = global
+            .into_iter()
+            .chain([credentials.to_str().unwrap()])
+            .chain(args.iter().copied());
+        let cli = Cli::try_parse_from(argv).unwrap();
+
+        let mut out = Vec::new();
+        todoem_cli::run(cli, &mut out).await?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    /// Runs `todoem -o json <args>` and parses what it printed.
+    pub async fn json(&self, args: &[&str]) -> Value {
+        let args: Vec<&str> = ["-o", "json"].iter().chain(args).copied().collect();
+        let out = self.run(&args).await.unwrap();
+        serde_json::from_str(&out).unwrap_or_else(|e| panic!("{}: {}", e, out))
+    }
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn login_is_kept_until_logout(pool: PgPool) {
+    let todoem = Todoem::spawn(pool).await;
+    let out = todoem
+        .run(&["login", "luffy", "--password", "luffy"])
+        .await
+        .unwrap();
+    assert!(out.contains("as luffy"), "{}", out);
+
+    let me = todoem.json(&["whoami"]).await;
+    assert_eq!(me["username"], "luffy");
+
+    todoem.run(&["logout"]).await.unwrap();
+    let e = todoem.run(&["whoami"]).await.unwrap_err();
+    assert!(matches!(e, Error::NotLoggedIn), "{:?}", e);
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn a_wrong_password_stores_nothing(pool: PgPool) {
+    let todoem = Todoem::spawn(pool).await;
+    let e = todoem
+        .run(&["login", "luffy", "--password", "zoro"])
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("401"), "{}", e);
+    assert!(!todoem.dir.path().join("credentials.toml").exists());
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn tokens_log_in_without_a_password(pool: PgPool) {
+    let todoem = Todoem::spawn(pool).await.logged_in("luffy").await;
+    let client = Client::new(&todoem.server);
+    client
+        .login(&LoginRequest {
+            login: "luffy".to_string(),
+            password: "luffy".to_string(),
+            device_name: None,
+        })
+        .await
+        .unwrap();
+    let token = client
+        .create_token(&CreateTokenRequest {
+            name: "cli".to_string(),
+            scopes: vec![Scope::ReadTasks],
+            expires_in_days: None,
+        })
+        .await
+        .unwrap();
+
+    let scripted = todoem.other();
+    scripted
+        .run(&["login", "--token", &token.value])
+        .await
+        .unwrap();
+    // without read:profile, due dates are shown in UTC
+    let tasks = scripted.json(&["ls"]).await;
+    assert_eq!(tasks, serde_json::json!([]));
+}
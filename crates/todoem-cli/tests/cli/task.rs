@@ -0,0 +1,99 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use serde_json::Value;
+use sqlx::PgPool;
+use todoem_cli::due::{self, Due};
+use todoem_cli::Error;
+
+use crate::Todoem;
+
+fn ids(tasks: &Value) -> Vec<i64> {
+    tasks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_i64().unwrap())
+        .collect()
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn tasks_are_added_completed_and_undone(pool: PgPool) {
+    let todoem = Todoem::spawn(pool).await.logged_in("luffy").await;
+
+    let task = todoem
+        .json(&[
+            "add",
+            "Find",
+            "the",
+            "One",
+            "Piece",
+            "--due",
+            "tomorrow 5pm",
+        ])
+        .await;
+    assert_eq!(task["task"], "Find the One Piece");
+    // fixture users are in UTC
+    let Ok(Due::At(at)) = due::parse("tomorrow 5pm", Utc::now().with_timezone(&Tz::UTC)) else {
+        panic!("not a time");
+    };
+    assert_eq!(task["due_date"].as_str().unwrap().parse(), Ok(at));
+
+    let all_day = todoem
+        .json(&["add", "Eat", "--due", "fri", "--repeat", "weekly"])
+        .await;
+    assert!(all_day["due_on"].is_string());
+    assert_eq!(all_day["repeat_frequency"], "weekly");
+
+    let id = task["id"].as_i64().unwrap().to_string();
+    let out = todoem.run(&["done", &id]).await.unwrap();
+    assert_eq!(out, format!("Completed task {}\n", id));
+    let done = todoem.json(&["tasks", "--done"]).await;
+    assert_eq!(ids(&done), [task["id"].as_i64().unwrap()]);
+    let open = todoem.json(&["ls"]).await;
+    assert_eq!(ids(&open), [all_day["id"].as_i64().unwrap()]);
+
+    todoem.run(&["undo", &id]).await.unwrap();
+    assert_eq!(ids(&todoem.json(&["ls"]).await).len(), 2);
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn tables_line_up(pool: PgPool) {
+    let todoem = Todoem::spawn(pool).await.logged_in("luffy").await;
+    todoem.run(&["add", "Eat meat"]).await.unwrap();
+    todoem
+        .run(&["add", "Sail", "--due", "2030-01-02 9am"])
+        .await
+        .unwrap();
+
+    let out = todoem.run(&["ls"]).await.unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", out);
+    assert!(lines[0].starts_with("ID  DONE  TASK      DUE"), "{}", out);
+    assert!(lines[1].contains("Sail      2030-01-02 09:00"), "{}", out);
+    assert!(lines[2].contains("Eat meat"), "{}", out);
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn mistakes_are_explained(pool: PgPool) {
+    let todoem = Todoem::spawn(pool).await;
+    let e = todoem.run(&["ls"]).await.unwrap_err();
+    assert!(matches!(e, Error::NotLoggedIn), "{:?}", e);
+
+    let todoem = todoem.logged_in("luffy").await;
+    let e = todoem
+        .run(&["add", "Nap", "--due", "someday"])
+        .await
+        .unwrap_err();
+    assert!(matches!(e, Error::Invalid(_)), "{:?}", e);
+    let e = todoem.run(&["done", "0"]).await.unwrap_err();
+    assert!(e.to_string().contains("404"), "{}", e);
+}
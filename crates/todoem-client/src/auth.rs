@@ -0,0 +1,96 @@
+use reqwest::{Method, StatusCode};
+use todoem_core::models::user::Profile;
+use todoem_core::types::{auth as T, oidc as O, SuccessResponse};
+
+use crate::{Client, Error};
+
+/// How a password sign-in went.
+#[derive(Debug)]
+pub enum Login {
+    /// Signed in; the client now uses the session.
+    SignedIn(Profile),
+    /// The account has two-factor authentication; finish with
+    /// [`Client::second_factor`].
+    SecondFactor(T::Challenge),
+}
+
+impl Client {
+    /// Signs in with a password, keeping the session it starts.
+    pub async fn login(&self, req: &T::LoginRequest) -> Result<Login, Error> {
+        let res = self
+            .execute(self.request(Method::POST, "/auth/login").json(req))
+            .await?;
+        if res.status() == StatusCode::ACCEPTED {
+            return Ok(Login::SecondFactor(res.json().await?));
+        }
+        self.start_session(&res);
+        Ok(Login::SignedIn(res.json().await?))
+    }
+
+    /// Finishes a sign-in with a code, keeping the session it starts.
+    pub async fn second_factor(&self, req: &T::SecondFactorRequest) -> Result<Profile, Error> {
+        let res = self
+            .execute(self.request(Method::POST, "/auth/2fa").json(req))
+            .await?;
+        self.start_session(&res);
+        Ok(res.json().await?)
+    }
+
+    pub async fn forgot_password(
+        &self,
+        req: &T::ForgotPasswordRequest,
+    ) -> Result<SuccessResponse, Error> {
+        self.send(
+            self.request(Method::POST, "/auth/password/forgot")
+                .json(req),
+        )
+        .await
+    }
+
+    pub async fn reset_password(
+        &self,
+        req: &T::ResetPasswordRequest,
+    ) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::POST, "/auth/password/reset").json(req))
+            .await
+    }
+
+    pub async fn verify_email(
+        &self,
+        req: &T::VerifyEmailRequest,
+    ) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::POST, "/auth/email/verify").json(req))
+            .await
+    }
+
+    /// Names of the single sign-on providers the server offers.
+    pub async fn get_oidc_providers(&self) -> Result<Vec<String>, Error> {
+        self.send(self.request(Method::GET, "/auth/oidc/providers"))
+            .await
+    }
+
+    /// Where to send a browser to sign in at `provider`. The provider sends
+    /// it back to the server's callback, which only browsers follow.
+    pub fn oidc_login_url(&self, provider: &str, params: &O::LoginParams) -> Result<String, Error> {
+        let req = self
+            .request(Method::GET, &format!("/auth/oidc/{}/login", provider))
+            .query(params)
+            .build()?;
+        Ok(req.url().to_string())
+    }
+
+    /// The account a first single sign-on would create.
+    pub async fn get_oidc_signup(&self) -> Result<todoem_core::models::identity::Signup, Error> {
+        self.send(self.request(Method::GET, "/auth/oidc/signup"))
+            .await
+    }
+
+    /// Creates that account, keeping the session it starts.
+    pub async fn complete_oidc_signup(&self, req: &O::SignupRequest) -> Result<Profile, Error> {
+        let res = self
+            .execute(self.request(Method::POST, "/auth/oidc/signup").json(req))
+            .await?;
+        self.start_session(&res);
+        Ok(res.json().await?)
+    }
+}
@@ -0,0 +1,43 @@
+use reqwest::Method;
+use todoem_core::models::{filter::SavedFilter, task::Task};
+use todoem_core::types::filter as T;
+
+use crate::{Client, Error, Pages};
+
+impl Client {
+    pub async fn create_filter(&self, req: &T::SaveFilterRequest) -> Result<SavedFilter, Error> {
+        self.send(self.request(Method::POST, "/filters").json(req))
+            .await
+    }
+
+    pub async fn get_filters(&self) -> Result<Vec<SavedFilter>, Error> {
+        self.send(self.request(Method::GET, "/filters")).await
+    }
+
+    pub async fn get_filter(&self, id: i64) -> Result<SavedFilter, Error> {
+        self.send(self.request(Method::GET, &format!("/filters/{}", id)))
+            .await
+    }
+
+    pub async fn update_filter(
+        &self,
+        id: i64,
+        req: &T::SaveFilterRequest,
+    ) -> Result<SavedFilter, Error> {
+        self.send(
+            self.request(Method::PUT, &format!("/filters/{}", id))
+                .json(req),
+        )
+        .await
+    }
+
+    pub async fn delete_filter(&self, id: i64) -> Result<(), Error> {
+        self.send_empty(self.request(Method::DELETE, &format!("/filters/{}", id)))
+            .await
+    }
+
+    /// The tasks a saved filter matches, page by page.
+    pub fn get_filter_tasks(&self, id: i64) -> Pages<Task> {
+        Pages::query(self, &format!("/filters/{}/tasks", id), Vec::new())
+    }
+}
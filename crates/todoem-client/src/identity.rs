@@ -0,0 +1,23 @@
+use reqwest::Method;
+use todoem_core::models::identity::Identity;
+use todoem_core::types::oidc as T;
+
+use crate::{Client, Error};
+
+impl Client {
+    pub async fn get_identities(&self) -> Result<Vec<Identity>, Error> {
+        self.send(self.request(Method::GET, "/identities")).await
+    }
+
+    /// Where to send a browser to sign in at the provider whose account is
+    /// then linked.
+    pub async fn link_identity(&self, req: &T::LinkRequest) -> Result<T::Authorization, Error> {
+        self.send(self.request(Method::POST, "/identities").json(req))
+            .await
+    }
+
+    pub async fn unlink_identity(&self, id: i64) -> Result<(), Error> {
+        self.send_empty(self.request(Method::DELETE, &format!("/identities/{}", id)))
+            .await
+    }
+}
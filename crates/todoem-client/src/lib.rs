@@ -0,0 +1,213 @@
+//! A typed client of the todoem API, over the request and response types of
+//! `todoem-core`. Methods are named after the handlers they call and grouped
+//! per route the way the server is, one module each.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), todoem_client::Error> {
+//! use todoem_client::{Auth, Client};
+//!
+//! let client = Client::new("https://todoem.example").with_auth(Auth::Token("tdm_...".into()));
+//! let tasks = client.get_all_undone_tasks().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod auth;
+mod filter;
+mod identity;
+mod list;
+mod me;
+mod pages;
+mod probes;
+mod session;
+mod stats;
+mod task;
+mod token;
+mod two_factor;
+mod user;
+mod webhook;
+
+pub use auth::Login;
+pub use pages::Pages;
+pub use reqwest::StatusCode;
+pub use todoem_core::{errors::APIError, models, types};
+
+use reqwest::{
+    header::{AUTHORIZATION, COOKIE, SET_COOKIE},
+    Method, RequestBuilder, Response,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use todoem_core::errors::ErrorBody;
+
+/// Where the version of the API this client speaks is mounted.
+const API_PREFIX: &str = "/api/v1";
+
+/// The cookie a sign-in sets, as `todoem-server` names it.
+const ACCESS_COOKIE: &str = "access_token";
+
+/// How requests are authenticated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum Auth {
+    #[default]
+    None,
+    /// The `access_token` cookie of a session, as set by signing in.
+    Session(String),
+    /// A personal access token, sent as a bearer token.
+    Token(String),
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The server refused the request.
+    Api {
+        error: APIError,
+        /// The `X-Request-Id` it was handled under, to quote when reporting it.
+        request_id: Option<String>,
+    },
+    /// The request could not be sent, or its response read.
+    Http(reqwest::Error),
+}
+
+impl Error {
+    /// The status of a refused request.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Api { error, .. } => Some(error.status()),
+            Error::Http(e) => e.status(),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Api { error, .. } => write!(f, "{}", error),
+            Error::Http(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Api { error, .. } => Some(error),
+            Error::Http(e) => Some(e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+/// Calls a todoem server. Clones share their credentials, so a sign-in
+/// through one is seen by all.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    auth: Arc<RwLock<Auth>>,
+}
+
+impl Client {
+    /// A client of the server at `base_url`, such as `https://todoem.example`.
+    pub fn new(base_url: &str) -> Self {
+        Self::with_http(base_url, reqwest::Client::new())
+    }
+
+    /// As [`Client::new`], sending requests through `http`.
+    pub fn with_http(base_url: &str, http: reqwest::Client) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            auth: Arc::default(),
+        }
+    }
+
+    pub fn with_auth(self, auth: Auth) -> Self {
+        self.set_auth(auth);
+        self
+    }
+
+    /// The credentials in use, to store them between runs.
+    pub fn auth(&self) -> Auth {
+        self.auth.read().expect("auth lock poisoned").clone()
+    }
+
+    pub fn set_auth(&self, auth: Auth) {
+        *self.auth.write().expect("auth lock poisoned") = auth;
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// A request to `path` outside the versioned API, such as `/healthz`.
+    fn unversioned(&self, method: Method, path: &str) -> RequestBuilder {
+        let req = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        match self.auth() {
+            Auth::None => req,
+            Auth::Session(cookie) => req.header(COOKIE, format!("{}={}", ACCESS_COOKIE, cookie)),
+            Auth::Token(token) => req.header(AUTHORIZATION, format!("Bearer {}", token)),
+        }
+    }
+
+    /// A request to `path` of the API, such as `/task/all`.
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.unversioned(method, &format!("{}{}", API_PREFIX, path))
+    }
+
+    /// Sends `req` and reads back the JSON body of its answer.
+    async fn send<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T, Error> {
+        let res = self.execute(req).await?;
+        Ok(res.json().await?)
+    }
+
+    /// Sends `req`, whose answer has no body worth reading.
+    async fn send_empty(&self, req: RequestBuilder) -> Result<(), Error> {
+        self.execute(req).await?;
+        Ok(())
+    }
+
+    /// Sends `req` and turns error statuses into [`Error::Api`].
+    async fn execute(&self, req: RequestBuilder) -> Result<Response, Error> {
+        let res = req.send().await?;
+        let status = res.status();
+        if status.is_success() {
+            return Ok(res);
+        }
+
+        let text = res.text().await?;
+        // a few rejections, of malformed paths for one, are plain text
+        let body = serde_json::from_str(&text).unwrap_or(ErrorBody {
+            error: text,
+            details: None,
+            request_id: None,
+        });
+        let request_id = body.request_id.clone();
+        Err(Error::Api {
+            error: APIError::from((status, body)),
+            request_id,
+        })
+    }
+
+    /// Keeps the session cookie a sign-in set.
+    fn start_session(&self, res: &Response) {
+        let cookie = res
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .filter_map(|v| v.split(';').next()?.split_once('='))
+            .find(|(name, _)| name.trim() == ACCESS_COOKIE);
+        if let Some((_, value)) = cookie {
+            self.set_auth(Auth::Session(value.trim().to_string()));
+        }
+    }
+}
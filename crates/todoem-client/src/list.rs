@@ -0,0 +1,29 @@
+use reqwest::Method;
+use todoem_core::models::list::List;
+use todoem_core::types::{list as T, SuccessResponse};
+use uuid::Uuid;
+
+use crate::{Client, Error, Pages};
+
+impl Client {
+    pub async fn create_list(&self, req: &T::CreateListRequest) -> Result<List, Error> {
+        self.send(self.request(Method::POST, "/list").json(req))
+            .await
+    }
+
+    /// The caller's own lists and the ones sent to them, page by page.
+    pub fn get_lists(&self) -> Pages<List> {
+        Pages::query(self, "/list", Vec::new())
+    }
+
+    pub async fn get_list(&self, id: Uuid) -> Result<T::ListView, Error> {
+        self.send(self.request(Method::GET, &format!("/list/{}", id)))
+            .await
+    }
+
+    /// Sends a copy of a list to a connection.
+    pub async fn send_list(&self, id: Uuid, user_id: Uuid) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::POST, &format!("/list/{}/send/{}", id, user_id)))
+            .await
+    }
+}
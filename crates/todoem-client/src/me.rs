@@ -0,0 +1,43 @@
+use reqwest::{multipart, Method};
+use todoem_core::models::user::Profile;
+use todoem_core::types::{me as T, SuccessResponse};
+
+use crate::{Client, Error};
+
+impl Client {
+    pub async fn get_me(&self) -> Result<Profile, Error> {
+        self.send(self.request(Method::GET, "/me")).await
+    }
+
+    pub async fn update_me(&self, req: &T::UpdateProfileRequest) -> Result<Profile, Error> {
+        self.send(self.request(Method::PATCH, "/me").json(req))
+            .await
+    }
+
+    /// Schedules the account for deletion, which [`Client::restore_me`] undoes
+    /// until it is purged.
+    pub async fn delete_me(&self) -> Result<T::DeletionScheduled, Error> {
+        self.send(self.request(Method::DELETE, "/me")).await
+    }
+
+    pub async fn restore_me(&self) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::POST, "/me/restore")).await
+    }
+
+    pub async fn request_verification(&self) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::POST, "/me/email/verification"))
+            .await
+    }
+
+    /// Replaces the avatar with `image`, a PNG, JPEG, WebP or GIF file.
+    pub async fn upload_avatar(&self, image: Vec<u8>) -> Result<Profile, Error> {
+        let form = multipart::Form::new().part("avatar", multipart::Part::bytes(image));
+        self.send(self.request(Method::PUT, "/me/avatar").multipart(form))
+            .await
+    }
+
+    pub async fn delete_avatar(&self) -> Result<(), Error> {
+        self.send_empty(self.request(Method::DELETE, "/me/avatar"))
+            .await
+    }
+}
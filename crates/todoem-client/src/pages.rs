@@ -0,0 +1,103 @@
+use futures_util::{stream, Stream, StreamExt};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+
+use crate::{Client, Error};
+
+/// The pages of a paged listing, fetched one at a time as they are asked
+/// for. The listing ends at an empty page, or at one shorter than the first.
+pub struct Pages<T> {
+    client: Client,
+    path: String,
+    query: Vec<(&'static str, String)>,
+    /// Whether the page number is the last segment of the path rather than
+    /// `?p=`.
+    page_in_path: bool,
+    next: Option<u16>,
+    page_len: Option<usize>,
+    _items: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Pages<T> {
+    pub(crate) fn query(client: &Client, path: &str, query: Vec<(&'static str, String)>) -> Self {
+        Self {
+            client: client.clone(),
+            path: path.to_string(),
+            query,
+            page_in_path: false,
+            next: Some(1),
+            page_len: None,
+            _items: PhantomData,
+        }
+    }
+
+    pub(crate) fn path(client: &Client, path: &str) -> Self {
+        Self {
+            page_in_path: true,
+            ..Self::query(client, path, Vec::new())
+        }
+    }
+
+    /// Starts at page `p` instead of the first.
+    pub fn from_page(mut self, p: u16) -> Self {
+        self.next = Some(p);
+        self
+    }
+
+    /// The next page, or `None` past the last one or after an error.
+    pub async fn next_page(&mut self) -> Option<Result<Vec<T>, Error>> {
+        let p = self.next?;
+        let req = match self.page_in_path {
+            true => self
+                .client
+                .request(Method::GET, &format!("{}/{}", self.path, p)),
+            false => self
+                .client
+                .request(Method::GET, &self.path)
+                .query(&self.query)
+                .query(&[("p", p)]),
+        };
+
+        let page: Vec<T> = match self.client.send(req).await {
+            Ok(page) => page,
+            Err(e) => {
+                self.next = None;
+                return Some(Err(e));
+            }
+        };
+        let page_len = *self.page_len.get_or_insert(page.len());
+        self.next = match page.len() < page_len || page.is_empty() {
+            true => None,
+            false => p.checked_add(1),
+        };
+        match page.is_empty() {
+            true => None,
+            false => Some(Ok(page)),
+        }
+    }
+
+    /// Every item of the remaining pages.
+    pub async fn all(mut self) -> Result<Vec<T>, Error> {
+        let mut items = Vec::new();
+        while let Some(page) = self.next_page().await {
+            items.extend(page?);
+        }
+        Ok(items)
+    }
+
+    /// The items of the remaining pages one by one, fetching pages as needed.
+    pub fn items(self) -> impl Stream<Item = Result<T, Error>> {
+        stream::unfold(self, |mut pages| async move {
+            let page = pages.next_page().await?;
+            Some((page, pages))
+        })
+        .flat_map(|page| {
+            let items: Vec<_> = match page {
+                Ok(items) => items.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            };
+            stream::iter(items)
+        })
+    }
+}
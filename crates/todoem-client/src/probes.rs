@@ -0,0 +1,38 @@
+use reqwest::{header::AUTHORIZATION, Method};
+use serde_json::Value;
+
+use crate::{Client, Error};
+
+impl Client {
+    /// Whether the server is up.
+    pub async fn healthz(&self) -> Result<Value, Error> {
+        self.send(self.unversioned(Method::GET, "/healthz")).await
+    }
+
+    /// Whether the server is ready to serve; otherwise an error with the
+    /// failed checks as details.
+    pub async fn readyz(&self) -> Result<Value, Error> {
+        self.send(self.unversioned(Method::GET, "/readyz")).await
+    }
+
+    /// The Prometheus metrics, with the metrics token if the server wants
+    /// one.
+    pub async fn metrics(&self, token: Option<&str>) -> Result<String, Error> {
+        let mut req = self.unversioned(Method::GET, "/metrics");
+        if let Some(token) = token {
+            req = req.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        Ok(self.execute(req).await?.text().await?)
+    }
+
+    /// The public keys session tokens are signed with, as a JWK set.
+    pub async fn jwks(&self) -> Result<Value, Error> {
+        self.send(self.unversioned(Method::GET, "/.well-known/jwks.json"))
+            .await
+    }
+
+    /// The OpenAPI document of the API.
+    pub async fn openapi(&self) -> Result<Value, Error> {
+        self.send(self.request(Method::GET, "/openapi.json")).await
+    }
+}
@@ -0,0 +1,29 @@
+use reqwest::{header::SET_COOKIE, Method};
+use todoem_core::models::session::Session;
+use uuid::Uuid;
+
+use crate::{Auth, Client, Error, ACCESS_COOKIE};
+
+impl Client {
+    pub async fn get_sessions(&self) -> Result<Vec<Session>, Error> {
+        self.send(self.request(Method::GET, "/me/sessions")).await
+    }
+
+    /// Signs a device out; revoking the session in use forgets it.
+    pub async fn revoke_session(&self, id: Uuid) -> Result<(), Error> {
+        let res = self
+            .execute(self.request(Method::DELETE, &format!("/me/sessions/{}", id)))
+            .await?;
+        // the server removes the cookie of the session it was asked through
+        let signed_out = res
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .any(|v| v.starts_with(&format!("{}=", ACCESS_COOKIE)));
+        if signed_out {
+            self.set_auth(Auth::None);
+        }
+        Ok(())
+    }
+}
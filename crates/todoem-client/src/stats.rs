@@ -0,0 +1,10 @@
+use reqwest::Method;
+use todoem_core::models::stats::Stats;
+
+use crate::{Client, Error};
+
+impl Client {
+    pub async fn get_stats(&self) -> Result<Stats, Error> {
+        self.send(self.request(Method::GET, "/stats")).await
+    }
+}
@@ -0,0 +1,108 @@
+use reqwest::Method;
+use todoem_core::models::task::Task;
+use todoem_core::types::task as T;
+
+use crate::{Client, Error, Pages};
+
+impl Client {
+    pub async fn create_task(&self, req: &T::CreateTaskRequest) -> Result<Task, Error> {
+        self.send(self.request(Method::POST, "/task").json(req))
+            .await
+    }
+
+    pub async fn get_task(&self, id: i64) -> Result<Task, Error> {
+        self.send(self.request(Method::GET, &format!("/task/{}", id)))
+            .await
+    }
+
+    pub async fn update_task(&self, id: i64, req: &T::UpdateTaskRequest) -> Result<(), Error> {
+        self.send_empty(
+            self.request(Method::PUT, &format!("/task/{}", id))
+                .json(req),
+        )
+        .await
+    }
+
+    pub async fn delete_task(&self, id: i64) -> Result<(), Error> {
+        self.send_empty(self.request(Method::DELETE, &format!("/task/{}", id)))
+            .await
+    }
+
+    /// Completes a task; a recurring one moves on to its next occurrence.
+    pub async fn done_task(&self, id: i64) -> Result<(), Error> {
+        self.send_empty(self.request(Method::PUT, &format!("/task/done/{}", id)))
+            .await
+    }
+
+    pub async fn undone_task(&self, id: i64) -> Result<(), Error> {
+        self.send_empty(self.request(Method::PUT, &format!("/task/undone/{}", id)))
+            .await
+    }
+
+    /// Every occurrence due between two days, both included.
+    pub async fn get_due_tasks(
+        &self,
+        range: &T::DueRangeParams,
+    ) -> Result<Vec<T::TaskOccurrence>, Error> {
+        self.send(self.request(Method::GET, "/task/due").query(range))
+            .await
+    }
+
+    pub async fn get_today_view(&self) -> Result<T::TodayView, Error> {
+        self.send(self.request(Method::GET, "/task/view/today"))
+            .await
+    }
+
+    pub async fn get_overdue_view(&self) -> Result<T::TaskView<Task>, Error> {
+        self.send(self.request(Method::GET, "/task/view/overdue"))
+            .await
+    }
+
+    pub async fn get_upcoming_view(&self) -> Result<T::UpcomingView, Error> {
+        self.send(self.request(Method::GET, "/task/view/upcoming"))
+            .await
+    }
+
+    pub async fn get_undated_view(&self) -> Result<T::TaskView<Task>, Error> {
+        self.send(self.request(Method::GET, "/task/view/no-due-date"))
+            .await
+    }
+
+    pub async fn get_view_counts(&self) -> Result<T::ViewCounts, Error> {
+        self.send(self.request(Method::GET, "/task/view/counts"))
+            .await
+    }
+
+    pub async fn get_all_tasks(&self) -> Result<Vec<Task>, Error> {
+        self.send(self.request(Method::GET, "/task/all")).await
+    }
+
+    /// Every task, page by page, where `get_all_tasks` stops at a hundred.
+    pub fn get_task_pages(&self) -> Pages<Task> {
+        Pages::query(self, "/task/all", Vec::new())
+    }
+
+    pub async fn get_all_done_tasks(&self) -> Result<Vec<Task>, Error> {
+        self.send(self.request(Method::GET, "/task/all/done")).await
+    }
+
+    pub async fn get_all_undone_tasks(&self) -> Result<Vec<Task>, Error> {
+        self.send(self.request(Method::GET, "/task/all/undone"))
+            .await
+    }
+
+    pub async fn delete_all_tasks(&self) -> Result<(), Error> {
+        self.send_empty(self.request(Method::DELETE, "/task/all"))
+            .await
+    }
+
+    pub async fn delete_all_done_tasks(&self) -> Result<(), Error> {
+        self.send_empty(self.request(Method::DELETE, "/task/all/done"))
+            .await
+    }
+
+    pub async fn delete_all_undone_tasks(&self) -> Result<(), Error> {
+        self.send_empty(self.request(Method::DELETE, "/task/all/undone"))
+            .await
+    }
+}
@@ -0,0 +1,22 @@
+use reqwest::Method;
+use todoem_core::models::token::{ApiToken, NewApiToken};
+use todoem_core::types::token as T;
+
+use crate::{Client, Error};
+
+impl Client {
+    /// Creates a personal access token, whose secret is only shown now.
+    pub async fn create_token(&self, req: &T::CreateTokenRequest) -> Result<NewApiToken, Error> {
+        self.send(self.request(Method::POST, "/tokens").json(req))
+            .await
+    }
+
+    pub async fn get_tokens(&self) -> Result<Vec<ApiToken>, Error> {
+        self.send(self.request(Method::GET, "/tokens")).await
+    }
+
+    pub async fn revoke_token(&self, id: i64) -> Result<(), Error> {
+        self.send_empty(self.request(Method::DELETE, &format!("/tokens/{}", id)))
+            .await
+    }
+}
@@ -0,0 +1,38 @@
+use reqwest::Method;
+use todoem_core::models::two_factor::TwoFactorStatus;
+use todoem_core::types::{two_factor as T, SuccessResponse};
+
+use crate::{Client, Error};
+
+impl Client {
+    pub async fn get_two_factor_status(&self) -> Result<TwoFactorStatus, Error> {
+        self.send(self.request(Method::GET, "/me/2fa")).await
+    }
+
+    /// Starts enrolling an authenticator app, finished by
+    /// [`Client::confirm_totp`].
+    pub async fn enroll_totp(&self) -> Result<T::Enrollment, Error> {
+        self.send(self.request(Method::POST, "/me/2fa/totp")).await
+    }
+
+    pub async fn confirm_totp(&self, req: &T::CodeRequest) -> Result<T::RecoveryCodes, Error> {
+        self.send(self.request(Method::POST, "/me/2fa/totp/confirm").json(req))
+            .await
+    }
+
+    pub async fn regenerate_recovery_codes(
+        &self,
+        req: &T::CodeRequest,
+    ) -> Result<T::RecoveryCodes, Error> {
+        self.send(
+            self.request(Method::POST, "/me/2fa/recovery-codes")
+                .json(req),
+        )
+        .await
+    }
+
+    pub async fn disable_two_factor(&self, req: &T::CodeRequest) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::POST, "/me/2fa/disable").json(req))
+            .await
+    }
+}
@@ -0,0 +1,100 @@
+use reqwest::Method;
+use todoem_core::models::user as M;
+use todoem_core::types::{user as T, SuccessResponse};
+use uuid::Uuid;
+
+use crate::{Client, Error, Pages};
+
+impl Client {
+    /// Users whose name or username matches `q`, page by page.
+    pub fn search(&self, q: &str) -> Pages<M::User> {
+        Pages::query(self, "/user/search", vec![("q", q.to_string())])
+    }
+
+    pub async fn view_user_profile(&self, id: Uuid) -> Result<T::ViewUser, Error> {
+        self.send(self.request(Method::GET, &format!("/user/{}/profile", id)))
+            .await
+    }
+
+    /// The PNG avatar of a user.
+    pub async fn get_avatar(&self, id: Uuid) -> Result<Vec<u8>, Error> {
+        let res = self
+            .execute(self.request(Method::GET, &format!("/user/{}/avatar", id)))
+            .await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    pub async fn request_connection(&self, id: Uuid) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::POST, &format!("/user/{}/request", id)))
+            .await
+    }
+
+    /// Withdraws a connection request sent to `id`.
+    pub async fn delete_request_connection(&self, id: Uuid) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::DELETE, &format!("/user/{}/request", id)))
+            .await
+    }
+
+    pub async fn accept_connection(&self, id: Uuid) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::PUT, &format!("/user/{}/accept", id)))
+            .await
+    }
+
+    pub async fn reject_connection(&self, id: Uuid) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::PUT, &format!("/user/{}/reject", id)))
+            .await
+    }
+
+    pub async fn get_received_requests(&self) -> Result<Vec<M::User>, Error> {
+        self.send(self.request(Method::GET, "/user/requests/received"))
+            .await
+    }
+
+    pub async fn get_sent_requests(&self) -> Result<Vec<M::User>, Error> {
+        self.send(self.request(Method::GET, "/user/requests/sent"))
+            .await
+    }
+
+    pub async fn block_user(&self, id: Uuid) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::POST, &format!("/user/{}/block", id)))
+            .await
+    }
+
+    pub async fn unblock_user(&self, id: Uuid) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::DELETE, &format!("/user/{}/block", id)))
+            .await
+    }
+
+    pub async fn get_blocked_users(&self) -> Result<Vec<M::User>, Error> {
+        self.send(self.request(Method::GET, "/user/blocked")).await
+    }
+
+    pub async fn get_privacy(&self) -> Result<M::Privacy, Error> {
+        self.send(self.request(Method::GET, "/user/privacy")).await
+    }
+
+    pub async fn update_privacy(&self, req: &T::UpdatePrivacyRequest) -> Result<M::Privacy, Error> {
+        self.send(self.request(Method::PUT, "/user/privacy").json(req))
+            .await
+    }
+
+    /// The users connected with the caller, page by page.
+    pub fn get_listers(&self) -> Pages<M::User> {
+        Pages::path(self, "/user/listers/page")
+    }
+
+    /// The connected users matching `q`, page by page.
+    pub fn search_listers(&self, q: &str) -> Pages<M::User> {
+        Pages::query(self, "/user/listers/search", vec![("q", q.to_string())])
+    }
+
+    pub async fn view_lister_profile(&self, id: Uuid) -> Result<T::ViewUser, Error> {
+        self.send(self.request(Method::GET, &format!("/user/listers/{}", id)))
+            .await
+    }
+
+    pub async fn disconnect_lister(&self, id: Uuid) -> Result<SuccessResponse, Error> {
+        self.send(self.request(Method::PUT, &format!("/user/listers/{}/disconnect", id)))
+            .await
+    }
+}
@@ -0,0 +1,69 @@
+use reqwest::Method;
+use todoem_core::models::webhook::{Delivery, Webhook, WebhookWithSecret};
+use todoem_core::types::webhook as T;
+
+use crate::{Client, Error, Pages};
+
+impl Client {
+    /// Registers a webhook, whose signing secret is only shown now.
+    pub async fn create_webhook(
+        &self,
+        req: &T::CreateWebhookRequest,
+    ) -> Result<WebhookWithSecret, Error> {
+        self.send(self.request(Method::POST, "/webhooks").json(req))
+            .await
+    }
+
+    pub async fn get_webhooks(&self) -> Result<Vec<Webhook>, Error> {
+        self.send(self.request(Method::GET, "/webhooks")).await
+    }
+
+    pub async fn get_webhook(&self, id: i64) -> Result<Webhook, Error> {
+        self.send(self.request(Method::GET, &format!("/webhooks/{}", id)))
+            .await
+    }
+
+    pub async fn update_webhook(
+        &self,
+        id: i64,
+        req: &T::UpdateWebhookRequest,
+    ) -> Result<Webhook, Error> {
+        self.send(
+            self.request(Method::PUT, &format!("/webhooks/{}", id))
+                .json(req),
+        )
+        .await
+    }
+
+    pub async fn delete_webhook(&self, id: i64) -> Result<(), Error> {
+        self.send_empty(self.request(Method::DELETE, &format!("/webhooks/{}", id)))
+            .await
+    }
+
+    pub async fn rotate_secret(&self, id: i64) -> Result<WebhookWithSecret, Error> {
+        self.send(self.request(Method::POST, &format!("/webhooks/{}/secret", id)))
+            .await
+    }
+
+    /// Queues a `ping` delivery to the webhook.
+    pub async fn ping_webhook(&self, id: i64) -> Result<Delivery, Error> {
+        self.send(self.request(Method::POST, &format!("/webhooks/{}/ping", id)))
+            .await
+    }
+
+    /// The deliveries to a webhook, newest first, page by page.
+    pub fn get_deliveries(&self, id: i64) -> Pages<Delivery> {
+        Pages::query(self, &format!("/webhooks/{}/deliveries", id), Vec::new())
+    }
+
+    pub async fn get_delivery(&self, id: i64, delivery_id: i64) -> Result<Delivery, Error> {
+        let path = format!("/webhooks/{}/deliveries/{}", id, delivery_id);
+        self.send(self.request(Method::GET, &path)).await
+    }
+
+    /// Queues a delivery again, as a new delivery.
+    pub async fn replay_delivery(&self, id: i64, delivery_id: i64) -> Result<Delivery, Error> {
+        let path = format!("/webhooks/{}/deliveries/{}/replay", id, delivery_id);
+        self.send(self.request(Method::POST, &path)).await
+    }
+}
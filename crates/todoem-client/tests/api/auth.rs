@@ -0,0 +1,88 @@
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use todoem_client::models::token::Scope;
+use todoem_client::types::{auth::LoginRequest, token::CreateTokenRequest};
+use todoem_client::{Auth, Client, Error};
+
+use crate::{sign_in, spawn, LUFFY};
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn signing_in_keeps_the_session(pool: PgPool) {
+    let client = spawn(pool).await;
+    assert_eq!(client.auth(), Auth::None);
+    let anonymous = client.get_me().await.unwrap_err();
+    assert_eq!(anonymous.status(), Some(StatusCode::UNAUTHORIZED));
+
+    sign_in(&client, "luffy").await;
+    assert!(matches!(client.auth(), Auth::Session(_)));
+    assert_eq!(client.get_me().await.unwrap().id, LUFFY);
+
+    // stored credentials work from another client
+    let again = Client::new(client.base_url()).with_auth(client.auth());
+    let sessions = again.get_sessions().await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0].current);
+
+    again.revoke_session(sessions[0].id).await.unwrap();
+    assert_eq!(again.auth(), Auth::None);
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn refusals_are_typed_errors(pool: PgPool) {
+    let client = spawn(pool).await;
+    let req = LoginRequest {
+        login: "luffy".to_string(),
+        password: "zoro".to_string(),
+        device_name: None,
+    };
+    match client.login(&req).await.unwrap_err() {
+        Error::Api { error, request_id } => {
+            assert_eq!(error.status(), StatusCode::UNAUTHORIZED);
+            assert!(request_id.is_some());
+        }
+        e => panic!("{:?}", e),
+    }
+    assert_eq!(client.auth(), Auth::None);
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn tokens_authenticate_within_their_scopes(pool: PgPool) {
+    let client = spawn(pool).await;
+    sign_in(&client, "luffy").await;
+    let token = client
+        .create_token(&CreateTokenRequest {
+            name: "cli".to_string(),
+            scopes: vec![Scope::ReadTasks],
+            expires_in_days: None,
+        })
+        .await
+        .unwrap();
+
+    let scoped = Client::new(client.base_url()).with_auth(Auth::Token(token.value));
+    assert!(scoped.get_all_tasks().await.unwrap().is_empty());
+    let outside = scoped.get_tokens().await.unwrap_err();
+    assert_eq!(outside.status(), Some(StatusCode::FORBIDDEN));
+
+    client.revoke_token(token.token.id).await.unwrap();
+    let revoked = scoped.get_all_tasks().await.unwrap_err();
+    assert_eq!(revoked.status(), Some(StatusCode::UNAUTHORIZED));
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn probes_are_outside_the_api(pool: PgPool) {
+    let client = spawn(pool).await;
+    assert_eq!(client.healthz().await.unwrap()["status"], "ok");
+    assert!(client.openapi().await.unwrap()["paths"].is_object());
+}
@@ -0,0 +1,58 @@
+//! Tests of the client against a server spawned in process on a free port,
+//! over a fresh database per test (see the server's own tests).
+
+mod auth;
+mod task;
+mod user;
+
+use sqlx::PgPool;
+use std::{future::IntoFuture, net::SocketAddr, sync::Arc};
+use todoem_client::types::auth::LoginRequest;
+use todoem_client::{Client, Login};
+use todoem_server::config::{Config, Settings};
+use todoem_server::routes;
+use todoem_server::services::{
+    metrics::Metrics, shutdown::Shutdown, storage::LocalStorage, storage::SharedStorage,
+};
+use uuid::{uuid, Uuid};
+
+pub const LUFFY: Uuid = uuid!("5d43fc3c-8acb-48f9-9b25-8f8bd6f3d834");
+pub const ZORO: Uuid = uuid!("4157ee44-1de0-4168-a1f3-7ad6a5fd09b6");
+pub const NAMI: Uuid = uuid!("f6d1dabe-7766-4a6c-b34e-75e444cc3cbd");
+
+/// Serves the API over `pool` and returns a client of it without
+/// credentials.
+pub async fn spawn(pool: PgPool) -> Client {
+    let mut settings = Settings::default();
+    settings.jwt.secret_key = "secret".to_string();
+    // as many as the fixture passwords are hashed with, so none is rehashed
+    settings.accounts.password_iterations = 1_000;
+    let config = Config::new(settings, pool).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("todoem-test-{}", Uuid::new_v4()));
+    let storage: SharedStorage = Arc::new(LocalStorage::new(dir));
+    let router = routes::init(config, storage, Shutdown::new(), Metrics::new());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    );
+    tokio::spawn(server.into_future());
+
+    Client::new(&format!("http://{}", addr))
+}
+
+/// Signs in as a fixture user, whose password is their username.
+pub async fn sign_in(client: &Client, username: &str) {
+    let req = LoginRequest {
+        login: username.to_string(),
+        password: username.to_string(),
+        device_name: None,
+    };
+    match client.login(&req).await.unwrap() {
+        Login::SignedIn(profile) => assert_eq!(profile.username, username),
+        Login::SecondFactor(_) => panic!("{} has no second factor", username),
+    }
+}
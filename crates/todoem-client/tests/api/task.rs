@@ -0,0 +1,74 @@
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use todoem_client::models::filter::Filter;
+use todoem_client::types::{filter::SaveFilterRequest, task::CreateTaskRequest};
+
+use crate::{sign_in, spawn};
+
+fn new_task(task: &str) -> CreateTaskRequest {
+    CreateTaskRequest {
+        task: task.to_string(),
+        description: String::new(),
+        due_date: None,
+        due_on: None,
+        repeat_frequency: None,
+    }
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn tasks_go_through_their_lifecycle(pool: PgPool) {
+    let client = spawn(pool).await;
+    sign_in(&client, "luffy").await;
+
+    let task = client
+        .create_task(&new_task("Find the One Piece"))
+        .await
+        .unwrap();
+    assert!(!task.done);
+
+    client.done_task(task.id).await.unwrap();
+    assert!(client.get_task(task.id).await.unwrap().done);
+    assert_eq!(client.get_all_done_tasks().await.unwrap().len(), 1);
+
+    client.undone_task(task.id).await.unwrap();
+    assert_eq!(client.get_all_undone_tasks().await.unwrap().len(), 1);
+
+    client.delete_task(task.id).await.unwrap();
+    let gone = client.get_task(task.id).await.unwrap_err();
+    assert_eq!(gone.status(), Some(StatusCode::NOT_FOUND));
+}
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn pages_are_fetched_until_the_last(pool: PgPool) {
+    let client = spawn(pool).await;
+    sign_in(&client, "luffy").await;
+    for i in 0..12 {
+        client
+            .create_task(&new_task(&format!("task {}", i)))
+            .await
+            .unwrap();
+    }
+    let filter = client
+        .create_filter(&SaveFilterRequest {
+            name: "open".to_string(),
+            filter: Filter::Done(false),
+        })
+        .await
+        .unwrap();
+
+    let mut pages = client.get_filter_tasks(filter.id);
+    assert_eq!(pages.next_page().await.unwrap().unwrap().len(), 10);
+    assert_eq!(pages.next_page().await.unwrap().unwrap().len(), 2);
+    assert!(pages.next_page().await.is_none());
+
+    let all = client.get_filter_tasks(filter.id).all().await.unwrap();
+    assert_eq!(all.len(), 12);
+    let rest = client.get_filter_tasks(filter.id).from_page(2).all();
+    assert_eq!(rest.await.unwrap().len(), 2);
+}
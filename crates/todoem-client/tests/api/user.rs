@@ -0,0 +1,31 @@
+use futures_util::TryStreamExt;
+use sqlx::PgPool;
+use todoem_client::Client;
+
+use crate::{sign_in, spawn, LUFFY, ZORO};
+
+#[sqlx::test(
+    migrations = "../todoem-server/src/db/migrations",
+    fixtures("../../../todoem-server/src/db/fixtures/users.sql")
+)]
+async fn connections_are_requested_and_accepted(pool: PgPool) {
+    let luffy = spawn(pool).await;
+    let zoro = Client::new(luffy.base_url());
+    sign_in(&luffy, "luffy").await;
+    sign_in(&zoro, "zoro").await;
+
+    luffy.request_connection(ZORO).await.unwrap();
+    let sent = luffy.get_sent_requests().await.unwrap();
+    assert_eq!(sent.iter().map(|u| u.id).collect::<Vec<_>>(), [ZORO]);
+    let received = zoro.get_received_requests().await.unwrap();
+    assert_eq!(received.iter().map(|u| u.id).collect::<Vec<_>>(), [LUFFY]);
+
+    zoro.accept_connection(LUFFY).await.unwrap();
+    let listers: Vec<_> = luffy.get_listers().items().try_collect().await.unwrap();
+    assert_eq!(listers.iter().map(|u| u.id).collect::<Vec<_>>(), [ZORO]);
+    let found = zoro.search_listers("luf").all().await.unwrap();
+    assert_eq!(found.iter().map(|u| u.id).collect::<Vec<_>>(), [LUFFY]);
+
+    luffy.disconnect_lister(ZORO).await.unwrap();
+    assert!(luffy.get_listers().all().await.unwrap().is_empty());
+}
@@ -0,0 +1,92 @@
+use chrono::{offset::LocalResult, DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::models::task::Task;
+
+/// When a task is due: at an instant, or on a whole day in its owner's zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Due {
+    At(DateTime<Utc>),
+    On(NaiveDate),
+}
+
+impl Due {
+    pub fn of(task: &Task) -> Option<Self> {
+        task.due_on
+            .map(Due::On)
+            .or_else(|| task.due_date.map(Due::At))
+    }
+
+    /// Where the occurrences of a recurring task are counted from. Tasks
+    /// without an anchor count from their current due date.
+    pub fn anchor_of(task: &Task) -> Option<Self> {
+        task.repeat_anchor_on
+            .map(Due::On)
+            .or_else(|| task.repeat_anchor_date.map(Due::At))
+            .or_else(|| Self::of(task))
+    }
+
+    /// The calendar day this falls on for someone in `tz`.
+    pub fn date_in(&self, tz: Tz) -> NaiveDate {
+        match self {
+            Due::At(at) => at.with_timezone(&tz).date_naive(),
+            Due::On(date) => *date,
+        }
+    }
+
+    pub fn at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Due::At(at) => Some(*at),
+            Due::On(_) => None,
+        }
+    }
+}
+
+/// Resolves a wall-clock time in `tz`. Times repeated when clocks go back take
+/// the earlier instant, times skipped when they go forward move past the gap.
+pub fn resolve_local(tz: Tz, mut local: NaiveDateTime) -> DateTime<Utc> {
+    loop {
+        match tz.from_local_datetime(&local) {
+            LocalResult::Single(at) => return at.with_timezone(&Utc),
+            LocalResult::Ambiguous(earliest, _) => return earliest.with_timezone(&Utc),
+            LocalResult::None => local += Duration::minutes(15),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::New_York;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn due_date_uses_local_day() {
+        // 23:30 in New York is already the next day in UTC
+        let due = Due::At(utc("2024-03-01T04:30:00Z"));
+        assert_eq!(due.date_in(New_York), date(2024, 2, 29));
+        assert_eq!(due.date_in(Tz::UTC), date(2024, 3, 1));
+    }
+
+    #[test]
+    fn skipped_and_repeated_times_resolve() {
+        let skipped = date(2024, 3, 10).and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(
+            resolve_local(New_York, skipped),
+            utc("2024-03-10T07:00:00Z")
+        );
+
+        let repeated = date(2024, 11, 3).and_hms_opt(1, 30, 0).unwrap();
+        assert_eq!(
+            resolve_local(New_York, repeated),
+            utc("2024-11-03T05:30:00Z")
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use utoipa::{
+    openapi::{RefOr, Schema},
+    PartialSchema, ToSchema,
+};
+
+#[cfg(feature = "axum")]
+tokio::task_local! {
+    /// The `X-Request-Id` of the request being handled, quoted in error
+    /// bodies. Set by the server around each request.
+    pub static REQUEST_ID: String;
+}
+
+#[derive(Debug)]
+pub struct APIError(StatusCode, String, Option<serde_json::Value>);
+
+/// What every error response carries.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBody {
+    /// A message for people.
+    pub error: String,
+    /// Which fields failed validation and why, or other specifics.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+    /// The `X-Request-Id` of the request, to quote when reporting it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for APIError {
+    fn into_response(self) -> axum::response::Response {
+        let body = ErrorBody {
+            error: self.1,
+            details: self.2,
+            request_id: REQUEST_ID.try_with(|id| id.clone()).ok(),
+        };
+        (self.0, axum::Json(body)).into_response()
+    }
+}
+
+/// Documented as the body it responds with.
+impl PartialSchema for APIError {
+    fn schema() -> RefOr<Schema> {
+        ErrorBody::schema()
+    }
+}
+
+impl ToSchema for APIError {}
+
+impl APIError {
+    pub fn new(status: StatusCode, msg: &str) -> Self {
+        Self(status, msg.to_string(), None)
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.2 = Some(details);
+        self
+    }
+
+    pub fn server() -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong")
+    }
+
+    pub fn bad(msg: &str) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, msg)
+    }
+
+    pub fn unprocessable(msg: &str) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, msg)
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "Not found")
+    }
+
+    pub fn auth() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "Unauthorized")
+    }
+
+    pub fn forbidden() -> Self {
+        Self::new(StatusCode::FORBIDDEN, "Forbidden")
+    }
+
+    pub fn too_many_requests() -> Self {
+        Self::new(StatusCode::TOO_MANY_REQUESTS, "Too many requests")
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.0
+    }
+
+    pub fn message(&self) -> &str {
+        &self.1
+    }
+
+    pub fn details(&self) -> Option<&serde_json::Value> {
+        self.2.as_ref()
+    }
+}
+
+/// An error response as read back by a client.
+impl From<(StatusCode, ErrorBody)> for APIError {
+    fn from((status, body): (StatusCode, ErrorBody)) -> Self {
+        Self(status, body.error, body.details)
+    }
+}
+
+impl std::fmt::Display for APIError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.0, self.1)
+    }
+}
+
+impl std::error::Error for APIError {}
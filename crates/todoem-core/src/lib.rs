@@ -0,0 +1,9 @@
+//! What the todoem server and its clients share: the models, the request and
+//! response bodies of the API, its errors, the rules requests are validated
+//! by, and when tasks fall due.
+
+pub mod due;
+pub mod errors;
+pub mod models;
+pub mod types;
+pub mod validation;
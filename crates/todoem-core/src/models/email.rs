@@ -0,0 +1,41 @@
+use uuid::Uuid;
+
+/// What an emailed token proves when it comes back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "email_token_purpose", rename_all = "snake_case")
+)]
+pub enum EmailTokenPurpose {
+    VerifyEmail,
+    ResetPassword,
+}
+
+/// A redeemed email token: who it was for and the address it was sent to.
+#[derive(Debug)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct EmailGrant {
+    pub user_id: Uuid,
+    pub email: String,
+}
+
+/// What password sign-in and recovery need to know about an account.
+#[derive(Debug)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Credentials {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub password: Option<String>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct OutboundEmail {
+    pub id: i64,
+    pub to_address: String,
+    pub subject: String,
+    pub body: String,
+    pub attempts: i32,
+}
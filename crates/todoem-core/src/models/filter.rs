@@ -0,0 +1,133 @@
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{
+    openapi::{schema::SchemaType, ObjectBuilder, RefOr, Schema, Type},
+    PartialSchema, ToSchema,
+};
+
+use super::task::Frequency;
+
+/// A day relative to today in the user's time zone (`today`, `+7`, `-1`) or a
+/// fixed `YYYY-MM-DD` date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum DayRef {
+    Relative(i32),
+    Date(NaiveDate),
+}
+
+impl DayRef {
+    pub fn resolve(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            DayRef::Relative(n) if n >= 0 => today.checked_add_days(Days::new(n as u64)),
+            DayRef::Relative(n) => today.checked_sub_days(Days::new(n.unsigned_abs() as u64)),
+            DayRef::Date(date) => Some(date),
+        }
+    }
+}
+
+impl TryFrom<String> for DayRef {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        if s == "today" {
+            return Ok(DayRef::Relative(0));
+        }
+        if s.starts_with(['+', '-']) {
+            return s
+                .parse()
+                .map(DayRef::Relative)
+                .map_err(|_| format!("invalid day offset {:?}", s));
+        }
+        s.parse()
+            .map(DayRef::Date)
+            .map_err(|_| format!("expected today, +N, -N or YYYY-MM-DD, got {:?}", s))
+    }
+}
+
+impl From<DayRef> for String {
+    fn from(day: DayRef) -> Self {
+        match day {
+            DayRef::Relative(0) => "today".to_string(),
+            DayRef::Relative(n) => format!("{:+}", n),
+            DayRef::Date(date) => date.to_string(),
+        }
+    }
+}
+
+/// Documented as the string it is written as.
+impl PartialSchema for DayRef {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(SchemaType::Type(Type::String))
+            .pattern(Some(r"^(today|[+-][0-9]+|[0-9]{4}-[0-9]{2}-[0-9]{2})$"))
+            .description(Some(
+                "`today`, days from today like `+7` or `-1`, or a `YYYY-MM-DD` date",
+            ))
+            .into()
+    }
+}
+
+impl ToSchema for DayRef {}
+
+/// Inclusive range of days; an open end is unbounded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DayRange {
+    pub from: Option<DayRef>,
+    pub to: Option<DayRef>,
+}
+
+/// Filter expression over task fields, e.g.
+/// `{"and": [{"done": false}, {"due": {"from": "today", "to": "+6"}}, {"text": "work"}]}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+#[schema(no_recursion)]
+pub enum Filter {
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+    Done(bool),
+    /// Case-insensitive substring of the title or the description.
+    Text(String),
+    HasDue(bool),
+    /// Due on one of these days, timed or all-day.
+    Due(DayRange),
+    Created(DayRange),
+    Repeats(bool),
+    Frequency(Frequency),
+}
+
+impl Filter {
+    /// Visits this node and all of its descendants.
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Filter)) {
+        f(self);
+        match self {
+            Filter::And(args) | Filter::Or(args) => args.iter().for_each(|arg| arg.walk(f)),
+            Filter::Not(arg) => arg.walk(f),
+            _ => {}
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        match self {
+            Filter::And(args) | Filter::Or(args) => {
+                1 + args.iter().map(Filter::depth).max().unwrap_or(0)
+            }
+            Filter::Not(arg) => 1 + arg.depth(),
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct SavedFilter {
+    pub id: i64,
+    pub user_id: uuid::Uuid,
+    pub name: String,
+    #[schema(value_type = Filter)]
+    #[cfg_attr(feature = "sqlx", sqlx(json))]
+    pub filter: Filter,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// An account at an OpenID Connect provider linked to a user.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Identity {
+    pub id: i64,
+    pub provider: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+/// A sign-in sent to a provider, looked up again by its `state`.
+#[derive(Debug)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct PendingLogin {
+    pub provider: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub redirect_to: String,
+    pub link_user_id: Option<uuid::Uuid>,
+}
+
+/// A first sign-in waiting for the user to confirm a username.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Signup {
+    pub provider: String,
+    #[serde(skip)]
+    pub subject: String,
+    pub email: String,
+    pub name: Option<String>,
+    /// Suggested from the provider's profile; may already be taken.
+    pub username: String,
+}
@@ -1,18 +1,25 @@
 use chrono::{DateTime, Utc};
 
+/// A list of tasks, made by its owner or sent to them by a connection.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct List {
     pub id: uuid::Uuid,
     pub user_id: uuid::Uuid,
     pub name: String,
     pub description: String,
-    pub task_count: u16,
+    pub task_count: i16,
     pub done: bool,
+    /// Who sent the list, `None` for lists the owner made.
     pub sent_by_id: Option<uuid::Uuid>,
+    /// When the list was received, or made.
     pub sent_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct ListTask {
-    pub id: u64,
+    pub id: i64,
     pub list_id: uuid::Uuid,
     pub task: String,
     pub description: String,
@@ -0,0 +1,27 @@
+pub mod email;
+pub mod filter;
+pub mod group;
+pub mod identity;
+pub mod list;
+pub mod session;
+pub mod stats;
+pub mod task;
+pub mod token;
+pub mod two_factor;
+pub mod user;
+pub mod webhook;
+
+use serde::{Deserialize, Serialize};
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct AuthUser {
+    pub id: uuid::Uuid,
+    pub email: String,
+}
+
+/// How the current request was authenticated, next to its [`AuthUser`].
+#[derive(Debug, Clone)]
+pub enum Credential {
+    Session { id: uuid::Uuid },
+    Token { scopes: Vec<String> },
+}
@@ -0,0 +1,70 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::task::Frequency;
+
+/// Completions in the day, week (starting Monday) or month starting on `period`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct PeriodCount {
+    pub period: NaiveDate,
+    pub completed: i64,
+}
+
+/// Runs of consecutive days with at least one completion. The current streak
+/// survives until a whole day passes without one.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Streaks {
+    pub current: i64,
+    pub longest: i64,
+}
+
+/// Completions of tasks with a due date, done by the end of it or later.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Punctuality {
+    pub on_time: i64,
+    pub late: i64,
+    pub rate: Option<f64>,
+}
+
+/// On-time completions out of every occurrence, missed ones included.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Adherence {
+    pub completed: i64,
+    pub on_time: i64,
+    pub missed: i64,
+    pub adherence: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct TaskAdherence {
+    pub task_id: i64,
+    pub task: String,
+    pub repeat_frequency: Option<Frequency>,
+    #[cfg_attr(feature = "sqlx", sqlx(flatten))]
+    #[serde(flatten)]
+    pub adherence: Adherence,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecurringStats {
+    #[serde(flatten)]
+    pub total: Adherence,
+    pub tasks: Vec<TaskAdherence>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Stats {
+    pub timezone: String,
+    pub days: Vec<PeriodCount>,
+    pub weeks: Vec<PeriodCount>,
+    pub months: Vec<PeriodCount>,
+    pub streaks: Streaks,
+    pub punctuality: Punctuality,
+    pub recurring: RecurringStats,
+}
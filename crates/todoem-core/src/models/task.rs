@@ -0,0 +1,37 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "frequency", rename_all = "lowercase")
+)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Task {
+    pub id: i64,
+    pub user_id: uuid::Uuid,
+    pub task: String,
+    pub description: String,
+    pub done: bool,
+    pub due_date: Option<DateTime<Utc>>,
+    /// All-day due date, in the owner's time zone. Never set together with `due_date`.
+    pub due_on: Option<NaiveDate>,
+    pub repeat_frequency: Option<Frequency>,
+    /// The due date as last set by the owner, which recurrences are counted
+    /// from. Completions move `due_date`/`due_on` on and leave these alone.
+    #[serde(skip)]
+    pub repeat_anchor_date: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub repeat_anchor_on: Option<NaiveDate>,
+    pub done_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
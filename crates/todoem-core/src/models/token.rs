@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What a personal access token may do. Cookie sessions may do everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum Scope {
+    #[serde(rename = "read:tasks")]
+    ReadTasks,
+    #[serde(rename = "write:tasks")]
+    WriteTasks,
+    #[serde(rename = "read:profile")]
+    ReadProfile,
+    #[serde(rename = "write:profile")]
+    WriteProfile,
+    #[serde(rename = "connections")]
+    Connections,
+    #[serde(rename = "webhooks")]
+    Webhooks,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ReadTasks => "read:tasks",
+            Scope::WriteTasks => "write:tasks",
+            Scope::ReadProfile => "read:profile",
+            Scope::WriteProfile => "write:profile",
+            Scope::Connections => "connections",
+            Scope::Webhooks => "webhooks",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A newly created token, the only time its value is revealed.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewApiToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub value: String,
+}
+
+/// The owner and grants of a valid token.
+#[derive(Debug)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct TokenGrant {
+    pub id: i64,
+    pub user_id: uuid::Uuid,
+    pub email: String,
+    pub scopes: Vec<String>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
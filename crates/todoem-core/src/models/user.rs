@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct User {
+    pub id: uuid::Uuid,
+    pub name: Option<String>,
+    pub username: String,
+}
+
+/// Who can find a user through `/user/search`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "search_visibility", rename_all = "snake_case")
+)]
+pub enum SearchVisibility {
+    Everyone,
+    /// Only an exact username match finds the user.
+    Username,
+    Nobody,
+}
+
+/// Who can send a user a connection request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "request_policy", rename_all = "snake_case")
+)]
+pub enum RequestPolicy {
+    Everyone,
+    FriendsOfFriends,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Privacy {
+    pub search_visibility: SearchVisibility,
+    pub request_policy: RequestPolicy,
+}
+
+/// Relationship between two users. A pair without a row in `connections` has
+/// none. A blocked pair stays blocked while either user blocks the other; who
+/// blocks whom is kept, per direction, in `user_blocks`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "connection_state", rename_all = "lowercase")
+)]
+pub enum ConnectionState {
+    Pending,
+    Connected,
+    Rejected,
+    Blocked,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Connection {
+    pub state: ConnectionState,
+    /// Whoever made the last transition.
+    pub actor_id: uuid::Uuid,
+}
+
+/// The signed-in user's own view of their account.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Profile {
+    pub id: uuid::Uuid,
+    pub username: String,
+    pub name: Option<String>,
+    pub email: String,
+    pub bio: Option<String>,
+    pub timezone: String,
+    pub locale: String,
+    pub has_avatar: bool,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub deletion_requested_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum WebhookEvent {
+    #[serde(rename = "task.created")]
+    TaskCreated,
+    #[serde(rename = "task.updated")]
+    TaskUpdated,
+    #[serde(rename = "task.completed")]
+    TaskCompleted,
+    #[serde(rename = "task.deleted")]
+    TaskDeleted,
+    #[serde(rename = "connection.requested")]
+    ConnectionRequested,
+    #[serde(rename = "connection.accepted")]
+    ConnectionAccepted,
+    #[serde(rename = "list.received")]
+    ListReceived,
+    /// Only sent on demand, to check that an endpoint is reachable.
+    #[serde(rename = "ping")]
+    Ping,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::TaskCreated => "task.created",
+            WebhookEvent::TaskUpdated => "task.updated",
+            WebhookEvent::TaskCompleted => "task.completed",
+            WebhookEvent::TaskDeleted => "task.deleted",
+            WebhookEvent::ConnectionRequested => "connection.requested",
+            WebhookEvent::ConnectionAccepted => "connection.accepted",
+            WebhookEvent::ListReceived => "list.received",
+            WebhookEvent::Ping => "ping",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Webhook {
+    pub id: i64,
+    pub user_id: uuid::Uuid,
+    pub url: String,
+    pub description: String,
+    pub events: Vec<String>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A webhook together with its signing secret, which is only revealed when the
+/// webhook is created or the secret rotated.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookWithSecret {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "delivery_status", rename_all = "lowercase")
+)]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Delivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event: String,
+    #[schema(value_type = Object)]
+    #[cfg_attr(feature = "sqlx", sqlx(json))]
+    pub payload: serde_json::Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub replay_of: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// A delivery claimed by the dispatcher, with what it needs to send it.
+#[derive(Debug)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct DueDelivery {
+    pub id: i64,
+    pub event: String,
+    #[cfg_attr(feature = "sqlx", sqlx(json))]
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::validation::{Limits, Validate, Validator};
+
+const DEVICE_NAME_MAX_LEN: usize = 100;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    /// Username or email address.
+    pub login: String,
+    pub password: String,
+    /// Shown in the session list; guessed from the user agent when absent.
+    pub device_name: Option<String>,
+}
+
+impl Validate for LoginRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        v.field("login", self.login.as_str())
+            .not_blank()
+            .max_len(255);
+        v.field("password", self.password.as_str())
+            .max_len(limits.password_max_len);
+        validate_device_name(v, &self.device_name);
+    }
+}
+
+fn validate_device_name(v: &mut Validator, device_name: &Option<String>) {
+    if let Some(name) = device_name {
+        v.field("device_name", name.as_str())
+            .not_blank()
+            .max_len(DEVICE_NAME_MAX_LEN);
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+impl Validate for ForgotPasswordRequest {
+    fn validate(&self, v: &mut Validator, _limits: &Limits) {
+        v.field("email", self.email.as_str())
+            .not_blank()
+            .max_len(255);
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+}
+
+impl Validate for ResetPasswordRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        v.field("token", self.token.as_str())
+            .not_blank()
+            .max_len(128);
+        validate_password(v, limits, &self.password);
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+impl Validate for VerifyEmailRequest {
+    fn validate(&self, v: &mut Validator, _limits: &Limits) {
+        v.field("token", self.token.as_str())
+            .not_blank()
+            .max_len(128);
+    }
+}
+
+pub fn validate_password(v: &mut Validator, limits: &Limits, password: &str) {
+    v.field("password", password)
+        .min_len(limits.password_min_len)
+        .max_len(limits.password_max_len);
+}
+
+/// Answer to a password check when a second factor is still needed.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Challenge {
+    /// Step-up token to send back with the code; also set as a cookie.
+    pub challenge: String,
+    pub expires_in: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SecondFactorRequest {
+    /// Taken from the cookie when absent.
+    pub challenge: Option<String>,
+    /// An authenticator code or a recovery code.
+    pub code: String,
+    pub device_name: Option<String>,
+}
+
+impl Validate for SecondFactorRequest {
+    fn validate(&self, v: &mut Validator, _limits: &Limits) {
+        v.field("code", self.code.as_str()).not_blank().max_len(32);
+        validate_device_name(v, &self.device_name);
+    }
+}
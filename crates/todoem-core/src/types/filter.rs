@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::filter::{DayRange, DayRef, Filter};
+use crate::validation::{Limits, Validate, Validator};
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SaveFilterRequest {
+    pub name: String,
+    pub filter: Filter,
+}
+
+impl Validate for SaveFilterRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        v.field("name", self.name.as_str())
+            .not_blank()
+            .max_len(limits.filter_name_max_len);
+
+        let mut nodes = 0;
+        let mut texts_ok = true;
+        let mut days_ok = true;
+        self.filter.walk(&mut |node| {
+            nodes += 1;
+            match node {
+                Filter::Text(text) => {
+                    texts_ok &=
+                        !text.trim().is_empty() && text.chars().count() <= limits.search_max_len;
+                }
+                Filter::Due(DayRange { from, to }) | Filter::Created(DayRange { from, to }) => {
+                    days_ok &= [from, to].into_iter().flatten().all(|day| match day {
+                        DayRef::Relative(n) => (*n as i64).abs() <= limits.due_date_max_future_days,
+                        DayRef::Date(_) => true,
+                    });
+                }
+                _ => {}
+            }
+        });
+
+        v.field("filter", &self.filter)
+            .check(
+                |_| nodes <= limits.filter_max_nodes,
+                &format!("must have at most {} conditions", limits.filter_max_nodes),
+            )
+            .check(
+                |f| f.depth() <= limits.filter_max_depth,
+                &format!(
+                    "must be nested at most {} levels deep",
+                    limits.filter_max_depth
+                ),
+            )
+            .check(
+                |_| texts_ok,
+                &format!(
+                    "text conditions must be 1 to {} characters",
+                    limits.search_max_len
+                ),
+            )
+            .check(
+                |_| days_ok,
+                &format!(
+                    "day offsets must be within {} days",
+                    limits.due_date_max_future_days
+                ),
+            );
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::list::{List, ListTask};
+use crate::validation::{Limits, Validate, Validator};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateListRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// The tasks of the list, in order.
+    #[serde(default)]
+    pub tasks: Vec<String>,
+}
+
+impl Validate for CreateListRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        v.field("name", self.name.as_str())
+            .not_blank()
+            .max_len(limits.list_name_max_len);
+        if let Some(description) = &self.description {
+            v.field("description", description.as_str())
+                .max_len(limits.description_max_len);
+        }
+        v.field("tasks", &self.tasks)
+            .check(
+                |t| t.len() <= limits.list_max_tasks,
+                &format!("must have at most {} tasks", limits.list_max_tasks),
+            )
+            .check(
+                |t| t.iter().all(|task| !task.trim().is_empty()),
+                "must not contain blank tasks",
+            )
+            .check(
+                |t| {
+                    t.iter()
+                        .all(|task| task.chars().count() <= limits.task_max_len)
+                },
+                &format!(
+                    "must have tasks of at most {} characters",
+                    limits.task_max_len
+                ),
+            );
+    }
+}
+
+/// A list with its tasks.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ListView {
+    pub list: List,
+    pub tasks: Vec<ListTask>,
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::validation::{Limits, Validate, Validator};
+
+/// Names that would be confusing or collide with routes.
+const RESERVED_USERNAMES: &[&str] = &[
+    "admin",
+    "api",
+    "me",
+    "root",
+    "support",
+    "system",
+    "todoem",
+    "null",
+    "undefined",
+];
+
+/// Every field is optional; an empty `name` or `bio` clears it.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct UpdateProfileRequest {
+    pub name: Option<String>,
+    pub username: Option<String>,
+    pub bio: Option<String>,
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
+}
+
+fn is_username(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_lowercase())
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+pub fn validate_username(v: &mut Validator, limits: &Limits, username: &str) {
+    v.field("username", username)
+        .min_len(limits.username_min_len)
+        .max_len(limits.username_max_len)
+        .check(
+            is_username,
+            "must start with a lowercase letter and contain only a-z, 0-9 and _",
+        )
+        .check(|u| !RESERVED_USERNAMES.contains(&u), "is reserved");
+}
+
+/// A BCP 47 language tag such as `en` or `pt-BR`.
+fn is_locale(s: &str) -> bool {
+    let mut parts = s.split('-');
+    let lang = parts.next().unwrap_or_default();
+    (2..=3).contains(&lang.len())
+        && lang.chars().all(|c| c.is_ascii_lowercase())
+        && parts.all(|p| (2..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+impl Validate for UpdateProfileRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        if let Some(name) = &self.name {
+            v.field("name", name.as_str()).max_len(limits.name_max_len);
+        }
+        if let Some(username) = &self.username {
+            validate_username(v, limits, username);
+        }
+        if let Some(bio) = &self.bio {
+            v.field("bio", bio.as_str()).max_len(limits.bio_max_len);
+        }
+        if let Some(timezone) = &self.timezone {
+            v.field("timezone", timezone.as_str()).timezone();
+        }
+        if let Some(locale) = &self.locale {
+            v.field("locale", locale.as_str())
+                .check(is_locale, "must be a language tag such as en or pt-BR");
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeletionScheduled {
+    pub deletion_requested_at: chrono::DateTime<chrono::Utc>,
+    pub purge_after: chrono::DateTime<chrono::Utc>,
+}
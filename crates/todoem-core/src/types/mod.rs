@@ -0,0 +1,40 @@
+//! Request and response bodies, in the shape of the latest API version.
+//! When a version changes a body, the shape older versions keep moves to a
+//! `v1` module beside the current one, converted with `From`, and the handler
+//! picks one by the API version it is called under.
+
+pub mod auth;
+pub mod filter;
+pub mod list;
+pub mod me;
+pub mod oidc;
+pub mod task;
+pub mod token;
+pub mod two_factor;
+pub mod user;
+pub mod webhook;
+
+use utoipa::{IntoParams, ToSchema};
+
+use crate::validation::{Limits, Validate, Validator};
+
+/// `?p=` of paged listings, starting at 1.
+#[derive(serde::Serialize, serde::Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    pub p: Option<u16>,
+}
+
+impl Validate for PageParams {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        if let Some(p) = &self.p {
+            v.field("p", p).range(1, limits.search_max_page);
+        }
+    }
+}
+
+/// The body of responses that only say what was done.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct SuccessResponse {
+    pub msg: String,
+}
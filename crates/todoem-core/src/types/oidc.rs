@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::types::me::validate_username;
+use crate::validation::{Limits, Validate, Validator};
+
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LoginParams {
+    /// Path to send the browser back to afterwards.
+    pub redirect_to: Option<String>,
+}
+
+/// Only paths on this site, so that sign-in cannot be used to redirect elsewhere.
+fn is_local_path(s: &str) -> bool {
+    s.starts_with('/') && !s.starts_with("//") && !s.contains('\\')
+}
+
+impl Validate for LoginParams {
+    fn validate(&self, v: &mut Validator, _limits: &Limits) {
+        if let Some(redirect_to) = &self.redirect_to {
+            v.field("redirect_to", redirect_to.as_str())
+                .max_len(2048)
+                .check(is_local_path, "must be a path on this site");
+        }
+    }
+}
+
+impl LoginParams {
+    pub fn redirect_to(&self) -> &str {
+        self.redirect_to.as_deref().unwrap_or("/")
+    }
+}
+
+/// What a provider sends the browser back with.
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Validate for CallbackParams {
+    fn validate(&self, _v: &mut Validator, _limits: &Limits) {}
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SignupRequest {
+    pub username: String,
+}
+
+impl Validate for SignupRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        validate_username(v, limits, &self.username);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Authorization {
+    pub authorization_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct LinkRequest {
+    pub provider: String,
+    pub redirect_to: Option<String>,
+}
+
+impl Validate for LinkRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        LoginParams {
+            redirect_to: self.redirect_to.clone(),
+        }
+        .validate(v, limits);
+    }
+}
@@ -0,0 +1,148 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::task::{Frequency, Task};
+use crate::validation::{Limits, Validate, Validator};
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct CreateTaskRequest {
+    pub task: String,
+    pub description: String,
+    pub due_date: Option<DateTime<Utc>>,
+    /// All-day due date in the user's time zone, instead of `due_date`.
+    pub due_on: Option<NaiveDate>,
+    pub repeat_frequency: Option<Frequency>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct UpdateTaskRequest {
+    pub task: String,
+    pub description: String,
+    pub due_date: Option<DateTime<Utc>>,
+    pub due_on: Option<NaiveDate>,
+    pub repeat_frequency: Option<Frequency>,
+}
+
+fn validate_task(
+    v: &mut Validator,
+    limits: &Limits,
+    task: &str,
+    description: &str,
+    due_date: &Option<DateTime<Utc>>,
+    due_on: &Option<NaiveDate>,
+) {
+    v.field("task", task)
+        .not_blank()
+        .max_len(limits.task_max_len);
+    v.field("description", description)
+        .max_len(limits.description_max_len);
+    if let Some(due_date) = due_date {
+        v.field("due_date", due_date).within_days(
+            limits.due_date_max_past_days,
+            limits.due_date_max_future_days,
+        );
+    }
+    if let Some(due_on) = due_on {
+        v.field("due_on", due_on)
+            .check(|_| due_date.is_none(), "cannot be combined with due_date")
+            .within_days(
+                limits.due_date_max_past_days,
+                limits.due_date_max_future_days,
+            );
+    }
+}
+
+impl Validate for CreateTaskRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        validate_task(
+            v,
+            limits,
+            &self.task,
+            &self.description,
+            &self.due_date,
+            &self.due_on,
+        );
+    }
+}
+
+impl Validate for UpdateTaskRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        validate_task(
+            v,
+            limits,
+            &self.task,
+            &self.description,
+            &self.due_date,
+            &self.due_on,
+        );
+    }
+}
+
+/// Days in the user's time zone, both inclusive.
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DueRangeParams {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Validate for DueRangeParams {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        v.field("to", &self.to)
+            .check(|to| *to >= self.from, "must not be before from")
+            .check(
+                |to| (*to - self.from).num_days() < limits.due_range_max_days,
+                &format!(
+                    "must be less than {} days after from",
+                    limits.due_range_max_days
+                ),
+            );
+    }
+}
+
+/// One occurrence of a task; recurring tasks show up once per occurrence.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TaskOccurrence {
+    /// The day it is due on in the user's time zone.
+    pub date: NaiveDate,
+    /// When it is due, unless it is an all-day task.
+    pub at: Option<DateTime<Utc>>,
+    pub task: Task,
+}
+
+/// A view with a badge count. `count` may exceed `tasks.len()` when the list is capped.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TaskView<T> {
+    pub count: i64,
+    pub tasks: Vec<T>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TodayView {
+    pub date: NaiveDate,
+    pub count: i64,
+    pub tasks: Vec<TaskOccurrence>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct UpcomingDay {
+    pub date: NaiveDate,
+    pub count: i64,
+    pub tasks: Vec<TaskOccurrence>,
+}
+
+/// The days after today, each listed even when nothing is due.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct UpcomingView {
+    pub count: i64,
+    pub days: Vec<UpcomingDay>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ViewCounts {
+    pub today: i64,
+    pub overdue: i64,
+    pub upcoming: i64,
+    pub no_due_date: i64,
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::token::Scope;
+use crate::validation::{Limits, Validate, Validator};
+
+const NAME_MAX_LEN: usize = 100;
+const EXPIRES_IN_MAX_DAYS: u16 = 366;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Never expires when absent.
+    pub expires_in_days: Option<u16>,
+}
+
+impl Validate for CreateTokenRequest {
+    fn validate(&self, v: &mut Validator, _limits: &Limits) {
+        v.field("name", self.name.as_str())
+            .not_blank()
+            .max_len(NAME_MAX_LEN);
+        v.field("scopes", &self.scopes)
+            .check(|s| !s.is_empty(), "must not be empty");
+        if let Some(days) = &self.expires_in_days {
+            v.field("expires_in_days", days)
+                .range(1, EXPIRES_IN_MAX_DAYS);
+        }
+    }
+}
+
+/// Scope names as stored, without duplicates.
+pub fn scope_names(scopes: &[Scope]) -> Vec<&'static str> {
+    let mut names: Vec<_> = scopes.iter().map(Scope::as_str).collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
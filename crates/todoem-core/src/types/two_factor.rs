@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::validation::{Limits, Validate, Validator};
+
+/// An authenticator code, or a recovery code where one is accepted.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CodeRequest {
+    pub code: String,
+}
+
+impl Validate for CodeRequest {
+    fn validate(&self, v: &mut Validator, _limits: &Limits) {
+        v.field("code", self.code.as_str()).not_blank().max_len(32);
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Enrollment {
+    /// Base32, for typing into an authenticator by hand.
+    pub secret: String,
+    pub provisioning_uri: String,
+    pub qr_svg: String,
+}
+
+/// Shown once: only their hashes are kept.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
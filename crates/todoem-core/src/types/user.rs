@@ -0,0 +1,58 @@
+use crate::models::user as M;
+use crate::validation::{Limits, Validate, Validator};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchParams {
+    pub q: String,
+    pub p: Option<u16>,
+}
+
+impl Validate for SearchParams {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        v.field("q", self.q.as_str())
+            .not_blank()
+            .min_len(limits.search_min_len)
+            .max_len(limits.search_max_len);
+        if let Some(p) = &self.p {
+            v.field("p", p).range(1, limits.search_max_page);
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdatePrivacyRequest {
+    pub search_visibility: M::SearchVisibility,
+    pub request_policy: M::RequestPolicy,
+}
+
+// both fields are closed enums, deserialization already rejects anything else
+impl Validate for UpdatePrivacyRequest {
+    fn validate(&self, _v: &mut Validator, _limits: &Limits) {}
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ViewUser {
+    pub id: uuid::Uuid,
+    pub username: String,
+    pub name: Option<String>,
+    pub connected: bool,
+    pub sent_connection: bool,
+    pub received_connection: bool,
+}
+
+impl ViewUser {
+    pub fn from(user: M::User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            name: user.name,
+            connected: false,
+            sent_connection: false,
+            received_connection: false,
+        }
+    }
+}
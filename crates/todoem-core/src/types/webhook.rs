@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::webhook::WebhookEvent;
+use crate::validation::{Limits, Validate, Validator};
+
+const URL_MAX_LEN: usize = 2048;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    #[serde(default)]
+    pub description: String,
+    pub events: Vec<WebhookEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct UpdateWebhookRequest {
+    pub url: String,
+    #[serde(default)]
+    pub description: String,
+    pub events: Vec<WebhookEvent>,
+    pub active: bool,
+}
+
+fn validate_webhook(
+    v: &mut Validator,
+    limits: &Limits,
+    url: &str,
+    description: &str,
+    events: &[WebhookEvent],
+) {
+    // any host is taken here; where it resolves to is checked on every
+    // delivery, as that can change after the webhook is registered
+    v.field("url", url).max_len(URL_MAX_LEN).http_url();
+    v.field("description", description)
+        .max_len(limits.description_max_len);
+    v.field("events", events)
+        .check(|e| !e.is_empty(), "must not be empty")
+        .check(
+            |e| !e.contains(&WebhookEvent::Ping),
+            "ping is only sent on demand",
+        );
+}
+
+impl Validate for CreateWebhookRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        validate_webhook(v, limits, &self.url, &self.description, &self.events);
+    }
+}
+
+impl Validate for UpdateWebhookRequest {
+    fn validate(&self, v: &mut Validator, limits: &Limits) {
+        validate_webhook(v, limits, &self.url, &self.description, &self.events);
+    }
+}
+
+/// Event names as stored, without duplicates.
+pub fn event_names(events: &[WebhookEvent]) -> Vec<&'static str> {
+    let mut names: Vec<_> = events.iter().map(WebhookEvent::as_str).collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
@@ -0,0 +1,327 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::errors::APIError;
+
+/// Upper and lower bounds enforced on request payloads.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    pub task_max_len: usize,
+    pub description_max_len: usize,
+    pub list_name_max_len: usize,
+    pub list_max_tasks: usize,
+    pub name_max_len: usize,
+    pub bio_max_len: usize,
+    pub username_min_len: usize,
+    pub username_max_len: usize,
+    pub search_min_len: usize,
+    pub search_max_len: usize,
+    pub search_max_page: u16,
+    pub due_date_max_past_days: i64,
+    pub due_date_max_future_days: i64,
+    pub due_range_max_days: i64,
+    pub filter_name_max_len: usize,
+    pub filter_max_nodes: usize,
+    pub filter_max_depth: usize,
+    pub password_min_len: usize,
+    pub password_max_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            task_max_len: 255,
+            description_max_len: 10_000,
+            list_name_max_len: 255,
+            list_max_tasks: 100,
+            name_max_len: 255,
+            bio_max_len: 500,
+            username_min_len: 3,
+            username_max_len: 30,
+            search_min_len: 2,
+            search_max_len: 64,
+            search_max_page: 100,
+            due_date_max_past_days: 365,
+            due_date_max_future_days: 365 * 10,
+            due_range_max_days: 366,
+            filter_name_max_len: 100,
+            filter_max_nodes: 50,
+            filter_max_depth: 8,
+            password_min_len: 10,
+            password_max_len: 256,
+        }
+    }
+}
+
+/// Implemented by request types, which the server checks as it extracts
+/// them.
+pub trait Validate {
+    fn validate(&self, v: &mut Validator, limits: &Limits);
+}
+
+/// Collects every rule violation of a request, keyed by field name.
+#[derive(Default)]
+pub struct Validator {
+    errors: BTreeMap<&'static str, Vec<String>>,
+}
+
+impl Validator {
+    pub fn field<'v, T: ?Sized>(&'v mut self, name: &'static str, value: &'v T) -> Field<'v, T> {
+        Field {
+            name,
+            value,
+            validator: self,
+        }
+    }
+
+    fn push(&mut self, field: &'static str, msg: String) {
+        self.errors.entry(field).or_default().push(msg);
+    }
+
+    pub fn finish(self) -> Result<(), APIError> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+
+        Err(APIError::unprocessable("Validation failed")
+            .with_details(serde_json::json!({ "fields": self.errors })))
+    }
+}
+
+pub struct Field<'v, T: ?Sized> {
+    name: &'static str,
+    value: &'v T,
+    validator: &'v mut Validator,
+}
+
+impl<T: ?Sized> Field<'_, T> {
+    fn fail(&mut self, msg: String) {
+        self.validator.push(self.name, msg);
+    }
+
+    /// Fails with `msg` unless `rule` holds for the value.
+    pub fn check(mut self, rule: impl FnOnce(&T) -> bool, msg: &str) -> Self {
+        if !rule(self.value) {
+            self.fail(msg.to_string());
+        }
+        self
+    }
+}
+
+impl Field<'_, str> {
+    pub fn not_blank(mut self) -> Self {
+        if self.value.trim().is_empty() {
+            self.fail("must not be blank".to_string());
+        }
+        self
+    }
+
+    pub fn min_len(mut self, min: usize) -> Self {
+        if self.value.chars().count() < min {
+            self.fail(format!("must be at least {} characters", min));
+        }
+        self
+    }
+
+    pub fn max_len(mut self, max: usize) -> Self {
+        if self.value.chars().count() > max {
+            self.fail(format!("must be at most {} characters", max));
+        }
+        self
+    }
+
+    /// An absolute `http` or `https` URL.
+    pub fn http_url(mut self) -> Self {
+        match url::Url::parse(self.value) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => {}
+            _ => self.fail("must be an http or https URL".to_string()),
+        }
+        self
+    }
+
+    /// An IANA time zone name such as `Europe/Berlin`.
+    pub fn timezone(mut self) -> Self {
+        if self.value.parse::<chrono_tz::Tz>().is_err() {
+            self.fail("must be an IANA time zone such as Europe/Berlin".to_string());
+        }
+        self
+    }
+}
+
+impl Field<'_, DateTime<Utc>> {
+    /// Rejects dates more than `past_days` before or `future_days` after now.
+    pub fn within_days(mut self, past_days: i64, future_days: i64) -> Self {
+        let now = Utc::now();
+        if *self.value < now - Duration::days(past_days) {
+            self.fail(format!(
+                "must not be more than {} days in the past",
+                past_days
+            ));
+        } else if *self.value > now + Duration::days(future_days) {
+            self.fail(format!(
+                "must not be more than {} days in the future",
+                future_days
+            ));
+        }
+        self
+    }
+}
+
+impl Field<'_, NaiveDate> {
+    /// Like the timestamp version, measured in whole days from today (UTC).
+    pub fn within_days(mut self, past_days: i64, future_days: i64) -> Self {
+        let today = Utc::now().date_naive();
+        if *self.value < today - Duration::days(past_days) {
+            self.fail(format!(
+                "must not be more than {} days in the past",
+                past_days
+            ));
+        } else if *self.value > today + Duration::days(future_days) {
+            self.fail(format!(
+                "must not be more than {} days in the future",
+                future_days
+            ));
+        }
+        self
+    }
+}
+
+impl Field<'_, u16> {
+    pub fn range(mut self, min: u16, max: u16) -> Self {
+        if *self.value < min || *self.value > max {
+            self.fail(format!("must be between {} and {}", min, max));
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The violations `rules` record on a fresh validator, by field.
+    fn errors(rules: impl FnOnce(&mut Validator)) -> BTreeMap<&'static str, Vec<String>> {
+        let mut v = Validator::default();
+        rules(&mut v);
+        v.errors
+    }
+
+    #[test]
+    fn string_rules() {
+        assert!(errors(|v| {
+            v.field("s", "abc").not_blank().min_len(3).max_len(3);
+        })
+        .is_empty());
+
+        let e = errors(|v| {
+            v.field("s", "  ").not_blank();
+        });
+        assert_eq!(e["s"], ["must not be blank"]);
+
+        // lengths count characters, not bytes
+        let e = errors(|v| {
+            v.field("s", "äöü").min_len(4).max_len(2);
+        });
+        assert_eq!(
+            e["s"],
+            [
+                "must be at least 4 characters",
+                "must be at most 2 characters"
+            ]
+        );
+    }
+
+    #[test]
+    fn http_url_needs_a_web_scheme_and_a_host() {
+        for ok in ["https://example.com/hook", "http://localhost:8080"] {
+            assert!(errors(|v| {
+                v.field("url", ok).http_url();
+            })
+            .is_empty());
+        }
+        for bad in [
+            "ftp://example.com",
+            "example.com",
+            "https://",
+            "file:///etc",
+        ] {
+            let e = errors(|v| {
+                v.field("url", bad).http_url();
+            });
+            assert_eq!(e["url"], ["must be an http or https URL"], "{}", bad);
+        }
+    }
+
+    #[test]
+    fn timezone_is_an_iana_name() {
+        assert!(errors(|v| {
+            v.field("tz", "Europe/Berlin").timezone();
+        })
+        .is_empty());
+        let e = errors(|v| {
+            v.field("tz", "Mars/Olympus").timezone();
+        });
+        assert_eq!(e["tz"].len(), 1);
+    }
+
+    #[test]
+    fn within_days_bounds_both_ways() {
+        let now = Utc::now();
+        let check = |at: DateTime<Utc>| {
+            errors(|v| {
+                v.field("due", &at).within_days(1, 2);
+            })
+        };
+        assert!(check(now).is_empty());
+        assert_eq!(
+            check(now - Duration::days(2))["due"],
+            ["must not be more than 1 days in the past"]
+        );
+        assert_eq!(
+            check(now + Duration::days(3))["due"],
+            ["must not be more than 2 days in the future"]
+        );
+
+        let today = now.date_naive();
+        let check = |on: NaiveDate| {
+            errors(|v| {
+                v.field("due", &on).within_days(1, 2);
+            })
+        };
+        assert!(check(today - Duration::days(1)).is_empty());
+        assert!(check(today + Duration::days(2)).is_empty());
+        assert_eq!(check(today - Duration::days(2))["due"].len(), 1);
+        assert_eq!(check(today + Duration::days(3))["due"].len(), 1);
+    }
+
+    #[test]
+    fn range_and_check() {
+        let e = errors(|v| {
+            v.field("n", &5u16).range(1, 5);
+            v.field("m", &0u16).range(1, 5);
+            v.field("list", &Vec::<u8>::new())
+                .check(|l| !l.is_empty(), "must not be empty");
+        });
+        assert!(!e.contains_key("n"));
+        assert_eq!(e["m"], ["must be between 1 and 5"]);
+        assert_eq!(e["list"], ["must not be empty"]);
+    }
+
+    #[test]
+    fn every_violation_is_reported() {
+        let mut v = Validator::default();
+        v.field("name", "").not_blank().min_len(1);
+        v.field("url", "nope").http_url();
+        v.field("tz", "nowhere").timezone();
+        assert_eq!(v.errors["name"].len(), 2);
+
+        let e = v.finish().unwrap_err();
+        assert_eq!(e.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let fields = &e.details().unwrap()["fields"];
+        assert_eq!(fields.as_object().unwrap().len(), 3);
+        assert!(Validator::default().finish().is_ok());
+    }
+}
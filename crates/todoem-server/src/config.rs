@@ -0,0 +1,1462 @@
+use crate::db;
+use crate::services::jwt_keys::{JwtKeys, SharedJwtKeys};
+use axum_extra::extract::cookie::SameSite;
+use chrono::NaiveDate;
+use clap::{Parser, ValueEnum};
+use http::HeaderValue;
+use jsonwebtoken::Algorithm;
+use serde::Deserialize;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::{env, fmt, fs, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use tracing::{info, instrument};
+use tracing_subscriber::EnvFilter;
+
+pub use todoem_core::validation::Limits;
+
+/// Why the server refused to start.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file is missing, unreadable or not valid for [`Settings`].
+    File {
+        path: PathBuf,
+        reason: String,
+    },
+    /// An environment variable does not parse as its setting.
+    Env {
+        key: String,
+        value: String,
+    },
+    /// Settings that parse but cannot work, one message each.
+    Invalid(Vec<String>),
+    Database(sqlx::Error),
+    Migrations(sqlx::migrate::MigrateError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::File { path, reason } => {
+                write!(f, "cannot load {}: {}", path.display(), reason)
+            }
+            Self::Env { key, value } => write!(f, "{} is not a valid value: {:?}", key, value),
+            Self::Invalid(problems) => {
+                write!(f, "invalid configuration:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+            Self::Database(e) => write!(f, "cannot connect to the database: {}", e),
+            Self::Migrations(e) => write!(f, "cannot run migrations: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Database(e) => Some(e),
+            Self::Migrations(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Command line flags, which override the file and the environment.
+#[derive(Debug, Default, Parser)]
+#[command(name = "todoem-server", version, about = "The todoem API server")]
+pub struct Cli {
+    /// TOML file to read settings from
+    #[arg(short, long, env = "TODOEM_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Address to listen on [default: 0.0.0.0:8080]
+    #[arg(long)]
+    pub bind: Option<SocketAddr>,
+    /// Log output [default: text]
+    #[arg(long, value_enum)]
+    pub log_format: Option<LogFormat>,
+    /// Log filter, like `info,sqlx=warn` [default: info]
+    #[arg(long)]
+    pub log_level: Option<String>,
+    /// Check the configuration and exit without connecting to anything
+    #[arg(long)]
+    pub check_config: bool,
+}
+
+/// Everything that can be configured, read from the defaults, then the TOML
+/// file, then the environment, then the command line. See
+/// `config.example.toml` for the file layout.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub server: Server,
+    pub database: Database,
+    pub cors: Cors,
+    pub cookies: Cookies,
+    pub jwt: Jwt,
+    pub log: Log,
+    pub telemetry: Telemetry,
+    pub api: Api,
+    pub limits: Limits,
+    pub rate_limits: RateLimits,
+    pub accounts: Accounts,
+    pub webhooks: Webhooks,
+    pub oidc: Oidc,
+    pub mail: Mail,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub pool: PgPool,
+    pub server: Server,
+    pub cors: Cors,
+    pub cookies: Cookies,
+    pub jwt_keys: SharedJwtKeys,
+    pub telemetry: Telemetry,
+    pub api: Api,
+    pub limits: Limits,
+    pub rate_limits: RateLimits,
+    pub accounts: Accounts,
+    pub webhooks: Webhooks,
+    pub oidc: Oidc,
+    pub mail: Mail,
+}
+
+/// The HTTP listener.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Server {
+    pub bind: SocketAddr,
+    /// Requests still running after this long are answered with a 408.
+    pub request_timeout_secs: u64,
+    /// How long a shutdown waits for requests and background jobs to finish
+    /// before closing the database pool regardless.
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            request_timeout_secs: 30,
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+impl Server {
+    /// Reads `BIND_ADDR`, `REQUEST_TIMEOUT_SECS` and `SHUTDOWN_TIMEOUT_SECS`
+    /// from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            bind: env_or("BIND_ADDR", d.bind)?,
+            request_timeout_secs: env_or("REQUEST_TIMEOUT_SECS", d.request_timeout_secs)?,
+            shutdown_timeout_secs: env_or("SHUTDOWN_TIMEOUT_SECS", d.shutdown_timeout_secs)?,
+        })
+    }
+}
+
+/// The Postgres connection pool.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Database {
+    pub url: String,
+    pub max_connections: u32,
+    /// Connections kept open even when idle.
+    pub min_connections: u32,
+    /// How long a request waits for a free connection before failing.
+    pub acquire_timeout_secs: u64,
+    /// Idle connections above `min_connections` are closed after this long;
+    /// 0 keeps them.
+    pub idle_timeout_secs: u64,
+    /// Connections are replaced after this long; 0 keeps them.
+    pub max_lifetime_secs: u64,
+}
+
+impl fmt::Debug for Database {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Database")
+            .field("max_connections", &self.max_connections)
+            .field("min_connections", &self.min_connections)
+            .field("acquire_timeout_secs", &self.acquire_timeout_secs)
+            .field("idle_timeout_secs", &self.idle_timeout_secs)
+            .field("max_lifetime_secs", &self.max_lifetime_secs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 10 * 60,
+            max_lifetime_secs: 30 * 60,
+        }
+    }
+}
+
+impl Database {
+    /// Reads `DATABASE_URL` and `DATABASE_*` overrides from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            url: env_or("DATABASE_URL", d.url)?,
+            max_connections: env_or("DATABASE_MAX_CONNECTIONS", d.max_connections)?,
+            min_connections: env_or("DATABASE_MIN_CONNECTIONS", d.min_connections)?,
+            acquire_timeout_secs: env_or("DATABASE_ACQUIRE_TIMEOUT_SECS", d.acquire_timeout_secs)?,
+            idle_timeout_secs: env_or("DATABASE_IDLE_TIMEOUT_SECS", d.idle_timeout_secs)?,
+            max_lifetime_secs: env_or("DATABASE_MAX_LIFETIME_SECS", d.max_lifetime_secs)?,
+        })
+    }
+
+    fn pool_options(&self) -> PgPoolOptions {
+        let secs = |secs| (secs > 0).then(|| Duration::from_secs(secs));
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_secs))
+            .idle_timeout(secs(self.idle_timeout_secs))
+            .max_lifetime(secs(self.max_lifetime_secs))
+    }
+}
+
+/// Which browser origins may call the API.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cors {
+    /// Origins like `https://app.example.com`, or `*` for any.
+    pub allowed_origins: Vec<String>,
+    /// Let those origins send the session cookie along.
+    pub allow_credentials: bool,
+    pub max_age_secs: u64,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+            allow_credentials: false,
+            max_age_secs: 60 * 60,
+        }
+    }
+}
+
+impl Cors {
+    /// Reads `CORS_*` overrides from the environment, with the origins
+    /// comma-separated.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        let allowed_origins = match env::var("CORS_ALLOWED_ORIGINS") {
+            Ok(origins) => origins
+                .split(',')
+                .map(str::trim)
+                .filter(|o| !o.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(_) => d.allowed_origins,
+        };
+        Ok(Self {
+            allowed_origins,
+            allow_credentials: env_or("CORS_ALLOW_CREDENTIALS", d.allow_credentials)?,
+            max_age_secs: env_or("CORS_MAX_AGE_SECS", d.max_age_secs)?,
+        })
+    }
+
+    pub fn allows_any(&self) -> bool {
+        self.allowed_origins.iter().any(|o| o == "*")
+    }
+
+    /// The listed origins as header values, leaving out `*`.
+    pub fn origins(&self) -> Vec<HeaderValue> {
+        self.allowed_origins
+            .iter()
+            .filter(|o| *o != "*")
+            .filter_map(|o| HeaderValue::from_str(o).ok())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CookieSameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl std::str::FromStr for CookieSameSite {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Self::Strict),
+            "lax" => Ok(Self::Lax),
+            "none" => Ok(Self::None),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<CookieSameSite> for SameSite {
+    fn from(same_site: CookieSameSite) -> Self {
+        match same_site {
+            CookieSameSite::Strict => SameSite::Strict,
+            CookieSameSite::Lax => SameSite::Lax,
+            CookieSameSite::None => SameSite::None,
+        }
+    }
+}
+
+/// Attributes of the session and sign-in cookies.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cookies {
+    /// Only send cookies over HTTPS.
+    pub secure: bool,
+    /// `lax` lets sign-in redirects from identity providers carry the cookies.
+    pub same_site: CookieSameSite,
+    /// Share cookies with subdomains of this domain; by default they stay on
+    /// the host that set them.
+    pub domain: Option<String>,
+}
+
+impl Default for Cookies {
+    fn default() -> Self {
+        Self {
+            secure: true,
+            same_site: CookieSameSite::Lax,
+            domain: None,
+        }
+    }
+}
+
+impl Cookies {
+    /// Reads `SECURE_COOKIES` and `COOKIE_*` overrides from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            secure: env_or("SECURE_COOKIES", d.secure)?,
+            same_site: env_or("COOKIE_SAME_SITE", d.same_site)?,
+            domain: env::var("COOKIE_DOMAIN").ok().or(d.domain),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum JwtAlgorithm {
+    #[serde(rename = "HS256")]
+    Hs256,
+    #[serde(rename = "HS384")]
+    Hs384,
+    #[serde(rename = "HS512")]
+    Hs512,
+    #[serde(rename = "RS256")]
+    Rs256,
+    #[serde(rename = "EdDSA")]
+    EdDsa,
+}
+
+impl JwtAlgorithm {
+    pub fn is_hmac(self) -> bool {
+        matches!(self, Self::Hs256 | Self::Hs384 | Self::Hs512)
+    }
+}
+
+impl std::str::FromStr for JwtAlgorithm {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "HS256" => Ok(Self::Hs256),
+            "HS384" => Ok(Self::Hs384),
+            "HS512" => Ok(Self::Hs512),
+            "RS256" => Ok(Self::Rs256),
+            "EdDSA" => Ok(Self::EdDsa),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for JwtAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Hs256 => "HS256",
+            Self::Hs384 => "HS384",
+            Self::Hs512 => "HS512",
+            Self::Rs256 => "RS256",
+            Self::EdDsa => "EdDSA",
+        };
+        f.write_str(name)
+    }
+}
+
+impl From<JwtAlgorithm> for Algorithm {
+    fn from(algorithm: JwtAlgorithm) -> Self {
+        match algorithm {
+            JwtAlgorithm::Hs256 => Algorithm::HS256,
+            JwtAlgorithm::Hs384 => Algorithm::HS384,
+            JwtAlgorithm::Hs512 => Algorithm::HS512,
+            JwtAlgorithm::Rs256 => Algorithm::RS256,
+            JwtAlgorithm::EdDsa => Algorithm::EdDSA,
+        }
+    }
+}
+
+/// Signing of session and step-up tokens; see `services::jwt_keys`.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Jwt {
+    /// Algorithm of the key that signs new tokens.
+    pub algorithm: JwtAlgorithm,
+    /// HMAC secret for the HS algorithms. Left in place next to an RS256 or
+    /// EdDSA key, it still verifies the tokens signed before the switch.
+    pub secret_key: String,
+    /// `kid` of the signing key, for RS256 and EdDSA.
+    pub key_id: Option<String>,
+    /// PEM files of the signing key pair, for RS256 and EdDSA.
+    pub private_key_file: Option<PathBuf>,
+    pub public_key_file: Option<PathBuf>,
+    /// Public keys that verify tokens but sign none: the next key, published
+    /// ahead of a rotation, and retired keys until their tokens expire.
+    pub verification_keys: Vec<VerificationKey>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerificationKey {
+    pub key_id: String,
+    pub algorithm: JwtAlgorithm,
+    pub public_key_file: PathBuf,
+}
+
+impl fmt::Debug for Jwt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Jwt")
+            .field("algorithm", &self.algorithm)
+            .field("key_id", &self.key_id)
+            .field("private_key_file", &self.private_key_file)
+            .field("public_key_file", &self.public_key_file)
+            .field("verification_keys", &self.verification_keys)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Jwt {
+    fn default() -> Self {
+        Self {
+            algorithm: JwtAlgorithm::Hs256,
+            secret_key: String::new(),
+            key_id: None,
+            private_key_file: None,
+            public_key_file: None,
+            verification_keys: Vec::new(),
+        }
+    }
+}
+
+impl Jwt {
+    /// HMAC keys shorter than the hash give away strength.
+    const MIN_SECRET_LEN: usize = 32;
+
+    /// Reads `JWT_*` and `SECRET_KEY` overrides from the environment;
+    /// verification keys are only read from the file.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            algorithm: env_or("JWT_ALGORITHM", d.algorithm)?,
+            secret_key: env_or("SECRET_KEY", d.secret_key)?,
+            key_id: env::var("JWT_KEY_ID").ok().or(d.key_id),
+            private_key_file: env::var("JWT_PRIVATE_KEY_FILE")
+                .ok()
+                .map(PathBuf::from)
+                .or(d.private_key_file),
+            public_key_file: env::var("JWT_PUBLIC_KEY_FILE")
+                .ok()
+                .map(PathBuf::from)
+                .or(d.public_key_file),
+            verification_keys: d.verification_keys,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines
+    Text,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Log {
+    pub format: LogFormat,
+    /// A `tracing` filter, like `info,sqlx=warn`.
+    pub level: String,
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Text,
+            level: "info".to_string(),
+        }
+    }
+}
+
+impl Log {
+    /// Reads `LOG_FORMAT` and `RUST_LOG` from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            format: env_or("LOG_FORMAT", d.format)?,
+            level: env_or("RUST_LOG", d.level)?,
+        })
+    }
+
+    pub fn filter(&self) -> EnvFilter {
+        EnvFilter::try_new(&self.level).unwrap_or_else(|_| EnvFilter::new("info"))
+    }
+}
+
+/// Prometheus metrics and OpenTelemetry trace export.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Telemetry {
+    /// When set, `/metrics` asks for it as a bearer token.
+    pub metrics_token: Option<String>,
+    /// Base URL of an OTLP/HTTP collector, like `http://localhost:4318`;
+    /// spans are not exported without one.
+    pub otlp_endpoint: Option<String>,
+    /// `service.name` of the exported spans.
+    pub service_name: String,
+}
+
+impl fmt::Debug for Telemetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Telemetry")
+            .field("otlp_endpoint", &self.otlp_endpoint)
+            .field("service_name", &self.service_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self {
+            metrics_token: None,
+            otlp_endpoint: None,
+            service_name: "todoem".to_string(),
+        }
+    }
+}
+
+impl Telemetry {
+    /// Reads `METRICS_TOKEN` and the standard `OTEL_EXPORTER_OTLP_ENDPOINT`
+    /// and `OTEL_SERVICE_NAME` from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            metrics_token: env::var("METRICS_TOKEN").ok().or(d.metrics_token),
+            otlp_endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .ok()
+                .or(d.otlp_endpoint),
+            service_name: env_or("OTEL_SERVICE_NAME", d.service_name)?,
+        })
+    }
+
+    /// Where spans are sent, per the OTLP/HTTP convention.
+    pub fn traces_endpoint(&self) -> Option<String> {
+        self.otlp_endpoint
+            .as_ref()
+            .map(|base| format!("{}/v1/traces", base.trim_end_matches('/')))
+    }
+}
+
+/// The unversioned `/api`, an alias of v1 from before versioning. It is
+/// announced as deprecated from `unversioned_deprecated` and answers
+/// `410 Gone` from `unversioned_sunset`, both from midnight UTC.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Api {
+    pub unversioned_deprecated: NaiveDate,
+    pub unversioned_sunset: NaiveDate,
+}
+
+impl Default for Api {
+    fn default() -> Self {
+        Self {
+            unversioned_deprecated: NaiveDate::from_ymd_opt(2026, 10, 19).unwrap(),
+            unversioned_sunset: NaiveDate::from_ymd_opt(2027, 4, 19).unwrap(),
+        }
+    }
+}
+
+impl Api {
+    /// Reads `API_*` overrides from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            unversioned_deprecated: env_or("API_UNVERSIONED_DEPRECATED", d.unversioned_deprecated)?,
+            unversioned_sunset: env_or("API_UNVERSIONED_SUNSET", d.unversioned_sunset)?,
+        })
+    }
+}
+
+/// Reads `LIMIT_*` overrides of `d` from the environment.
+fn limits_with_env(d: Limits) -> Result<Limits, ConfigError> {
+    Ok(Limits {
+        task_max_len: env_or("LIMIT_TASK_MAX_LEN", d.task_max_len)?,
+        description_max_len: env_or("LIMIT_DESCRIPTION_MAX_LEN", d.description_max_len)?,
+        list_name_max_len: env_or("LIMIT_LIST_NAME_MAX_LEN", d.list_name_max_len)?,
+        list_max_tasks: env_or("LIMIT_LIST_MAX_TASKS", d.list_max_tasks)?,
+        name_max_len: env_or("LIMIT_NAME_MAX_LEN", d.name_max_len)?,
+        bio_max_len: env_or("LIMIT_BIO_MAX_LEN", d.bio_max_len)?,
+        username_min_len: env_or("LIMIT_USERNAME_MIN_LEN", d.username_min_len)?,
+        username_max_len: env_or("LIMIT_USERNAME_MAX_LEN", d.username_max_len)?,
+        search_min_len: env_or("LIMIT_SEARCH_MIN_LEN", d.search_min_len)?,
+        search_max_len: env_or("LIMIT_SEARCH_MAX_LEN", d.search_max_len)?,
+        search_max_page: env_or("LIMIT_SEARCH_MAX_PAGE", d.search_max_page)?,
+        due_date_max_past_days: env_or("LIMIT_DUE_DATE_MAX_PAST_DAYS", d.due_date_max_past_days)?,
+        due_date_max_future_days: env_or(
+            "LIMIT_DUE_DATE_MAX_FUTURE_DAYS",
+            d.due_date_max_future_days,
+        )?,
+        due_range_max_days: env_or("LIMIT_DUE_RANGE_MAX_DAYS", d.due_range_max_days)?,
+        filter_name_max_len: env_or("LIMIT_FILTER_NAME_MAX_LEN", d.filter_name_max_len)?,
+        filter_max_nodes: env_or("LIMIT_FILTER_MAX_NODES", d.filter_max_nodes)?,
+        filter_max_depth: env_or("LIMIT_FILTER_MAX_DEPTH", d.filter_max_depth)?,
+        password_min_len: env_or("LIMIT_PASSWORD_MIN_LEN", d.password_min_len)?,
+        password_max_len: env_or("LIMIT_PASSWORD_MAX_LEN", d.password_max_len)?,
+    })
+}
+
+/// Request budgets enforced by `middlewares::rate_limit`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimits {
+    pub user_per_minute: u32,
+    pub ip_per_minute: u32,
+    pub connection_requests_per_day: i32,
+    /// Take the client IP from `X-Forwarded-For` (only behind a trusted proxy).
+    pub trust_proxy: bool,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            user_per_minute: 120,
+            ip_per_minute: 300,
+            connection_requests_per_day: 50,
+            trust_proxy: false,
+        }
+    }
+}
+
+impl RateLimits {
+    /// Reads `RATE_LIMIT_*` overrides from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            user_per_minute: env_or("RATE_LIMIT_USER_PER_MINUTE", d.user_per_minute)?,
+            ip_per_minute: env_or("RATE_LIMIT_IP_PER_MINUTE", d.ip_per_minute)?,
+            connection_requests_per_day: env_or(
+                "RATE_LIMIT_CONNECTION_REQUESTS_PER_DAY",
+                d.connection_requests_per_day,
+            )?,
+            trust_proxy: env_or("RATE_LIMIT_TRUST_PROXY", d.trust_proxy)?,
+        })
+    }
+}
+
+/// Account lifecycle settings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Accounts {
+    pub username_cooldown_days: i32,
+    pub deletion_grace_days: i32,
+    /// Root directory of the local file storage (avatars).
+    pub storage_dir: String,
+    pub avatar_max_bytes: usize,
+    pub avatar_size: u32,
+    /// Unrevoked personal access tokens a user may hold at once.
+    pub api_tokens_max_per_user: i64,
+    /// Lifetime of the `access_token` cookie issued on sign-in.
+    pub session_ttl_hours: i64,
+    /// How long `jwt_auth` trusts a session lookup before asking the database
+    /// again; a revocation from another instance takes up to this long.
+    pub session_cache_secs: u64,
+    /// PBKDF2 rounds for new password hashes; older hashes are upgraded on sign-in.
+    pub password_iterations: u32,
+}
+
+impl Default for Accounts {
+    fn default() -> Self {
+        Self {
+            username_cooldown_days: 30,
+            deletion_grace_days: 30,
+            storage_dir: "./data".to_string(),
+            avatar_max_bytes: 5 * 1024 * 1024,
+            avatar_size: 256,
+            api_tokens_max_per_user: 20,
+            session_ttl_hours: 7 * 24,
+            session_cache_secs: 30,
+            password_iterations: 600_000,
+        }
+    }
+}
+
+impl Accounts {
+    /// Reads `ACCOUNT_*` and `STORAGE_DIR` overrides from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            username_cooldown_days: env_or(
+                "ACCOUNT_USERNAME_COOLDOWN_DAYS",
+                d.username_cooldown_days,
+            )?,
+            deletion_grace_days: env_or("ACCOUNT_DELETION_GRACE_DAYS", d.deletion_grace_days)?,
+            storage_dir: env_or("STORAGE_DIR", d.storage_dir)?,
+            avatar_max_bytes: env_or("ACCOUNT_AVATAR_MAX_BYTES", d.avatar_max_bytes)?,
+            avatar_size: env_or("ACCOUNT_AVATAR_SIZE", d.avatar_size)?,
+            api_tokens_max_per_user: env_or(
+                "ACCOUNT_API_TOKENS_MAX_PER_USER",
+                d.api_tokens_max_per_user,
+            )?,
+            session_ttl_hours: env_or("ACCOUNT_SESSION_TTL_HOURS", d.session_ttl_hours)?,
+            session_cache_secs: env_or("ACCOUNT_SESSION_CACHE_SECS", d.session_cache_secs)?,
+            password_iterations: env_or("ACCOUNT_PASSWORD_ITERATIONS", d.password_iterations)?,
+        })
+    }
+}
+
+/// Webhook registration and delivery settings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Webhooks {
+    pub max_per_user: i64,
+    /// Deliveries are given up after this many failed attempts.
+    pub max_attempts: i32,
+    /// Delay before the first retry, doubled on every further one.
+    pub backoff_base_secs: u64,
+    pub backoff_max_secs: u64,
+    pub timeout_secs: u64,
+    pub poll_interval_ms: u64,
+    pub batch_size: i64,
+    /// Deliver to loopback, private and link-local addresses too. Only for
+    /// receivers on the same machine or network, such as in tests.
+    pub allow_private_targets: bool,
+}
+
+impl Default for Webhooks {
+    fn default() -> Self {
+        Self {
+            max_per_user: 10,
+            max_attempts: 8,
+            backoff_base_secs: 30,
+            backoff_max_secs: 6 * 60 * 60,
+            timeout_secs: 10,
+            poll_interval_ms: 1000,
+            batch_size: 32,
+            allow_private_targets: false,
+        }
+    }
+}
+
+impl Webhooks {
+    /// Reads `WEBHOOK_*` overrides from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            max_per_user: env_or("WEBHOOK_MAX_PER_USER", d.max_per_user)?,
+            max_attempts: env_or("WEBHOOK_MAX_ATTEMPTS", d.max_attempts)?,
+            backoff_base_secs: env_or("WEBHOOK_BACKOFF_BASE_SECS", d.backoff_base_secs)?,
+            backoff_max_secs: env_or("WEBHOOK_BACKOFF_MAX_SECS", d.backoff_max_secs)?,
+            timeout_secs: env_or("WEBHOOK_TIMEOUT_SECS", d.timeout_secs)?,
+            poll_interval_ms: env_or("WEBHOOK_POLL_INTERVAL_MS", d.poll_interval_ms)?,
+            batch_size: env_or("WEBHOOK_BATCH_SIZE", d.batch_size)?,
+            allow_private_targets: env_or(
+                "WEBHOOK_ALLOW_PRIVATE_TARGETS",
+                d.allow_private_targets,
+            )?,
+        })
+    }
+}
+
+/// OpenID Connect sign-in.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Oidc {
+    pub providers: Vec<OidcProvider>,
+    /// Public base URL of this server, which callback URLs are built from.
+    pub public_url: String,
+    /// How long a sign-in, or the username choice after a first one, may take.
+    pub flow_ttl_secs: i64,
+}
+
+/// A provider registered as `OIDC_<NAME>_*`.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OidcProvider {
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    /// Absent for public clients, which rely on PKCE alone.
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    #[serde(default = "default_scopes")]
+    pub scopes: String,
+}
+
+impl fmt::Debug for OidcProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OidcProvider")
+            .field("name", &self.name)
+            .field("issuer", &self.issuer)
+            .field("client_id", &self.client_id)
+            .field("scopes", &self.scopes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Oidc {
+    fn default() -> Self {
+        Self {
+            providers: Vec::new(),
+            public_url: "http://localhost:8080".to_string(),
+            flow_ttl_secs: 10 * 60,
+        }
+    }
+}
+
+impl Oidc {
+    pub fn provider(&self, name: &str) -> Option<&OidcProvider> {
+        self.providers.iter().find(|p| p.name == name)
+    }
+
+    /// Reads the comma-separated `OIDC_PROVIDERS` and, for each name, its
+    /// `OIDC_<NAME>_ISSUER`, `_CLIENT_ID`, `_CLIENT_SECRET` and `_SCOPES`.
+    /// Providers from the environment replace those from the file.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        let providers = match env::var("OIDC_PROVIDERS") {
+            Ok(names) => Self::providers_from_env(&names)?,
+            Err(_) => d.providers,
+        };
+
+        Ok(Self {
+            providers: providers
+                .into_iter()
+                .map(|p| OidcProvider {
+                    name: p.name.to_lowercase(),
+                    issuer: p.issuer.trim_end_matches('/').to_string(),
+                    ..p
+                })
+                .collect(),
+            public_url: env_or("PUBLIC_URL", d.public_url)?
+                .trim_end_matches('/')
+                .to_string(),
+            flow_ttl_secs: env_or("OIDC_FLOW_TTL_SECS", d.flow_ttl_secs)?,
+        })
+    }
+
+    fn providers_from_env(names: &str) -> Result<Vec<OidcProvider>, ConfigError> {
+        let mut providers = Vec::new();
+        for name in names.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let prefix = format!("OIDC_{}", name.to_uppercase());
+            let var = |field: &str| {
+                env::var(format!("{}_{}", prefix, field))
+                    .ok()
+                    .filter(|v| !v.is_empty())
+            };
+            let (Some(issuer), Some(client_id)) = (var("ISSUER"), var("CLIENT_ID")) else {
+                return Err(ConfigError::Invalid(vec![format!(
+                    "OIDC provider {} needs {}_ISSUER and {}_CLIENT_ID",
+                    name, prefix, prefix
+                )]));
+            };
+            providers.push(OidcProvider {
+                name: name.to_string(),
+                issuer,
+                client_id,
+                client_secret: var("CLIENT_SECRET"),
+                scopes: var("SCOPES").unwrap_or_else(default_scopes),
+            });
+        }
+        Ok(providers)
+    }
+}
+
+fn default_scopes() -> String {
+    "openid email profile".to_string()
+}
+
+/// Where outgoing mail goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailTransport {
+    Smtp,
+    /// Writes `.eml` files for development.
+    File,
+    /// Keeps messages in memory, for tests.
+    Memory,
+}
+
+impl std::str::FromStr for MailTransport {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "smtp" => Ok(Self::Smtp),
+            "file" => Ok(Self::File),
+            "memory" => Ok(Self::Memory),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    None,
+    /// Upgrade a plain connection, usually on port 587.
+    StartTls,
+    /// TLS from the start, usually on port 465.
+    Tls,
+}
+
+impl std::str::FromStr for SmtpTls {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "starttls" => Ok(Self::StartTls),
+            "tls" => Ok(Self::Tls),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Outgoing mail and the account emails sent with it.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Mail {
+    pub transport: MailTransport,
+    /// `From` header, e.g. `todoem <no-reply@example.com>`.
+    pub from: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_tls: SmtpTls,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    /// Directory of the `file` transport.
+    pub file_dir: String,
+    /// Base URL of the pages that links in emails point to.
+    pub link_url: String,
+    pub verify_ttl_hours: i64,
+    pub reset_ttl_minutes: i64,
+    /// Messages are given up after this many failed attempts.
+    pub max_attempts: i32,
+    pub poll_interval_ms: u64,
+    pub batch_size: i64,
+}
+
+impl fmt::Debug for Mail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Mail")
+            .field("transport", &self.transport)
+            .field("from", &self.from)
+            .field("smtp_host", &self.smtp_host)
+            .field("smtp_port", &self.smtp_port)
+            .field("smtp_tls", &self.smtp_tls)
+            .field("smtp_username", &self.smtp_username)
+            .field("file_dir", &self.file_dir)
+            .field("link_url", &self.link_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Mail {
+    fn default() -> Self {
+        Self {
+            transport: MailTransport::File,
+            from: "todoem <no-reply@localhost>".to_string(),
+            smtp_host: "localhost".to_string(),
+            smtp_port: 587,
+            smtp_tls: SmtpTls::StartTls,
+            smtp_username: None,
+            smtp_password: None,
+            file_dir: "./data/mail".to_string(),
+            link_url: "http://localhost:8080".to_string(),
+            verify_ttl_hours: 48,
+            reset_ttl_minutes: 60,
+            max_attempts: 5,
+            poll_interval_ms: 1000,
+            batch_size: 16,
+        }
+    }
+}
+
+impl Mail {
+    /// Reads `MAIL_*` and `SMTP_*` overrides from the environment.
+    fn with_env(self) -> Result<Self, ConfigError> {
+        let d = self;
+        Ok(Self {
+            transport: env_or("MAIL_TRANSPORT", d.transport)?,
+            from: env_or("MAIL_FROM", d.from)?,
+            smtp_host: env_or("SMTP_HOST", d.smtp_host)?,
+            smtp_port: env_or("SMTP_PORT", d.smtp_port)?,
+            smtp_tls: env_or("SMTP_TLS", d.smtp_tls)?,
+            smtp_username: env::var("SMTP_USERNAME").ok().or(d.smtp_username),
+            smtp_password: env::var("SMTP_PASSWORD").ok().or(d.smtp_password),
+            file_dir: env_or("MAIL_FILE_DIR", d.file_dir)?,
+            link_url: env_or("MAIL_LINK_URL", d.link_url)?
+                .trim_end_matches('/')
+                .to_string(),
+            verify_ttl_hours: env_or("MAIL_VERIFY_TTL_HOURS", d.verify_ttl_hours)?,
+            reset_ttl_minutes: env_or("MAIL_RESET_TTL_MINUTES", d.reset_ttl_minutes)?,
+            max_attempts: env_or("MAIL_MAX_ATTEMPTS", d.max_attempts)?,
+            poll_interval_ms: env_or("MAIL_POLL_INTERVAL_MS", d.poll_interval_ms)?,
+            batch_size: env_or("MAIL_BATCH_SIZE", d.batch_size)?,
+        })
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> Result<T, ConfigError> {
+    match env::var(key) {
+        Ok(val) => val.parse().map_err(|_| ConfigError::Env {
+            key: key.to_string(),
+            value: val,
+        }),
+        Err(_) => Ok(default),
+    }
+}
+
+impl Settings {
+    /// Layers the file named by `cli`, the environment and `cli` itself on
+    /// the defaults, and checks the result.
+    pub fn load(cli: &Cli) -> Result<Self, ConfigError> {
+        let settings = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        let mut settings = settings.with_env()?;
+        settings.apply(cli);
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn from_file(path: &PathBuf) -> Result<Self, ConfigError> {
+        let file_error = |reason: String| ConfigError::File {
+            path: path.clone(),
+            reason,
+        };
+        let text = fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+        toml::from_str(&text).map_err(|e| file_error(e.to_string()))
+    }
+
+    fn with_env(self) -> Result<Self, ConfigError> {
+        Ok(Self {
+            server: self.server.with_env()?,
+            database: self.database.with_env()?,
+            cors: self.cors.with_env()?,
+            cookies: self.cookies.with_env()?,
+            jwt: self.jwt.with_env()?,
+            log: self.log.with_env()?,
+            telemetry: self.telemetry.with_env()?,
+            api: self.api.with_env()?,
+            limits: limits_with_env(self.limits)?,
+            rate_limits: self.rate_limits.with_env()?,
+            accounts: self.accounts.with_env()?,
+            webhooks: self.webhooks.with_env()?,
+            oidc: self.oidc.with_env()?,
+            mail: self.mail.with_env()?,
+        })
+    }
+
+    fn apply(&mut self, cli: &Cli) {
+        if let Some(bind) = cli.bind {
+            self.server.bind = bind;
+        }
+        if let Some(format) = cli.log_format {
+            self.log.format = format;
+        }
+        if let Some(level) = &cli.log_level {
+            self.log.level = level.clone();
+        }
+    }
+
+    /// Refuses settings that would fail later, or work in surprising ways,
+    /// listing every problem at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let mut require = |ok: bool, problem: &str| {
+            if !ok {
+                problems.push(problem.to_string());
+            }
+        };
+
+        require(
+            !self.database.url.is_empty(),
+            "database.url (DATABASE_URL) is required",
+        );
+        require(
+            self.database.max_connections > 0,
+            "database.max_connections must be at least 1",
+        );
+        require(
+            self.database.min_connections <= self.database.max_connections,
+            "database.min_connections must not exceed database.max_connections",
+        );
+        require(
+            self.database.acquire_timeout_secs > 0,
+            "database.acquire_timeout_secs must be at least 1",
+        );
+        require(
+            self.server.request_timeout_secs > 0,
+            "server.request_timeout_secs must be at least 1",
+        );
+
+        let jwt = &self.jwt;
+        require(
+            !jwt.algorithm.is_hmac() || !jwt.secret_key.is_empty(),
+            "jwt.secret_key (SECRET_KEY) is required for the HS algorithms",
+        );
+        require(
+            jwt.secret_key.is_empty() || jwt.secret_key.len() >= Jwt::MIN_SECRET_LEN,
+            "jwt.secret_key must be at least 32 bytes long",
+        );
+        require(
+            jwt.verification_keys.iter().all(|k| !k.algorithm.is_hmac()),
+            "jwt.verification_keys must be RS256 or EdDSA keys",
+        );
+
+        let cors = &self.cors;
+        require(
+            !(cors.allows_any() && cors.allowed_origins.len() > 1),
+            "cors.allowed_origins cannot mix * with other origins",
+        );
+        require(
+            !(cors.allows_any() && cors.allow_credentials),
+            "cors.allow_credentials needs explicit cors.allowed_origins, browsers refuse credentials for *",
+        );
+        require(
+            cors.allowed_origins
+                .iter()
+                .all(|o| o == "*" || is_origin(o)),
+            "cors.allowed_origins must be origins like https://app.example.com, without a path",
+        );
+
+        require(
+            self.cookies.same_site != CookieSameSite::None || self.cookies.secure,
+            "cookies.same_site = none needs cookies.secure, browsers drop such cookies otherwise",
+        );
+        require(
+            self.cookies.same_site != CookieSameSite::Strict || self.oidc.providers.is_empty(),
+            "OIDC sign-in needs cookies.same_site = lax or none, strict cookies are not sent on the redirect back from the provider",
+        );
+
+        require(
+            EnvFilter::try_new(&self.log.level).is_ok(),
+            "log.level (RUST_LOG) is not a valid filter",
+        );
+        require(
+            self.telemetry
+                .otlp_endpoint
+                .as_deref()
+                .is_none_or(is_http_url),
+            "telemetry.otlp_endpoint (OTEL_EXPORTER_OTLP_ENDPOINT) must be an http or https URL",
+        );
+        require(
+            self.telemetry
+                .metrics_token
+                .as_ref()
+                .is_none_or(|t| !t.is_empty()),
+            "telemetry.metrics_token (METRICS_TOKEN) must not be empty when set",
+        );
+
+        require(
+            self.api.unversioned_deprecated <= self.api.unversioned_sunset,
+            "api.unversioned_sunset must not come before api.unversioned_deprecated",
+        );
+
+        let limits = &self.limits;
+        require(
+            limits.username_min_len <= limits.username_max_len,
+            "limits.username_min_len must not exceed limits.username_max_len",
+        );
+        require(
+            limits.password_min_len <= limits.password_max_len,
+            "limits.password_min_len must not exceed limits.password_max_len",
+        );
+        require(
+            limits.search_min_len <= limits.search_max_len,
+            "limits.search_min_len must not exceed limits.search_max_len",
+        );
+
+        require(
+            is_http_url(&self.oidc.public_url),
+            "oidc.public_url (PUBLIC_URL) must be an http or https URL",
+        );
+        require(
+            is_http_url(&self.mail.link_url),
+            "mail.link_url (MAIL_LINK_URL) must be an http or https URL",
+        );
+        let providers = &self.oidc.providers;
+        require(
+            providers
+                .iter()
+                .all(|p| is_http_url(&p.issuer) && !p.client_id.is_empty()),
+            "every OIDC provider needs an http or https issuer and a client id",
+        );
+        require(
+            providers
+                .iter()
+                .enumerate()
+                .all(|(i, p)| providers[..i].iter().all(|q| q.name != p.name)),
+            "OIDC provider names must be unique",
+        );
+
+        let mail = &self.mail;
+        require(
+            mail.transport != MailTransport::Smtp || !mail.smtp_host.is_empty(),
+            "mail.smtp_host (SMTP_HOST) is required for the smtp transport",
+        );
+        require(
+            mail.smtp_username.is_some() == mail.smtp_password.is_some(),
+            "mail.smtp_username and mail.smtp_password go together",
+        );
+
+        // only worth reading once the settings above are sound
+        if problems.is_empty() {
+            if let Err(problem) = JwtKeys::load(&self.jwt) {
+                problems.push(problem);
+            }
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError::Invalid(problems)),
+        }
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+/// Browsers send `Origin` as scheme, host and port only.
+fn is_origin(origin: &str) -> bool {
+    is_http_url(origin)
+        && !origin.ends_with('/')
+        && reqwest::Url::parse(origin).is_ok_and(|u| u.path() == "/" && u.query().is_none())
+}
+
+#[instrument(skip_all)]
+pub async fn init(settings: Settings) -> Result<Config, ConfigError> {
+    info!("Connecting to database ...");
+    let pool = settings
+        .database
+        .pool_options()
+        .connect(&settings.database.url)
+        .await
+        .map_err(ConfigError::Database)?;
+
+    info!("Running migrations ...");
+    db::MIGRATOR
+        .run(&pool)
+        .await
+        .map_err(ConfigError::Migrations)?;
+    info!("Migrations ran successfully");
+
+    Config::new(settings, pool)
+}
+
+impl Config {
+    /// The configuration of the server around `pool`, which is expected to
+    /// be migrated already. Tests build their router from this.
+    pub fn new(settings: Settings, pool: PgPool) -> Result<Self, ConfigError> {
+        let jwt_keys = JwtKeys::load(&settings.jwt).map_err(|e| ConfigError::Invalid(vec![e]))?;
+
+        let Settings {
+            server,
+            database: _,
+            cors,
+            cookies,
+            jwt: _,
+            log: _,
+            telemetry,
+            api,
+            limits,
+            rate_limits,
+            accounts,
+            webhooks,
+            oidc,
+            mail,
+        } = settings;
+
+        Ok(Self {
+            pool,
+            server,
+            cors,
+            cookies,
+            jwt_keys: Arc::new(jwt_keys),
+            telemetry,
+            api,
+            limits,
+            rate_limits,
+            accounts,
+            webhooks,
+            oidc,
+            mail,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "xqcknM1ICw65eHLPB7+0UPgT2sdayTvIm0bRKhA+Oug=";
+
+    fn parse(toml: &str) -> Settings {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn problems(settings: &Settings) -> Vec<String> {
+        match settings.validate() {
+            Ok(()) => Vec::new(),
+            Err(ConfigError::Invalid(problems)) => problems,
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn file_settings_fill_in_defaults() {
+        let settings = parse(&format!(
+            r#"
+            [server]
+            bind = "127.0.0.1:3000"
+
+            [database]
+            url = "postgres://localhost/todoem"
+            max_connections = 20
+
+            [jwt]
+            secret_key = "{SECRET}"
+
+            [cors]
+            allowed_origins = ["https://app.example.com"]
+            allow_credentials = true
+
+            [api]
+            unversioned_sunset = "2027-10-19"
+
+            [mail]
+            transport = "smtp"
+            smtp_tls = "tls"
+
+            [[oidc.providers]]
+            name = "google"
+            issuer = "https://accounts.google.com"
+            client_id = "todoem"
+            "#
+        ));
+        assert_eq!(settings.server.bind.port(), 3000);
+        assert_eq!(settings.server.request_timeout_secs, 30);
+        assert_eq!(settings.database.max_connections, 20);
+        assert_eq!(settings.database.min_connections, 0);
+        assert_eq!(settings.mail.smtp_tls, SmtpTls::Tls);
+        assert_eq!(settings.oidc.providers[0].scopes, "openid email profile");
+        assert_eq!(settings.limits.task_max_len, 255);
+        assert_eq!(
+            settings.api.unversioned_sunset,
+            NaiveDate::from_ymd_opt(2027, 10, 19).unwrap()
+        );
+        assert_eq!(problems(&settings), Vec::<String>::new());
+    }
+
+    #[test]
+    fn unknown_keys_are_refused() {
+        let err = toml::from_str::<Settings>("[server]\nport = 8080").unwrap_err();
+        assert!(err.to_string().contains("unknown field `port`"));
+    }
+
+    #[test]
+    fn flags_override_the_file() {
+        let mut settings = parse("[log]\nformat = \"json\"\nlevel = \"debug\"");
+        let cli = Cli::try_parse_from([
+            "todoem-server",
+            "--bind",
+            "[::1]:9000",
+            "--log-level",
+            "warn",
+        ])
+        .unwrap();
+        settings.apply(&cli);
+        assert_eq!(settings.server.bind, "[::1]:9000".parse().unwrap());
+        assert_eq!(settings.log.format, LogFormat::Json);
+        assert_eq!(settings.log.level, "warn");
+    }
+
+    #[test]
+    fn lists_every_problem() {
+        let settings = parse(
+            r#"
+            [jwt]
+            secret_key = "short"
+
+            [cors]
+            allowed_origins = ["*"]
+            allow_credentials = true
+
+            [cookies]
+            secure = false
+            same_site = "none"
+
+            [database]
+            max_connections = 0
+
+            [api]
+            unversioned_deprecated = "2027-01-01"
+            unversioned_sunset = "2026-12-31"
+            "#,
+        );
+        let problems = problems(&settings);
+        assert_eq!(problems.len(), 6, "{:#?}", problems);
+        assert!(problems[0].contains("DATABASE_URL"));
+        assert!(problems.iter().any(|p| p.contains("32 bytes")));
+        assert!(problems.iter().any(|p| p.contains("credentials")));
+        assert!(problems.iter().any(|p| p.contains("same_site = none")));
+        assert!(problems
+            .iter()
+            .any(|p| p.contains("api.unversioned_sunset")));
+    }
+
+    #[test]
+    fn origins_have_no_path() {
+        assert!(is_origin("https://app.example.com"));
+        assert!(is_origin("http://localhost:5173"));
+        assert!(!is_origin("https://app.example.com/"));
+        assert!(!is_origin("https://app.example.com/todo"));
+        assert!(!is_origin("app.example.com"));
+    }
+}
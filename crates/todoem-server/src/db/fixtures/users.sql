@@ -0,0 +1,17 @@
+-- Users the tests sign in as, each with their username for a password,
+-- hashed with few iterations to keep the tests fast.
+INSERT INTO users (id, username, email, name, password)
+VALUES ('c8686820-72ce-4391-bdce-e4f260dea40f', 'toto', 'toto@bobo.co', 'toto sasa',
+  '$pbkdf2-sha256$i=1000$EmCexZ4S0yymqU0dwYwJgQ$zaBKjdGZ7uYIh+OaKzD9g5iy3LcxWrd9zPDk5InfBVc');
+
+INSERT INTO users (id, username, email, password)
+VALUES ('5d43fc3c-8acb-48f9-9b25-8f8bd6f3d834', 'luffy', 'luffy@op.co',
+  '$pbkdf2-sha256$i=1000$04gjILnNcVbysDloXb9arw$doWjPpJBPT9U8nFdMLGhxbmZyOE2GtU84sT5EGyIeOM');
+
+INSERT INTO users (id, username, email, name, password)
+VALUES ('4157ee44-1de0-4168-a1f3-7ad6a5fd09b6', 'zoro', 'zoro@op.co', 'zoro japan',
+  '$pbkdf2-sha256$i=1000$mvkuogb/HTQgIKUK5fkZLQ$lZXcZd1UYkO5CnsioO1hD8XYu1Lfjq+9Bhl99GcMiR8');
+
+INSERT INTO users (id, username, email, password)
+VALUES ('f6d1dabe-7766-4a6c-b34e-75e444cc3cbd', 'nami', 'nami@op.co',
+  '$pbkdf2-sha256$i=1000$Dexc9bNj94S0Gp6YbxXlGQ$JCM+9EstV8S8EnJDkEfP/zb3jv94C/9JFBt32MzUu0w');
@@ -0,0 +1,22 @@
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS api_tokens (
+  id BIGSERIAL PRIMARY KEY,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+
+  name TEXT NOT NULL,
+  -- SHA-256 of the token; the token itself is only shown once
+  token_hash TEXT NOT NULL UNIQUE,
+  -- leading characters of the token, to tell tokens apart
+  prefix TEXT NOT NULL,
+  scopes TEXT[] NOT NULL,
+
+  expires_at TIMESTAMPTZ,
+  last_used_at TIMESTAMPTZ,
+  revoked_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX api_tokens_user_idx ON api_tokens (user_id);
+
+COMMIT;
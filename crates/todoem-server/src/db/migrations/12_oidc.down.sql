@@ -0,0 +1,10 @@
+BEGIN;
+
+DROP TABLE IF EXISTS oidc_signups;
+DROP TABLE IF EXISTS oidc_logins;
+DROP TABLE IF EXISTS user_identities;
+
+UPDATE users SET password = '' WHERE password IS NULL;
+ALTER TABLE users ALTER COLUMN password SET NOT NULL;
+
+COMMIT;
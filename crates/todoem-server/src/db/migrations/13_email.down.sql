@@ -0,0 +1,10 @@
+BEGIN;
+
+DROP TABLE IF EXISTS outbound_emails;
+DROP TYPE IF EXISTS email_status;
+DROP TABLE IF EXISTS email_tokens;
+DROP TYPE IF EXISTS email_token_purpose;
+
+ALTER TABLE users DROP COLUMN IF EXISTS email_verified_at;
+
+COMMIT;
@@ -0,0 +1,38 @@
+BEGIN;
+
+ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMPTZ;
+
+CREATE TYPE email_token_purpose AS ENUM ('verify_email', 'reset_password');
+
+CREATE TABLE IF NOT EXISTS email_tokens (
+  -- SHA-256 of the token sent by email
+  token_hash TEXT PRIMARY KEY,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  purpose email_token_purpose NOT NULL,
+  -- the address the token was sent to
+  email TEXT NOT NULL,
+  expires_at TIMESTAMPTZ NOT NULL,
+  used_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX email_tokens_user_idx ON email_tokens (user_id, purpose);
+
+-- outgoing mail; rows are deleted once sent
+CREATE TYPE email_status AS ENUM ('pending', 'failed');
+
+CREATE TABLE IF NOT EXISTS outbound_emails (
+  id BIGSERIAL PRIMARY KEY,
+  to_address TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  body TEXT NOT NULL,
+  status email_status NOT NULL DEFAULT 'pending',
+  attempts INTEGER NOT NULL DEFAULT 0,
+  next_attempt_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  error TEXT,
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX outbound_emails_due_idx ON outbound_emails (next_attempt_at) WHERE status = 'pending';
+
+COMMIT;
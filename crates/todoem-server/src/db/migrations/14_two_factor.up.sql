@@ -0,0 +1,24 @@
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS user_totp (
+  user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+  -- raw shared secret, needed to compute codes
+  secret BYTEA NOT NULL,
+  -- NULL until a first code proves the authenticator was set up
+  confirmed_at TIMESTAMPTZ,
+  -- time step of the last accepted code, so that a code works once
+  last_used_step BIGINT,
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS recovery_codes (
+  id BIGSERIAL PRIMARY KEY,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  -- SHA-256 of the normalised code
+  code_hash TEXT NOT NULL,
+  used_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  UNIQUE (user_id, code_hash)
+);
+
+COMMIT;
@@ -0,0 +1,19 @@
+BEGIN;
+
+-- one row per sign-in; the session id is the `sid` claim of its access token
+CREATE TABLE IF NOT EXISTS sessions (
+  id UUID PRIMARY KEY,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  device_name TEXT NOT NULL,
+  user_agent TEXT,
+  -- address of the latest request
+  ip TEXT,
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  last_seen_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  expires_at TIMESTAMPTZ NOT NULL,
+  revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX sessions_user_idx ON sessions (user_id);
+
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+
+ALTER TABLE tasks
+  DROP COLUMN IF EXISTS repeat_anchor_date,
+  DROP COLUMN IF EXISTS repeat_anchor_on;
+
+COMMIT;
@@ -0,0 +1,12 @@
+BEGIN;
+
+-- The due date a recurring task was last given by its owner. Completions move
+-- due_date/due_on on to the next occurrence and leave this in place, so that
+-- a task due on the 31st comes back to the 31st after a shorter month.
+ALTER TABLE tasks
+  ADD COLUMN repeat_anchor_date TIMESTAMPTZ,
+  ADD COLUMN repeat_anchor_on DATE;
+
+UPDATE tasks SET repeat_anchor_date = due_date, repeat_anchor_on = due_on;
+
+COMMIT;
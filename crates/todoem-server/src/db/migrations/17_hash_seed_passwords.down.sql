@@ -0,0 +1,3 @@
+-- Nothing to undo: the hashes stand for the same passwords, and plain text
+-- ones are no longer accepted.
+SELECT 1;
@@ -0,0 +1,19 @@
+BEGIN;
+
+-- The seed accounts were stored with their passwords in plain text, which
+-- signing in no longer accepts. Each password is still its username, now as
+-- PBKDF2-SHA256 with few iterations, raised to the configured count on the
+-- next sign-in. Accounts whose password has changed since are left alone.
+UPDATE users SET password = '$pbkdf2-sha256$i=1000$EmCexZ4S0yymqU0dwYwJgQ$zaBKjdGZ7uYIh+OaKzD9g5iy3LcxWrd9zPDk5InfBVc'
+WHERE id = 'c8686820-72ce-4391-bdce-e4f260dea40f' AND password = 'toto';
+
+UPDATE users SET password = '$pbkdf2-sha256$i=1000$04gjILnNcVbysDloXb9arw$doWjPpJBPT9U8nFdMLGhxbmZyOE2GtU84sT5EGyIeOM'
+WHERE id = '5d43fc3c-8acb-48f9-9b25-8f8bd6f3d834' AND password = 'luffy';
+
+UPDATE users SET password = '$pbkdf2-sha256$i=1000$mvkuogb/HTQgIKUK5fkZLQ$lZXcZd1UYkO5CnsioO1hD8XYu1Lfjq+9Bhl99GcMiR8'
+WHERE id = '4157ee44-1de0-4168-a1f3-7ad6a5fd09b6' AND password = 'zoro';
+
+UPDATE users SET password = '$pbkdf2-sha256$i=1000$Dexc9bNj94S0Gp6YbxXlGQ$JCM+9EstV8S8EnJDkEfP/zb3jv94C/9JFBt32MzUu0w'
+WHERE id = 'f6d1dabe-7766-4a6c-b34e-75e444cc3cbd' AND password = 'nami';
+
+COMMIT;
@@ -0,0 +1,16 @@
+BEGIN;
+
+DROP VIEW user_connections;
+DELETE FROM connections WHERE state = 'blocked';
+
+ALTER TYPE connection_state RENAME TO connection_state_old;
+CREATE TYPE connection_state AS ENUM ('pending', 'connected', 'rejected');
+ALTER TABLE connections ALTER COLUMN state TYPE connection_state USING state::text::connection_state;
+DROP TYPE connection_state_old;
+
+CREATE VIEW user_connections AS
+SELECT user_a AS user_id, user_b AS connected_id, updated_at AS connected_at FROM connections WHERE state = 'connected'
+UNION ALL
+SELECT user_b AS user_id, user_a AS connected_id, updated_at AS connected_at FROM connections WHERE state = 'connected';
+
+COMMIT;
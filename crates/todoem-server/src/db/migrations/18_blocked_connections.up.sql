@@ -0,0 +1,27 @@
+BEGIN;
+
+-- Blocked is a state of the pair like the others, so that a block and a
+-- request can never both stand. The row's actor_id is the last user to block;
+-- user_blocks still records every direction, and the row stays blocked until
+-- neither user blocks the other.
+DROP VIEW user_connections;
+
+ALTER TYPE connection_state RENAME TO connection_state_old;
+CREATE TYPE connection_state AS ENUM ('pending', 'connected', 'rejected', 'blocked');
+ALTER TABLE connections ALTER COLUMN state TYPE connection_state USING state::text::connection_state;
+DROP TYPE connection_state_old;
+
+INSERT INTO connections (user_a, user_b, actor_id, state, created_at, updated_at)
+SELECT DISTINCT ON (LEAST(blocker_id, blocked_id), GREATEST(blocker_id, blocked_id))
+  LEAST(blocker_id, blocked_id), GREATEST(blocker_id, blocked_id), blocker_id, 'blocked', blocked_at, blocked_at
+FROM user_blocks
+ORDER BY LEAST(blocker_id, blocked_id), GREATEST(blocker_id, blocked_id), blocked_at DESC
+ON CONFLICT (user_a, user_b) DO UPDATE
+SET state = 'blocked', actor_id = EXCLUDED.actor_id, updated_at = EXCLUDED.updated_at;
+
+CREATE VIEW user_connections AS
+SELECT user_a AS user_id, user_b AS connected_id, updated_at AS connected_at FROM connections WHERE state = 'connected'
+UNION ALL
+SELECT user_b AS user_id, user_a AS connected_id, updated_at AS connected_at FROM connections WHERE state = 'connected';
+
+COMMIT;
@@ -0,0 +1,2 @@
+-- Nothing to undo: the seed accounts are not brought back.
+SELECT 1;
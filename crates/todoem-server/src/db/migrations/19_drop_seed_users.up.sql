@@ -0,0 +1,13 @@
+BEGIN;
+
+-- The accounts seeded by 2_data had their usernames for passwords, and made
+-- every deployment open to anyone who read this repository. They are test
+-- data, and now live in src/db/fixtures/users.sql for the tests alone.
+DELETE FROM users WHERE id IN (
+  'c8686820-72ce-4391-bdce-e4f260dea40f',
+  '5d43fc3c-8acb-48f9-9b25-8f8bd6f3d834',
+  '4157ee44-1de0-4168-a1f3-7ad6a5fd09b6',
+  'f6d1dabe-7766-4a6c-b34e-75e444cc3cbd'
+);
+
+COMMIT;
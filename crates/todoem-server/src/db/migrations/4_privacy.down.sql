@@ -0,0 +1,12 @@
+BEGIN;
+
+DROP TABLE IF EXISTS user_blocks;
+
+ALTER TABLE users
+  DROP COLUMN IF EXISTS search_visibility,
+  DROP COLUMN IF EXISTS request_policy;
+
+DROP TYPE IF EXISTS search_visibility;
+DROP TYPE IF EXISTS request_policy;
+
+COMMIT;
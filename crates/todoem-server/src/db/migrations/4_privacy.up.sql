@@ -0,0 +1,19 @@
+BEGIN;
+
+CREATE TYPE search_visibility AS ENUM ('everyone', 'username', 'nobody');
+CREATE TYPE request_policy AS ENUM ('everyone', 'friends_of_friends');
+
+ALTER TABLE users
+  ADD COLUMN search_visibility search_visibility NOT NULL DEFAULT 'everyone',
+  ADD COLUMN request_policy request_policy NOT NULL DEFAULT 'everyone';
+
+CREATE TABLE IF NOT EXISTS user_blocks (
+  blocker_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  blocked_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  blocked_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  PRIMARY KEY (blocker_id, blocked_id)
+);
+
+CREATE INDEX user_blocks_blocked_idx ON user_blocks (blocked_id);
+
+COMMIT;
@@ -0,0 +1,31 @@
+BEGIN;
+
+DROP VIEW IF EXISTS user_connections;
+
+CREATE TABLE IF NOT EXISTS user_connections (
+user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+connected_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+connected_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+PRIMARY KEY (user_id, connected_id)
+);
+
+CREATE TABLE IF NOT EXISTS user_connection_requests (
+sender_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+receiver_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+sent_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+PRIMARY KEY (sender_id, receiver_id)
+);
+
+INSERT INTO user_connections (user_id, connected_id, connected_at)
+SELECT user_a, user_b, updated_at FROM connections WHERE state = 'connected'
+UNION ALL
+SELECT user_b, user_a, updated_at FROM connections WHERE state = 'connected';
+
+INSERT INTO user_connection_requests (sender_id, receiver_id, sent_at)
+SELECT actor_id, CASE WHEN actor_id = user_a THEN user_b ELSE user_a END, updated_at
+FROM connections WHERE state = 'pending';
+
+DROP TABLE IF EXISTS connections;
+DROP TYPE IF EXISTS connection_state;
+
+COMMIT;
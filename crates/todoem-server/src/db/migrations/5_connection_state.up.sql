@@ -0,0 +1,46 @@
+BEGIN;
+
+CREATE TYPE connection_state AS ENUM ('pending', 'connected', 'rejected');
+
+-- One row per unordered pair of users; no row means no relationship.
+-- actor_id is whoever made the last transition: the requester while pending,
+-- the accepter once connected and the rejecter once rejected. Blocks stay in
+-- user_blocks, one row per direction, so both users of a pair can block the other.
+CREATE TABLE IF NOT EXISTS connections (
+  user_a UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  user_b UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  actor_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  state connection_state NOT NULL,
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  PRIMARY KEY (user_a, user_b),
+  CHECK (user_a < user_b),
+  CHECK (actor_id = user_a OR actor_id = user_b)
+);
+
+CREATE INDEX connections_user_b_idx ON connections (user_b);
+
+INSERT INTO connections (user_a, user_b, actor_id, state, created_at, updated_at)
+SELECT user_id, connected_id, user_id, 'connected', connected_at, connected_at
+FROM user_connections WHERE user_id < connected_id
+ON CONFLICT DO NOTHING;
+
+INSERT INTO connections (user_a, user_b, actor_id, state, created_at, updated_at)
+SELECT LEAST(sender_id, receiver_id), GREATEST(sender_id, receiver_id), sender_id, 'pending', sent_at, sent_at
+FROM user_connection_requests
+ON CONFLICT DO NOTHING;
+
+-- a block ends whatever relationship the pair had
+DELETE FROM connections c USING user_blocks b
+WHERE c.user_a = LEAST(b.blocker_id, b.blocked_id) AND c.user_b = GREATEST(b.blocker_id, b.blocked_id);
+
+DROP TABLE user_connections;
+DROP TABLE user_connection_requests;
+
+-- Both directions of every established connection, for read queries.
+CREATE VIEW user_connections AS
+SELECT user_a AS user_id, user_b AS connected_id, updated_at AS connected_at FROM connections WHERE state = 'connected'
+UNION ALL
+SELECT user_b AS user_id, user_a AS connected_id, updated_at AS connected_at FROM connections WHERE state = 'connected';
+
+COMMIT;
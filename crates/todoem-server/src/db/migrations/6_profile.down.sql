@@ -0,0 +1,14 @@
+BEGIN;
+
+DROP INDEX IF EXISTS users_deletion_requested_idx;
+DROP INDEX IF EXISTS users_username_unique_idx;
+
+ALTER TABLE users
+  DROP COLUMN IF EXISTS bio,
+  DROP COLUMN IF EXISTS timezone,
+  DROP COLUMN IF EXISTS locale,
+  DROP COLUMN IF EXISTS avatar_key,
+  DROP COLUMN IF EXISTS username_changed_at,
+  DROP COLUMN IF EXISTS deletion_requested_at;
+
+COMMIT;
@@ -0,0 +1,14 @@
+BEGIN;
+
+ALTER TABLE users
+  ADD COLUMN bio TEXT,
+  ADD COLUMN timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+  ADD COLUMN locale VARCHAR(35) NOT NULL DEFAULT 'en',
+  ADD COLUMN avatar_key TEXT,
+  ADD COLUMN username_changed_at TIMESTAMPTZ,
+  ADD COLUMN deletion_requested_at TIMESTAMPTZ;
+
+CREATE UNIQUE INDEX users_username_unique_idx ON users (lower(username));
+CREATE INDEX users_deletion_requested_idx ON users (deletion_requested_at) WHERE deletion_requested_at IS NOT NULL;
+
+COMMIT;
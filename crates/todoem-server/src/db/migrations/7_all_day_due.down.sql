@@ -0,0 +1,10 @@
+BEGIN;
+
+DROP INDEX IF EXISTS tasks_user_due_on_idx;
+DROP INDEX IF EXISTS tasks_user_due_date_idx;
+
+ALTER TABLE tasks
+  DROP CONSTRAINT IF EXISTS tasks_single_due_check,
+  DROP COLUMN IF EXISTS due_on;
+
+COMMIT;
@@ -0,0 +1,10 @@
+BEGIN;
+
+ALTER TABLE tasks
+  ADD COLUMN due_on DATE,
+  ADD CONSTRAINT tasks_single_due_check CHECK (due_date IS NULL OR due_on IS NULL);
+
+CREATE INDEX tasks_user_due_date_idx ON tasks (user_id, due_date) WHERE due_date IS NOT NULL;
+CREATE INDEX tasks_user_due_on_idx ON tasks (user_id, due_on) WHERE due_on IS NOT NULL;
+
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+
+DROP TABLE IF EXISTS task_completions;
+
+ALTER TABLE tasks DROP COLUMN IF EXISTS done_at;
+
+COMMIT;
@@ -0,0 +1,23 @@
+BEGIN;
+
+ALTER TABLE tasks ADD COLUMN done_at TIMESTAMPTZ;
+
+-- one row per completion, kept when the task itself is deleted
+CREATE TABLE IF NOT EXISTS task_completions (
+  id BIGSERIAL PRIMARY KEY,
+  task_id BIGINT REFERENCES tasks(id) ON DELETE SET NULL,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  completed_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+  -- the occurrence that was completed
+  due_date TIMESTAMPTZ,
+  due_on DATE,
+  recurring BOOLEAN NOT NULL DEFAULT FALSE,
+  -- occurrences of a recurring task passed over by this completion
+  skipped INT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX task_completions_user_idx ON task_completions (user_id, completed_at);
+CREATE INDEX task_completions_task_idx ON task_completions (task_id, completed_at DESC);
+
+COMMIT;
@@ -0,0 +1,6 @@
+use sqlx::migrate::Migrator;
+
+pub mod query;
+
+/// The migrations built into the binary, run at startup.
+pub static MIGRATOR: Migrator = sqlx::migrate!("src/db/migrations");
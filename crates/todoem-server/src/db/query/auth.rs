@@ -0,0 +1,168 @@
+use sqlx::PgPool;
+use todoem_core::{
+    errors::APIError,
+    models::email::{Credentials, EmailGrant, EmailTokenPurpose},
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+const CREDENTIALS: &str = "id, username, email, password";
+
+/// Looks an account up by username, or else by email address.
+#[instrument(skip_all)]
+pub async fn select_credentials(
+    pool: &PgPool,
+    login: &str,
+) -> Result<Option<Credentials>, APIError> {
+    match sqlx::query_as::<_, Credentials>(&format!(
+        "
+        SELECT {} FROM users
+        WHERE username = $1 OR lower(email) = lower($1)
+        ORDER BY username = $1 DESC, created_at
+        LIMIT 1;
+        ",
+        CREDENTIALS
+    ))
+    .bind(login)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(credentials) => Ok(credentials),
+        Err(e) => {
+            tracing::error!("Failed to select credentials: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Accounts registered with `email` that are not being deleted.
+#[instrument(skip_all)]
+pub async fn select_accounts_by_email(
+    pool: &PgPool,
+    email: &str,
+) -> Result<Vec<Credentials>, APIError> {
+    match sqlx::query_as::<_, Credentials>(&format!(
+        "
+        SELECT {} FROM users
+        WHERE lower(email) = lower($1) AND deletion_requested_at IS NULL;
+        ",
+        CREDENTIALS
+    ))
+    .bind(email)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(accounts) => Ok(accounts),
+        Err(e) => {
+            tracing::error!("Failed to select accounts by email: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn update_password(pool: &PgPool, user_id: Uuid, hash: &str) -> Result<(), APIError> {
+    match sqlx::query("UPDATE users SET password = $2 WHERE id = $1;")
+        .bind(user_id)
+        .bind(hash)
+        .execute(pool)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to update password: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Stores a new token, replacing any unused ones the user had for the same
+/// purpose so that only the latest email works.
+#[instrument(skip_all)]
+pub async fn insert_email_token(
+    pool: &PgPool,
+    user_id: Uuid,
+    purpose: EmailTokenPurpose,
+    email: &str,
+    token_hash: &str,
+    ttl_secs: f64,
+) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        WITH superseded AS (
+            DELETE FROM email_tokens WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL
+        )
+        INSERT INTO email_tokens (token_hash, user_id, purpose, email, expires_at)
+        VALUES ($4, $1, $2, $3, CURRENT_TIMESTAMP + make_interval(secs => $5));
+        ",
+    )
+    .bind(user_id)
+    .bind(purpose)
+    .bind(email)
+    .bind(token_hash)
+    .bind(ttl_secs)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to insert email token: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Marks a token used, if it is unused and unexpired.
+#[instrument(skip_all)]
+pub async fn consume_email_token(
+    pool: &PgPool,
+    token_hash: &str,
+    purpose: EmailTokenPurpose,
+) -> Result<Option<EmailGrant>, APIError> {
+    match sqlx::query_as::<_, EmailGrant>(
+        "
+        UPDATE email_tokens SET used_at = CURRENT_TIMESTAMP
+        WHERE token_hash = $1 AND purpose = $2
+            AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+        RETURNING user_id, email;
+        ",
+    )
+    .bind(token_hash)
+    .bind(purpose)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(grant) => Ok(grant),
+        Err(e) => {
+            tracing::error!("Failed to consume email token: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Marks the user's email verified, unless it has changed since `email` was
+/// sent the token. Returns whether it was.
+#[instrument(skip_all)]
+pub async fn mark_email_verified(
+    pool: &PgPool,
+    user_id: Uuid,
+    email: &str,
+) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        UPDATE users SET email_verified_at = COALESCE(email_verified_at, CURRENT_TIMESTAMP)
+        WHERE id = $1 AND email = $2;
+        ",
+    )
+    .bind(user_id)
+    .bind(email)
+    .execute(pool)
+    .await
+    {
+        Ok(res) => Ok(res.rows_affected() > 0),
+        Err(e) => {
+            tracing::error!("Failed to mark email verified: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
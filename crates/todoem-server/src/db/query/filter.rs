@@ -0,0 +1,137 @@
+use http::StatusCode;
+use sqlx::{types::Json, PgPool};
+use todoem_core::{
+    errors::APIError, models::filter::SavedFilter, types::filter::SaveFilterRequest,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+fn save_error(e: sqlx::Error, action: &str) -> APIError {
+    if let sqlx::Error::Database(db) = &e {
+        if db.is_unique_violation() {
+            return APIError::new(
+                StatusCode::CONFLICT,
+                "A filter with this name already exists",
+            );
+        }
+    }
+    tracing::error!("Failed to {} filter: {:?}", action, e);
+    APIError::server()
+}
+
+#[instrument(skip_all)]
+pub async fn insert_filter(
+    pool: &PgPool,
+    user_id: Uuid,
+    req: SaveFilterRequest,
+) -> Result<SavedFilter, APIError> {
+    sqlx::query_as::<_, SavedFilter>(
+        "
+        INSERT INTO saved_filters (user_id, name, filter)
+        VALUES ($1, $2, $3) RETURNING *;
+        ",
+    )
+    .bind(user_id)
+    .bind(req.name.trim())
+    .bind(Json(req.filter))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| save_error(e, "insert"))
+}
+
+#[instrument(skip_all)]
+pub async fn select_filters(pool: &PgPool, user_id: Uuid) -> Result<Vec<SavedFilter>, APIError> {
+    match sqlx::query_as::<_, SavedFilter>(
+        "
+        SELECT * FROM saved_filters WHERE user_id = $1 ORDER BY lower(name);
+        ",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(filters) => Ok(filters),
+        Err(e) => {
+            tracing::error!("Failed to select filters: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_filter(
+    pool: &PgPool,
+    user_id: Uuid,
+    filter_id: i64,
+) -> Result<SavedFilter, APIError> {
+    match sqlx::query_as::<_, SavedFilter>(
+        "
+        SELECT * FROM saved_filters WHERE id = $1 AND user_id = $2;
+        ",
+    )
+    .bind(filter_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(filter) => Ok(filter),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to select filter: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn update_filter(
+    pool: &PgPool,
+    user_id: Uuid,
+    filter_id: i64,
+    req: SaveFilterRequest,
+) -> Result<SavedFilter, APIError> {
+    match sqlx::query_as::<_, SavedFilter>(
+        "
+        UPDATE saved_filters SET name = $3, filter = $4, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND user_id = $2 RETURNING *;
+        ",
+    )
+    .bind(filter_id)
+    .bind(user_id)
+    .bind(req.name.trim())
+    .bind(Json(req.filter))
+    .fetch_one(pool)
+    .await
+    {
+        Ok(filter) => Ok(filter),
+        Err(sqlx::Error::RowNotFound) => Err(APIError::not_found()),
+        Err(e) => Err(save_error(e, "update")),
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn delete_filter(pool: &PgPool, user_id: Uuid, filter_id: i64) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        DELETE FROM saved_filters WHERE id = $1 AND user_id = $2;
+        ",
+    )
+    .bind(filter_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    {
+        Ok(row) => {
+            if row.rows_affected() == 0 {
+                return Err(APIError::not_found());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete filter: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::{
+    errors::APIError,
+    models::identity::{Identity, PendingLogin, Signup},
+    models::AuthUser,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Records a sign-in sent to a provider, dropping the ones older than `ttl_secs`.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all)]
+pub async fn insert_login(
+    pool: &PgPool,
+    state: &str,
+    provider: &str,
+    nonce: &str,
+    code_verifier: &str,
+    redirect_to: &str,
+    link_user_id: Option<Uuid>,
+    ttl_secs: f64,
+) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        WITH expired AS (
+            DELETE FROM oidc_logins
+            WHERE created_at < CURRENT_TIMESTAMP - make_interval(secs => $7)
+        )
+        INSERT INTO oidc_logins (state, provider, nonce, code_verifier, redirect_to, link_user_id)
+        VALUES ($1, $2, $3, $4, $5, $6);
+        ",
+    )
+    .bind(state)
+    .bind(provider)
+    .bind(nonce)
+    .bind(code_verifier)
+    .bind(redirect_to)
+    .bind(link_user_id)
+    .bind(ttl_secs)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to insert oidc login: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Consumes a pending sign-in, so that each `state` is only redeemed once.
+#[instrument(skip_all)]
+pub async fn take_login(
+    pool: &PgPool,
+    state: &str,
+    provider: &str,
+    ttl_secs: f64,
+) -> Result<Option<PendingLogin>, APIError> {
+    match sqlx::query_as::<_, PendingLogin>(
+        "
+        DELETE FROM oidc_logins
+        WHERE state = $1 AND provider = $2
+            AND created_at >= CURRENT_TIMESTAMP - make_interval(secs => $3)
+        RETURNING provider, nonce, code_verifier, redirect_to, link_user_id;
+        ",
+    )
+    .bind(state)
+    .bind(provider)
+    .bind(ttl_secs)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(login) => Ok(login),
+        Err(e) => {
+            tracing::error!("Failed to take oidc login: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// The user an identity is linked to, recording the sign-in.
+#[instrument(skip_all)]
+pub async fn select_identity_user(
+    pool: &PgPool,
+    provider: &str,
+    subject: &str,
+) -> Result<Option<AuthUser>, APIError> {
+    match sqlx::query_as::<_, AuthUser>(
+        "
+        UPDATE user_identities i SET last_login_at = CURRENT_TIMESTAMP
+        FROM users u
+        WHERE u.id = i.user_id AND i.provider = $1 AND i.subject = $2
+        RETURNING u.id, u.email;
+        ",
+    )
+    .bind(provider)
+    .bind(subject)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(user) => Ok(user),
+        Err(e) => {
+            tracing::error!("Failed to select identity user: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn insert_identity_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    provider: &str,
+    subject: &str,
+    email: Option<&str>,
+) -> Result<Identity, APIError> {
+    match sqlx::query_as::<_, Identity>(
+        "
+        INSERT INTO user_identities (user_id, provider, subject, email, last_login_at)
+        VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)
+        RETURNING id, provider, email, created_at, last_login_at;
+        ",
+    )
+    .bind(user_id)
+    .bind(provider)
+    .bind(subject)
+    .bind(email)
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(identity) => Ok(identity),
+        Err(e) => {
+            if let sqlx::Error::Database(db) = &e {
+                if db.is_unique_violation() {
+                    return Err(APIError::new(
+                        StatusCode::CONFLICT,
+                        "An account of this provider is already linked",
+                    ));
+                }
+            }
+            tracing::error!("Failed to insert identity: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_identities(pool: &PgPool, user_id: Uuid) -> Result<Vec<Identity>, APIError> {
+    match sqlx::query_as::<_, Identity>(
+        "
+        SELECT id, provider, email, created_at, last_login_at
+        FROM user_identities WHERE user_id = $1 ORDER BY id;
+        ",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(identities) => Ok(identities),
+        Err(e) => {
+            tracing::error!("Failed to select identities: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Unlinks an identity unless it is the only way left to sign in.
+/// Returns `false` when nothing was unlinked.
+#[instrument(skip_all)]
+pub async fn delete_identity(
+    pool: &PgPool,
+    user_id: Uuid,
+    identity_id: i64,
+) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        DELETE FROM user_identities
+        WHERE id = $1 AND user_id = $2 AND (
+            (SELECT COUNT(*) FROM user_identities WHERE user_id = $2) > 1
+            OR (SELECT password IS NOT NULL FROM users WHERE id = $2)
+        );
+        ",
+    )
+    .bind(identity_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    {
+        Ok(r) => Ok(r.rows_affected() == 1),
+        Err(e) => {
+            tracing::error!("Failed to delete identity: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Records a first sign-in, dropping the ones older than `ttl_secs`.
+#[instrument(skip_all)]
+pub async fn insert_signup(
+    pool: &PgPool,
+    token_hash: &str,
+    signup: &Signup,
+    ttl_secs: f64,
+) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        WITH expired AS (
+            DELETE FROM oidc_signups
+            WHERE created_at < CURRENT_TIMESTAMP - make_interval(secs => $7)
+        )
+        INSERT INTO oidc_signups (token_hash, provider, subject, email, name, username)
+        VALUES ($1, $2, $3, $4, $5, $6);
+        ",
+    )
+    .bind(token_hash)
+    .bind(&signup.provider)
+    .bind(&signup.subject)
+    .bind(&signup.email)
+    .bind(&signup.name)
+    .bind(&signup.username)
+    .bind(ttl_secs)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to insert oidc signup: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_signup(
+    pool: &PgPool,
+    token_hash: &str,
+    ttl_secs: f64,
+) -> Result<Option<Signup>, APIError> {
+    match sqlx::query_as::<_, Signup>(
+        "
+        SELECT provider, subject, email, name, username FROM oidc_signups
+        WHERE token_hash = $1 AND created_at >= CURRENT_TIMESTAMP - make_interval(secs => $2);
+        ",
+    )
+    .bind(token_hash)
+    .bind(ttl_secs)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(signup) => Ok(signup),
+        Err(e) => {
+            tracing::error!("Failed to select oidc signup: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn take_signup_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    token_hash: &str,
+    ttl_secs: f64,
+) -> Result<Option<Signup>, APIError> {
+    match sqlx::query_as::<_, Signup>(
+        "
+        DELETE FROM oidc_signups
+        WHERE token_hash = $1 AND created_at >= CURRENT_TIMESTAMP - make_interval(secs => $2)
+        RETURNING provider, subject, email, name, username;
+        ",
+    )
+    .bind(token_hash)
+    .bind(ttl_secs)
+    .fetch_optional(&mut **tx)
+    .await
+    {
+        Ok(signup) => Ok(signup),
+        Err(e) => {
+            tracing::error!("Failed to take oidc signup: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Creates an account without a password.
+#[instrument(skip_all)]
+pub async fn insert_user_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    username: &str,
+    email: &str,
+    name: Option<&str>,
+) -> Result<AuthUser, APIError> {
+    match sqlx::query_as::<_, AuthUser>(
+        "
+        INSERT INTO users (id, username, email, name) VALUES ($1, $2, $3, $4)
+        RETURNING id, email;
+        ",
+    )
+    .bind(Uuid::new_v4())
+    .bind(username)
+    .bind(email)
+    .bind(name)
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(user) => Ok(user),
+        Err(e) => {
+            if let sqlx::Error::Database(db) = &e {
+                if db.is_unique_violation() {
+                    return Err(APIError::new(
+                        StatusCode::CONFLICT,
+                        "Username is already taken",
+                    ));
+                }
+            }
+            tracing::error!("Failed to insert user: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use super::{offset, PAGE_LIMIT};
+use sqlx::PgPool;
+use todoem_core::errors::APIError;
+use todoem_core::models::list::{List, ListTask};
+use todoem_core::types::list::CreateListRequest;
+use tracing::instrument;
+use uuid::Uuid;
+
+#[instrument(skip_all)]
+pub async fn insert_list_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    list: CreateListRequest,
+) -> Result<List, APIError> {
+    let created = match sqlx::query_as::<_, List>(
+        "
+    INSERT INTO lists (id, user_id, name, description, task_count)
+    VALUES ($1, $2, $3, $4, $5) RETURNING id, user_id, name, COALESCE(description, '') AS description, task_count, done, sent_by_id, sent_at;
+    ",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(&list.name)
+    .bind(list.description.as_deref().unwrap_or_default())
+    .bind(list.tasks.len() as i16)
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(created) => created,
+        Err(e) => {
+            tracing::error!("Failed to insert list: {:?}", e);
+            return Err(APIError::server());
+        }
+    };
+
+    if let Err(e) = sqlx::query(
+        "
+    INSERT INTO list_tasks (list_id, task, description)
+    SELECT $1, task, '' FROM UNNEST($2::text[]) WITH ORDINALITY AS t(task, n) ORDER BY n;
+    ",
+    )
+    .bind(created.id)
+    .bind(&list.tasks)
+    .execute(&mut **tx)
+    .await
+    {
+        tracing::error!("Failed to insert list tasks: {:?}", e);
+        return Err(APIError::server());
+    }
+
+    Ok(created)
+}
+
+/// The user's lists, newest first.
+#[instrument(skip_all)]
+pub async fn select_lists(pool: &PgPool, user_id: Uuid, page: i16) -> Result<Vec<List>, APIError> {
+    match sqlx::query_as::<_, List>(
+        "
+    SELECT id, user_id, name, COALESCE(description, '') AS description, task_count, done, sent_by_id, sent_at FROM lists WHERE user_id = $1
+    ORDER BY sent_at DESC, id LIMIT $2 OFFSET $3;
+    ",
+    )
+    .bind(user_id)
+    .bind(PAGE_LIMIT)
+    .bind(offset(page))
+    .fetch_all(pool)
+    .await
+    {
+        Ok(lists) => Ok(lists),
+        Err(e) => {
+            tracing::error!("Failed to select lists: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_list(pool: &PgPool, user_id: Uuid, list_id: Uuid) -> Result<List, APIError> {
+    match sqlx::query_as::<_, List>(
+        "SELECT id, user_id, name, COALESCE(description, '') AS description, task_count, done, sent_by_id, sent_at FROM lists WHERE id = $1 AND user_id = $2;",
+    )
+    .bind(list_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(list)) => Ok(list),
+        Ok(None) => Err(APIError::not_found()),
+        Err(e) => {
+            tracing::error!("Failed to select list: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_list_tasks(pool: &PgPool, list_id: Uuid) -> Result<Vec<ListTask>, APIError> {
+    match sqlx::query_as::<_, ListTask>(
+        "
+    SELECT id, list_id, task, COALESCE(description, '') AS description, done
+    FROM list_tasks WHERE list_id = $1 ORDER BY id;
+    ",
+    )
+    .bind(list_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            tracing::error!("Failed to select list tasks: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Copies `list_id` of `sender_id`, with its tasks, into the lists of
+/// `recipient_id`. Returns `None` if it was sent to them before.
+#[instrument(skip_all)]
+pub async fn send_list_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    sender_id: Uuid,
+    list_id: Uuid,
+    recipient_id: Uuid,
+) -> Result<Option<List>, APIError> {
+    match sqlx::query(
+        "
+    INSERT INTO list_sent_to_users (list_id, user_id)
+    SELECT id, $3 FROM lists WHERE id = $1 AND user_id = $2
+    ON CONFLICT DO NOTHING;
+    ",
+    )
+    .bind(list_id)
+    .bind(sender_id)
+    .bind(recipient_id)
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(r) if r.rows_affected() == 0 => return Ok(None),
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Failed to record sent list: {:?}", e);
+            return Err(APIError::server());
+        }
+    }
+
+    let received = match sqlx::query_as::<_, List>(
+        "
+    INSERT INTO lists (id, user_id, name, description, task_count, sent_by_id)
+    SELECT $1, $4, name, description, task_count, user_id FROM lists WHERE id = $2 AND user_id = $3
+    RETURNING id, user_id, name, COALESCE(description, '') AS description, task_count, done, sent_by_id, sent_at;
+    ",
+    )
+    .bind(Uuid::new_v4())
+    .bind(list_id)
+    .bind(sender_id)
+    .bind(recipient_id)
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(received) => received,
+        Err(e) => {
+            tracing::error!("Failed to copy sent list: {:?}", e);
+            return Err(APIError::server());
+        }
+    };
+
+    // the recipient gets the tasks as they are now, none of them done
+    if let Err(e) = sqlx::query(
+        "
+    INSERT INTO list_tasks (list_id, task, description)
+    SELECT $1, task, description FROM list_tasks WHERE list_id = $2 ORDER BY id;
+    ",
+    )
+    .bind(received.id)
+    .bind(list_id)
+    .execute(&mut **tx)
+    .await
+    {
+        tracing::error!("Failed to copy sent list tasks: {:?}", e);
+        return Err(APIError::server());
+    }
+
+    Ok(Some(received))
+}
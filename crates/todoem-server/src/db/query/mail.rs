@@ -0,0 +1,97 @@
+use crate::services::mail::Message;
+use sqlx::PgPool;
+use todoem_core::{errors::APIError, models::email::OutboundEmail};
+use tracing::instrument;
+
+#[instrument(skip_all)]
+pub async fn enqueue_email(pool: &PgPool, message: &Message) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        INSERT INTO outbound_emails (to_address, subject, body) VALUES ($1, $2, $3);
+        ",
+    )
+    .bind(&message.to)
+    .bind(&message.subject)
+    .bind(&message.body)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to enqueue email: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Claims up to `limit` due emails. Each is pushed back by `lease_secs` so that
+/// another worker does not pick it up while it is being sent.
+#[instrument(skip_all)]
+pub async fn claim_due_emails(
+    pool: &PgPool,
+    limit: i64,
+    lease_secs: f64,
+) -> Result<Vec<OutboundEmail>, APIError> {
+    match sqlx::query_as::<_, OutboundEmail>(
+        "
+        UPDATE outbound_emails
+        SET attempts = attempts + 1,
+            next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $2)
+        WHERE id IN (
+            SELECT id FROM outbound_emails
+            WHERE status = 'pending' AND next_attempt_at <= CURRENT_TIMESTAMP
+            ORDER BY next_attempt_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, to_address, subject, body, attempts;
+        ",
+    )
+    .bind(limit)
+    .bind(lease_secs)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(emails) => Ok(emails),
+        Err(e) => {
+            tracing::error!("Failed to claim outbound emails: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Records the outcome of an attempt. Sent emails are deleted. A failed one is
+/// retried after `retry_in_secs`, or given up on when that is `None`, in which
+/// case its body (which may hold a live token) is dropped.
+#[instrument(skip_all)]
+pub async fn record_email_attempt(
+    pool: &PgPool,
+    email_id: i64,
+    error: Option<&str>,
+    retry_in_secs: Option<f64>,
+) -> Result<(), APIError> {
+    let query = match error {
+        None => sqlx::query("DELETE FROM outbound_emails WHERE id = $1;").bind(email_id),
+        Some(error) => sqlx::query(
+            "
+            UPDATE outbound_emails SET
+                status = CASE WHEN $3::FLOAT8 IS NULL THEN 'failed' ELSE 'pending' END::email_status,
+                body = CASE WHEN $3::FLOAT8 IS NULL THEN '' ELSE body END,
+                next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => COALESCE($3, 0)),
+                error = $2
+            WHERE id = $1;
+            ",
+        )
+        .bind(email_id)
+        .bind(error)
+        .bind(retry_in_secs),
+    };
+
+    match query.execute(pool).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to record email attempt: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use chrono::{DateTime, Duration, Utc};
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::{errors::APIError, models::user as M, types::me::UpdateProfileRequest};
+use tracing::instrument;
+use uuid::Uuid;
+
+const PROFILE: &str = "
+    id, username, name, email, bio, timezone, locale,
+    avatar_key IS NOT NULL AS has_avatar, email_verified_at, created_at, deletion_requested_at
+";
+
+#[instrument(skip_all)]
+pub async fn select_profile(pool: &PgPool, user_id: Uuid) -> Result<M::Profile, APIError> {
+    match sqlx::query_as::<_, M::Profile>(&format!("SELECT {} FROM users WHERE id = $1;", PROFILE))
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(profile) => Ok(profile),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to select profile: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// The user's IANA time zone name.
+#[instrument(skip_all)]
+pub async fn select_timezone(pool: &PgPool, user_id: Uuid) -> Result<String, APIError> {
+    match sqlx::query_scalar::<_, String>("SELECT timezone FROM users WHERE id = $1;")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(timezone) => Ok(timezone),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to select timezone: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Renames the user unless they already did so within the last `cooldown_days`.
+/// Returns `false` when the cooldown has not passed yet.
+#[instrument(skip_all)]
+pub async fn rename_user_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    username: &str,
+    cooldown_days: i32,
+) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        UPDATE users SET username = $2, username_changed_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND (
+            username_changed_at IS NULL
+            OR username_changed_at < CURRENT_TIMESTAMP - make_interval(days => $3)
+        );
+        ",
+    )
+    .bind(user_id)
+    .bind(username)
+    .bind(cooldown_days)
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(r) => Ok(r.rows_affected() == 1),
+        Err(e) => {
+            if let sqlx::Error::Database(db) = &e {
+                if db.is_unique_violation() {
+                    return Err(APIError::new(
+                        StatusCode::CONFLICT,
+                        "Username is already taken",
+                    ));
+                }
+            }
+            tracing::error!("Failed to rename user: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Applies the optional profile fields of `req`; an empty `name` or `bio` clears it.
+#[instrument(skip_all)]
+pub async fn update_profile_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    req: &UpdateProfileRequest,
+) -> Result<M::Profile, APIError> {
+    match sqlx::query_as::<_, M::Profile>(&format!(
+        "
+        UPDATE users SET
+            name = CASE WHEN $2::text IS NULL THEN name ELSE NULLIF(trim($2), '') END,
+            bio = CASE WHEN $3::text IS NULL THEN bio ELSE NULLIF(trim($3), '') END,
+            timezone = COALESCE($4, timezone),
+            locale = COALESCE($5, locale)
+        WHERE id = $1
+        RETURNING {};
+        ",
+        PROFILE
+    ))
+    .bind(user_id)
+    .bind(&req.name)
+    .bind(&req.bio)
+    .bind(&req.timezone)
+    .bind(&req.locale)
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(profile) => Ok(profile),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to update profile: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Points the user at a new avatar and returns the key of the one it replaced.
+#[instrument(skip_all)]
+pub async fn update_avatar_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: Option<&str>,
+) -> Result<Option<String>, APIError> {
+    match sqlx::query_scalar::<_, Option<String>>(
+        "
+        UPDATE users u SET avatar_key = $2
+        FROM (SELECT avatar_key FROM users WHERE id = $1 FOR UPDATE) old
+        WHERE u.id = $1
+        RETURNING old.avatar_key;
+        ",
+    )
+    .bind(user_id)
+    .bind(key)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(old) => Ok(old),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to update avatar: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Starts the deletion grace period, keeping the original date if already started.
+#[instrument(skip_all)]
+pub async fn schedule_deletion(pool: &PgPool, user_id: Uuid) -> Result<DateTime<Utc>, APIError> {
+    match sqlx::query_scalar::<_, DateTime<Utc>>(
+        "
+        UPDATE users SET deletion_requested_at = COALESCE(deletion_requested_at, CURRENT_TIMESTAMP)
+        WHERE id = $1 RETURNING deletion_requested_at;
+        ",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(requested_at) => Ok(requested_at),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to schedule account deletion: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn cancel_deletion(pool: &PgPool, user_id: Uuid) -> Result<bool, APIError> {
+    match sqlx::query(
+        "UPDATE users SET deletion_requested_at = NULL WHERE id = $1 AND deletion_requested_at IS NOT NULL;",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await
+    {
+        Ok(r) => Ok(r.rows_affected() == 1),
+        Err(e) => {
+            tracing::error!("Failed to cancel account deletion: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Deletes every account whose grace period ended, together with groups left
+/// without members. Tasks, lists and connections go with the `users` row via
+/// `ON DELETE CASCADE`. Returns the avatar keys of the deleted accounts.
+#[instrument(skip_all)]
+pub async fn purge_deleted_accounts(
+    pool: &PgPool,
+    grace_days: i32,
+) -> Result<Vec<String>, APIError> {
+    let cutoff = Utc::now() - Duration::days(grace_days as i64);
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            tracing::error!("Failed to start transaction: {:?}", e);
+            return Err(APIError::server());
+        }
+    };
+
+    if let Err(e) = sqlx::query(
+        "
+        WITH purged AS (SELECT id FROM users WHERE deletion_requested_at < $1)
+        DELETE FROM groups g
+        WHERE EXISTS (
+            SELECT 1 FROM group_users gu WHERE gu.group_id = g.id AND gu.user_id IN (SELECT id FROM purged)
+        )
+        AND NOT EXISTS (
+            SELECT 1 FROM group_users gu WHERE gu.group_id = g.id AND gu.user_id NOT IN (SELECT id FROM purged)
+        );
+        ",
+    )
+    .bind(cutoff)
+    .execute(&mut *tx)
+    .await
+    {
+        tracing::error!("Failed to purge groups of deleted accounts: {:?}", e);
+        return Err(APIError::server());
+    }
+
+    let avatars = match sqlx::query_scalar::<_, Option<String>>(
+        "DELETE FROM users WHERE deletion_requested_at < $1 RETURNING avatar_key;",
+    )
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await
+    {
+        Ok(avatars) => avatars,
+        Err(e) => {
+            tracing::error!("Failed to purge deleted accounts: {:?}", e);
+            return Err(APIError::server());
+        }
+    };
+
+    match tx.commit().await {
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            return Err(APIError::server());
+        }
+    }
+
+    if !avatars.is_empty() {
+        tracing::info!("Purged {} deleted accounts", avatars.len());
+    }
+
+    Ok(avatars.into_iter().flatten().collect())
+}
+
+/// Avatar of a user that is not pending deletion.
+#[instrument(skip_all)]
+pub async fn select_avatar_key(pool: &PgPool, user_id: Uuid) -> Result<Option<String>, APIError> {
+    match sqlx::query_scalar::<_, Option<String>>(
+        "SELECT avatar_key FROM users WHERE id = $1 AND deletion_requested_at IS NULL;",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(key) => Ok(key.flatten()),
+        Err(e) => {
+            tracing::error!("Failed to select avatar: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
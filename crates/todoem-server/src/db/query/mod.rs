@@ -0,0 +1,23 @@
+pub mod auth;
+pub mod filter;
+pub mod health;
+pub mod identity;
+pub mod list;
+pub mod mail;
+pub mod me;
+pub mod session;
+pub mod stats;
+pub mod task;
+pub mod token;
+pub mod two_factor;
+pub mod user;
+pub mod webhook;
+
+const PAGE_LIMIT: i16 = 10;
+
+fn offset(page: i16) -> i16 {
+    (page - 1) * PAGE_LIMIT
+}
+
+#[cfg(test)]
+mod tests;
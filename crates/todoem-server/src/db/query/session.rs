@@ -0,0 +1,164 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use todoem_core::{errors::APIError, models::session::Session};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Sessions are kept this long after they end, then purged.
+const RETENTION_DAYS: i32 = 30;
+
+#[instrument(skip_all)]
+pub async fn insert_session(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+    device_name: &str,
+    user_agent: Option<&str>,
+    ip: Option<&str>,
+    expires_at: DateTime<Utc>,
+) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        INSERT INTO sessions (id, user_id, device_name, user_agent, ip, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6);
+        ",
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(device_name)
+    .bind(user_agent)
+    .bind(ip)
+    .bind(expires_at)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to insert session: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// The user's live sessions, most recently used first.
+#[instrument(skip_all)]
+pub async fn select_sessions(
+    pool: &PgPool,
+    user_id: Uuid,
+    current: Option<Uuid>,
+) -> Result<Vec<Session>, APIError> {
+    match sqlx::query_as::<_, Session>(
+        "
+        SELECT id, device_name, user_agent, ip, created_at, last_seen_at, expires_at,
+            id IS NOT DISTINCT FROM $2 AS current
+        FROM sessions
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+        ORDER BY last_seen_at DESC;
+        ",
+    )
+    .bind(user_id)
+    .bind(current)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(sessions) => Ok(sessions),
+        Err(e) => {
+            tracing::error!("Failed to select sessions: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Returns whether the user had such a live session.
+#[instrument(skip_all)]
+pub async fn revoke_session(pool: &PgPool, user_id: Uuid, id: Uuid) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        UPDATE sessions SET revoked_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL;
+        ",
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    {
+        Ok(res) => Ok(res.rows_affected() > 0),
+        Err(e) => {
+            tracing::error!("Failed to revoke session: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Ends every session of the user and returns their ids.
+#[instrument(skip_all)]
+pub async fn revoke_all_sessions(pool: &PgPool, user_id: Uuid) -> Result<Vec<Uuid>, APIError> {
+    match sqlx::query_scalar::<_, Uuid>(
+        "
+        UPDATE sessions SET revoked_at = CURRENT_TIMESTAMP
+        WHERE user_id = $1 AND revoked_at IS NULL
+        RETURNING id;
+        ",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(ids) => Ok(ids),
+        Err(e) => {
+            tracing::error!("Failed to revoke sessions: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Records a request on a session and returns whether the session is live.
+#[instrument(skip_all)]
+pub async fn touch_session(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+    ip: Option<&str>,
+) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        UPDATE sessions SET last_seen_at = CURRENT_TIMESTAMP, ip = COALESCE($3, ip)
+        WHERE id = $1 AND user_id = $2
+            AND revoked_at IS NULL AND expires_at > CURRENT_TIMESTAMP;
+        ",
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(ip)
+    .execute(pool)
+    .await
+    {
+        Ok(res) => Ok(res.rows_affected() > 0),
+        Err(e) => {
+            tracing::error!("Failed to touch session: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn purge_sessions(pool: &PgPool) -> Result<u64, APIError> {
+    match sqlx::query(
+        "
+        DELETE FROM sessions
+        WHERE LEAST(expires_at, COALESCE(revoked_at, expires_at))
+            < CURRENT_TIMESTAMP - make_interval(days => $1::INT);
+        ",
+    )
+    .bind(RETENTION_DAYS)
+    .execute(pool)
+    .await
+    {
+        Ok(res) => Ok(res.rows_affected()),
+        Err(e) => {
+            tracing::error!("Failed to purge sessions: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
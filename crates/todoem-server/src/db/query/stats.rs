@@ -0,0 +1,205 @@
+use sqlx::PgPool;
+use todoem_core::{errors::APIError, models::stats as M};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Completions of user `$1`, with whether each was on time in zone `$2`.
+/// All-day tasks count as on time until the end of their day.
+const COMPLETIONS: &str = "
+    completions AS (
+        SELECT c.*, (
+            (c.due_on IS NOT NULL AND (c.completed_at AT TIME ZONE $2)::date <= c.due_on)
+            OR (c.due_date IS NOT NULL AND c.completed_at <= c.due_date)
+        ) AS on_time
+        FROM task_completions c WHERE c.user_id = $1
+    )";
+
+const ADHERENCE: &str = "
+    COUNT(*) AS completed,
+    COUNT(*) FILTER (WHERE c.on_time) AS on_time,
+    COALESCE(SUM(c.skipped), 0)::BIGINT AS missed,
+    COUNT(*) FILTER (WHERE c.on_time)::FLOAT8 / NULLIF(COUNT(*) + SUM(c.skipped), 0) AS adherence";
+
+#[derive(Debug, Clone, Copy)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    fn unit(&self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+}
+
+/// Completions per period for the last `count` periods in zone `tz`, the
+/// current one included, with empty periods as zero.
+#[instrument(skip_all)]
+pub async fn select_completion_series(
+    pool: &PgPool,
+    user_id: Uuid,
+    tz: &str,
+    period: Period,
+    count: i32,
+) -> Result<Vec<M::PeriodCount>, APIError> {
+    match sqlx::query_as::<_, M::PeriodCount>(
+        "
+        WITH periods AS (
+            SELECT generate_series(
+                date_trunc($3, now() AT TIME ZONE $2) - ($4 - 1) * ('1 ' || $3)::INTERVAL,
+                date_trunc($3, now() AT TIME ZONE $2),
+                ('1 ' || $3)::INTERVAL
+            ) AS start
+        )
+        SELECT p.start::DATE AS period, COUNT(c.id) AS completed
+        FROM periods p
+        LEFT JOIN task_completions c
+            ON c.user_id = $1 AND date_trunc($3, c.completed_at AT TIME ZONE $2) = p.start
+        GROUP BY p.start ORDER BY p.start;
+        ",
+    )
+    .bind(user_id)
+    .bind(tz)
+    .bind(period.unit())
+    .bind(count)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(series) => Ok(series),
+        Err(e) => {
+            tracing::error!("Failed to select completion series: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_streaks(
+    pool: &PgPool,
+    user_id: Uuid,
+    tz: &str,
+) -> Result<M::Streaks, APIError> {
+    match sqlx::query_as::<_, M::Streaks>(
+        "
+        WITH days AS (
+            SELECT DISTINCT (completed_at AT TIME ZONE $2)::DATE AS day
+            FROM task_completions WHERE user_id = $1
+        ), runs AS (
+            SELECT day, day - (ROW_NUMBER() OVER (ORDER BY day))::INT AS run FROM days
+        ), streaks AS (
+            SELECT MAX(day) AS last_day, COUNT(*) AS len FROM runs GROUP BY run
+        )
+        SELECT
+            COALESCE(MAX(len) FILTER (WHERE last_day >= (now() AT TIME ZONE $2)::DATE - 1), 0) AS current,
+            COALESCE(MAX(len), 0) AS longest
+        FROM streaks;
+        ",
+    )
+    .bind(user_id)
+    .bind(tz)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(streaks) => Ok(streaks),
+        Err(e) => {
+            tracing::error!("Failed to select streaks: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_punctuality(
+    pool: &PgPool,
+    user_id: Uuid,
+    tz: &str,
+) -> Result<M::Punctuality, APIError> {
+    match sqlx::query_as::<_, M::Punctuality>(&format!(
+        "
+        WITH {}
+        SELECT
+            COUNT(*) FILTER (WHERE on_time) AS on_time,
+            COUNT(*) FILTER (WHERE NOT on_time) AS late,
+            COUNT(*) FILTER (WHERE on_time)::FLOAT8 / NULLIF(COUNT(*), 0) AS rate
+        FROM completions
+        WHERE due_on IS NOT NULL OR due_date IS NOT NULL;
+        ",
+        COMPLETIONS
+    ))
+    .bind(user_id)
+    .bind(tz)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(punctuality) => Ok(punctuality),
+        Err(e) => {
+            tracing::error!("Failed to select punctuality: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Adherence over every recurring completion, deleted tasks included.
+#[instrument(skip_all)]
+pub async fn select_recurring_adherence(
+    pool: &PgPool,
+    user_id: Uuid,
+    tz: &str,
+) -> Result<M::Adherence, APIError> {
+    match sqlx::query_as::<_, M::Adherence>(&format!(
+        "
+        WITH {}
+        SELECT {} FROM completions c WHERE c.recurring;
+        ",
+        COMPLETIONS, ADHERENCE
+    ))
+    .bind(user_id)
+    .bind(tz)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(adherence) => Ok(adherence),
+        Err(e) => {
+            tracing::error!("Failed to select recurring adherence: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Adherence per recurring task that still exists, least adhered to first.
+#[instrument(skip_all)]
+pub async fn select_task_adherence(
+    pool: &PgPool,
+    user_id: Uuid,
+    tz: &str,
+) -> Result<Vec<M::TaskAdherence>, APIError> {
+    match sqlx::query_as::<_, M::TaskAdherence>(&format!(
+        "
+        WITH {}
+        SELECT t.id AS task_id, t.task, t.repeat_frequency, {}
+        FROM completions c
+        INNER JOIN tasks t ON t.id = c.task_id
+        WHERE c.recurring
+        GROUP BY t.id
+        ORDER BY adherence ASC NULLS LAST, t.id
+        LIMIT 100;
+        ",
+        COMPLETIONS, ADHERENCE
+    ))
+    .bind(user_id)
+    .bind(tz)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            tracing::error!("Failed to select task adherence: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
@@ -0,0 +1,661 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use tracing::instrument;
+
+use super::{offset, PAGE_LIMIT};
+use crate::services::calendar::{self, Due};
+use todoem_core::{
+    errors::APIError,
+    models::{
+        filter::{DayRange, Filter},
+        task::Task,
+    },
+    types::task::{CreateTaskRequest, UpdateTaskRequest},
+};
+
+#[instrument(skip_all)]
+pub async fn insert_task_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: uuid::Uuid,
+    task: CreateTaskRequest,
+) -> Result<Task, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    INSERT INTO tasks (user_id, task, description, due_date, due_on, repeat_frequency, repeat_anchor_date, repeat_anchor_on)
+    VALUES ($1, $2, $3, $4, $5, $6, $4, $5) RETURNING *;
+    ",
+    )
+    .bind(user_id)
+    .bind(task.task)
+    .bind(task.description)
+    .bind(task.due_date)
+    .bind(task.due_on)
+    .bind(task.repeat_frequency)
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(task) => Ok(task),
+        Err(e) => {
+            tracing::error!("Failed to insert task: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_task(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+    task_id: i64,
+) -> Result<Task, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    SELECT * FROM tasks WHERE id = $1 AND user_id = $2 LIMIT 1;
+    ",
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    {
+        Ok(task) => Ok(task),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to select task: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn update_task_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: uuid::Uuid,
+    task_id: i64,
+    task: UpdateTaskRequest,
+) -> Result<Task, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    UPDATE tasks SET task = $1, description = $2, due_date = $3, due_on = $4, repeat_frequency = $5,
+        -- a due date sent back unchanged keeps the recurrence where it started
+        repeat_anchor_date = CASE WHEN due_date IS NOT DISTINCT FROM $3 AND due_on IS NOT DISTINCT FROM $4
+            THEN repeat_anchor_date ELSE $3 END,
+        repeat_anchor_on = CASE WHEN due_date IS NOT DISTINCT FROM $3 AND due_on IS NOT DISTINCT FROM $4
+            THEN repeat_anchor_on ELSE $4 END
+    WHERE id = $6 AND user_id = $7 RETURNING *;
+    ",
+    )
+    .bind(task.task)
+    .bind(task.description)
+    .bind(task.due_date)
+    .bind(task.due_on)
+    .bind(task.repeat_frequency)
+    .bind(task_id)
+    .bind(user_id)
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(task) => Ok(task),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to update task: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn delete_task_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: uuid::Uuid,
+    task_id: i64,
+) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+    DELETE FROM tasks WHERE id = $1 AND user_id = $2;
+    ",
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(row) => {
+            if row.rows_affected() == 0 {
+                return Err(APIError::not_found());
+            }
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete task: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_task_for_update_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: uuid::Uuid,
+    task_id: i64,
+) -> Result<Task, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    SELECT * FROM tasks WHERE id = $1 AND user_id = $2 FOR UPDATE;
+    ",
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(task) => Ok(task),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to select task for update: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+fn split_due(due: Option<Due>) -> (Option<DateTime<Utc>>, Option<NaiveDate>) {
+    match due {
+        Some(Due::At(at)) => (Some(at), None),
+        Some(Due::On(date)) => (None, Some(date)),
+        None => (None, None),
+    }
+}
+
+/// Records a completion of the task's current occurrence. A recurring task is
+/// moved on to `next` and stays undone; any other task is marked done.
+#[instrument(skip_all)]
+pub async fn complete_task_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    task: &Task,
+    next: Option<(Due, u32)>,
+) -> Result<(), APIError> {
+    if let Err(e) = sqlx::query(
+        "
+    INSERT INTO task_completions (task_id, user_id, due_date, due_on, recurring, skipped)
+    VALUES ($1, $2, $3, $4, $5, $6);
+    ",
+    )
+    .bind(task.id)
+    .bind(task.user_id)
+    .bind(task.due_date)
+    .bind(task.due_on)
+    .bind(task.repeat_frequency.is_some())
+    .bind(next.map_or(0, |(_, skipped)| skipped as i32))
+    .execute(&mut **tx)
+    .await
+    {
+        tracing::error!("Failed to insert completion: {:#?}", e);
+        return Err(APIError::server());
+    }
+
+    let result = match next {
+        Some((due, _)) => {
+            let (due_date, due_on) = split_due(Some(due));
+            sqlx::query("UPDATE tasks SET due_date = $2, due_on = $3 WHERE id = $1;")
+                .bind(task.id)
+                .bind(due_date)
+                .bind(due_on)
+                .execute(&mut **tx)
+                .await
+        }
+        None => {
+            sqlx::query("UPDATE tasks SET done = true, done_at = CURRENT_TIMESTAMP WHERE id = $1;")
+                .bind(task.id)
+                .execute(&mut **tx)
+                .await
+        }
+    };
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to mark task as done: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Takes back the latest completion: a done task is reopened, a recurring one
+/// moves back to the occurrence that completion was for.
+#[instrument(skip_all)]
+pub async fn uncomplete_task_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    task: &Task,
+) -> Result<(), APIError> {
+    if !task.done && task.repeat_frequency.is_none() {
+        return Ok(());
+    }
+
+    let last = match sqlx::query_as::<_, (Option<DateTime<Utc>>, Option<NaiveDate>)>(
+        "
+    DELETE FROM task_completions WHERE id = (
+        SELECT id FROM task_completions WHERE task_id = $1 ORDER BY completed_at DESC LIMIT 1
+    ) RETURNING due_date, due_on;
+    ",
+    )
+    .bind(task.id)
+    .fetch_optional(&mut **tx)
+    .await
+    {
+        Ok(last) => last,
+        Err(e) => {
+            tracing::error!("Failed to delete completion: {:#?}", e);
+            return Err(APIError::server());
+        }
+    };
+
+    let result = if task.done {
+        sqlx::query("UPDATE tasks SET done = false, done_at = NULL WHERE id = $1;")
+            .bind(task.id)
+            .execute(&mut **tx)
+            .await
+    } else if let Some((due_date, due_on)) = last {
+        sqlx::query("UPDATE tasks SET due_date = $2, due_on = $3 WHERE id = $1;")
+            .bind(task.id)
+            .bind(due_date)
+            .bind(due_on)
+            .execute(&mut **tx)
+            .await
+    } else {
+        return Ok(());
+    };
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to mark task as undone: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_all_tasks(pool: PgPool, user_id: uuid::Uuid) -> Result<Vec<Task>, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    SELECT * FROM tasks WHERE user_id = $1 ORDER BY id DESC LIMIT 100;
+    ",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            tracing::error!("Failed to select all tasks: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Every task of the user, page by page, newest first.
+#[instrument(skip_all)]
+pub async fn select_tasks_page(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+    page: i16,
+) -> Result<Vec<Task>, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    SELECT * FROM tasks WHERE user_id = $1 ORDER BY id DESC LIMIT $2 OFFSET $3;
+    ",
+    )
+    .bind(user_id)
+    .bind(PAGE_LIMIT)
+    .bind(offset(page))
+    .fetch_all(&pool)
+    .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            tracing::error!("Failed to select page of tasks: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_all_tasks_by_status(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+    done: bool,
+) -> Result<Vec<Task>, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    SELECT * FROM tasks WHERE user_id = $1 AND done = $2 ORDER BY id DESC LIMIT 100;
+    ",
+    )
+    .bind(user_id)
+    .bind(done)
+    .fetch_all(&pool)
+    .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            tracing::error!("Failed to select all done tasks: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn delete_all_tasks(pool: PgPool, user_id: uuid::Uuid) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+    DELETE FROM tasks WHERE user_id = $1;
+    ",
+    )
+    .bind(user_id)
+    .execute(&pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to delete all tasks: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn delete_all_tasks_by_status(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+    done: bool,
+) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+    DELETE FROM tasks WHERE user_id = $1 AND done = $2;
+    ",
+    )
+    .bind(user_id)
+    .bind(done)
+    .execute(&pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to delete all tasks by status: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Undone tasks due on the days `from..=to`, plus recurring ones that started
+/// before `to` and may have occurrences in between. `start` and `end` are the
+/// bounds of those days in the user's time zone.
+#[instrument(skip_all)]
+pub async fn select_tasks_due_between(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<Task>, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    SELECT * FROM tasks
+    WHERE user_id = $1 AND done = false AND (
+        (due_on <= $3 AND (due_on >= $2 OR repeat_frequency IS NOT NULL))
+        OR (due_date < $5 AND (due_date >= $4 OR repeat_frequency IS NOT NULL))
+    )
+    ORDER BY id;
+    ",
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .bind(start)
+    .bind(end)
+    .fetch_all(&pool)
+    .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            tracing::error!("Failed to select due tasks: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Undone tasks due before today: timed ones before `start_of_today`, all-day
+/// ones before `today`. `tz` only orders timed and all-day tasks by local day.
+#[instrument(skip_all)]
+pub async fn select_overdue_tasks(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+    tz: &str,
+    today: NaiveDate,
+    start_of_today: DateTime<Utc>,
+) -> Result<Vec<Task>, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    SELECT * FROM tasks
+    WHERE user_id = $1 AND done = false AND (due_on < $3 OR due_date < $4)
+    ORDER BY COALESCE(due_on, (due_date AT TIME ZONE $2)::date), due_date NULLS FIRST, id
+    LIMIT 100;
+    ",
+    )
+    .bind(user_id)
+    .bind(tz)
+    .bind(today)
+    .bind(start_of_today)
+    .fetch_all(&pool)
+    .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            tracing::error!("Failed to select overdue tasks: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_undated_tasks(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+) -> Result<Vec<Task>, APIError> {
+    match sqlx::query_as::<_, Task>(
+        "
+    SELECT * FROM tasks
+    WHERE user_id = $1 AND done = false AND due_on IS NULL AND due_date IS NULL
+    ORDER BY id DESC LIMIT 100;
+    ",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            tracing::error!("Failed to select undated tasks: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Uncapped sizes of the overdue and no due date views.
+#[instrument(skip_all)]
+pub async fn count_overdue_and_undated_tasks(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+    today: NaiveDate,
+    start_of_today: DateTime<Utc>,
+) -> Result<(i64, i64), APIError> {
+    match sqlx::query_as::<_, (i64, i64)>(
+        "
+    SELECT
+        COUNT(*) FILTER (WHERE due_on < $2 OR due_date < $3),
+        COUNT(*) FILTER (WHERE due_on IS NULL AND due_date IS NULL)
+    FROM tasks
+    WHERE user_id = $1 AND done = false;
+    ",
+    )
+    .bind(user_id)
+    .bind(today)
+    .bind(start_of_today)
+    .fetch_one(&pool)
+    .await
+    {
+        Ok(counts) => Ok(counts),
+        Err(e) => {
+            tracing::error!("Failed to count overdue and undated tasks: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// What relative days in a filter are resolved against.
+pub struct FilterContext {
+    pub tz: Tz,
+    pub today: NaiveDate,
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Appends `filter` as a boolean SQL expression. Every operand is bound as a
+/// parameter; only fixed column names and operators end up in the SQL text.
+/// Leaves are wrapped in `COALESCE(.., FALSE)` so `not` behaves on NULL columns.
+fn push_filter(qb: &mut QueryBuilder<'_, Postgres>, filter: &Filter, ctx: &FilterContext) {
+    match filter {
+        Filter::And(args) | Filter::Or(args) => {
+            let (sep, empty) = match filter {
+                Filter::And(_) => (" AND ", "TRUE"),
+                _ => (" OR ", "FALSE"),
+            };
+            if args.is_empty() {
+                qb.push(empty);
+                return;
+            }
+            qb.push("(");
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    qb.push(sep);
+                }
+                push_filter(qb, arg, ctx);
+            }
+            qb.push(")");
+        }
+        Filter::Not(arg) => {
+            qb.push("NOT ");
+            push_filter(qb, arg, ctx);
+        }
+        leaf => {
+            qb.push("COALESCE(");
+            push_leaf(qb, leaf, ctx);
+            qb.push(", FALSE)");
+        }
+    }
+}
+
+fn push_leaf(qb: &mut QueryBuilder<'_, Postgres>, leaf: &Filter, ctx: &FilterContext) {
+    match leaf {
+        Filter::Done(done) => {
+            qb.push("done = ").push_bind(*done);
+        }
+        Filter::Text(text) => {
+            let pattern = format!("%{}%", escape_like(text));
+            qb.push("(task ILIKE ")
+                .push_bind(pattern.clone())
+                .push(" OR description ILIKE ")
+                .push_bind(pattern)
+                .push(")");
+        }
+        Filter::HasDue(has) => {
+            qb.push("(due_on IS NOT NULL OR due_date IS NOT NULL) = ")
+                .push_bind(*has);
+        }
+        Filter::Due(range) => {
+            qb.push("(");
+            push_day_range(qb, Some("due_on"), "due_date", range, ctx);
+            qb.push(")");
+        }
+        Filter::Created(range) => push_day_range(qb, None, "created_at", range, ctx),
+        Filter::Repeats(repeats) => {
+            qb.push("(repeat_frequency IS NOT NULL) = ")
+                .push_bind(*repeats);
+        }
+        Filter::Frequency(frequency) => {
+            qb.push("repeat_frequency = ").push_bind(frequency.clone());
+        }
+        Filter::And(_) | Filter::Or(_) | Filter::Not(_) => unreachable!("not a leaf"),
+    }
+}
+
+/// Matches days of `range` in the user's zone on a date column and/or a
+/// timestamp column.
+fn push_day_range(
+    qb: &mut QueryBuilder<'_, Postgres>,
+    date_col: Option<&'static str>,
+    ts_col: &'static str,
+    range: &DayRange,
+    ctx: &FilterContext,
+) {
+    let from = range.from.and_then(|day| day.resolve(ctx.today));
+    let to = range.to.and_then(|day| day.resolve(ctx.today));
+
+    if let Some(col) = date_col {
+        qb.push("(").push(col).push(" IS NOT NULL");
+        if let Some(from) = from {
+            qb.push(" AND ").push(col).push(" >= ").push_bind(from);
+        }
+        if let Some(to) = to {
+            qb.push(" AND ").push(col).push(" <= ").push_bind(to);
+        }
+        qb.push(") OR ");
+    }
+
+    qb.push("(").push(ts_col).push(" IS NOT NULL");
+    if let Some(from) = from {
+        qb.push(" AND ")
+            .push(ts_col)
+            .push(" >= ")
+            .push_bind(calendar::start_of_day(ctx.tz, from));
+    }
+    if let Some(to) = to {
+        let (_, end) = calendar::day_range(ctx.tz, to, to);
+        qb.push(" AND ").push(ts_col).push(" < ").push_bind(end);
+    }
+    qb.push(")");
+}
+
+#[instrument(skip_all)]
+pub async fn select_filtered_tasks(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+    filter: &Filter,
+    ctx: &FilterContext,
+    page: i16,
+) -> Result<Vec<Task>, APIError> {
+    let mut qb = QueryBuilder::new("SELECT * FROM tasks WHERE user_id = ");
+    qb.push_bind(user_id).push(" AND ");
+    push_filter(&mut qb, filter, ctx);
+    qb.push(" ORDER BY id DESC LIMIT ")
+        .push_bind(PAGE_LIMIT)
+        .push(" OFFSET ")
+        .push_bind(offset(page));
+
+    match qb.build_query_as::<Task>().fetch_all(&pool).await {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            tracing::error!("Failed to select filtered tasks: {:#?}", e);
+            Err(APIError::server())
+        }
+    }
+}
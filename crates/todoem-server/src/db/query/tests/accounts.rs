@@ -0,0 +1,104 @@
+use super::{LUFFY, ZORO};
+use crate::db::query::me as Q;
+use crate::services::passwords::{self, Verified};
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn purge_waits_for_grace_period(pool: PgPool) {
+    Q::schedule_deletion(&pool, LUFFY).await.unwrap();
+    assert!(Q::purge_deleted_accounts(&pool, 30)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(Q::select_profile(&pool, LUFFY).await.is_ok());
+
+    assert!(Q::cancel_deletion(&pool, LUFFY).await.unwrap());
+    assert!(!Q::cancel_deletion(&pool, LUFFY).await.unwrap());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn purge_cascades_to_owned_data(pool: PgPool) {
+    let shared = uuid::Uuid::new_v4();
+    let solo = uuid::Uuid::new_v4();
+    sqlx::query(
+        "
+        INSERT INTO tasks (user_id, task) VALUES ($1, 'gone');
+        ",
+    )
+    .bind(LUFFY)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO groups (id, name) VALUES ($1, 'shared'), ($2, 'solo');")
+        .bind(shared)
+        .bind(solo)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO group_users (group_id, user_id) VALUES ($1, $3), ($1, $4), ($2, $3);")
+        .bind(shared)
+        .bind(solo)
+        .bind(LUFFY)
+        .bind(ZORO)
+        .execute(&pool)
+        .await
+        .unwrap();
+    Q::update_avatar_key(&pool, LUFFY, Some("avatars/luffy.png"))
+        .await
+        .unwrap();
+
+    Q::schedule_deletion(&pool, LUFFY).await.unwrap();
+    sqlx::query(
+        "UPDATE users SET deletion_requested_at = now() - interval '31 days' WHERE id = $1;",
+    )
+    .bind(LUFFY)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let avatars = Q::purge_deleted_accounts(&pool, 30).await.unwrap();
+    assert_eq!(avatars, vec!["avatars/luffy.png".to_string()]);
+
+    let count = |sql: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>(sql)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+    assert_eq!(
+        count("SELECT count(*) FROM users WHERE username = 'luffy'").await,
+        0
+    );
+    assert_eq!(count("SELECT count(*) FROM tasks").await, 0);
+    assert_eq!(count("SELECT count(*) FROM groups").await, 1);
+    assert_eq!(count("SELECT count(*) FROM group_users").await, 1);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn fixture_passwords_are_hashed_usernames(pool: PgPool) {
+    let users: Vec<(String, String)> = sqlx::query_as("SELECT username, password FROM users;")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users.len(), 4);
+    for (username, stored) in users {
+        assert_eq!(
+            passwords::verify(&username, &stored, 1_000),
+            Verified::Yes,
+            "{}",
+            username
+        );
+    }
+}
+
+#[sqlx::test(migrations = "src/db/migrations")]
+async fn migrations_create_no_accounts(pool: PgPool) {
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users, 0);
+}
@@ -0,0 +1,287 @@
+use super::{LUFFY, NAMI, TOTO, ZORO};
+use crate::db::query::user as Q;
+use sqlx::PgPool;
+use todoem_core::models::user::ConnectionState::{self, *};
+use uuid::Uuid;
+
+async fn request(pool: &PgPool, sender: Uuid, receiver: Uuid) -> bool {
+    let mut tx = pool.begin().await.unwrap();
+    let sent = Q::request_connection_tx(&mut tx, sender, receiver)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    sent
+}
+
+async fn accept(pool: &PgPool, receiver: Uuid, sender: Uuid) -> bool {
+    let mut tx = pool.begin().await.unwrap();
+    let accepted = Q::accept_request_tx(&mut tx, receiver, sender)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    accepted
+}
+
+async fn state(pool: &PgPool, a: Uuid, b: Uuid) -> Option<(ConnectionState, Uuid)> {
+    Q::select_connection(pool, a, b)
+        .await
+        .unwrap()
+        .map(|c| (c.state, c.actor_id))
+}
+
+fn ids(users: Vec<todoem_core::models::user::User>) -> Vec<Uuid> {
+    users.into_iter().map(|u| u.id).collect()
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn none_to_pending(pool: PgPool) {
+    assert_eq!(state(&pool, ZORO, LUFFY).await, None);
+    assert!(request(&pool, ZORO, LUFFY).await);
+    assert_eq!(state(&pool, LUFFY, ZORO).await, Some((Pending, ZORO)));
+
+    let sent = Q::select_sent_requests(&pool, ZORO).await.unwrap();
+    let received = Q::select_received_requests(&pool, LUFFY).await.unwrap();
+    assert_eq!(ids(sent), vec![LUFFY]);
+    assert_eq!(ids(received), vec![ZORO]);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn pending_rejects_duplicate_and_reverse_requests(pool: PgPool) {
+    assert!(request(&pool, ZORO, LUFFY).await);
+    assert!(!request(&pool, ZORO, LUFFY).await);
+    assert!(!request(&pool, LUFFY, ZORO).await);
+    assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Pending, ZORO)));
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn request_to_unknown_user_is_not_found(pool: PgPool) {
+    let mut tx = pool.begin().await.unwrap();
+    let res = Q::request_connection_tx(&mut tx, ZORO, Uuid::new_v4()).await;
+    assert!(res.is_err());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn pending_to_none_by_requester(pool: PgPool) {
+    assert!(request(&pool, ZORO, LUFFY).await);
+    assert!(!Q::cancel_request(&pool, LUFFY, ZORO).await.unwrap());
+    assert!(Q::cancel_request(&pool, ZORO, LUFFY).await.unwrap());
+    assert_eq!(state(&pool, ZORO, LUFFY).await, None);
+    assert!(!Q::cancel_request(&pool, ZORO, LUFFY).await.unwrap());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn pending_to_connected_by_receiver(pool: PgPool) {
+    assert!(request(&pool, ZORO, LUFFY).await);
+    assert!(!accept(&pool, ZORO, LUFFY).await);
+    assert!(accept(&pool, LUFFY, ZORO).await);
+    assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Connected, LUFFY)));
+    assert!(!accept(&pool, LUFFY, ZORO).await);
+    assert!(!request(&pool, ZORO, LUFFY).await);
+
+    let zoro = Q::select_listers(&pool, ZORO, 1).await.unwrap();
+    let luffy = Q::select_listers(&pool, LUFFY, 1).await.unwrap();
+    assert_eq!(ids(zoro), vec![LUFFY]);
+    assert_eq!(ids(luffy), vec![ZORO]);
+    assert!(Q::select_received_requests(&pool, LUFFY)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn pending_to_rejected_by_receiver(pool: PgPool) {
+    assert!(request(&pool, ZORO, LUFFY).await);
+    assert!(!Q::reject_request(&pool, ZORO, LUFFY).await.unwrap());
+    assert!(Q::reject_request(&pool, LUFFY, ZORO).await.unwrap());
+    assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Rejected, LUFFY)));
+
+    // the rejected requester cannot ask again ...
+    assert!(!request(&pool, ZORO, LUFFY).await);
+    assert!(!accept(&pool, ZORO, LUFFY).await);
+
+    // ... but the rejecter can change their mind
+    assert!(request(&pool, LUFFY, ZORO).await);
+    assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Pending, LUFFY)));
+    assert!(accept(&pool, ZORO, LUFFY).await);
+    assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Connected, ZORO)));
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn connected_to_none_by_either(pool: PgPool) {
+    assert!(!Q::disconnect(&pool, ZORO, LUFFY).await.unwrap());
+
+    assert!(request(&pool, ZORO, LUFFY).await);
+    assert!(!Q::disconnect(&pool, ZORO, LUFFY).await.unwrap());
+    assert!(accept(&pool, LUFFY, ZORO).await);
+    assert!(Q::disconnect(&pool, LUFFY, ZORO).await.unwrap());
+    assert_eq!(state(&pool, ZORO, LUFFY).await, None);
+
+    assert!(request(&pool, LUFFY, ZORO).await);
+    assert!(accept(&pool, ZORO, LUFFY).await);
+    assert!(Q::disconnect(&pool, LUFFY, ZORO).await.unwrap());
+    assert!(Q::select_listers(&pool, ZORO, 1).await.unwrap().is_empty());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn blocking_ends_any_connection(pool: PgPool) {
+    // none
+    assert!(Q::block(&pool, ZORO, LUFFY).await.unwrap());
+    assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Blocked, ZORO)));
+
+    // pending
+    assert!(request(&pool, NAMI, ZORO).await);
+    assert!(Q::block(&pool, ZORO, NAMI).await.unwrap());
+    assert_eq!(state(&pool, ZORO, NAMI).await, Some((Blocked, ZORO)));
+    assert!(Q::select_received_requests(&pool, ZORO)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // connected
+    assert!(request(&pool, TOTO, ZORO).await);
+    assert!(accept(&pool, ZORO, TOTO).await);
+    assert!(Q::block(&pool, TOTO, ZORO).await.unwrap());
+    assert_eq!(state(&pool, ZORO, TOTO).await, Some((Blocked, TOTO)));
+    assert!(Q::select_listers(&pool, ZORO, 1).await.unwrap().is_empty());
+
+    // rejected
+    assert!(request(&pool, LUFFY, NAMI).await);
+    assert!(Q::reject_request(&pool, NAMI, LUFFY).await.unwrap());
+    assert!(Q::block(&pool, LUFFY, NAMI).await.unwrap());
+    assert_eq!(state(&pool, LUFFY, NAMI).await, Some((Blocked, LUFFY)));
+
+    assert_eq!(
+        ids(Q::select_blocked_users(&pool, ZORO).await.unwrap()),
+        vec![NAMI, LUFFY]
+    );
+    assert_eq!(
+        ids(Q::select_blocked_users(&pool, TOTO).await.unwrap()),
+        vec![ZORO]
+    );
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn blocked_refuses_everything_but_unblock_by_blocker(pool: PgPool) {
+    assert!(Q::block(&pool, ZORO, LUFFY).await.unwrap());
+
+    assert!(!Q::block(&pool, ZORO, LUFFY).await.unwrap());
+    assert!(!request(&pool, ZORO, LUFFY).await);
+    assert!(!request(&pool, LUFFY, ZORO).await);
+    assert!(Q::is_blocked(&pool, LUFFY, ZORO).await.unwrap());
+    assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Blocked, ZORO)));
+    assert!(!accept(&pool, LUFFY, ZORO).await);
+    assert!(!Q::disconnect(&pool, LUFFY, ZORO).await.unwrap());
+
+    let found = Q::search(pool.clone(), LUFFY, "zoro".to_string(), 1)
+        .await
+        .unwrap();
+    assert!(found.is_empty());
+
+    assert!(!Q::unblock(&pool, LUFFY, ZORO).await.unwrap());
+    assert!(Q::unblock(&pool, ZORO, LUFFY).await.unwrap());
+    assert!(!Q::is_blocked(&pool, LUFFY, ZORO).await.unwrap());
+    assert_eq!(state(&pool, ZORO, LUFFY).await, None);
+    assert!(request(&pool, LUFFY, ZORO).await);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn mutual_blocks_are_kept_apart(pool: PgPool) {
+    assert!(Q::block(&pool, ZORO, LUFFY).await.unwrap());
+    assert!(Q::block(&pool, LUFFY, ZORO).await.unwrap());
+    assert!(!Q::block(&pool, LUFFY, ZORO).await.unwrap());
+    assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Blocked, LUFFY)));
+
+    assert_eq!(
+        ids(Q::select_blocked_users(&pool, ZORO).await.unwrap()),
+        vec![LUFFY]
+    );
+    assert_eq!(
+        ids(Q::select_blocked_users(&pool, LUFFY).await.unwrap()),
+        vec![ZORO]
+    );
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn unblocking_one_side_keeps_the_other_block(pool: PgPool) {
+    assert!(Q::block(&pool, ZORO, LUFFY).await.unwrap());
+    assert!(Q::block(&pool, LUFFY, ZORO).await.unwrap());
+
+    assert!(Q::unblock(&pool, ZORO, LUFFY).await.unwrap());
+    assert!(!Q::unblock(&pool, ZORO, LUFFY).await.unwrap());
+    assert!(Q::select_blocked_users(&pool, ZORO)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        ids(Q::select_blocked_users(&pool, LUFFY).await.unwrap()),
+        vec![ZORO]
+    );
+
+    // luffy's block still stands between them
+    assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Blocked, LUFFY)));
+    assert!(Q::is_blocked(&pool, ZORO, LUFFY).await.unwrap());
+    assert!(!request(&pool, ZORO, LUFFY).await);
+    let found = Q::search(pool.clone(), ZORO, "luffy".to_string(), 1)
+        .await
+        .unwrap();
+    assert!(found.is_empty());
+
+    assert!(Q::unblock(&pool, LUFFY, ZORO).await.unwrap());
+    assert_eq!(state(&pool, ZORO, LUFFY).await, None);
+    assert!(request(&pool, ZORO, LUFFY).await);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn concurrent_crossed_requests_leave_one_pending(pool: PgPool) {
+    for _ in 0..10 {
+        let (a, b) = tokio::join!(request(&pool, ZORO, LUFFY), request(&pool, LUFFY, ZORO));
+        assert!(a ^ b);
+        let (_, actor) = state(&pool, ZORO, LUFFY).await.unwrap();
+        assert_eq!(actor, if a { ZORO } else { LUFFY });
+        assert!(
+            Q::cancel_request(&pool, actor, if a { LUFFY } else { ZORO })
+                .await
+                .unwrap()
+        );
+    }
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn concurrent_accepts_connect_once(pool: PgPool) {
+    assert!(request(&pool, ZORO, LUFFY).await);
+    let (a, b) = tokio::join!(accept(&pool, LUFFY, ZORO), accept(&pool, LUFFY, ZORO));
+    assert!(a ^ b);
+    assert_eq!(Q::select_listers(&pool, ZORO, 1).await.unwrap().len(), 1);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn concurrent_block_and_request_leave_the_pair_blocked(pool: PgPool) {
+    for _ in 0..10 {
+        let (blocked, _) = tokio::join!(Q::block(&pool, ZORO, LUFFY), request(&pool, LUFFY, ZORO));
+        assert!(blocked.unwrap());
+        assert_eq!(state(&pool, ZORO, LUFFY).await, Some((Blocked, ZORO)));
+        assert!(Q::select_received_requests(&pool, ZORO)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(Q::unblock(&pool, ZORO, LUFFY).await.unwrap());
+        assert_eq!(state(&pool, ZORO, LUFFY).await, None);
+    }
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn daily_quota_stops_at_limit(pool: PgPool) {
+    let mut tx = pool.begin().await.unwrap();
+    assert!(Q::consume_daily_quota_tx(&mut tx, ZORO, "test", 2)
+        .await
+        .unwrap());
+    assert!(Q::consume_daily_quota_tx(&mut tx, ZORO, "test", 2)
+        .await
+        .unwrap());
+    assert!(!Q::consume_daily_quota_tx(&mut tx, ZORO, "test", 2)
+        .await
+        .unwrap());
+    assert!(Q::consume_daily_quota_tx(&mut tx, LUFFY, "test", 2)
+        .await
+        .unwrap());
+}
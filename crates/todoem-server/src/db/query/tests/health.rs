@@ -0,0 +1,72 @@
+use crate::routes::health;
+use crate::services::shutdown::Shutdown;
+use axum::{body::Body, extract::Extension, Router};
+use http::{Request, StatusCode};
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+fn app(pool: &PgPool, shutdown: &Shutdown) -> Router {
+    health::init()
+        .into_router()
+        .with_state(pool.clone())
+        .layer(Extension(shutdown.clone()))
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, serde_json::Value) {
+    let req = Request::get(uri).body(Body::empty()).unwrap();
+    let res = app.clone().oneshot(req).await.unwrap();
+    let status = res.status();
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[sqlx::test(migrations = "src/db/migrations")]
+async fn ready_once_migrated(pool: PgPool) {
+    let shutdown = Shutdown::new();
+    let app = app(&pool, &shutdown);
+    assert_eq!(get(&app, "/healthz").await.0, StatusCode::OK);
+    let (status, body) = get(&app, "/readyz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ready");
+
+    shutdown.trigger();
+    let (status, body) = get(&app, "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["details"]["shutting_down"], true);
+    assert_eq!(get(&app, "/healthz").await.0, StatusCode::OK);
+}
+
+#[sqlx::test(migrations = "src/db/migrations")]
+async fn not_ready_with_pending_migrations(pool: PgPool) {
+    let latest: i64 = sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = $1")
+        .bind(latest)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = app(&pool, &Shutdown::new());
+    let (status, body) = get(&app, "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["details"]["database"], "ok");
+    assert_eq!(
+        body["details"]["pending_migrations"],
+        serde_json::json!([latest])
+    );
+}
+
+#[sqlx::test(migrations = "src/db/migrations")]
+async fn not_ready_without_database(pool: PgPool) {
+    let app = app(&pool, &Shutdown::new());
+    pool.close().await;
+    let (status, body) = get(&app, "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["details"]["database"], "unavailable");
+    // liveness does not depend on the database
+    assert_eq!(get(&app, "/healthz").await.0, StatusCode::OK);
+}
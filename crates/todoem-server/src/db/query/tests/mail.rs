@@ -0,0 +1,326 @@
+use super::{keys, LUFFY, NAMI};
+use crate::config::{Accounts, Cookies, Mail, MailTransport, SmtpTls};
+use crate::db::query::{auth as Q, me as QMe};
+use crate::handlers::extract::ClientInfo;
+use crate::handlers::{auth as H, extract::ValidJson};
+use crate::services::auth::SessionIssuer;
+use crate::services::mail::queue::{self, MailQueue};
+use crate::services::mail::{MailError, Mailer, MemoryMailer, Message, SmtpMailer};
+use crate::services::metrics::Metrics;
+use crate::services::passwords;
+use crate::services::sessions::SessionCache;
+use crate::services::tokens;
+use axum::async_trait;
+use axum::extract::{Extension, State};
+use axum::response::IntoResponse;
+use axum_extra::extract::cookie::CookieJar;
+use http::StatusCode;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use todoem_core::models::email::EmailTokenPurpose;
+use todoem_core::types::auth as T;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+fn accounts() -> Accounts {
+    Accounts {
+        password_iterations: 10,
+        ..Default::default()
+    }
+}
+
+/// The token in the link of a sent email.
+fn token_in(message: &Message) -> String {
+    let start = message.body.find("token=").expect("no link in email") + "token=".len();
+    message.body[start..start + 64].to_string()
+}
+
+async fn deliver(pool: &PgPool) -> Vec<Message> {
+    let mailer = Arc::new(MemoryMailer::default());
+    MailQueue::new(
+        pool.clone(),
+        mailer.clone(),
+        Mail::default(),
+        Metrics::new(),
+    )
+    .send_due()
+    .await;
+    mailer.take()
+}
+
+async fn login(pool: &PgPool, login: &str, password: &str) -> StatusCode {
+    let accounts = accounts();
+    let sessions = SessionIssuer::new(keys(), &accounts, &Cookies::default());
+    let req = T::LoginRequest {
+        login: login.to_string(),
+        password: password.to_string(),
+        device_name: None,
+    };
+    H::login(
+        Extension(accounts),
+        Extension(sessions),
+        State(pool.clone()),
+        ClientInfo::default(),
+        CookieJar::new(),
+        ValidJson(req),
+    )
+    .await
+    .into_response()
+    .status()
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn verification_link_is_single_use(pool: PgPool) {
+    let mail = Mail::default();
+    queue::send_verification(&pool, &mail, LUFFY, "luffy", "luffy@op.co")
+        .await
+        .unwrap();
+    queue::send_verification(&pool, &mail, LUFFY, "luffy", "luffy@op.co")
+        .await
+        .unwrap();
+    let sent = deliver(&pool).await;
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].to, "luffy@op.co");
+    assert!(sent[0]
+        .body
+        .contains("http://localhost:8080/verify-email?token="));
+
+    let verify = |token: String| {
+        let pool = pool.clone();
+        async move {
+            H::verify_email(State(pool), ValidJson(T::VerifyEmailRequest { token }))
+                .await
+                .into_response()
+                .status()
+        }
+    };
+    // the second email replaced the first link
+    assert_eq!(verify(token_in(&sent[0])).await, StatusCode::BAD_REQUEST);
+    assert_eq!(verify(token_in(&sent[1])).await, StatusCode::OK);
+    assert_eq!(verify(token_in(&sent[1])).await, StatusCode::BAD_REQUEST);
+
+    let profile = QMe::select_profile(&pool, LUFFY).await.unwrap();
+    assert!(profile.email_verified_at.is_some());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn expired_tokens_are_refused(pool: PgPool) {
+    let hash = tokens::hash("expired");
+    Q::insert_email_token(
+        &pool,
+        LUFFY,
+        EmailTokenPurpose::ResetPassword,
+        "luffy@op.co",
+        &hash,
+        -1.0,
+    )
+    .await
+    .unwrap();
+    let grant = Q::consume_email_token(&pool, &hash, EmailTokenPurpose::ResetPassword)
+        .await
+        .unwrap();
+    assert!(grant.is_none());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn login_rehashes_outdated_passwords(pool: PgPool) {
+    let weak = passwords::hash("luffy", 5);
+    Q::update_password(&pool, LUFFY, &weak).await.unwrap();
+    assert_eq!(
+        login(&pool, "luffy", "zoro").await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        login(&pool, "nobody", "luffy").await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(login(&pool, "luffy", "luffy").await, StatusCode::OK);
+
+    let stored = Q::select_credentials(&pool, "LUFFY@op.co")
+        .await
+        .unwrap()
+        .unwrap()
+        .password
+        .unwrap();
+    assert!(stored.starts_with("$pbkdf2-sha256$i=10$"));
+    assert_eq!(login(&pool, "luffy@op.co", "luffy").await, StatusCode::OK);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn password_reset_replaces_password(pool: PgPool) {
+    let mail = Mail::default();
+    let forgot = T::ForgotPasswordRequest {
+        email: "luffy@op.co".to_string(),
+    };
+    H::forgot_password(
+        Extension(mail.clone()),
+        State(pool.clone()),
+        ValidJson(forgot),
+    )
+    .await
+    .unwrap();
+    // unknown addresses get the same answer and no email
+    let unknown = T::ForgotPasswordRequest {
+        email: "buggy@op.co".to_string(),
+    };
+    H::forgot_password(Extension(mail), State(pool.clone()), ValidJson(unknown))
+        .await
+        .unwrap();
+    let sent = deliver(&pool).await;
+    assert_eq!(sent.len(), 1);
+
+    let reset = |token: String| {
+        let pool = pool.clone();
+        async move {
+            let req = T::ResetPasswordRequest {
+                token,
+                password: "gum-gum pistol".to_string(),
+            };
+            let cache = Arc::new(SessionCache::new(Duration::from_secs(30)));
+            H::reset_password(
+                Extension(accounts()),
+                Extension(cache),
+                State(pool),
+                ValidJson(req),
+            )
+            .await
+            .into_response()
+            .status()
+        }
+    };
+    assert_eq!(reset(token_in(&sent[0])).await, StatusCode::OK);
+    assert_eq!(reset(token_in(&sent[0])).await, StatusCode::BAD_REQUEST);
+
+    assert_eq!(
+        login(&pool, "luffy", "luffy").await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        login(&pool, "luffy", "gum-gum pistol").await,
+        StatusCode::OK
+    );
+    let profile = QMe::select_profile(&pool, LUFFY).await.unwrap();
+    assert!(profile.email_verified_at.is_some());
+
+    let notice = deliver(&pool).await;
+    assert_eq!(notice.len(), 1);
+    assert_eq!(notice[0].subject, "Your password was changed");
+}
+
+struct Refusing;
+
+#[async_trait]
+impl Mailer for Refusing {
+    async fn send(&self, _message: &Message) -> Result<(), MailError> {
+        Err(MailError("550 mailbox unavailable".to_string()))
+    }
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn failed_emails_are_retried_then_dropped(pool: PgPool) {
+    let mail = Mail {
+        max_attempts: 2,
+        ..Default::default()
+    };
+    queue::send_verification(&pool, &mail, NAMI, "nami", "nami@op.co")
+        .await
+        .unwrap();
+    let sender = MailQueue::new(pool.clone(), Arc::new(Refusing), mail, Metrics::new());
+
+    assert_eq!(sender.send_due().await, 1);
+    // backing off
+    assert_eq!(sender.send_due().await, 0);
+    let (status, body): (String, String) =
+        sqlx::query_as("SELECT status::TEXT, body FROM outbound_emails")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "pending");
+    assert!(!body.is_empty());
+
+    sqlx::query("UPDATE outbound_emails SET next_attempt_at = CURRENT_TIMESTAMP")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(sender.send_due().await, 1);
+    let (status, body, error): (String, String, String) =
+        sqlx::query_as("SELECT status::TEXT, body, error FROM outbound_emails")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "failed");
+    // the body held a live link
+    assert_eq!(body, "");
+    assert_eq!(error, "550 mailbox unavailable");
+}
+
+/// A plain text SMTP server that accepts one message and returns the
+/// commands and data it was sent.
+async fn smtp_server() -> (u16, tokio::task::JoinHandle<Vec<String>>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut received = Vec::new();
+        write.write_all(b"220 mock ready\r\n").await.unwrap();
+        let mut in_data = false;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            received.push(line.clone());
+            let reply: &[u8] = match (in_data, line.as_str()) {
+                (true, ".") => {
+                    in_data = false;
+                    b"250 queued\r\n"
+                }
+                (true, _) => continue,
+                (false, l) if l.starts_with("EHLO") => b"250-mock\r\n250 AUTH PLAIN\r\n",
+                (false, l) if l.starts_with("AUTH") => b"235 ok\r\n",
+                (false, "DATA") => {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                }
+                (false, "QUIT") => {
+                    write.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                }
+                _ => b"250 ok\r\n",
+            };
+            write.write_all(reply).await.unwrap();
+        }
+        received
+    });
+    (port, server)
+}
+
+#[tokio::test]
+async fn smtp_conversation() {
+    let (port, server) = smtp_server().await;
+    let mailer = SmtpMailer::new(&Mail {
+        transport: MailTransport::Smtp,
+        from: "todoem <no-reply@todoem.test>".to_string(),
+        smtp_host: "127.0.0.1".to_string(),
+        smtp_port: port,
+        smtp_tls: SmtpTls::None,
+        smtp_username: Some("user".to_string()),
+        smtp_password: Some("pass".to_string()),
+        ..Default::default()
+    });
+    let message = Message {
+        to: "Nami <nami@op.co>".to_string(),
+        subject: "Tangerines".to_string(),
+        body: "ripe".to_string(),
+    };
+    mailer.send(&message).await.unwrap();
+
+    let received = server.await.unwrap();
+    assert_eq!(received[0], "EHLO localhost");
+    // base64 of "\0user\0pass"
+    assert_eq!(received[1], "AUTH PLAIN AHVzZXIAcGFzcw==");
+    assert_eq!(received[2], "MAIL FROM:<no-reply@todoem.test>");
+    assert_eq!(received[3], "RCPT TO:<nami@op.co>");
+    assert_eq!(received[4], "DATA");
+    assert!(received.contains(&"Subject: Tangerines".to_string()));
+    assert!(received.contains(&"cmlwZQ==".to_string()));
+    assert_eq!(received.last().unwrap(), "QUIT");
+}
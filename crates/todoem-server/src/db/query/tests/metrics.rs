@@ -0,0 +1,41 @@
+use crate::middlewares::metrics::track_requests;
+use crate::routes::health;
+use crate::services::{metrics::Metrics, shutdown::Shutdown};
+use crate::telemetry::query_timer;
+use axum::{body::Body, extract::Extension, middleware, Router};
+use http::Request;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[sqlx::test(migrations = "src/db/migrations")]
+async fn requests_and_queries_are_measured(pool: PgPool) {
+    let metrics = Metrics::new();
+    let subscriber = tracing_subscriber::registry().with(query_timer(metrics.clone()));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let app: Router = health::init()
+        .into_router()
+        .with_state(pool.clone())
+        .layer(Extension(Shutdown::new()))
+        .layer(middleware::from_fn_with_state(
+            metrics.clone(),
+            track_requests,
+        ));
+    for uri in ["/readyz", "/readyz", "/healthz/../secrets"] {
+        let req = Request::get(uri).body(Body::empty()).unwrap();
+        app.clone().oneshot(req).await.unwrap();
+    }
+
+    let rendered = metrics.render(&pool).await;
+    for line in [
+        r#"todoem_http_requests_total{method="GET",route="/readyz",status="200"} 2"#,
+        r#"todoem_http_requests_total{method="GET",route="unmatched",status="404"} 1"#,
+        r#"todoem_http_request_duration_seconds_count{method="GET",route="/readyz",status="200"} 2"#,
+        r#"todoem_db_query_duration_seconds_count{query="health::ping"} 2"#,
+        r#"todoem_db_query_duration_seconds_count{query="health::pending_migrations"} 2"#,
+        r#"todoem_db_pool_connections{state="idle"}"#,
+    ] {
+        assert!(rendered.contains(line), "no {} in\n{}", line, rendered);
+    }
+}
@@ -0,0 +1,52 @@
+//! Query tests run against a real Postgres. `#[sqlx::test]` creates a fresh
+//! database per test on the server behind `DATABASE_URL` and applies
+//! `src/db/migrations`, then the users of `src/db/fixtures/users.sql`.
+
+mod accounts;
+mod connections;
+mod health;
+mod mail;
+mod metrics;
+mod oidc;
+mod sessions;
+mod stats;
+mod tasks;
+mod tokens;
+mod two_factor;
+mod webhooks;
+
+use crate::config::Jwt;
+use crate::services::jwt_keys::{JwtKeys, SharedJwtKeys};
+use std::sync::Arc;
+use todoem_core::models::AuthUser;
+use uuid::{uuid, Uuid};
+
+pub const TOTO: Uuid = uuid!("c8686820-72ce-4391-bdce-e4f260dea40f");
+pub const LUFFY: Uuid = uuid!("5d43fc3c-8acb-48f9-9b25-8f8bd6f3d834");
+pub const ZORO: Uuid = uuid!("4157ee44-1de0-4168-a1f3-7ad6a5fd09b6");
+pub const NAMI: Uuid = uuid!("f6d1dabe-7766-4a6c-b34e-75e444cc3cbd");
+
+pub const SECRET_KEY: &str = "secret";
+
+/// Signing keys made from [`SECRET_KEY`].
+pub fn keys() -> SharedJwtKeys {
+    let jwt = Jwt {
+        secret_key: SECRET_KEY.to_string(),
+        ..Default::default()
+    };
+    Arc::new(JwtKeys::load(&jwt).unwrap())
+}
+
+pub fn luffy() -> AuthUser {
+    AuthUser {
+        id: LUFFY,
+        email: "luffy@op.co".to_string(),
+    }
+}
+
+pub fn zoro() -> AuthUser {
+    AuthUser {
+        id: ZORO,
+        email: "zoro@op.co".to_string(),
+    }
+}
@@ -0,0 +1,119 @@
+use super::{keys, luffy, LUFFY, NAMI, SECRET_KEY};
+use crate::config::{Accounts, Cookies};
+use crate::db::query::session as Q;
+use crate::handlers::extract::ClientInfo;
+use crate::middlewares::jwt::{jwt_auth, AuthState};
+use crate::services::auth::{SessionIssuer, SESSION_AUDIENCE};
+use crate::services::sessions::{SessionCache, SharedSessionCache};
+use axum::{body::Body, extract::Extension, middleware, routing::get, Router};
+use http::{header::COOKIE, Request, StatusCode};
+use sqlx::PgPool;
+use std::{sync::Arc, time::Duration};
+use todoem_core::models::Credential;
+use tower::ServiceExt;
+
+fn app(pool: &PgPool, cache: SharedSessionCache) -> Router {
+    let auth = AuthState {
+        keys: keys(),
+        pool: pool.clone(),
+        sessions: cache,
+        trust_proxy: false,
+    };
+    Router::new()
+        .route(
+            "/",
+            get(|Extension(credential): Extension<Credential>| async move {
+                match credential {
+                    Credential::Session { id } => id.to_string(),
+                    Credential::Token { .. } => String::new(),
+                }
+            }),
+        )
+        .route_layer(middleware::from_fn_with_state(auth, jwt_auth))
+}
+
+/// Signs luffy in and returns the cookie header and session id.
+async fn sign_in(pool: &PgPool, user_agent: &str) -> (String, uuid::Uuid) {
+    let client = ClientInfo {
+        user_agent: Some(user_agent.to_string()),
+        ip: Some("203.0.113.7".parse().unwrap()),
+    };
+    let cookie = SessionIssuer::new(keys(), &Accounts::default(), &Cookies::default())
+        .start(pool, &luffy(), &client, None)
+        .await
+        .unwrap();
+    let sessions = Q::select_sessions(pool, LUFFY, None).await.unwrap();
+    (
+        format!("{}={}", cookie.name(), cookie.value()),
+        sessions[0].id,
+    )
+}
+
+async fn status(app: &Router, cookie: &str) -> StatusCode {
+    let req = Request::get("/")
+        .header(COOKIE, cookie)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(req).await.unwrap().status()
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn revoked_sessions_are_rejected(pool: PgPool) {
+    let cache = Arc::new(SessionCache::new(Duration::from_secs(60)));
+    let app = app(&pool, cache.clone());
+
+    let (phone, phone_id) = sign_in(
+        &pool,
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) Safari/604.1",
+    )
+    .await;
+    let (laptop, laptop_id) = sign_in(&pool, "Mozilla/5.0 (X11; Linux x86_64) Firefox/127.0").await;
+    assert_eq!(status(&app, &phone).await, StatusCode::OK);
+    assert_eq!(status(&app, &laptop).await, StatusCode::OK);
+
+    let listed = Q::select_sessions(&pool, LUFFY, Some(laptop_id))
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 2);
+    let current = listed.iter().find(|s| s.current).unwrap();
+    assert_eq!(current.device_name, "Firefox on Linux");
+    assert_eq!(current.ip.as_deref(), Some("203.0.113.7"));
+
+    // only the owner can revoke
+    assert!(!Q::revoke_session(&pool, NAMI, phone_id).await.unwrap());
+    assert!(Q::revoke_session(&pool, LUFFY, phone_id).await.unwrap());
+    cache.revoked(phone_id);
+    assert_eq!(status(&app, &phone).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status(&app, &laptop).await, StatusCode::OK);
+
+    let listed = Q::select_sessions(&pool, LUFFY, None).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].device_name, "Firefox on Linux");
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn lookups_are_cached(pool: PgPool) {
+    let cached = app(&pool, Arc::new(SessionCache::new(Duration::from_secs(60))));
+    let uncached = app(&pool, Arc::new(SessionCache::new(Duration::ZERO)));
+    let (cookie, _) = sign_in(&pool, "curl/8.8.0").await;
+    assert_eq!(status(&cached, &cookie).await, StatusCode::OK);
+
+    // revoked by another instance, which this one learns within the ttl
+    Q::revoke_all_sessions(&pool, LUFFY).await.unwrap();
+    assert_eq!(status(&cached, &cookie).await, StatusCode::OK);
+    assert_eq!(status(&uncached, &cookie).await, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn tokens_without_session_are_rejected(pool: PgPool) {
+    let app = app(&pool, Arc::new(SessionCache::new(Duration::from_secs(60))));
+    // a well signed token from before sessions were tracked
+    let claims = serde_json::json!({
+        "id": LUFFY, "email": "luffy@op.co", "aud": SESSION_AUDIENCE,
+        "exp": chrono::Utc::now().timestamp() + 60,
+    });
+    let key = jsonwebtoken::EncodingKey::from_secret(SECRET_KEY.as_ref());
+    let token = jsonwebtoken::encode(&Default::default(), &claims, &key).unwrap();
+    let status = status(&app, &format!("access_token={}", token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
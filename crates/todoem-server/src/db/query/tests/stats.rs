@@ -0,0 +1,120 @@
+use super::LUFFY;
+use crate::db::query::{stats as Q, task as QTask};
+use crate::services::calendar::{self, Due};
+use chrono::{Days, Utc};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+
+async fn complete(pool: &PgPool, days_ago: u64, hour: u32, due_days_ago: Option<u64>) {
+    let at = (Utc::now().date_naive() - Days::new(days_ago))
+        .and_hms_opt(hour, 0, 0)
+        .unwrap()
+        .and_utc();
+    let due_on = due_days_ago.map(|d| Utc::now().date_naive() - Days::new(d));
+    sqlx::query(
+        "
+        INSERT INTO task_completions (user_id, completed_at, due_on) VALUES ($1, $2, $3);
+        ",
+    )
+    .bind(LUFFY)
+    .bind(at)
+    .bind(due_on)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn streaks_and_punctuality(pool: PgPool) {
+    // streak of three ending yesterday, and an older one of four
+    for days_ago in [1, 2, 3, 6, 7, 8, 9] {
+        complete(&pool, days_ago, 12, None).await;
+    }
+    complete(&pool, 2, 13, Some(3)).await;
+    complete(&pool, 1, 13, Some(1)).await;
+
+    let streaks = Q::select_streaks(&pool, LUFFY, "UTC").await.unwrap();
+    assert_eq!((streaks.current, streaks.longest), (3, 4));
+
+    let punctuality = Q::select_punctuality(&pool, LUFFY, "UTC").await.unwrap();
+    assert_eq!((punctuality.on_time, punctuality.late), (1, 1));
+    assert_eq!(punctuality.rate, Some(0.5));
+
+    let days = Q::select_completion_series(&pool, LUFFY, "UTC", Q::Period::Day, 30)
+        .await
+        .unwrap();
+    assert_eq!(days.len(), 30);
+    assert_eq!(days.last().unwrap().period, Utc::now().date_naive());
+    assert_eq!(days.iter().map(|d| d.completed).sum::<i64>(), 9);
+    assert_eq!(days[28].completed, 2);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn streak_days_follow_timezone(pool: PgPool) {
+    // 01:00 and 23:00 UTC on the same day are two different days in Tokyo
+    complete(&pool, 5, 1, None).await;
+    complete(&pool, 5, 23, None).await;
+
+    let utc = Q::select_streaks(&pool, LUFFY, "UTC").await.unwrap();
+    let tokyo = Q::select_streaks(&pool, LUFFY, "Asia/Tokyo").await.unwrap();
+    assert_eq!(utc.longest, 1);
+    assert_eq!(tokyo.longest, 2);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn recurring_completion_moves_on_and_back(pool: PgPool) {
+    let today = Utc::now().date_naive();
+    let task: (i64,) = sqlx::query_as(
+        "
+        INSERT INTO tasks (user_id, task, description, due_on, repeat_frequency)
+        VALUES ($1, 'water plants', '', $2, 'daily') RETURNING id;
+        ",
+    )
+    .bind(LUFFY)
+    .bind(today - Days::new(2))
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let task = QTask::select_task_for_update_tx(&mut tx, LUFFY, task.0)
+        .await
+        .unwrap();
+    let next = calendar::advance(
+        Due::of(&task).unwrap(),
+        Due::anchor_of(&task).unwrap(),
+        task.repeat_frequency.as_ref().unwrap(),
+        Tz::UTC,
+        today,
+    );
+    assert_eq!(next, Some((Due::On(today + Days::new(1)), 2)));
+    QTask::complete_task_tx(&mut tx, &task, next).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let moved = QTask::select_task(pool.clone(), LUFFY, task.id)
+        .await
+        .unwrap();
+    assert!(!moved.done);
+    assert_eq!(moved.due_on, Some(today + Days::new(1)));
+
+    let adherence = Q::select_recurring_adherence(&pool, LUFFY, "UTC")
+        .await
+        .unwrap();
+    assert_eq!(
+        (adherence.completed, adherence.on_time, adherence.missed),
+        (1, 0, 2)
+    );
+    assert_eq!(
+        Q::select_task_adherence(&pool, LUFFY, "UTC")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let mut tx = pool.begin().await.unwrap();
+    QTask::uncomplete_task_tx(&mut tx, &moved).await.unwrap();
+    tx.commit().await.unwrap();
+    let back = QTask::select_task(pool, LUFFY, task.id).await.unwrap();
+    assert_eq!(back.due_on, Some(today - Days::new(2)));
+}
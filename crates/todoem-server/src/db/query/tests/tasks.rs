@@ -0,0 +1,127 @@
+use super::LUFFY;
+use crate::db::query::task as Q;
+use crate::services::calendar;
+use chrono::NaiveDate;
+use chrono_tz::America::New_York;
+use sqlx::PgPool;
+use todoem_core::models::filter::Filter;
+
+async fn insert(pool: &PgPool, task: &str, due_date: Option<&str>, due_on: Option<&str>) {
+    sqlx::query(
+        "
+        INSERT INTO tasks (user_id, task, description, due_date, due_on)
+        VALUES ($1, $2, '', $3::timestamptz, $4::date);
+        ",
+    )
+    .bind(LUFFY)
+    .bind(task)
+    .bind(due_date)
+    .bind(due_on)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn due_range_follows_local_day(pool: PgPool) {
+    // 23:30 on the 1st in New York, already the 2nd in UTC
+    insert(&pool, "late evening", Some("2024-03-02T04:30:00Z"), None).await;
+    insert(&pool, "all day", None, Some("2024-03-01")).await;
+    insert(&pool, "next day", Some("2024-03-02T05:30:00Z"), None).await;
+
+    let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let (start, end) = calendar::day_range(New_York, day, day);
+    let tasks = Q::select_tasks_due_between(pool, LUFFY, day, day, start, end)
+        .await
+        .unwrap();
+    let names: Vec<_> = tasks.iter().map(|t| t.task.as_str()).collect();
+    assert_eq!(names, ["late evening", "all day"]);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn overdue_excludes_today(pool: PgPool) {
+    insert(&pool, "yesterday", None, Some("2024-02-29")).await;
+    insert(&pool, "this morning", Some("2024-03-01T06:00:00Z"), None).await;
+    insert(&pool, "today", None, Some("2024-03-01")).await;
+    insert(&pool, "someday", None, None).await;
+
+    let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let start = calendar::start_of_day(New_York, today);
+    let overdue = Q::select_overdue_tasks(pool.clone(), LUFFY, "America/New_York", today, start)
+        .await
+        .unwrap();
+    assert_eq!(overdue.len(), 1);
+    assert_eq!(overdue[0].task, "yesterday");
+    assert_eq!(
+        Q::count_overdue_and_undated_tasks(pool, LUFFY, today, start)
+            .await
+            .unwrap(),
+        (1, 1)
+    );
+}
+
+async fn filtered(pool: &PgPool, filter: serde_json::Value) -> Vec<String> {
+    let filter: Filter = serde_json::from_value(filter).unwrap();
+    let ctx = Q::FilterContext {
+        tz: New_York,
+        today: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+    };
+    let mut names: Vec<_> = Q::select_filtered_tasks(pool.clone(), LUFFY, &filter, &ctx, 1)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.task)
+        .collect();
+    names.sort();
+    names
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn filters_compile_to_sql(pool: PgPool) {
+    insert(&pool, "work: report", None, Some("2024-03-04")).await;
+    insert(&pool, "work: 100% done", Some("2024-03-08T04:30:00Z"), None).await;
+    insert(&pool, "groceries", None, Some("2024-03-02")).await;
+    insert(&pool, "someday", None, None).await;
+
+    assert_eq!(
+        filtered(
+            &pool,
+            serde_json::json!({"and": [{"text": "work"}, {"due": {"from": "today", "to": "+6"}}]})
+        )
+        .await,
+        ["work: 100% done", "work: report"]
+    );
+    // 23:30 on the 7th locally, outside of a week starting on the 1st
+    assert_eq!(
+        filtered(
+            &pool,
+            serde_json::json!({"due": {"from": "today", "to": "+5"}})
+        )
+        .await,
+        ["groceries", "work: report"]
+    );
+    assert_eq!(
+        filtered(&pool, serde_json::json!({"text": "0%"})).await,
+        ["work: 100% done"]
+    );
+    assert_eq!(
+        filtered(&pool, serde_json::json!({"not": {"has_due": true}})).await,
+        ["someday"]
+    );
+    assert_eq!(
+        filtered(
+            &pool,
+            serde_json::json!({"not": {"due": {"from": "2024-03-03", "to": null}}})
+        )
+        .await,
+        ["groceries", "someday"]
+    );
+    assert_eq!(
+        filtered(
+            &pool,
+            serde_json::json!({"text": "'; DROP TABLE tasks; --"})
+        )
+        .await,
+        Vec::<String>::new()
+    );
+}
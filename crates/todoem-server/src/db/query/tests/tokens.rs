@@ -0,0 +1,88 @@
+use super::{LUFFY, ZORO};
+use crate::db::query::token as Q;
+use crate::services::tokens;
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+
+async fn issue(pool: &PgPool, scopes: &[&str], expires_in: Option<Duration>) -> (i64, String) {
+    let (value, hash, prefix) = tokens::generate();
+    let token = Q::insert_token(
+        pool,
+        LUFFY,
+        "cli",
+        &hash,
+        &prefix,
+        scopes,
+        expires_in.map(|d| Utc::now() + d),
+        20,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(value.starts_with(&token.prefix));
+    (token.id, value)
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn grant_resolves_owner_and_scopes(pool: PgPool) {
+    let (id, value) = issue(&pool, &["read:tasks"], Some(Duration::days(1))).await;
+
+    let grant = Q::select_grant(&pool, &tokens::hash(&value))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(grant.id, id);
+    assert_eq!(grant.user_id, LUFFY);
+    assert_eq!(grant.scopes, vec!["read:tasks"]);
+    assert!(grant.last_used_at.is_none());
+
+    Q::touch_token(&pool, id).await.unwrap();
+    let tokens = Q::select_tokens(&pool, LUFFY).await.unwrap();
+    assert!(tokens[0].last_used_at.is_some());
+
+    let unknown = tokens::generate().0;
+    assert!(Q::select_grant(&pool, &tokens::hash(&unknown))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn expired_and_revoked_tokens_grant_nothing(pool: PgPool) {
+    let (_, expired) = issue(&pool, &["read:tasks"], Some(Duration::seconds(-1))).await;
+    assert!(Q::select_grant(&pool, &tokens::hash(&expired))
+        .await
+        .unwrap()
+        .is_none());
+
+    let (id, value) = issue(&pool, &["read:tasks"], None).await;
+    assert!(!Q::revoke_token(&pool, ZORO, id).await.unwrap());
+    assert!(Q::revoke_token(&pool, LUFFY, id).await.unwrap());
+    assert!(!Q::revoke_token(&pool, LUFFY, id).await.unwrap());
+    assert!(Q::select_grant(&pool, &tokens::hash(&value))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn revoked_tokens_free_up_the_limit(pool: PgPool) {
+    let (_, hash, prefix) = tokens::generate();
+    let first = Q::insert_token(&pool, LUFFY, "a", &hash, &prefix, &[], None, 1)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let (_, hash, prefix) = tokens::generate();
+    let limited = Q::insert_token(&pool, LUFFY, "b", &hash, &prefix, &[], None, 1)
+        .await
+        .unwrap();
+    assert!(limited.is_none());
+
+    Q::revoke_token(&pool, LUFFY, first.id).await.unwrap();
+    let (_, hash, prefix) = tokens::generate();
+    let second = Q::insert_token(&pool, LUFFY, "b", &hash, &prefix, &[], None, 1)
+        .await
+        .unwrap();
+    assert!(second.is_some());
+}
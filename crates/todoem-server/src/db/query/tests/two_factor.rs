@@ -0,0 +1,178 @@
+use super::{keys, zoro, ZORO};
+use crate::config::{Accounts, Cookies};
+use crate::db::query::two_factor as Q;
+use crate::handlers::extract::ClientInfo;
+use crate::handlers::{auth as A, extract::ValidJson, two_factor as H};
+use crate::services::auth::{SessionIssuer, SESSION_AUDIENCE};
+use crate::services::two_factor;
+use axum::extract::{Extension, State};
+use axum::response::IntoResponse;
+use axum_extra::extract::cookie::CookieJar;
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::models::AuthUser;
+use todoem_core::types::{auth as TA, two_factor as T};
+
+fn sessions() -> SessionIssuer {
+    SessionIssuer::new(keys(), &Accounts::default(), &Cookies::default())
+}
+
+async fn json(res: impl IntoResponse) -> (StatusCode, serde_json::Value) {
+    let res = res.into_response();
+    let status = res.status();
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+fn code(code: &str) -> ValidJson<T::CodeRequest> {
+    ValidJson(T::CodeRequest {
+        code: code.to_string(),
+    })
+}
+
+/// Enrolls zoro and returns the secret and recovery codes.
+async fn enable(pool: &PgPool) -> (Vec<u8>, Vec<String>) {
+    let (status, enrollment) = json(H::enroll(Extension(zoro()), State(pool.clone())).await).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(enrollment["provisioning_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("otpauth://totp/todoem:zoro?secret="));
+    assert!(enrollment["qr_svg"].as_str().unwrap().contains("<svg"));
+
+    let secret = Q::select_totp(pool, ZORO).await.unwrap().unwrap().secret;
+    assert_eq!(enrollment["secret"], two_factor::base32(&secret));
+
+    let res = H::confirm(Extension(zoro()), State(pool.clone()), code("000000")).await;
+    assert_eq!(json(res).await.0, StatusCode::BAD_REQUEST);
+    let current = two_factor::current_code(&secret);
+    let res = H::confirm(Extension(zoro()), State(pool.clone()), code(&current)).await;
+    let (status, body) = json(res).await;
+    assert_eq!(status, StatusCode::OK);
+    let codes = body["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_str().unwrap().to_string())
+        .collect();
+    (secret, codes)
+}
+
+async fn password_step(pool: &PgPool) -> (StatusCode, serde_json::Value) {
+    let req = TA::LoginRequest {
+        login: "zoro".to_string(),
+        password: "zoro".to_string(),
+        device_name: None,
+    };
+    let res = A::login(
+        Extension(Accounts {
+            password_iterations: 10,
+            ..Default::default()
+        }),
+        Extension(sessions()),
+        State(pool.clone()),
+        ClientInfo::default(),
+        CookieJar::new(),
+        ValidJson(req),
+    )
+    .await;
+    json(res).await
+}
+
+async fn second_step(pool: &PgPool, challenge: &str, code: &str) -> StatusCode {
+    let req = TA::SecondFactorRequest {
+        challenge: Some(challenge.to_string()),
+        code: code.to_string(),
+        device_name: None,
+    };
+    let res = A::second_factor(
+        Extension(sessions()),
+        State(pool.clone()),
+        ClientInfo::default(),
+        CookieJar::new(),
+        ValidJson(req),
+    )
+    .await;
+    json(res).await.0
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn sign_in_asks_for_second_factor(pool: PgPool) {
+    assert_eq!(password_step(&pool).await.0, StatusCode::OK);
+    let (secret, recovery) = enable(&pool).await;
+
+    let (status, body) = password_step(&pool).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let challenge = body["challenge"].as_str().unwrap();
+
+    // forget the code used to confirm, which may still be the current one
+    sqlx::query("UPDATE user_totp SET last_used_step = NULL")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let current = two_factor::current_code(&secret);
+    assert_eq!(
+        second_step(&pool, challenge, &current).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        second_step(&pool, challenge, &current).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        second_step(&pool, "not-a-token", &recovery[0]).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let typed = recovery[0].to_uppercase().replace('-', "");
+    assert_eq!(second_step(&pool, challenge, &typed).await, StatusCode::OK);
+    assert_eq!(
+        second_step(&pool, challenge, &recovery[0]).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let (_, status) = json(H::get_status(Extension(zoro()), State(pool.clone())).await).await;
+    assert_eq!(status["enabled"], true);
+    assert_eq!(status["recovery_codes_left"], 9);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn step_up_and_session_tokens_are_not_interchangeable(pool: PgPool) {
+    enable(&pool).await;
+    let (_, body) = password_step(&pool).await;
+    let challenge = body["challenge"].as_str().unwrap();
+
+    let keys = keys();
+    assert!(keys
+        .verify::<AuthUser>(challenge, SESSION_AUDIENCE)
+        .is_err());
+
+    let session = sessions().token(&zoro(), uuid::Uuid::new_v4()).unwrap();
+    assert!(keys.verify::<AuthUser>(&session, SESSION_AUDIENCE).is_ok());
+    assert!(sessions().verify_challenge(&session).is_err());
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn disabling_takes_a_code(pool: PgPool) {
+    let (_, recovery) = enable(&pool).await;
+    let res = H::enroll(Extension(zoro()), State(pool.clone())).await;
+    assert_eq!(json(res).await.0, StatusCode::CONFLICT);
+
+    let res =
+        H::regenerate_recovery_codes(Extension(zoro()), State(pool.clone()), code(&recovery[0]))
+            .await;
+    let (status, body) = json(res).await;
+    assert_eq!(status, StatusCode::OK);
+    let fresh = body["recovery_codes"][0].as_str().unwrap();
+
+    // the old codes went with the regeneration
+    let res = H::disable(Extension(zoro()), State(pool.clone()), code(&recovery[1])).await;
+    assert_eq!(json(res).await.0, StatusCode::BAD_REQUEST);
+    let res = H::disable(Extension(zoro()), State(pool.clone()), code(fresh)).await;
+    assert_eq!(json(res).await.0, StatusCode::OK);
+
+    assert!(!Q::is_enabled(&pool, ZORO).await.unwrap());
+    assert_eq!(password_step(&pool).await.0, StatusCode::OK);
+}
@@ -0,0 +1,201 @@
+use super::LUFFY;
+use crate::config::Webhooks;
+use crate::db::query::webhook as Q;
+use crate::services::metrics::Metrics;
+use crate::services::webhooks::{self, Dispatcher};
+use axum::{extract::State, http::HeaderMap, routing::post, Router};
+use http::StatusCode;
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+use todoem_core::models::webhook::{DeliveryStatus, WebhookEvent};
+
+type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+/// Settings that let deliveries reach the local receivers below.
+fn local() -> Webhooks {
+    Webhooks {
+        allow_private_targets: true,
+        ..Webhooks::default()
+    }
+}
+
+/// A local receiver answering with `status`, recording every request.
+async fn receiver(status: StatusCode) -> (String, Received) {
+    replying(status, String::new()).await
+}
+
+/// A local receiver answering with `status` and `reply`.
+async fn replying(status: StatusCode, reply: String) -> (String, Received) {
+    let received = Received::default();
+    let app = Router::new()
+        .route(
+            "/hook",
+            post(
+                move |State(received): State<Received>, headers: HeaderMap, body: String| async move {
+                    received.lock().unwrap().push((headers, body));
+                    (status, reply)
+                },
+            ),
+        )
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (url, received)
+}
+
+async fn emit(pool: &PgPool, event: WebhookEvent, data: impl serde::Serialize) {
+    let mut tx = pool.begin().await.unwrap();
+    webhooks::emit_tx(&mut tx, LUFFY, event, data)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+}
+
+async fn register(pool: &PgPool, url: &str, events: &[&str]) -> (i64, String) {
+    let secret = webhooks::generate_secret();
+    let webhook = Q::insert_webhook(pool, LUFFY, url, "", events, &secret, 10)
+        .await
+        .unwrap()
+        .unwrap();
+    (webhook.id, secret)
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn delivers_signed_events_matching_filter(pool: PgPool) {
+    let (url, received) = receiver(StatusCode::OK).await;
+    let (id, secret) = register(&pool, &url, &["task.created"]).await;
+
+    let data = serde_json::json!({ "id": 1, "task": "write tests" });
+    emit(&pool, WebhookEvent::TaskCreated, &data).await;
+    emit(&pool, WebhookEvent::TaskDeleted, &data).await;
+
+    let dispatcher = Dispatcher::new(pool.clone(), local(), Metrics::new());
+    assert_eq!(dispatcher.dispatch_due().await, 1);
+
+    let received = std::mem::take(&mut *received.lock().unwrap());
+    assert_eq!(received.len(), 1);
+    let (headers, body) = &received[0];
+    assert_eq!(headers[webhooks::EVENT_HEADER], "task.created");
+    let signature = headers[webhooks::SIGNATURE_HEADER].to_str().unwrap();
+    assert!(webhooks::verify(&secret, signature, body));
+    assert!(!webhooks::verify("whsec_other", signature, body));
+    let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(payload["data"], data);
+
+    let log = Q::select_deliveries(&pool, LUFFY, id, 1).await.unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].status, DeliveryStatus::Succeeded);
+    assert_eq!(log[0].response_status, Some(200));
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn failed_deliveries_back_off_and_give_up(pool: PgPool) {
+    let (url, received) = receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+    let (id, _) = register(&pool, &url, &["task.completed"]).await;
+    emit(&pool, WebhookEvent::TaskCompleted, 1).await;
+
+    let config = Webhooks {
+        max_attempts: 2,
+        ..local()
+    };
+    let metrics = Metrics::new();
+    let dispatcher = Dispatcher::new(pool.clone(), config, metrics.clone());
+    assert_eq!(dispatcher.dispatch_due().await, 1);
+    // not due again before the backoff passes
+    assert_eq!(dispatcher.dispatch_due().await, 0);
+
+    let delivery = &Q::select_deliveries(&pool, LUFFY, id, 1).await.unwrap()[0];
+    assert_eq!(delivery.status, DeliveryStatus::Pending);
+    assert_eq!(delivery.attempts, 1);
+    assert!(delivery.next_attempt_at > chrono::Utc::now() + chrono::Duration::seconds(20));
+
+    sqlx::query("UPDATE webhook_deliveries SET next_attempt_at = CURRENT_TIMESTAMP;")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(dispatcher.dispatch_due().await, 1);
+    let delivery = Q::select_delivery(&pool, LUFFY, id, delivery.id)
+        .await
+        .unwrap();
+    assert_eq!(delivery.status, DeliveryStatus::Failed);
+    assert_eq!(delivery.response_status, Some(500));
+    assert_eq!(received.lock().unwrap().len(), 2);
+    let rendered = metrics.render(&pool).await;
+    assert!(rendered.contains(r#"todoem_job_items_total{job="webhooks",outcome="retried"} 1"#));
+    assert!(rendered.contains(r#"todoem_job_items_total{job="webhooks",outcome="dropped"} 1"#));
+    assert!(rendered.contains(r#"todoem_job_runs_total{job="webhooks"} 3"#));
+
+    // a replay is a fresh delivery of the same payload
+    let replay = Q::enqueue_delivery(
+        &pool,
+        id,
+        &delivery.event,
+        &delivery.payload,
+        Some(delivery.id),
+    )
+    .await
+    .unwrap();
+    assert_eq!(replay.status, DeliveryStatus::Pending);
+    assert_eq!(replay.payload, delivery.payload);
+    assert_eq!(dispatcher.dispatch_due().await, 1);
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn private_targets_are_refused(pool: PgPool) {
+    let (url, received) = receiver(StatusCode::OK).await;
+    let (id, _) = register(&pool, &url, &["task.created"]).await;
+    emit(&pool, WebhookEvent::TaskCreated, 1).await;
+
+    let dispatcher = Dispatcher::new(pool.clone(), Webhooks::default(), Metrics::new());
+    assert_eq!(dispatcher.dispatch_due().await, 1);
+
+    assert!(received.lock().unwrap().is_empty());
+    let delivery = &Q::select_deliveries(&pool, LUFFY, id, 1).await.unwrap()[0];
+    assert_eq!(delivery.response_status, None);
+    assert_eq!(delivery.response_body, None);
+    assert_eq!(
+        delivery.error.as_deref(),
+        Some("refused to deliver to non-public address 127.0.0.1")
+    );
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn long_replies_are_cut(pool: PgPool) {
+    let (url, _) = replying(StatusCode::OK, "é".repeat(1_000_000)).await;
+    let (id, _) = register(&pool, &url, &["task.created"]).await;
+    emit(&pool, WebhookEvent::TaskCreated, 1).await;
+
+    let dispatcher = Dispatcher::new(pool.clone(), local(), Metrics::new());
+    assert_eq!(dispatcher.dispatch_due().await, 1);
+
+    let delivery = &Q::select_deliveries(&pool, LUFFY, id, 1).await.unwrap()[0];
+    assert_eq!(delivery.status, DeliveryStatus::Succeeded);
+    assert_eq!(delivery.response_body, Some("é".repeat(1024)));
+}
+
+#[sqlx::test(migrations = "src/db/migrations", fixtures("../../fixtures/users.sql"))]
+async fn events_are_queued_with_their_action(pool: PgPool) {
+    let (url, _) = receiver(StatusCode::OK).await;
+    let (id, _) = register(&pool, &url, &["task.created"]).await;
+
+    // rolled back along with whatever caused it
+    let mut tx = pool.begin().await.unwrap();
+    webhooks::emit_tx(&mut tx, LUFFY, WebhookEvent::TaskCreated, 1)
+        .await
+        .unwrap();
+    tx.rollback().await.unwrap();
+    assert!(Q::select_deliveries(&pool, LUFFY, id, 1)
+        .await
+        .unwrap()
+        .is_empty());
+
+    emit(&pool, WebhookEvent::TaskCreated, 1).await;
+    assert_eq!(
+        Q::select_deliveries(&pool, LUFFY, id, 1)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use todoem_core::{
+    errors::APIError,
+    models::token::{ApiToken, TokenGrant},
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+const TOKEN: &str = "
+    id, name, prefix, scopes, expires_at, last_used_at, revoked_at, created_at
+";
+
+/// Inserts a token unless the user already has `max_per_user` unrevoked ones.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all)]
+pub async fn insert_token(
+    pool: &PgPool,
+    user_id: Uuid,
+    name: &str,
+    token_hash: &str,
+    prefix: &str,
+    scopes: &[&str],
+    expires_at: Option<DateTime<Utc>>,
+    max_per_user: i64,
+) -> Result<Option<ApiToken>, APIError> {
+    match sqlx::query_as::<_, ApiToken>(&format!(
+        "
+        INSERT INTO api_tokens (user_id, name, token_hash, prefix, scopes, expires_at)
+        SELECT $1, $2, $3, $4, $5, $6
+        WHERE (SELECT COUNT(*) FROM api_tokens WHERE user_id = $1 AND revoked_at IS NULL) < $7
+        RETURNING {};
+        ",
+        TOKEN
+    ))
+    .bind(user_id)
+    .bind(name)
+    .bind(token_hash)
+    .bind(prefix)
+    .bind(scopes)
+    .bind(expires_at)
+    .bind(max_per_user)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(token) => Ok(token),
+        Err(e) => {
+            tracing::error!("Failed to insert api token: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_tokens(pool: &PgPool, user_id: Uuid) -> Result<Vec<ApiToken>, APIError> {
+    match sqlx::query_as::<_, ApiToken>(&format!(
+        "
+        SELECT {} FROM api_tokens WHERE user_id = $1 ORDER BY id DESC;
+        ",
+        TOKEN
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(tokens) => Ok(tokens),
+        Err(e) => {
+            tracing::error!("Failed to select api tokens: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Returns `false` when there is no such unrevoked token.
+#[instrument(skip_all)]
+pub async fn revoke_token(pool: &PgPool, user_id: Uuid, token_id: i64) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        UPDATE api_tokens SET revoked_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL;
+        ",
+    )
+    .bind(token_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    {
+        Ok(r) => Ok(r.rows_affected() == 1),
+        Err(e) => {
+            tracing::error!("Failed to revoke api token: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Looks up an unrevoked, unexpired token of an active account.
+#[instrument(skip_all)]
+pub async fn select_grant(pool: &PgPool, token_hash: &str) -> Result<Option<TokenGrant>, APIError> {
+    match sqlx::query_as::<_, TokenGrant>(
+        "
+        SELECT t.id, t.user_id, u.email, t.scopes, t.last_used_at
+        FROM api_tokens t
+        INNER JOIN users u ON u.id = t.user_id
+        WHERE t.token_hash = $1
+            AND t.revoked_at IS NULL
+            AND (t.expires_at IS NULL OR t.expires_at > CURRENT_TIMESTAMP)
+            AND u.deletion_requested_at IS NULL;
+        ",
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(grant) => Ok(grant),
+        Err(e) => {
+            tracing::error!("Failed to select api token: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn touch_token(pool: &PgPool, token_id: i64) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        UPDATE api_tokens SET last_used_at = CURRENT_TIMESTAMP WHERE id = $1;
+        ",
+    )
+    .bind(token_id)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to touch api token: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use sqlx::PgPool;
+use todoem_core::{
+    errors::APIError,
+    models::two_factor::{Totp, TwoFactorStatus},
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+#[instrument(skip_all)]
+pub async fn select_totp(pool: &PgPool, user_id: Uuid) -> Result<Option<Totp>, APIError> {
+    match sqlx::query_as::<_, Totp>(
+        "SELECT secret, confirmed_at FROM user_totp WHERE user_id = $1;",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(totp) => Ok(totp),
+        Err(e) => {
+            tracing::error!("Failed to select totp: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn is_enabled(pool: &PgPool, user_id: Uuid) -> Result<bool, APIError> {
+    match sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM user_totp WHERE user_id = $1 AND confirmed_at IS NOT NULL);",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(enabled) => Ok(enabled),
+        Err(e) => {
+            tracing::error!("Failed to check two-factor status: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_status(pool: &PgPool, user_id: Uuid) -> Result<TwoFactorStatus, APIError> {
+    match sqlx::query_as::<_, TwoFactorStatus>(
+        "
+        SELECT t.confirmed_at IS NOT NULL AS enabled, t.confirmed_at AS enabled_at,
+            (SELECT COUNT(*) FROM recovery_codes r WHERE r.user_id = $1 AND r.used_at IS NULL)
+                AS recovery_codes_left
+        FROM (SELECT $1::UUID AS user_id) u
+        LEFT JOIN user_totp t ON t.user_id = u.user_id;
+        ",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(status) => Ok(status),
+        Err(e) => {
+            tracing::error!("Failed to select two-factor status: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Stores a secret awaiting confirmation, replacing an earlier unconfirmed
+/// one. Returns `false` when two-factor is already enabled.
+#[instrument(skip_all)]
+pub async fn upsert_pending_totp(
+    pool: &PgPool,
+    user_id: Uuid,
+    secret: &[u8],
+) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        INSERT INTO user_totp (user_id, secret) VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE
+            SET secret = EXCLUDED.secret, created_at = CURRENT_TIMESTAMP
+            WHERE user_totp.confirmed_at IS NULL;
+        ",
+    )
+    .bind(user_id)
+    .bind(secret)
+    .execute(pool)
+    .await
+    {
+        Ok(res) => Ok(res.rows_affected() > 0),
+        Err(e) => {
+            tracing::error!("Failed to store totp secret: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Records that the code of `step` was used. Returns `false` if it, or a
+/// later one, already was.
+#[instrument(skip_all)]
+pub async fn use_totp_step(pool: &PgPool, user_id: Uuid, step: i64) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        UPDATE user_totp SET last_used_step = $2
+        WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2);
+        ",
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(pool)
+    .await
+    {
+        Ok(res) => Ok(res.rows_affected() > 0),
+        Err(e) => {
+            tracing::error!("Failed to record totp use: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn confirm_totp_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    step: i64,
+) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        UPDATE user_totp SET confirmed_at = CURRENT_TIMESTAMP, last_used_step = $2
+        WHERE user_id = $1 AND confirmed_at IS NULL;
+        ",
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(res) => Ok(res.rows_affected() > 0),
+        Err(e) => {
+            tracing::error!("Failed to confirm totp: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn delete_two_factor(pool: &PgPool, user_id: Uuid) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        WITH codes AS (DELETE FROM recovery_codes WHERE user_id = $1)
+        DELETE FROM user_totp WHERE user_id = $1;
+        ",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to delete two-factor settings: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Replaces all of the user's recovery codes, used or not.
+#[instrument(skip_all)]
+pub async fn replace_recovery_codes_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    code_hashes: &[String],
+) -> Result<(), APIError> {
+    if let Err(e) = sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1;")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await
+    {
+        tracing::error!("Failed to delete recovery codes: {:?}", e);
+        return Err(APIError::server());
+    }
+
+    match sqlx::query(
+        "
+        INSERT INTO recovery_codes (user_id, code_hash) SELECT $1, UNNEST($2::TEXT[]);
+        ",
+    )
+    .bind(user_id)
+    .bind(code_hashes)
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to insert recovery codes: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Marks an unused recovery code used. Returns whether there was one.
+#[instrument(skip_all)]
+pub async fn consume_recovery_code(
+    pool: &PgPool,
+    user_id: Uuid,
+    code_hash: &str,
+) -> Result<bool, APIError> {
+    match sqlx::query(
+        "
+        UPDATE recovery_codes SET used_at = CURRENT_TIMESTAMP
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL;
+        ",
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(pool)
+    .await
+    {
+        Ok(res) => Ok(res.rows_affected() > 0),
+        Err(e) => {
+            tracing::error!("Failed to consume recovery code: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
@@ -0,0 +1,650 @@
+use super::{offset, PAGE_LIMIT};
+use sqlx::PgPool;
+use todoem_core::{errors::APIError, models::user as M};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Users visible to `user_id` for `search_query`, honouring each user's
+/// search visibility and hiding blocks in either direction.
+#[instrument(skip_all)]
+pub async fn search(
+    pool: PgPool,
+    user_id: Uuid,
+    search_query: String,
+    page: i16,
+) -> Result<Vec<M::User>, APIError> {
+    match sqlx::query_as::<_, M::User>(
+        "
+    SELECT u.id, u.username, u.name FROM users u
+    WHERE u.deletion_requested_at IS NULL AND (
+        (u.search_visibility = 'everyone' AND (u.username ILIKE '%' || $2 || '%' OR u.name ILIKE '%' || $2 || '%'))
+        OR (u.search_visibility = 'username' AND lower(u.username) = lower($2))
+    )
+    AND NOT EXISTS (
+        SELECT 1 FROM user_blocks b
+        WHERE (b.blocker_id = $1 AND b.blocked_id = u.id) OR (b.blocker_id = u.id AND b.blocked_id = $1)
+    )
+    ORDER BY u.created_at LIMIT $3 OFFSET $4;
+    ",
+    )
+    .bind(user_id)
+    .bind(search_query)
+    .bind(PAGE_LIMIT)
+    .bind(offset(page))
+    .fetch_all(&pool)
+    .await
+    {
+        Ok(users) => Ok(users),
+        Err(e) => {
+            tracing::error!("Failed to search for users: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_user_profile(pool: &PgPool, id: Uuid) -> Result<M::User, APIError> {
+    match sqlx::query_as::<_, M::User>(
+        "SELECT id, username, name from users WHERE id = $1 AND deletion_requested_at IS NULL",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(user) => Ok(user),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to select user profile: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_connection(
+    pool: &PgPool,
+    user_id: Uuid,
+    other_id: Uuid,
+) -> Result<Option<M::Connection>, APIError> {
+    match sqlx::query_as::<_, M::Connection>(
+        "
+        SELECT state, actor_id FROM connections
+        WHERE user_a = LEAST($1::uuid, $2::uuid) AND user_b = GREATEST($1::uuid, $2::uuid);
+        ",
+    )
+    .bind(user_id)
+    .bind(other_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(c) => Ok(c),
+        Err(e) => {
+            tracing::error!("Failed to select connection: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Serialises every transition of the pair `user_id`/`other_id` for the rest
+/// of `tx`, whichever of them acts and whether or not the pair has a row yet.
+async fn lock_pair_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    other_id: Uuid,
+) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        SELECT pg_advisory_xact_lock(
+            hashtextextended(LEAST($1::uuid, $2::uuid)::text || GREATEST($1::uuid, $2::uuid)::text, 0)
+        );
+        ",
+    )
+    .bind(user_id)
+    .bind(other_id)
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to lock connection: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// none -> pending, or rejected -> pending when the rejecter asks back.
+/// Returns `false` when the pair is in any other state or either side blocks the other.
+#[instrument(skip_all)]
+pub async fn request_connection_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    sender_id: Uuid,
+    receiver_id: Uuid,
+) -> Result<bool, APIError> {
+    lock_pair_tx(tx, sender_id, receiver_id).await?;
+    match sqlx::query(
+        "
+        INSERT INTO connections (user_a, user_b, actor_id, state)
+        SELECT LEAST($1::uuid, $2::uuid), GREATEST($1::uuid, $2::uuid), $1, 'pending'
+        WHERE NOT EXISTS (
+            SELECT 1 FROM user_blocks
+            WHERE (blocker_id = $1 AND blocked_id = $2) OR (blocker_id = $2 AND blocked_id = $1)
+        )
+        ON CONFLICT (user_a, user_b) DO UPDATE
+        SET state = 'pending', actor_id = EXCLUDED.actor_id, updated_at = CURRENT_TIMESTAMP
+        WHERE connections.state = 'rejected' AND connections.actor_id = EXCLUDED.actor_id;
+        ",
+    )
+    .bind(sender_id)
+    .bind(receiver_id)
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(r) => Ok(r.rows_affected() == 1),
+        Err(e) => {
+            if let sqlx::Error::Database(db) = &e {
+                if db.is_foreign_key_violation() {
+                    return Err(APIError::not_found());
+                }
+            }
+            tracing::error!("Failed to request connection: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Runs a single-row transition between `user_id` and `other_id`, where `$1`
+/// is the acting user and `$2` the other one. Returns whether it applied.
+async fn transition(
+    pool: &PgPool,
+    query: &str,
+    user_id: Uuid,
+    other_id: Uuid,
+) -> Result<bool, APIError> {
+    match sqlx::query(query)
+        .bind(user_id)
+        .bind(other_id)
+        .execute(pool)
+        .await
+    {
+        Ok(r) => Ok(r.rows_affected() == 1),
+        Err(e) => {
+            if let sqlx::Error::Database(db) = &e {
+                if db.is_foreign_key_violation() {
+                    return Err(APIError::not_found());
+                }
+            }
+            tracing::error!("Failed to transition connection: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// pending -> none, by the requester.
+#[instrument(skip_all)]
+pub async fn cancel_request(
+    pool: &PgPool,
+    sender_id: Uuid,
+    receiver_id: Uuid,
+) -> Result<bool, APIError> {
+    transition(
+        pool,
+        "
+        DELETE FROM connections
+        WHERE user_a = LEAST($1::uuid, $2::uuid) AND user_b = GREATEST($1::uuid, $2::uuid)
+        AND state = 'pending' AND actor_id = $1;
+        ",
+        sender_id,
+        receiver_id,
+    )
+    .await
+}
+
+/// pending -> connected, by the receiver.
+#[instrument(skip_all)]
+pub async fn accept_request_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    receiver_id: Uuid,
+    sender_id: Uuid,
+) -> Result<bool, APIError> {
+    lock_pair_tx(tx, receiver_id, sender_id).await?;
+    match sqlx::query(
+        "
+        UPDATE connections SET state = 'connected', actor_id = $1, updated_at = CURRENT_TIMESTAMP
+        WHERE user_a = LEAST($1::uuid, $2::uuid) AND user_b = GREATEST($1::uuid, $2::uuid)
+        AND state = 'pending' AND actor_id = $2;
+        ",
+    )
+    .bind(receiver_id)
+    .bind(sender_id)
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(r) => Ok(r.rows_affected() == 1),
+        Err(e) => {
+            tracing::error!("Failed to accept connection request: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// pending -> rejected, by the receiver.
+#[instrument(skip_all)]
+pub async fn reject_request(
+    pool: &PgPool,
+    receiver_id: Uuid,
+    sender_id: Uuid,
+) -> Result<bool, APIError> {
+    transition(
+        pool,
+        "
+        UPDATE connections SET state = 'rejected', actor_id = $1, updated_at = CURRENT_TIMESTAMP
+        WHERE user_a = LEAST($1::uuid, $2::uuid) AND user_b = GREATEST($1::uuid, $2::uuid)
+        AND state = 'pending' AND actor_id = $2;
+        ",
+        receiver_id,
+        sender_id,
+    )
+    .await
+}
+
+/// connected -> none, by either user.
+#[instrument(skip_all)]
+pub async fn disconnect(pool: &PgPool, user_id: Uuid, other_id: Uuid) -> Result<bool, APIError> {
+    transition(
+        pool,
+        "
+        DELETE FROM connections
+        WHERE user_a = LEAST($1::uuid, $2::uuid) AND user_b = GREATEST($1::uuid, $2::uuid)
+        AND state = 'connected';
+        ",
+        user_id,
+        other_id,
+    )
+    .await
+}
+
+/// Whether either user blocks the other.
+#[instrument(skip_all)]
+pub async fn is_blocked(pool: &PgPool, user_id: Uuid, other_id: Uuid) -> Result<bool, APIError> {
+    match sqlx::query_scalar::<_, bool>(
+        "
+        SELECT EXISTS (
+            SELECT 1 FROM user_blocks
+            WHERE (blocker_id = $1 AND blocked_id = $2) OR (blocker_id = $2 AND blocked_id = $1)
+        );
+        ",
+    )
+    .bind(user_id)
+    .bind(other_id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(blocked) => Ok(blocked),
+        Err(e) => {
+            tracing::error!("Failed to check for blocks: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Blocks `blocked_id` for `blocker_id`: any state -> blocked, ending any
+/// connection or request between them. Each user of a pair blocks on their
+/// own, so a block by one side is kept whatever the other does. Returns
+/// `false` if `blocker_id` already blocked `blocked_id`.
+#[instrument(skip_all)]
+pub async fn block(pool: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<bool, APIError> {
+    let mut tx = begin(pool).await?;
+    lock_pair_tx(&mut tx, blocker_id, blocked_id).await?;
+
+    let blocked = match sqlx::query(
+        "
+        INSERT INTO user_blocks (blocker_id, blocked_id) VALUES ($1, $2)
+        ON CONFLICT DO NOTHING;
+        ",
+    )
+    .bind(blocker_id)
+    .bind(blocked_id)
+    .execute(&mut *tx)
+    .await
+    {
+        Ok(r) => r.rows_affected() == 1,
+        Err(e) => {
+            if let sqlx::Error::Database(db) = &e {
+                if db.is_foreign_key_violation() {
+                    return Err(APIError::not_found());
+                }
+            }
+            tracing::error!("Failed to block user: {:?}", e);
+            return Err(APIError::server());
+        }
+    };
+
+    if blocked {
+        if let Err(e) = sqlx::query(
+            "
+            INSERT INTO connections (user_a, user_b, actor_id, state)
+            VALUES (LEAST($1::uuid, $2::uuid), GREATEST($1::uuid, $2::uuid), $1, 'blocked')
+            ON CONFLICT (user_a, user_b) DO UPDATE
+            SET state = 'blocked', actor_id = EXCLUDED.actor_id, updated_at = CURRENT_TIMESTAMP;
+            ",
+        )
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .execute(&mut *tx)
+        .await
+        {
+            tracing::error!("Failed to mark connection blocked: {:?}", e);
+            return Err(APIError::server());
+        }
+    }
+
+    commit(tx).await?;
+    Ok(blocked)
+}
+
+/// Lifts `blocker_id`'s own block of `blocked_id`. The pair goes blocked ->
+/// none unless `blocked_id` blocks `blocker_id` too, in which case it stays
+/// blocked by them.
+#[instrument(skip_all)]
+pub async fn unblock(pool: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<bool, APIError> {
+    let mut tx = begin(pool).await?;
+    lock_pair_tx(&mut tx, blocker_id, blocked_id).await?;
+
+    let unblocked =
+        match sqlx::query("DELETE FROM user_blocks WHERE blocker_id = $1 AND blocked_id = $2;")
+            .bind(blocker_id)
+            .bind(blocked_id)
+            .execute(&mut *tx)
+            .await
+        {
+            Ok(r) => r.rows_affected() == 1,
+            Err(e) => {
+                tracing::error!("Failed to unblock user: {:?}", e);
+                return Err(APIError::server());
+            }
+        };
+
+    if unblocked {
+        if let Err(e) = sqlx::query(
+            "
+            WITH kept AS (
+                UPDATE connections SET actor_id = $2, updated_at = CURRENT_TIMESTAMP
+                WHERE user_a = LEAST($1::uuid, $2::uuid) AND user_b = GREATEST($1::uuid, $2::uuid)
+                AND state = 'blocked'
+                AND EXISTS (SELECT 1 FROM user_blocks WHERE blocker_id = $2 AND blocked_id = $1)
+                RETURNING 1
+            )
+            DELETE FROM connections
+            WHERE user_a = LEAST($1::uuid, $2::uuid) AND user_b = GREATEST($1::uuid, $2::uuid)
+            AND state = 'blocked' AND NOT EXISTS (SELECT 1 FROM kept);
+            ",
+        )
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .execute(&mut *tx)
+        .await
+        {
+            tracing::error!("Failed to lift blocked connection: {:?}", e);
+            return Err(APIError::server());
+        }
+    }
+
+    commit(tx).await?;
+    Ok(unblocked)
+}
+
+async fn begin(pool: &PgPool) -> Result<sqlx::Transaction<'static, sqlx::Postgres>, APIError> {
+    match pool.begin().await {
+        Ok(tx) => Ok(tx),
+        Err(e) => {
+            tracing::error!("Failed to start transaction: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+async fn commit(tx: sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(), APIError> {
+    match tx.commit().await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Users on the other side of `user_id`'s connections in `state`, filtered by
+/// whether `user_id` made the last transition.
+async fn select_counterparts(
+    pool: &PgPool,
+    user_id: Uuid,
+    state: M::ConnectionState,
+    acted: bool,
+) -> Result<Vec<M::User>, APIError> {
+    match sqlx::query_as::<_, M::User>(
+        "
+    SELECT u.id, u.username, u.name FROM connections c
+    INNER JOIN users u ON u.id = CASE WHEN c.user_a = $1 THEN c.user_b ELSE c.user_a END
+    WHERE (c.user_a = $1 OR c.user_b = $1) AND c.state = $2 AND (c.actor_id = $1) = $3
+    ORDER BY c.updated_at DESC
+    ;
+    ",
+    )
+    .bind(user_id)
+    .bind(state)
+    .bind(acted)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(users) => Ok(users),
+        Err(e) => {
+            tracing::error!("Failed to select connection counterparts: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_received_requests(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<M::User>, APIError> {
+    select_counterparts(pool, user_id, M::ConnectionState::Pending, false).await
+}
+
+#[instrument(skip_all)]
+pub async fn select_sent_requests(pool: &PgPool, user_id: Uuid) -> Result<Vec<M::User>, APIError> {
+    select_counterparts(pool, user_id, M::ConnectionState::Pending, true).await
+}
+
+#[instrument(skip_all)]
+pub async fn select_blocked_users(pool: &PgPool, user_id: Uuid) -> Result<Vec<M::User>, APIError> {
+    match sqlx::query_as::<_, M::User>(
+        "
+    SELECT u.id, u.username, u.name FROM user_blocks b
+    INNER JOIN users u ON u.id = b.blocked_id
+    WHERE b.blocker_id = $1
+    ORDER BY b.blocked_at DESC;
+    ",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(users) => Ok(users),
+        Err(e) => {
+            tracing::error!("Failed to select blocked users: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_listers(
+    pool: &PgPool,
+    user_id: Uuid,
+    page: i16,
+) -> Result<Vec<M::User>, APIError> {
+    match sqlx::query_as::<_, M::User>(
+        "
+        SELECT u.id, u.username, u.name FROM users u
+        INNER JOIN user_connections c ON u.id = c.connected_id
+        WHERE c.user_id = $1 ORDER BY c.connected_at ASC LIMIT $2 OFFSET $3;
+        ",
+    )
+    .bind(user_id)
+    .bind(PAGE_LIMIT)
+    .bind(offset(page))
+    .fetch_all(pool)
+    .await
+    {
+        Ok(users) => Ok(users),
+        Err(e) => {
+            tracing::error!("Failed to get listers: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn search_listers(
+    pool: &PgPool,
+    user_id: Uuid,
+    search_query: String,
+    page: i16,
+) -> Result<Vec<M::User>, APIError> {
+    match sqlx::query_as::<_, M::User>(
+        "
+        SELECT u.id, u.username, u.name FROM users u
+        INNER JOIN user_connections c ON u.id = c.connected_id
+        WHERE c.user_id = $1 AND (u.username ILIKE '%' || $2 || '%' OR u.name ILIKE '%' || $2 || '%') 
+        ORDER BY c.connected_at ASC LIMIT $3 OFFSET $4;
+        ",
+    )
+    .bind(user_id)
+    .bind(search_query)
+    .bind(PAGE_LIMIT)
+    .bind(offset(page))
+    .fetch_all(pool)
+    .await
+    {
+        Ok(users) => Ok(users),
+        Err(e) => {
+            tracing::error!("Failed to search listers: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Whether `user_id` and `other_id` share at least one connection.
+#[instrument(skip_all)]
+pub async fn is_friend_of_friend(
+    pool: &PgPool,
+    user_id: Uuid,
+    other_id: Uuid,
+) -> Result<bool, APIError> {
+    match sqlx::query_scalar(
+        "
+        SELECT EXISTS (
+            SELECT 1 FROM user_connections a
+            INNER JOIN user_connections b ON a.connected_id = b.user_id
+            WHERE a.user_id = $1 AND b.connected_id = $2
+        );
+        ",
+    )
+    .bind(user_id)
+    .bind(other_id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(i) => Ok(i),
+        Err(e) => {
+            tracing::error!("Failed to check friends of friends: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_privacy(pool: &PgPool, user_id: Uuid) -> Result<M::Privacy, APIError> {
+    match sqlx::query_as::<_, M::Privacy>(
+        "SELECT search_visibility, request_policy FROM users WHERE id = $1;",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(privacy) => Ok(privacy),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to select privacy: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn update_privacy(
+    pool: &PgPool,
+    user_id: Uuid,
+    privacy: &M::Privacy,
+) -> Result<(), APIError> {
+    match sqlx::query("UPDATE users SET search_visibility = $1, request_policy = $2 WHERE id = $3;")
+        .bind(privacy.search_visibility)
+        .bind(privacy.request_policy)
+        .bind(user_id)
+        .execute(pool)
+        .await
+    {
+        Ok(r) => {
+            if r.rows_affected() != 1 {
+                return Err(APIError::not_found());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!("Failed to update privacy: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Atomically counts one use of `action` against today's (UTC) quota.
+/// Returns `false` without counting when `limit` has already been reached.
+#[instrument(skip_all)]
+pub async fn consume_daily_quota_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    action: &str,
+    limit: i32,
+) -> Result<bool, APIError> {
+    if limit <= 0 {
+        return Ok(false);
+    }
+
+    match sqlx::query_scalar::<_, i32>(
+        "
+        INSERT INTO user_daily_quotas (user_id, action, day, used)
+        VALUES ($1, $2, (now() AT TIME ZONE 'utc')::date, 1)
+        ON CONFLICT (user_id, action, day) DO UPDATE SET used = user_daily_quotas.used + 1
+        WHERE user_daily_quotas.used < $3
+        RETURNING used;
+        ",
+    )
+    .bind(user_id)
+    .bind(action)
+    .bind(limit)
+    .fetch_optional(&mut **tx)
+    .await
+    {
+        Ok(used) => Ok(used.is_some()),
+        Err(e) => {
+            tracing::error!("Failed to consume daily quota: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
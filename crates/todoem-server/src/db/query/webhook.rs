@@ -0,0 +1,389 @@
+use super::{offset, PAGE_LIMIT};
+use sqlx::{types::Json, PgPool};
+use todoem_core::{
+    errors::APIError,
+    models::webhook::{Delivery, DueDelivery, Webhook},
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Inserts a webhook unless the user already has `max_per_user` of them.
+#[instrument(skip_all)]
+pub async fn insert_webhook(
+    pool: &PgPool,
+    user_id: Uuid,
+    url: &str,
+    description: &str,
+    events: &[&str],
+    secret: &str,
+    max_per_user: i64,
+) -> Result<Option<Webhook>, APIError> {
+    match sqlx::query_as::<_, Webhook>(
+        "
+        INSERT INTO webhooks (user_id, url, description, events, secret)
+        SELECT $1, $2, $3, $4, $5
+        WHERE (SELECT COUNT(*) FROM webhooks WHERE user_id = $1) < $6
+        RETURNING *;
+        ",
+    )
+    .bind(user_id)
+    .bind(url)
+    .bind(description)
+    .bind(events)
+    .bind(secret)
+    .bind(max_per_user)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(webhook) => Ok(webhook),
+        Err(e) => {
+            tracing::error!("Failed to insert webhook: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_webhooks(pool: &PgPool, user_id: Uuid) -> Result<Vec<Webhook>, APIError> {
+    match sqlx::query_as::<_, Webhook>(
+        "
+        SELECT * FROM webhooks WHERE user_id = $1 ORDER BY id;
+        ",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(webhooks) => Ok(webhooks),
+        Err(e) => {
+            tracing::error!("Failed to select webhooks: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_webhook(
+    pool: &PgPool,
+    user_id: Uuid,
+    webhook_id: i64,
+) -> Result<Webhook, APIError> {
+    match sqlx::query_as::<_, Webhook>(
+        "
+        SELECT * FROM webhooks WHERE id = $1 AND user_id = $2;
+        ",
+    )
+    .bind(webhook_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(webhook) => Ok(webhook),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to select webhook: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn update_webhook(
+    pool: &PgPool,
+    user_id: Uuid,
+    webhook_id: i64,
+    url: &str,
+    description: &str,
+    events: &[&str],
+    active: bool,
+) -> Result<Webhook, APIError> {
+    match sqlx::query_as::<_, Webhook>(
+        "
+        UPDATE webhooks
+        SET url = $3, description = $4, events = $5, active = $6, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND user_id = $2 RETURNING *;
+        ",
+    )
+    .bind(webhook_id)
+    .bind(user_id)
+    .bind(url)
+    .bind(description)
+    .bind(events)
+    .bind(active)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(webhook) => Ok(webhook),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to update webhook: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn update_secret(
+    pool: &PgPool,
+    user_id: Uuid,
+    webhook_id: i64,
+    secret: &str,
+) -> Result<Webhook, APIError> {
+    match sqlx::query_as::<_, Webhook>(
+        "
+        UPDATE webhooks SET secret = $3, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND user_id = $2 RETURNING *;
+        ",
+    )
+    .bind(webhook_id)
+    .bind(user_id)
+    .bind(secret)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(webhook) => Ok(webhook),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to rotate webhook secret: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn delete_webhook(pool: &PgPool, user_id: Uuid, webhook_id: i64) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        DELETE FROM webhooks WHERE id = $1 AND user_id = $2;
+        ",
+    )
+    .bind(webhook_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    {
+        Ok(row) => {
+            if row.rows_affected() == 0 {
+                return Err(APIError::not_found());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete webhook: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Queues `payload` for every active webhook of the user subscribed to `event`,
+/// in the transaction of the action it is about.
+#[instrument(skip_all)]
+pub async fn enqueue_event_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    event: &str,
+    payload: &serde_json::Value,
+) -> Result<u64, APIError> {
+    match sqlx::query(
+        "
+        INSERT INTO webhook_deliveries (webhook_id, event, payload)
+        SELECT id, $2, $3 FROM webhooks
+        WHERE user_id = $1 AND active AND $2 = ANY(events);
+        ",
+    )
+    .bind(user_id)
+    .bind(event)
+    .bind(Json(payload))
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(r) => Ok(r.rows_affected()),
+        Err(e) => {
+            tracing::error!("Failed to enqueue webhook event: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Queues `payload` for one webhook regardless of its event filter.
+#[instrument(skip_all)]
+pub async fn enqueue_delivery(
+    pool: &PgPool,
+    webhook_id: i64,
+    event: &str,
+    payload: &serde_json::Value,
+    replay_of: Option<i64>,
+) -> Result<Delivery, APIError> {
+    match sqlx::query_as::<_, Delivery>(
+        "
+        INSERT INTO webhook_deliveries (webhook_id, event, payload, replay_of)
+        VALUES ($1, $2, $3, $4) RETURNING *;
+        ",
+    )
+    .bind(webhook_id)
+    .bind(event)
+    .bind(Json(payload))
+    .bind(replay_of)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(delivery) => Ok(delivery),
+        Err(e) => {
+            tracing::error!("Failed to enqueue webhook delivery: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// The delivery log of a webhook, newest first.
+#[instrument(skip_all)]
+pub async fn select_deliveries(
+    pool: &PgPool,
+    user_id: Uuid,
+    webhook_id: i64,
+    page: i16,
+) -> Result<Vec<Delivery>, APIError> {
+    match sqlx::query_as::<_, Delivery>(
+        "
+        SELECT d.* FROM webhook_deliveries d
+        INNER JOIN webhooks w ON w.id = d.webhook_id
+        WHERE d.webhook_id = $1 AND w.user_id = $2
+        ORDER BY d.id DESC LIMIT $3 OFFSET $4;
+        ",
+    )
+    .bind(webhook_id)
+    .bind(user_id)
+    .bind(PAGE_LIMIT)
+    .bind(offset(page))
+    .fetch_all(pool)
+    .await
+    {
+        Ok(deliveries) => Ok(deliveries),
+        Err(e) => {
+            tracing::error!("Failed to select webhook deliveries: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn select_delivery(
+    pool: &PgPool,
+    user_id: Uuid,
+    webhook_id: i64,
+    delivery_id: i64,
+) -> Result<Delivery, APIError> {
+    match sqlx::query_as::<_, Delivery>(
+        "
+        SELECT d.* FROM webhook_deliveries d
+        INNER JOIN webhooks w ON w.id = d.webhook_id
+        WHERE d.id = $1 AND d.webhook_id = $2 AND w.user_id = $3;
+        ",
+    )
+    .bind(delivery_id)
+    .bind(webhook_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(delivery) => Ok(delivery),
+        Err(e) => {
+            if matches!(e, sqlx::Error::RowNotFound) {
+                return Err(APIError::not_found());
+            }
+            tracing::error!("Failed to select webhook delivery: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Claims up to `limit` pending deliveries that are due, pushing their next
+/// attempt `lease_secs` out so that a crashed dispatcher's claims expire and are
+/// picked up again. Concurrent dispatchers skip each other's rows.
+#[instrument(skip_all)]
+pub async fn claim_due_deliveries(
+    pool: &PgPool,
+    limit: i64,
+    lease_secs: f64,
+) -> Result<Vec<DueDelivery>, APIError> {
+    match sqlx::query_as::<_, DueDelivery>(
+        "
+        UPDATE webhook_deliveries d
+        SET attempts = d.attempts + 1,
+            last_attempt_at = CURRENT_TIMESTAMP,
+            next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $2)
+        FROM webhooks w
+        WHERE w.id = d.webhook_id AND d.id IN (
+            SELECT q.id FROM webhook_deliveries q
+            INNER JOIN webhooks qw ON qw.id = q.webhook_id
+            WHERE q.status = 'pending' AND q.next_attempt_at <= CURRENT_TIMESTAMP AND qw.active
+            ORDER BY q.next_attempt_at
+            LIMIT $1
+            FOR UPDATE OF q SKIP LOCKED
+        )
+        RETURNING d.id, d.event, d.payload, d.attempts, w.url, w.secret;
+        ",
+    )
+    .bind(limit)
+    .bind(lease_secs)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(deliveries) => Ok(deliveries),
+        Err(e) => {
+            tracing::error!("Failed to claim webhook deliveries: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Records the outcome of an attempt. A failed delivery is retried after
+/// `retry_in_secs`, or given up on when that is `None`.
+#[instrument(skip_all)]
+pub async fn record_attempt(
+    pool: &PgPool,
+    delivery_id: i64,
+    succeeded: bool,
+    response_status: Option<i32>,
+    response_body: Option<&str>,
+    error: Option<&str>,
+    retry_in_secs: Option<f64>,
+) -> Result<(), APIError> {
+    match sqlx::query(
+        "
+        UPDATE webhook_deliveries SET
+            status = CASE
+                WHEN $2 THEN 'succeeded'
+                WHEN $6::FLOAT8 IS NULL THEN 'failed'
+                ELSE 'pending'
+            END::delivery_status,
+            delivered_at = CASE WHEN $2 THEN CURRENT_TIMESTAMP END,
+            next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => COALESCE($6, 0)),
+            response_status = $3,
+            response_body = $4,
+            error = $5
+        WHERE id = $1;
+        ",
+    )
+    .bind(delivery_id)
+    .bind(succeeded)
+    .bind(response_status)
+    .bind(response_body)
+    .bind(error)
+    .bind(retry_in_secs)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to record webhook attempt: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use axum::{http::StatusCode, BoxError};
+use todoem_core::errors::APIError;
+use tracing::instrument;
+
+#[instrument]
+pub async fn handle_api_error(err: BoxError) -> APIError {
+    tracing::error!("Error: {:#?}", err);
+    if err.is::<tower::timeout::error::Elapsed>() {
+        APIError::new(StatusCode::REQUEST_TIMEOUT, "Request timed out")
+    } else {
+        APIError::server()
+    }
+}
@@ -0,0 +1,248 @@
+use crate::config::{Accounts, Mail};
+use crate::db::query::{auth as Q, me as QMe, session as QSession, two_factor as QTwo};
+use crate::handlers::extract::ClientInfo;
+use crate::handlers::extract::ValidJson;
+use crate::services::auth::{SessionIssuer, CHALLENGE_COOKIE};
+use crate::services::mail::{queue, templates::Template};
+use crate::services::passwords::{self, Verified};
+use crate::services::sessions::SharedSessionCache;
+use crate::services::{tokens, two_factor};
+use axum::extract::{Extension, State};
+use axum::response::{IntoResponse, Response};
+use axum_extra::extract::cookie::CookieJar;
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::models::email::EmailTokenPurpose;
+use todoem_core::models::user::Profile;
+use todoem_core::models::AuthUser;
+use todoem_core::types::auth as T;
+
+use todoem_core::errors::APIError;
+
+use super::response::{APIResponse, APISuccess};
+use todoem_core::types::SuccessResponse;
+
+/// Hashing is slow on purpose, so it runs off the async runtime.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, APIError> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| {
+        tracing::error!("Password hashing task failed: {:?}", e);
+        APIError::server()
+    })
+}
+
+fn invalid_token() -> APIError {
+    APIError::bad("Invalid or expired token")
+}
+
+/// Signs `user` in, or asks for their second factor first: `202` with a
+/// step-up token, also set as a cookie for browsers.
+async fn sign_in(
+    pool: &PgPool,
+    sessions: &SessionIssuer,
+    jar: CookieJar,
+    user: AuthUser,
+    client: &ClientInfo,
+    device_name: Option<&str>,
+) -> Result<Response, APIError> {
+    if QTwo::is_enabled(pool, user.id).await? {
+        let challenge = sessions.challenge(&user)?;
+        let jar = jar.add(sessions.challenge_cookie(challenge.clone()));
+        let body = T::Challenge {
+            challenge,
+            expires_in: sessions.challenge_ttl_secs(),
+        };
+        return Ok((jar, APIResponse::accepted(body)).into_response());
+    }
+
+    let profile = QMe::select_profile(pool, user.id).await?;
+    let cookie = sessions.start(pool, &user, client, device_name).await?;
+    Ok((jar.add(cookie), APIResponse::ok(profile)).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/login",
+    request_body = T::LoginRequest,
+    responses(
+        (status = 200, description = "Signed in; sets the session cookie", body = Profile),
+        (status = 202, description = "A second factor is needed; sets the challenge cookie", body = T::Challenge),
+        (status = 401, description = "Invalid username or password", body = APIError),
+        (status = 422, description = "Invalid request", body = APIError),
+        (status = 429, description = "Too many attempts", body = APIError),
+    )
+)]
+pub async fn login(
+    Extension(accounts): Extension<Accounts>,
+    Extension(sessions): Extension<SessionIssuer>,
+    State(pool): State<PgPool>,
+    client: ClientInfo,
+    jar: CookieJar,
+    ValidJson(req): ValidJson<T::LoginRequest>,
+) -> Result<Response, APIError> {
+    let credentials = Q::select_credentials(&pool, req.login.trim()).await?;
+    let iterations = accounts.password_iterations;
+    let stored = credentials.as_ref().and_then(|c| c.password.clone());
+    let password = req.password.clone();
+    let verified = blocking(move || match stored {
+        Some(stored) => passwords::verify(&password, &stored, iterations),
+        None => {
+            // take as long as a real check, so that response times do not
+            // tell which usernames exist
+            passwords::hash(&password, iterations);
+            Verified::No
+        }
+    })
+    .await?;
+
+    let account = match (credentials, verified) {
+        (Some(account), Verified::Yes | Verified::Outdated) => account,
+        _ => {
+            return Err(APIError::new(
+                StatusCode::UNAUTHORIZED,
+                "Invalid username or password",
+            ))
+        }
+    };
+    if verified == Verified::Outdated {
+        let password = req.password.clone();
+        let hash = blocking(move || passwords::hash(&password, iterations)).await?;
+        Q::update_password(&pool, account.id, &hash).await?;
+    }
+
+    let user = AuthUser {
+        id: account.id,
+        email: account.email,
+    };
+    let device_name = req.device_name.as_deref();
+    sign_in(&pool, &sessions, jar, user, &client, device_name).await
+}
+
+/// Completes a sign-in with an authenticator or recovery code.
+#[utoipa::path(
+    post,
+    path = "/2fa",
+    request_body = T::SecondFactorRequest,
+    responses(
+        (status = 200, description = "Signed in; sets the session cookie", body = Profile),
+        (status = 401, description = "Invalid code or expired challenge", body = APIError),
+        (status = 429, description = "Too many attempts", body = APIError),
+    )
+)]
+pub async fn second_factor(
+    Extension(sessions): Extension<SessionIssuer>,
+    State(pool): State<PgPool>,
+    client: ClientInfo,
+    jar: CookieJar,
+    ValidJson(req): ValidJson<T::SecondFactorRequest>,
+) -> Result<(CookieJar, APIResponse<Profile>), APIError> {
+    let challenge = req
+        .challenge
+        .or_else(|| jar.get(CHALLENGE_COOKIE).map(|c| c.value().to_string()))
+        .ok_or_else(APIError::auth)?;
+    let user = sessions.verify_challenge(&challenge)?;
+    if !two_factor::check(&pool, user.id, &req.code).await? {
+        return Err(APIError::new(StatusCode::UNAUTHORIZED, "Invalid code"));
+    }
+
+    let profile = QMe::select_profile(&pool, user.id).await?;
+    let cookie = sessions
+        .start(&pool, &user, &client, req.device_name.as_deref())
+        .await?;
+    let jar = jar.add(sessions.removal(CHALLENGE_COOKIE)).add(cookie);
+    Ok((jar, APIResponse::ok(profile)))
+}
+
+/// Always accepted, so that it does not reveal which addresses have accounts.
+#[utoipa::path(
+    post,
+    path = "/password/forgot",
+    request_body = T::ForgotPasswordRequest,
+    responses(
+        (status = 202, description = "Sent when an account uses the address", body = SuccessResponse),
+        (status = 429, description = "Too many requests", body = APIError),
+    )
+)]
+pub async fn forgot_password(
+    Extension(mail): Extension<Mail>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::ForgotPasswordRequest>,
+) -> Result<APISuccess, APIError> {
+    for account in Q::select_accounts_by_email(&pool, req.email.trim()).await? {
+        queue::send_password_reset(&pool, &mail, account.id, &account.username, &account.email)
+            .await?;
+    }
+    Ok(APISuccess::accepted_msg(
+        "If an account uses this address, a reset link is on its way",
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/password/reset",
+    request_body = T::ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password changed; other sessions are signed out", body = SuccessResponse),
+        (status = 400, description = "Invalid or expired token", body = APIError),
+        (status = 422, description = "Invalid password", body = APIError),
+    )
+)]
+pub async fn reset_password(
+    Extension(accounts): Extension<Accounts>,
+    Extension(cache): Extension<SharedSessionCache>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::ResetPasswordRequest>,
+) -> Result<APISuccess, APIError> {
+    let grant = Q::consume_email_token(
+        &pool,
+        &tokens::hash(&req.token),
+        EmailTokenPurpose::ResetPassword,
+    )
+    .await?
+    .ok_or_else(invalid_token)?;
+
+    let iterations = accounts.password_iterations;
+    let hash = blocking(move || passwords::hash(&req.password, iterations)).await?;
+    Q::update_password(&pool, grant.user_id, &hash).await?;
+    // whoever knew the old password is signed out
+    for id in QSession::revoke_all_sessions(&pool, grant.user_id).await? {
+        cache.revoked(id);
+    }
+    // following the link proved the address is theirs
+    Q::mark_email_verified(&pool, grant.user_id, &grant.email).await?;
+
+    let profile = QMe::select_profile(&pool, grant.user_id).await?;
+    let notice = Template::PasswordChanged.render(&grant.email, &[("username", &profile.username)]);
+    queue::enqueue(&pool, &notice).await?;
+
+    Ok(APISuccess::ok_msg("Password changed"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/email/verify",
+    request_body = T::VerifyEmailRequest,
+    responses(
+        (status = 200, description = "Email verified", body = SuccessResponse),
+        (status = 400, description = "Invalid or expired token", body = APIError),
+    )
+)]
+pub async fn verify_email(
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::VerifyEmailRequest>,
+) -> Result<APISuccess, APIError> {
+    let grant = Q::consume_email_token(
+        &pool,
+        &tokens::hash(&req.token),
+        EmailTokenPurpose::VerifyEmail,
+    )
+    .await?
+    .ok_or_else(invalid_token)?;
+
+    match Q::mark_email_verified(&pool, grant.user_id, &grant.email).await? {
+        true => Ok(APISuccess::ok_msg("Email verified")),
+        // the address was changed after the link was sent
+        false => Err(invalid_token()),
+    }
+}
@@ -0,0 +1,113 @@
+use axum::{
+    async_trait,
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        FromRequest, FromRequestParts, Json, Query, Request,
+    },
+    http::{request::Parts, StatusCode},
+};
+use serde::de::DeserializeOwned;
+
+use crate::config::{Limits, RateLimits};
+use crate::middlewares::rate_limit::client_ip;
+use todoem_core::errors::APIError;
+use todoem_core::validation::{Validate, Validator};
+
+/// JSON body that has been deserialized and passed its [`Validate`] rules.
+pub struct ValidJson<T>(pub T);
+
+/// Query string that has been deserialized and passed its [`Validate`] rules.
+pub struct ValidQuery<T>(pub T);
+
+fn validate<T: Validate>(value: &T, limits: Option<Limits>) -> Result<(), APIError> {
+    let Some(limits) = limits else {
+        tracing::error!("No Limits extension on the router, cannot validate the request");
+        return Err(APIError::server());
+    };
+    let mut v = Validator::default();
+    value.validate(&mut v, &limits);
+    v.finish()
+}
+
+fn json_rejection(rejection: JsonRejection) -> APIError {
+    match rejection {
+        JsonRejection::MissingJsonContentType(e) => {
+            APIError::new(StatusCode::UNSUPPORTED_MEDIA_TYPE, &e.to_string())
+        }
+        JsonRejection::JsonDataError(e) => APIError::unprocessable(&e.body_text()),
+        JsonRejection::JsonSyntaxError(e) => APIError::bad(&e.body_text()),
+        JsonRejection::BytesRejection(_) => APIError::bad("Invalid JSON"),
+        e => {
+            tracing::error!("Unknown JSON rejection error {:#?}", e);
+            APIError::server()
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = APIError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let limits = req.extensions().get::<Limits>().cloned();
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(json_rejection)?;
+        validate(&value, limits)?;
+        Ok(Self(value))
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = APIError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|e: QueryRejection| APIError::bad(&e.body_text()))?;
+        let limits = parts.extensions.get::<Limits>().cloned();
+        validate(&value, limits)?;
+        Ok(Self(value))
+    }
+}
+
+/// Where a sign-in comes from.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip: Option<std::net::IpAddr>,
+}
+
+/// The user agent and address of the client, for recording sessions.
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = APIError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let trust_proxy = parts
+            .extensions
+            .get::<RateLimits>()
+            .is_some_and(|r| r.trust_proxy);
+        let user_agent = parts
+            .headers
+            .get(http::header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|ua| ua.chars().take(512).collect());
+        Ok(Self {
+            user_agent,
+            ip: client_ip(&parts.headers, &parts.extensions, trust_proxy),
+        })
+    }
+}
@@ -0,0 +1,127 @@
+use crate::db::query::{filter as Q, me as QMe, task as QTask};
+use crate::handlers::extract::{ValidJson, ValidQuery};
+use crate::services::calendar;
+use axum::extract::{Extension, Path, State};
+use sqlx::PgPool;
+use todoem_core::models::filter::SavedFilter;
+use todoem_core::models::task::Task;
+use todoem_core::models::AuthUser;
+use todoem_core::types::filter as T;
+use todoem_core::types::PageParams;
+
+use todoem_core::errors::APIError;
+
+use super::response::APIResponse;
+
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = T::SaveFilterRequest,
+    responses(
+        (status = 201, description = "The new filter", body = SavedFilter),
+        (status = 422, description = "Invalid filter", body = APIError),
+    )
+)]
+pub async fn create_filter(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::SaveFilterRequest>,
+) -> Result<APIResponse<SavedFilter>, APIError> {
+    let filter = Q::insert_filter(&pool, user.id, req).await?;
+    Ok(APIResponse::created(filter))
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, body = Vec<SavedFilter>))
+)]
+pub async fn get_filters(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<SavedFilter>>, APIError> {
+    let filters = Q::select_filters(&pool, user.id).await?;
+    Ok(APIResponse::ok(filters))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    responses(
+        (status = 200, body = SavedFilter),
+        (status = 404, description = "No such filter", body = APIError),
+    )
+)]
+pub async fn get_filter(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse<SavedFilter>, APIError> {
+    let filter = Q::select_filter(&pool, user.id, id).await?;
+    Ok(APIResponse::ok(filter))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    request_body = T::SaveFilterRequest,
+    responses(
+        (status = 200, description = "The updated filter", body = SavedFilter),
+        (status = 404, description = "No such filter", body = APIError),
+        (status = 422, description = "Invalid filter", body = APIError),
+    )
+)]
+pub async fn update_filter(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    ValidJson(req): ValidJson<T::SaveFilterRequest>,
+) -> Result<APIResponse<SavedFilter>, APIError> {
+    let filter = Q::update_filter(&pool, user.id, id, req).await?;
+    Ok(APIResponse::ok(filter))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "No such filter", body = APIError),
+    )
+)]
+pub async fn delete_filter(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse, APIError> {
+    Q::delete_filter(&pool, user.id, id).await?;
+    Ok(APIResponse::no_content())
+}
+
+/// Tasks matching a saved filter, relative days resolved in the user's zone.
+#[utoipa::path(
+    get,
+    path = "/{id}/tasks",
+    params(PageParams),
+    responses(
+        (status = 200, body = Vec<Task>),
+        (status = 404, description = "No such filter", body = APIError),
+    )
+)]
+pub async fn get_filter_tasks(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    ValidQuery(params): ValidQuery<PageParams>,
+) -> Result<APIResponse<Vec<Task>>, APIError> {
+    let page = params.p.unwrap_or(1) as i16;
+
+    let saved = Q::select_filter(&pool, user.id, id).await?;
+    let tz = calendar::parse_tz(&QMe::select_timezone(&pool, user.id).await?);
+    let ctx = QTask::FilterContext {
+        tz,
+        today: calendar::today(tz),
+    };
+    let tasks = QTask::select_filtered_tasks(pool, user.id, &saved.filter, &ctx, page).await?;
+    Ok(APIResponse::ok(tasks))
+}
@@ -0,0 +1,66 @@
+use crate::db::query::health as Q;
+use crate::services::shutdown::Shutdown;
+use axum::{
+    extract::{Extension, State},
+    response::IntoResponse,
+    Json,
+};
+use http::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+use std::time::Duration;
+use todoem_core::errors::APIError;
+
+/// Readiness is reported as unavailable rather than left hanging when the
+/// database is this slow to answer.
+const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Liveness: the process is up and serving requests.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    responses((status = 200, description = "`{\"status\": \"ok\"}`", body = Object))
+)]
+pub async fn healthz() -> impl IntoResponse {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Readiness: the database answers and has every migration this build
+/// expects, and the server is not shutting down.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    responses(
+        (status = 200, description = "`{\"status\": \"ready\"}`", body = Object),
+        (status = 503, description = "Not ready, with the failed checks as details", body = APIError),
+    )
+)]
+pub async fn readyz(
+    State(pool): State<PgPool>,
+    Extension(shutdown): Extension<Shutdown>,
+) -> Result<impl IntoResponse, APIError> {
+    let checks = async {
+        Q::ping(&pool).await?;
+        Q::pending_migrations(&pool).await
+    };
+    let (database, pending) = match tokio::time::timeout(READY_TIMEOUT, checks).await {
+        Ok(Ok(pending)) => ("ok", pending),
+        Ok(Err(e)) => {
+            tracing::warn!("Readiness check failed: {:?}", e);
+            ("unavailable", Vec::new())
+        }
+        Err(_) => ("timed out", Vec::new()),
+    };
+
+    let shutting_down = shutdown.is_triggered();
+    if database != "ok" || !pending.is_empty() || shutting_down {
+        return Err(
+            APIError::new(StatusCode::SERVICE_UNAVAILABLE, "Not ready").with_details(json!({
+                "database": database,
+                "pending_migrations": pending,
+                "shutting_down": shutting_down,
+            })),
+        );
+    }
+    Ok(Json(json!({ "status": "ready" })))
+}
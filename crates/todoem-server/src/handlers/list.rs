@@ -0,0 +1,129 @@
+use crate::db::query::{list as Q, user as QUser};
+use crate::handlers::extract::{ValidJson, ValidQuery};
+use crate::services::webhooks;
+use axum::extract::{Extension, Path, State};
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::models::list::List;
+use todoem_core::models::user::ConnectionState;
+use todoem_core::models::webhook::WebhookEvent;
+use todoem_core::models::AuthUser;
+use todoem_core::types::{list as T, PageParams, SuccessResponse};
+
+use todoem_core::errors::APIError;
+
+use super::response::{APIResponse, APISuccess};
+
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = T::CreateListRequest,
+    responses(
+        (status = 201, description = "The new list", body = List),
+        (status = 422, description = "Invalid list", body = APIError),
+    )
+)]
+pub async fn create_list(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::CreateListRequest>,
+) -> Result<APIResponse<List>, APIError> {
+    let mut tx = begin(&pool).await?;
+    let list = Q::insert_list_tx(&mut tx, user.id, req).await?;
+    commit(tx).await?;
+    Ok(APIResponse::created(list))
+}
+
+/// The lists made by the user and the ones sent to them, newest first.
+#[utoipa::path(
+    get,
+    path = "/",
+    params(PageParams),
+    responses((status = 200, body = Vec<List>))
+)]
+pub async fn get_lists(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidQuery(params): ValidQuery<PageParams>,
+) -> Result<APIResponse<Vec<List>>, APIError> {
+    let page = params.p.unwrap_or(1) as i16;
+    let lists = Q::select_lists(&pool, user.id, page).await?;
+    Ok(APIResponse::ok(lists))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    responses(
+        (status = 200, body = T::ListView),
+        (status = 404, description = "No such list", body = APIError),
+    )
+)]
+pub async fn get_list(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APIResponse<T::ListView>, APIError> {
+    let list = Q::select_list(&pool, user.id, id).await?;
+    let tasks = Q::select_list_tasks(&pool, list.id).await?;
+    Ok(APIResponse::ok(T::ListView { list, tasks }))
+}
+
+/// Sends a copy of the list to a connection, who gets it with its tasks undone.
+#[utoipa::path(
+    post,
+    path = "/{id}/send/{user_id}",
+    responses(
+        (status = 200, description = "Sent", body = SuccessResponse),
+        (status = 403, description = "Not connected with this user", body = APIError),
+        (status = 404, description = "No such list", body = APIError),
+        (status = 409, description = "Already sent to this user", body = APIError),
+    )
+)]
+pub async fn send_list(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path((id, user_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+) -> Result<APISuccess, APIError> {
+    Q::select_list(&pool, user.id, id).await?;
+    let connection = QUser::select_connection(&pool, user.id, user_id).await?;
+    if user.id == user_id || !matches!(connection, Some(c) if c.state == ConnectionState::Connected)
+    {
+        return Err(APIError::new(
+            StatusCode::FORBIDDEN,
+            "Lists can only be sent to connections",
+        ));
+    }
+
+    let mut tx = begin(&pool).await?;
+    let Some(received) = Q::send_list_tx(&mut tx, user.id, id, user_id).await? else {
+        return Err(APIError::new(
+            StatusCode::CONFLICT,
+            "This list was already sent to this user",
+        ));
+    };
+    webhooks::emit_tx(&mut tx, user_id, WebhookEvent::ListReceived, &received).await?;
+    commit(tx).await?;
+
+    Ok(APIResponse::ok_msg("List sent"))
+}
+
+async fn begin(pool: &PgPool) -> Result<sqlx::Transaction<'static, sqlx::Postgres>, APIError> {
+    match pool.begin().await {
+        Ok(tx) => Ok(tx),
+        Err(e) => {
+            tracing::error!("Failed to start transaction: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+async fn commit(tx: sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(), APIError> {
+    match tx.commit().await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::config::{Accounts, Mail};
+use crate::db::query::me as Q;
+use crate::handlers::extract::ValidJson;
+use crate::services::{accounts, mail::queue, storage::SharedStorage};
+use axum::extract::{Extension, Multipart, State};
+use chrono::Duration;
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::models::user as M;
+use todoem_core::models::AuthUser;
+use todoem_core::types::me as T;
+
+use todoem_core::errors::APIError;
+
+use super::response::{APIResponse, APISuccess};
+use todoem_core::types::SuccessResponse;
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, body = M::Profile))
+)]
+pub async fn get_me(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<M::Profile>, APIError> {
+    let profile = Q::select_profile(&pool, user.id).await?;
+    Ok(APIResponse::ok(profile))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/",
+    request_body = T::UpdateProfileRequest,
+    responses(
+        (status = 200, description = "The updated profile", body = M::Profile),
+        (status = 400, description = "Username changed too recently", body = APIError),
+        (status = 409, description = "Username taken", body = APIError),
+        (status = 422, description = "Invalid profile", body = APIError),
+    )
+)]
+pub async fn update_me(
+    Extension(user): Extension<AuthUser>,
+    Extension(accounts): Extension<Accounts>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::UpdateProfileRequest>,
+) -> Result<APIResponse<M::Profile>, APIError> {
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            tracing::error!("Failed to start transaction: {:?}", e);
+            return Err(APIError::server());
+        }
+    };
+
+    if let Some(username) = &req.username {
+        let current = Q::select_profile(&pool, user.id).await?;
+        if &current.username != username
+            && !Q::rename_user_tx(&mut tx, user.id, username, accounts.username_cooldown_days)
+                .await?
+        {
+            return Err(APIError::bad(&format!(
+                "Username can only be changed once every {} days",
+                accounts.username_cooldown_days
+            )));
+        }
+    }
+
+    let profile = Q::update_profile_tx(&mut tx, user.id, &req).await?;
+
+    match tx.commit().await {
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            return Err(APIError::server());
+        }
+    }
+
+    Ok(APIResponse::ok(profile))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/",
+    responses((status = 202, description = "Deletion scheduled", body = T::DeletionScheduled))
+)]
+pub async fn delete_me(
+    Extension(user): Extension<AuthUser>,
+    Extension(accounts): Extension<Accounts>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<T::DeletionScheduled>, APIError> {
+    let requested_at = Q::schedule_deletion(&pool, user.id).await?;
+    Ok(APIResponse::new(
+        StatusCode::ACCEPTED,
+        T::DeletionScheduled {
+            deletion_requested_at: requested_at,
+            purge_after: requested_at + Duration::days(accounts.deletion_grace_days as i64),
+        },
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/restore",
+    responses(
+        (status = 200, description = "Deletion cancelled", body = SuccessResponse),
+        (status = 400, description = "Not scheduled for deletion", body = APIError),
+    )
+)]
+pub async fn restore_me(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APISuccess, APIError> {
+    if !Q::cancel_deletion(&pool, user.id).await? {
+        return Err(APIError::bad("Account is not scheduled for deletion"));
+    }
+    Ok(APIResponse::ok_msg("Account deletion cancelled"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/email/verification",
+    responses(
+        (status = 202, description = "Verification email sent", body = SuccessResponse),
+        (status = 409, description = "Already verified", body = APIError),
+    )
+)]
+pub async fn request_verification(
+    Extension(user): Extension<AuthUser>,
+    Extension(mail): Extension<Mail>,
+    State(pool): State<PgPool>,
+) -> Result<APISuccess, APIError> {
+    let profile = Q::select_profile(&pool, user.id).await?;
+    if profile.email_verified_at.is_some() {
+        return Err(APIError::new(
+            StatusCode::CONFLICT,
+            "Email is already verified",
+        ));
+    }
+    queue::send_verification(&pool, &mail, user.id, &profile.username, &profile.email).await?;
+    Ok(APISuccess::accepted_msg("Verification email sent"))
+}
+
+#[utoipa::path(
+    put,
+    path = "/avatar",
+    request_body(
+        content_type = "multipart/form-data",
+        description = "The image in an `avatar` field",
+    ),
+    responses(
+        (status = 200, description = "The updated profile", body = M::Profile),
+        (status = 413, description = "Image too large", body = APIError),
+        (status = 422, description = "Not a supported image", body = APIError),
+    )
+)]
+pub async fn upload_avatar(
+    Extension(user): Extension<AuthUser>,
+    Extension(accounts): Extension<Accounts>,
+    Extension(storage): Extension<SharedStorage>,
+    State(pool): State<PgPool>,
+    mut multipart: Multipart,
+) -> Result<APIResponse<M::Profile>, APIError> {
+    let mut upload = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("avatar") => match field.bytes().await {
+                Ok(bytes) => {
+                    upload = Some(bytes);
+                    break;
+                }
+                Err(e) => return Err(APIError::new(e.status(), &e.body_text())),
+            },
+            Ok(Some(_)) => continue,
+            Ok(None) => break,
+            Err(e) => return Err(APIError::new(e.status(), &e.body_text())),
+        }
+    }
+
+    let upload = match upload {
+        Some(bytes) => bytes,
+        None => return Err(APIError::bad("Multipart field 'avatar' is required")),
+    };
+    if upload.len() > accounts.avatar_max_bytes {
+        return Err(APIError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Avatar is too large",
+        ));
+    }
+
+    let size = accounts.avatar_size;
+    let avatar =
+        match tokio::task::spawn_blocking(move || accounts::resize_avatar(&upload, size)).await {
+            Ok(Ok(avatar)) => avatar,
+            Ok(Err(e)) => {
+                tracing::debug!("Rejected avatar upload: {:?}", e);
+                return Err(APIError::unprocessable("Avatar is not a supported image"));
+            }
+            Err(e) => {
+                tracing::error!("Failed to resize avatar: {:?}", e);
+                return Err(APIError::server());
+            }
+        };
+
+    // a fresh key per upload so clients never see a cached old avatar
+    let key = format!("avatars/{}/{}.png", user.id, uuid::Uuid::new_v4());
+    if let Err(e) = storage.put(&key, avatar).await {
+        tracing::error!("Failed to store avatar: {:?}", e);
+        return Err(APIError::server());
+    }
+
+    if let Some(old) = Q::update_avatar_key(&pool, user.id, Some(&key)).await? {
+        if let Err(e) = storage.delete(&old).await {
+            tracing::error!("Failed to delete old avatar {}: {:?}", old, e);
+        }
+    }
+
+    let profile = Q::select_profile(&pool, user.id).await?;
+    Ok(APIResponse::ok(profile))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/avatar",
+    responses((status = 204, description = "Removed"))
+)]
+pub async fn delete_avatar(
+    Extension(user): Extension<AuthUser>,
+    Extension(storage): Extension<SharedStorage>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse, APIError> {
+    if let Some(old) = Q::update_avatar_key(&pool, user.id, None).await? {
+        if let Err(e) = storage.delete(&old).await {
+            tracing::error!("Failed to delete avatar {}: {:?}", old, e);
+        }
+    }
+    Ok(APIResponse::no_content())
+}
@@ -0,0 +1,44 @@
+use crate::config::Telemetry;
+use crate::services::{metrics::Metrics, passwords::constant_time_eq};
+use axum::{
+    extract::{Extension, State},
+    response::IntoResponse,
+};
+use http::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    HeaderMap,
+};
+use sqlx::PgPool;
+use todoem_core::errors::APIError;
+
+/// Everything measured so far, in the Prometheus text format.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Prometheus text format", content_type = "text/plain"),
+        (status = 401, description = "The metrics token is configured and was not given", body = APIError),
+    )
+)]
+pub async fn metrics(
+    State(pool): State<PgPool>,
+    Extension(metrics): Extension<Metrics>,
+    Extension(telemetry): Extension<Telemetry>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, APIError> {
+    if let Some(token) = &telemetry.metrics_token {
+        let given = headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        if !constant_time_eq(given.as_bytes(), token.as_bytes()) {
+            return Err(APIError::auth());
+        }
+    }
+
+    Ok((
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render(&pool).await,
+    ))
+}
@@ -0,0 +1,17 @@
+pub mod auth;
+pub mod extract;
+pub mod filter;
+pub mod health;
+pub mod list;
+pub mod me;
+pub mod metrics;
+pub mod oidc;
+pub mod response;
+pub mod session;
+pub mod stats;
+pub mod task;
+pub mod token;
+pub mod two_factor;
+pub mod user;
+pub mod webhook;
+pub mod well_known;
@@ -0,0 +1,254 @@
+use crate::config::{Limits, Mail};
+use crate::db::query::{identity as Q, me as QMe, two_factor as QTwo};
+use crate::handlers::extract::ClientInfo;
+use crate::handlers::extract::{ValidJson, ValidQuery};
+use crate::services::auth::SessionIssuer;
+use crate::services::mail::queue;
+use crate::services::oidc::{self, SharedOidc, SignIn, SIGNUP_COOKIE, STATE_COOKIE};
+use crate::services::tokens;
+use axum::extract::{Extension, Path, State};
+use axum::response::Redirect;
+use axum_extra::extract::cookie::CookieJar;
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::models::identity::{Identity, Signup};
+use todoem_core::models::user::Profile;
+use todoem_core::models::AuthUser;
+use todoem_core::types::oidc as T;
+
+use todoem_core::errors::APIError;
+
+use super::response::APIResponse;
+
+fn flow_max_age(oidc: &SharedOidc) -> time::Duration {
+    time::Duration::seconds(oidc.flow_ttl_secs() as i64)
+}
+
+fn with_query(path: &str, query: &str) -> String {
+    match path.contains('?') {
+        true => format!("{}&{}", path, query),
+        false => format!("{}?{}", path, query),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/oidc/providers",
+    responses((status = 200, description = "Names of the configured providers", body = Vec<String>))
+)]
+pub async fn get_providers(
+    Extension(oidc): Extension<SharedOidc>,
+) -> Result<APIResponse<Vec<String>>, APIError> {
+    let names = oidc.provider_names().into_iter().map(String::from);
+    Ok(APIResponse::ok(names.collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/oidc/{provider}/login",
+    params(T::LoginParams),
+    responses(
+        (status = 303, description = "To the provider's sign-in page"),
+        (status = 404, description = "No such provider", body = APIError),
+        (status = 502, description = "The provider could not be reached", body = APIError),
+    )
+)]
+pub async fn login(
+    Extension(oidc): Extension<SharedOidc>,
+    Extension(sessions): Extension<SessionIssuer>,
+    State(pool): State<PgPool>,
+    Path(provider): Path<String>,
+    ValidQuery(params): ValidQuery<T::LoginParams>,
+    jar: CookieJar,
+) -> Result<(CookieJar, Redirect), APIError> {
+    let (url, state) = oidc
+        .authorize(&pool, &provider, params.redirect_to(), None)
+        .await?;
+    let jar = jar.add(sessions.build(STATE_COOKIE, state, flow_max_age(&oidc)));
+    Ok((jar, Redirect::to(&url)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/oidc/{provider}/callback",
+    params(T::CallbackParams),
+    responses(
+        (status = 303, description = "Back to the page the sign-in started from, signed in, \
+            or with `oidc=2fa` or `oidc=signup` when a step is left"),
+        (status = 400, description = "The sign-in failed or expired", body = APIError),
+        (status = 401, description = "The sign-in could not be verified", body = APIError),
+        (status = 502, description = "The provider could not be reached", body = APIError),
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn callback(
+    Extension(oidc): Extension<SharedOidc>,
+    Extension(sessions): Extension<SessionIssuer>,
+    Extension(limits): Extension<Limits>,
+    State(pool): State<PgPool>,
+    Path(provider): Path<String>,
+    ValidQuery(params): ValidQuery<T::CallbackParams>,
+    client: ClientInfo,
+    jar: CookieJar,
+) -> Result<(CookieJar, Redirect), APIError> {
+    if let Some(error) = &params.error {
+        return Err(APIError::bad(&format!(
+            "The identity provider did not sign you in: {}",
+            error
+        )));
+    }
+    let (Some(code), Some(state)) = (&params.code, &params.state) else {
+        return Err(APIError::bad("Missing code or state"));
+    };
+    // the state has to come back to the browser that was sent off with it
+    if jar.get(STATE_COOKIE).map(|c| c.value()) != Some(state.as_str()) {
+        return Err(APIError::bad(
+            "The sign-in was started in another browser, please try again",
+        ));
+    }
+    let jar = jar.add(sessions.removal(STATE_COOKIE));
+
+    let (login, claims) = oidc.redeem(&pool, &provider, code, state).await?;
+    let redirect_to = login.redirect_to.clone();
+    match oidc::sign_in(&pool, &login, claims, &limits, oidc.flow_ttl_secs()).await? {
+        SignIn::Existing(user) if QTwo::is_enabled(&pool, user.id).await? => {
+            let jar = jar.add(sessions.challenge_cookie(sessions.challenge(&user)?));
+            Ok((jar, Redirect::to(&with_query(&redirect_to, "oidc=2fa"))))
+        }
+        SignIn::Existing(user) => {
+            let jar = jar.add(sessions.start(&pool, &user, &client, None).await?);
+            Ok((jar, Redirect::to(&redirect_to)))
+        }
+        SignIn::Linked => Ok((jar, Redirect::to(&redirect_to))),
+        SignIn::Signup(token) => {
+            let jar = jar.add(sessions.build(SIGNUP_COOKIE, token, flow_max_age(&oidc)));
+            Ok((jar, Redirect::to(&with_query(&redirect_to, "oidc=signup"))))
+        }
+    }
+}
+
+fn signup_token(jar: &CookieJar) -> Result<String, APIError> {
+    jar.get(SIGNUP_COOKIE)
+        .map(|c| tokens::hash(c.value()))
+        .ok_or_else(APIError::not_found)
+}
+
+/// The pending first sign-in, with a suggested username.
+#[utoipa::path(
+    get,
+    path = "/oidc/signup",
+    responses(
+        (status = 200, body = Signup),
+        (status = 404, description = "No sign-up pending", body = APIError),
+    )
+)]
+pub async fn get_signup(
+    Extension(oidc): Extension<SharedOidc>,
+    State(pool): State<PgPool>,
+    jar: CookieJar,
+) -> Result<APIResponse<Signup>, APIError> {
+    let signup = Q::select_signup(&pool, &signup_token(&jar)?, oidc.flow_ttl_secs())
+        .await?
+        .ok_or_else(APIError::not_found)?;
+    Ok(APIResponse::ok(signup))
+}
+
+#[utoipa::path(
+    post,
+    path = "/oidc/signup",
+    request_body = T::SignupRequest,
+    responses(
+        (status = 201, description = "Account created; sets the session cookie", body = Profile),
+        (status = 400, description = "The sign-up expired", body = APIError),
+        (status = 404, description = "No sign-up pending", body = APIError),
+        (status = 409, description = "Username taken", body = APIError),
+        (status = 422, description = "Invalid username", body = APIError),
+    )
+)]
+pub async fn complete_signup(
+    Extension(oidc): Extension<SharedOidc>,
+    Extension(sessions): Extension<SessionIssuer>,
+    Extension(mail): Extension<Mail>,
+    State(pool): State<PgPool>,
+    client: ClientInfo,
+    jar: CookieJar,
+    ValidJson(req): ValidJson<T::SignupRequest>,
+) -> Result<(CookieJar, APIResponse<Profile>), APIError> {
+    let token = jar
+        .get(SIGNUP_COOKIE)
+        .map(|c| c.value().to_string())
+        .ok_or_else(APIError::not_found)?;
+    let user = oidc::complete_signup(&pool, &token, &req.username, oidc.flow_ttl_secs()).await?;
+    let profile = QMe::select_profile(&pool, user.id).await?;
+    queue::send_verification(&pool, &mail, user.id, &profile.username, &profile.email).await?;
+    let jar = jar
+        .add(sessions.removal(SIGNUP_COOKIE))
+        .add(sessions.start(&pool, &user, &client, None).await?);
+    Ok((jar, APIResponse::created(profile)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, body = Vec<Identity>))
+)]
+pub async fn get_identities(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<Identity>>, APIError> {
+    let identities = Q::select_identities(&pool, user.id).await?;
+    Ok(APIResponse::ok(identities))
+}
+
+/// Starts a sign-in at a provider whose account is then linked to the user.
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = T::LinkRequest,
+    responses(
+        (status = 200, description = "Where to send the browser to sign in at the provider", body = T::Authorization),
+        (status = 404, description = "No such provider", body = APIError),
+    )
+)]
+pub async fn link_identity(
+    Extension(user): Extension<AuthUser>,
+    Extension(oidc): Extension<SharedOidc>,
+    Extension(sessions): Extension<SessionIssuer>,
+    State(pool): State<PgPool>,
+    jar: CookieJar,
+    ValidJson(req): ValidJson<T::LinkRequest>,
+) -> Result<(CookieJar, APIResponse<T::Authorization>), APIError> {
+    let redirect_to = req.redirect_to.as_deref().unwrap_or("/");
+    let (authorization_url, state) = oidc
+        .authorize(&pool, &req.provider, redirect_to, Some(user.id))
+        .await?;
+    let jar = jar.add(sessions.build(STATE_COOKIE, state, flow_max_age(&oidc)));
+    Ok((jar, APIResponse::ok(T::Authorization { authorization_url })))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    responses(
+        (status = 204, description = "Unlinked"),
+        (status = 404, description = "No such identity", body = APIError),
+        (status = 409, description = "The only way left to sign in", body = APIError),
+    )
+)]
+pub async fn unlink_identity(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse, APIError> {
+    if Q::delete_identity(&pool, user.id, id).await? {
+        return Ok(APIResponse::no_content());
+    }
+    let identities = Q::select_identities(&pool, user.id).await?;
+    match identities.iter().any(|i| i.id == id) {
+        true => Err(APIError::new(
+            StatusCode::CONFLICT,
+            "This is the only way left to sign in to the account",
+        )),
+        false => Err(APIError::not_found()),
+    }
+}
@@ -1,11 +1,8 @@
-pub mod list;
-pub mod task;
-pub mod user;
-
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Json, Response},
 };
+use todoem_core::types::SuccessResponse;
 
 pub struct APIResponse<T = ()>(StatusCode, Option<Json<T>>)
 where
@@ -52,11 +49,6 @@ where
     }
 }
 
-#[derive(serde::Serialize)]
-pub struct SuccessResponse {
-    msg: String,
-}
-
 pub type APISuccess = APIResponse<SuccessResponse>;
 
 impl APISuccess {
@@ -68,4 +60,13 @@ impl APISuccess {
             })),
         )
     }
+
+    pub fn accepted_msg(msg: &str) -> Self {
+        APIResponse(
+            StatusCode::ACCEPTED,
+            Some(Json(SuccessResponse {
+                msg: msg.to_string(),
+            })),
+        )
+    }
 }
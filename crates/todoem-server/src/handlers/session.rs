@@ -0,0 +1,64 @@
+use crate::db::query::session as Q;
+use crate::services::auth::{SessionIssuer, ACCESS_COOKIE};
+use crate::services::sessions::SharedSessionCache;
+use axum::extract::{Extension, Path, State};
+use axum_extra::extract::cookie::CookieJar;
+use sqlx::PgPool;
+use todoem_core::models::session::Session;
+use todoem_core::models::{AuthUser, Credential};
+use uuid::Uuid;
+
+use todoem_core::errors::APIError;
+
+use super::response::APIResponse;
+
+fn current_session(credential: &Credential) -> Option<Uuid> {
+    match credential {
+        Credential::Session { id } => Some(*id),
+        Credential::Token { .. } => None,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, body = Vec<Session>))
+)]
+pub async fn get_sessions(
+    Extension(user): Extension<AuthUser>,
+    Extension(credential): Extension<Credential>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<Session>>, APIError> {
+    let sessions = Q::select_sessions(&pool, user.id, current_session(&credential)).await?;
+    Ok(APIResponse::ok(sessions))
+}
+
+/// Signs a device out. Revoking the current session signs out this client.
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    responses(
+        (status = 204, description = "Signed out"),
+        (status = 404, description = "No such session", body = APIError),
+    )
+)]
+pub async fn revoke_session(
+    Extension(user): Extension<AuthUser>,
+    Extension(credential): Extension<Credential>,
+    Extension(cache): Extension<SharedSessionCache>,
+    Extension(sessions): Extension<SessionIssuer>,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    jar: CookieJar,
+) -> Result<(CookieJar, APIResponse), APIError> {
+    if !Q::revoke_session(&pool, user.id, id).await? {
+        return Err(APIError::not_found());
+    }
+    cache.revoked(id);
+
+    let jar = match current_session(&credential) == Some(id) {
+        true => jar.add(sessions.removal(ACCESS_COOKIE)),
+        false => jar,
+    };
+    Ok((jar, APIResponse::no_content()))
+}
@@ -0,0 +1,49 @@
+use crate::db::query::{me as QMe, stats as Q};
+use crate::services::calendar;
+use axum::extract::{Extension, State};
+use sqlx::PgPool;
+use todoem_core::models::stats as M;
+use todoem_core::models::AuthUser;
+
+use todoem_core::errors::APIError;
+
+use super::response::APIResponse;
+
+const SERIES_DAYS: i32 = 30;
+const SERIES_WEEKS: i32 = 12;
+const SERIES_MONTHS: i32 = 12;
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, body = M::Stats))
+)]
+pub async fn get_stats(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<M::Stats>, APIError> {
+    let stored = QMe::select_timezone(&pool, user.id).await?;
+    // the name postgres sees must be one we can vouch for
+    let timezone = calendar::parse_tz(&stored).name().to_string();
+    let tz = timezone.as_str();
+
+    let (days, weeks, months, streaks, punctuality, total, tasks) = tokio::try_join!(
+        Q::select_completion_series(&pool, user.id, tz, Q::Period::Day, SERIES_DAYS),
+        Q::select_completion_series(&pool, user.id, tz, Q::Period::Week, SERIES_WEEKS),
+        Q::select_completion_series(&pool, user.id, tz, Q::Period::Month, SERIES_MONTHS),
+        Q::select_streaks(&pool, user.id, tz),
+        Q::select_punctuality(&pool, user.id, tz),
+        Q::select_recurring_adherence(&pool, user.id, tz),
+        Q::select_task_adherence(&pool, user.id, tz),
+    )?;
+
+    Ok(APIResponse::ok(M::Stats {
+        timezone,
+        days,
+        weeks,
+        months,
+        streaks,
+        punctuality,
+        recurring: M::RecurringStats { total, tasks },
+    }))
+}
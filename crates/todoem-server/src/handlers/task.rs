@@ -0,0 +1,449 @@
+use crate::db::query::{me as QMe, task as Q};
+use crate::handlers::extract::{ValidJson, ValidQuery};
+use crate::services::{calendar, webhooks};
+use axum::extract::{Extension, Path, State};
+use chrono::{Days, NaiveDate};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use todoem_core::models::task::Task;
+use todoem_core::models::webhook::WebhookEvent;
+use todoem_core::models::AuthUser;
+use todoem_core::types::{task as T, PageParams};
+
+use todoem_core::errors::APIError;
+
+use super::response::APIResponse;
+
+/// Days after today covered by the upcoming view.
+const UPCOMING_DAYS: u64 = 7;
+
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = T::CreateTaskRequest,
+    responses(
+        (status = 201, description = "The new task", body = Task),
+        (status = 422, description = "Invalid task", body = APIError),
+    )
+)]
+pub async fn create_task(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidJson(req_task): ValidJson<T::CreateTaskRequest>,
+) -> Result<APIResponse<Task>, APIError> {
+    let mut tx = begin(&pool).await?;
+    let task = Q::insert_task_tx(&mut tx, user.id, req_task).await?;
+    webhooks::emit_tx(&mut tx, user.id, WebhookEvent::TaskCreated, &task).await?;
+    commit(tx).await?;
+    Ok(APIResponse::created(task))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    responses(
+        (status = 200, body = Task),
+        (status = 404, description = "No such task", body = APIError),
+    )
+)]
+pub async fn get_task(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse<Task>, APIError> {
+    let task = Q::select_task(pool, user.id, id).await?;
+    Ok(APIResponse::ok(task))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    request_body = T::UpdateTaskRequest,
+    responses(
+        (status = 204, description = "Updated"),
+        (status = 404, description = "No such task", body = APIError),
+        (status = 422, description = "Invalid task", body = APIError),
+    )
+)]
+pub async fn update_task(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    ValidJson(req_task): ValidJson<T::UpdateTaskRequest>,
+) -> Result<APIResponse, APIError> {
+    let mut tx = begin(&pool).await?;
+    let task = Q::update_task_tx(&mut tx, user.id, id, req_task).await?;
+    webhooks::emit_tx(&mut tx, user.id, WebhookEvent::TaskUpdated, &task).await?;
+    commit(tx).await?;
+    Ok(APIResponse::no_content())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "No such task", body = APIError),
+    )
+)]
+pub async fn delete_task(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse, APIError> {
+    let mut tx = begin(&pool).await?;
+    Q::delete_task_tx(&mut tx, user.id, id).await?;
+    webhooks::emit_tx(
+        &mut tx,
+        user.id,
+        WebhookEvent::TaskDeleted,
+        serde_json::json!({ "id": id }),
+    )
+    .await?;
+    commit(tx).await?;
+    Ok(APIResponse::no_content())
+}
+
+async fn begin(pool: &PgPool) -> Result<sqlx::Transaction<'static, sqlx::Postgres>, APIError> {
+    match pool.begin().await {
+        Ok(tx) => Ok(tx),
+        Err(e) => {
+            tracing::error!("Failed to start transaction: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+async fn commit(tx: sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(), APIError> {
+    match tx.commit().await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            Err(APIError::server())
+        }
+    }
+}
+
+/// Completes the task's current occurrence. Recurring tasks move on to their
+/// next occurrence in the user's time zone instead of being marked done.
+#[utoipa::path(
+    put,
+    path = "/done/{id}",
+    responses(
+        (status = 204, description = "Completed, or already done"),
+        (status = 404, description = "No such task", body = APIError),
+    )
+)]
+pub async fn done_task(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse, APIError> {
+    let tz = user_tz(&pool, user.id).await?;
+
+    let mut tx = begin(&pool).await?;
+    let task = Q::select_task_for_update_tx(&mut tx, user.id, id).await?;
+    if task.done {
+        return Ok(APIResponse::no_content());
+    }
+
+    let next = match (
+        calendar::Due::of(&task),
+        calendar::Due::anchor_of(&task),
+        &task.repeat_frequency,
+    ) {
+        (Some(due), Some(anchor), Some(freq)) => {
+            calendar::advance(due, anchor, freq, tz, calendar::today(tz))
+        }
+        _ => None,
+    };
+    Q::complete_task_tx(&mut tx, &task, next).await?;
+    // the occurrence that was completed, not where a recurring task moved on to
+    webhooks::emit_tx(&mut tx, user.id, WebhookEvent::TaskCompleted, &task).await?;
+    commit(tx).await?;
+    Ok(APIResponse::no_content())
+}
+
+#[utoipa::path(
+    put,
+    path = "/undone/{id}",
+    responses(
+        (status = 204, description = "Marked as not done"),
+        (status = 404, description = "No such task", body = APIError),
+    )
+)]
+pub async fn undone_task(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse, APIError> {
+    let mut tx = begin(&pool).await?;
+    let task = Q::select_task_for_update_tx(&mut tx, user.id, id).await?;
+    Q::uncomplete_task_tx(&mut tx, &task).await?;
+    commit(tx).await?;
+    Ok(APIResponse::no_content())
+}
+
+/// The latest hundred tasks, as ever. With `?p=`, every task instead, ten to
+/// a page and newest first, for clients that need them all.
+#[utoipa::path(
+    get,
+    path = "/all",
+    params(PageParams),
+    responses((status = 200, body = Vec<Task>))
+)]
+pub async fn get_all_tasks(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidQuery(params): ValidQuery<PageParams>,
+) -> Result<APIResponse<Vec<Task>>, APIError> {
+    let tasks = match params.p {
+        Some(page) => Q::select_tasks_page(pool, user.id, page as i16).await?,
+        None => Q::select_all_tasks(pool, user.id).await?,
+    };
+    Ok(APIResponse::ok(tasks))
+}
+
+#[utoipa::path(
+    get,
+    path = "/all/done",
+    responses((status = 200, body = Vec<Task>))
+)]
+pub async fn get_all_done_tasks(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<Task>>, APIError> {
+    let tasks = Q::select_all_tasks_by_status(pool, user.id, true).await?;
+    Ok(APIResponse::ok(tasks))
+}
+
+#[utoipa::path(
+    get,
+    path = "/all/undone",
+    responses((status = 200, body = Vec<Task>))
+)]
+pub async fn get_all_undone_tasks(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<Task>>, APIError> {
+    let tasks = Q::select_all_tasks_by_status(pool, user.id, false).await?;
+    Ok(APIResponse::ok(tasks))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/all",
+    responses((status = 204, description = "Deleted"))
+)]
+pub async fn delete_all_tasks(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse, APIError> {
+    Q::delete_all_tasks(pool, user.id).await?;
+    Ok(APIResponse::no_content())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/all/done",
+    responses((status = 204, description = "Deleted"))
+)]
+pub async fn delete_all_done_tasks(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse, APIError> {
+    Q::delete_all_tasks_by_status(pool, user.id, true).await?;
+    Ok(APIResponse::no_content())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/all/undone",
+    responses((status = 204, description = "Deleted"))
+)]
+pub async fn delete_all_undone_tasks(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse, APIError> {
+    Q::delete_all_tasks_by_status(pool, user.id, false).await?;
+    Ok(APIResponse::no_content())
+}
+
+/// Occurrences due on the days `from..=to` in `tz`, recurring tasks expanded,
+/// ordered by day with all-day tasks first.
+async fn occurrences_between(
+    pool: PgPool,
+    user_id: uuid::Uuid,
+    tz: Tz,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<T::TaskOccurrence>, APIError> {
+    let (start, end) = calendar::day_range(tz, from, to);
+    let tasks = Q::select_tasks_due_between(pool, user_id, from, to, start, end).await?;
+
+    let mut due = Vec::new();
+    for task in tasks {
+        let (Some(first), Some(anchor)) =
+            (calendar::Due::of(&task), calendar::Due::anchor_of(&task))
+        else {
+            continue;
+        };
+        // a recurring task's series runs from its anchor, but not before the
+        // occurrence it is at now
+        let found = match &task.repeat_frequency {
+            Some(freq) => {
+                let from = from.max(first.date_in(tz));
+                calendar::occurrences(anchor, Some(freq), tz, from, to)
+            }
+            None => calendar::occurrences(first, None, tz, from, to),
+        };
+        due.extend(found.into_iter().map(|occurrence| T::TaskOccurrence {
+            date: occurrence.date_in(tz),
+            at: occurrence.at(),
+            task: task.clone(),
+        }));
+    }
+    due.sort_by_key(|o| (o.date, o.at, o.task.id));
+    Ok(due)
+}
+
+async fn user_tz(pool: &PgPool, user_id: uuid::Uuid) -> Result<Tz, APIError> {
+    let name = QMe::select_timezone(pool, user_id).await?;
+    Ok(calendar::parse_tz(&name))
+}
+
+#[utoipa::path(
+    get,
+    path = "/due",
+    params(T::DueRangeParams),
+    responses(
+        (status = 200, body = Vec<T::TaskOccurrence>),
+        (status = 422, description = "Invalid range", body = APIError),
+    )
+)]
+pub async fn get_due_tasks(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidQuery(range): ValidQuery<T::DueRangeParams>,
+) -> Result<APIResponse<Vec<T::TaskOccurrence>>, APIError> {
+    let tz = user_tz(&pool, user.id).await?;
+    let due = occurrences_between(pool, user.id, tz, range.from, range.to).await?;
+    Ok(APIResponse::ok(due))
+}
+
+#[utoipa::path(
+    get,
+    path = "/view/today",
+    responses((status = 200, body = T::TodayView))
+)]
+pub async fn get_today_view(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<T::TodayView>, APIError> {
+    let tz = user_tz(&pool, user.id).await?;
+    let today = calendar::today(tz);
+    let tasks = occurrences_between(pool, user.id, tz, today, today).await?;
+    Ok(APIResponse::ok(T::TodayView {
+        date: today,
+        count: tasks.len() as i64,
+        tasks,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/view/overdue",
+    responses((status = 200, body = T::TaskView<Task>))
+)]
+pub async fn get_overdue_view(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<T::TaskView<Task>>, APIError> {
+    let tz = user_tz(&pool, user.id).await?;
+    let today = calendar::today(tz);
+    let start = calendar::start_of_day(tz, today);
+    let (count, _) =
+        Q::count_overdue_and_undated_tasks(pool.clone(), user.id, today, start).await?;
+    let tasks = Q::select_overdue_tasks(pool, user.id, tz.name(), today, start).await?;
+    Ok(APIResponse::ok(T::TaskView { count, tasks }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/view/upcoming",
+    responses((status = 200, body = T::UpcomingView))
+)]
+pub async fn get_upcoming_view(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<T::UpcomingView>, APIError> {
+    let tz = user_tz(&pool, user.id).await?;
+    let today = calendar::today(tz);
+    let from = today + Days::new(1);
+    let to = today + Days::new(UPCOMING_DAYS);
+    let mut due = occurrences_between(pool, user.id, tz, from, to)
+        .await?
+        .into_iter()
+        .peekable();
+
+    let mut days = Vec::new();
+    for date in from.iter_days().take(UPCOMING_DAYS as usize) {
+        let mut tasks = Vec::new();
+        while let Some(o) = due.next_if(|o| o.date == date) {
+            tasks.push(o);
+        }
+        days.push(T::UpcomingDay {
+            date,
+            count: tasks.len() as i64,
+            tasks,
+        });
+    }
+    Ok(APIResponse::ok(T::UpcomingView {
+        count: days.iter().map(|d| d.count).sum(),
+        days,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/view/no-due-date",
+    responses((status = 200, body = T::TaskView<Task>))
+)]
+pub async fn get_undated_view(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<T::TaskView<Task>>, APIError> {
+    let tz = user_tz(&pool, user.id).await?;
+    let today = calendar::today(tz);
+    let start = calendar::start_of_day(tz, today);
+    let (_, count) =
+        Q::count_overdue_and_undated_tasks(pool.clone(), user.id, today, start).await?;
+    let tasks = Q::select_undated_tasks(pool, user.id).await?;
+    Ok(APIResponse::ok(T::TaskView { count, tasks }))
+}
+
+/// Badge counts of every view in one round trip.
+#[utoipa::path(
+    get,
+    path = "/view/counts",
+    responses((status = 200, body = T::ViewCounts))
+)]
+pub async fn get_view_counts(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<T::ViewCounts>, APIError> {
+    let tz = user_tz(&pool, user.id).await?;
+    let today = calendar::today(tz);
+    let start = calendar::start_of_day(tz, today);
+    let (overdue, no_due_date) =
+        Q::count_overdue_and_undated_tasks(pool.clone(), user.id, today, start).await?;
+    let due =
+        occurrences_between(pool, user.id, tz, today, today + Days::new(UPCOMING_DAYS)).await?;
+    let due_today = due.iter().filter(|o| o.date == today).count() as i64;
+    Ok(APIResponse::ok(T::ViewCounts {
+        today: due_today,
+        overdue,
+        upcoming: due.len() as i64 - due_today,
+        no_due_date,
+    }))
+}
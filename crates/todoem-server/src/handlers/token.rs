@@ -0,0 +1,90 @@
+use crate::config::Accounts;
+use crate::db::query::token as Q;
+use crate::handlers::extract::ValidJson;
+use crate::services::tokens;
+use axum::extract::{Extension, Path, State};
+use chrono::{Duration, Utc};
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::models::token::{ApiToken, NewApiToken};
+use todoem_core::models::AuthUser;
+use todoem_core::types::token as T;
+
+use todoem_core::errors::APIError;
+
+use super::response::APIResponse;
+
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = T::CreateTokenRequest,
+    responses(
+        (status = 201, description = "The token, with its value shown only this once", body = NewApiToken),
+        (status = 409, description = "Too many active tokens", body = APIError),
+        (status = 422, description = "Invalid token request", body = APIError),
+    )
+)]
+pub async fn create_token(
+    Extension(user): Extension<AuthUser>,
+    Extension(config): Extension<Accounts>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::CreateTokenRequest>,
+) -> Result<APIResponse<NewApiToken>, APIError> {
+    let (value, hash, prefix) = tokens::generate();
+    let expires_at = req
+        .expires_in_days
+        .map(|days| Utc::now() + Duration::days(days as i64));
+    let token = Q::insert_token(
+        &pool,
+        user.id,
+        req.name.trim(),
+        &hash,
+        &prefix,
+        &T::scope_names(&req.scopes),
+        expires_at,
+        config.api_tokens_max_per_user,
+    )
+    .await?
+    .ok_or_else(|| {
+        APIError::new(
+            StatusCode::CONFLICT,
+            &format!(
+                "At most {} tokens can be active at once",
+                config.api_tokens_max_per_user
+            ),
+        )
+    })?;
+    Ok(APIResponse::created(NewApiToken { token, value }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, body = Vec<ApiToken>))
+)]
+pub async fn get_tokens(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<ApiToken>>, APIError> {
+    let tokens = Q::select_tokens(&pool, user.id).await?;
+    Ok(APIResponse::ok(tokens))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    responses(
+        (status = 204, description = "Revoked"),
+        (status = 404, description = "No such token", body = APIError),
+    )
+)]
+pub async fn revoke_token(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse, APIError> {
+    match Q::revoke_token(&pool, user.id, id).await? {
+        true => Ok(APIResponse::no_content()),
+        false => Err(APIError::not_found()),
+    }
+}
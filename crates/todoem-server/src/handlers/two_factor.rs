@@ -0,0 +1,194 @@
+use crate::db::query::{me as QMe, two_factor as Q};
+use crate::handlers::extract::ValidJson;
+use crate::services::two_factor;
+use axum::extract::{Extension, State};
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::models::two_factor::TwoFactorStatus;
+use todoem_core::models::AuthUser;
+use todoem_core::types::two_factor as T;
+use uuid::Uuid;
+
+use todoem_core::errors::APIError;
+
+use super::response::{APIResponse, APISuccess};
+use todoem_core::types::SuccessResponse;
+
+fn invalid_code() -> APIError {
+    APIError::bad("Invalid code")
+}
+
+fn not_enabled() -> APIError {
+    APIError::new(
+        StatusCode::CONFLICT,
+        "Two-factor authentication is not enabled",
+    )
+}
+
+/// Hands out a new set of recovery codes, invalidating the old ones.
+async fn issue_recovery_codes(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+) -> Result<T::RecoveryCodes, APIError> {
+    let (codes, hashes) = two_factor::generate_recovery_codes();
+    Q::replace_recovery_codes_tx(tx, user_id, &hashes).await?;
+    Ok(T::RecoveryCodes {
+        recovery_codes: codes,
+    })
+}
+
+async fn begin(pool: &PgPool) -> Result<sqlx::Transaction<'static, sqlx::Postgres>, APIError> {
+    pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        APIError::server()
+    })
+}
+
+async fn commit(tx: sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(), APIError> {
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        APIError::server()
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, body = TwoFactorStatus))
+)]
+pub async fn get_status(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<TwoFactorStatus>, APIError> {
+    Ok(APIResponse::ok(Q::select_status(&pool, user.id).await?))
+}
+
+/// Starts enrolling an authenticator. Nothing changes at sign-in until a
+/// first code is confirmed.
+#[utoipa::path(
+    post,
+    path = "/totp",
+    responses(
+        (status = 201, description = "A secret to confirm with a code", body = T::Enrollment),
+        (status = 409, description = "Already enabled", body = APIError),
+    )
+)]
+pub async fn enroll(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<T::Enrollment>, APIError> {
+    let secret = two_factor::generate_secret();
+    if !Q::upsert_pending_totp(&pool, user.id, &secret).await? {
+        return Err(APIError::new(
+            StatusCode::CONFLICT,
+            "Two-factor authentication is already enabled",
+        ));
+    }
+
+    let profile = QMe::select_profile(&pool, user.id).await?;
+    let uri = two_factor::provisioning_uri(&profile.username, &secret);
+    Ok(APIResponse::created(T::Enrollment {
+        secret: two_factor::base32(&secret),
+        qr_svg: two_factor::qr_svg(&uri)?,
+        provisioning_uri: uri,
+    }))
+}
+
+/// Turns two-factor on with a first code from the authenticator, and returns
+/// the recovery codes.
+#[utoipa::path(
+    post,
+    path = "/totp/confirm",
+    request_body = T::CodeRequest,
+    responses(
+        (status = 200, description = "Enabled, with recovery codes shown only this once", body = T::RecoveryCodes),
+        (status = 400, description = "Invalid code", body = APIError),
+        (status = 404, description = "Not enrolled", body = APIError),
+        (status = 409, description = "Already enabled", body = APIError),
+    )
+)]
+pub async fn confirm(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::CodeRequest>,
+) -> Result<APIResponse<T::RecoveryCodes>, APIError> {
+    let totp = match Q::select_totp(&pool, user.id).await? {
+        Some(totp) if totp.confirmed_at.is_none() => totp,
+        Some(_) => {
+            return Err(APIError::new(
+                StatusCode::CONFLICT,
+                "Two-factor authentication is already enabled",
+            ))
+        }
+        None => return Err(APIError::not_found()),
+    };
+    let step = two_factor::verify_code(&totp.secret, &req.code).ok_or_else(invalid_code)?;
+
+    let mut tx = begin(&pool).await?;
+    if !Q::confirm_totp_tx(&mut tx, user.id, step).await? {
+        return Err(APIError::new(
+            StatusCode::CONFLICT,
+            "Two-factor authentication is already enabled",
+        ));
+    }
+    let codes = issue_recovery_codes(&mut tx, user.id).await?;
+    commit(tx).await?;
+
+    Ok(APIResponse::ok(codes))
+}
+
+#[utoipa::path(
+    post,
+    path = "/recovery-codes",
+    request_body = T::CodeRequest,
+    responses(
+        (status = 200, description = "New recovery codes, replacing the old ones", body = T::RecoveryCodes),
+        (status = 400, description = "Invalid code", body = APIError),
+        (status = 409, description = "Not enabled", body = APIError),
+    )
+)]
+pub async fn regenerate_recovery_codes(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::CodeRequest>,
+) -> Result<APIResponse<T::RecoveryCodes>, APIError> {
+    if !Q::is_enabled(&pool, user.id).await? {
+        return Err(not_enabled());
+    }
+    if !two_factor::check(&pool, user.id, &req.code).await? {
+        return Err(invalid_code());
+    }
+
+    let mut tx = begin(&pool).await?;
+    let codes = issue_recovery_codes(&mut tx, user.id).await?;
+    commit(tx).await?;
+    Ok(APIResponse::ok(codes))
+}
+
+/// Turns two-factor off. Takes a current code, so that a stolen session alone
+/// cannot weaken the account.
+#[utoipa::path(
+    post,
+    path = "/disable",
+    request_body = T::CodeRequest,
+    responses(
+        (status = 200, description = "Disabled", body = SuccessResponse),
+        (status = 400, description = "Invalid code", body = APIError),
+        (status = 409, description = "Not enabled", body = APIError),
+    )
+)]
+pub async fn disable(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::CodeRequest>,
+) -> Result<APISuccess, APIError> {
+    if !Q::is_enabled(&pool, user.id).await? {
+        return Err(not_enabled());
+    }
+    if !two_factor::check(&pool, user.id, &req.code).await? {
+        return Err(invalid_code());
+    }
+
+    Q::delete_two_factor(&pool, user.id).await?;
+    Ok(APISuccess::ok_msg("Two-factor authentication disabled"))
+}
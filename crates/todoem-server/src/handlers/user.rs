@@ -0,0 +1,561 @@
+use crate::config::{Limits, RateLimits};
+use crate::db::query::me as MQ;
+use crate::db::query::user as Q;
+use crate::handlers::extract::{ValidJson, ValidQuery};
+use crate::services::storage::SharedStorage;
+use crate::services::webhooks;
+use axum::extract::{Extension, Path, State};
+use axum::response::{IntoResponse, Response};
+use http::{header, StatusCode};
+use sqlx::PgPool;
+use todoem_core::models::user as M;
+use todoem_core::models::webhook::WebhookEvent;
+use todoem_core::models::AuthUser;
+use todoem_core::types::user as T;
+use todoem_core::validation::Validator;
+
+use todoem_core::errors::APIError;
+
+use super::response::{APIResponse, APISuccess};
+use todoem_core::types::SuccessResponse;
+
+#[utoipa::path(
+    get,
+    path = "/search",
+    params(T::SearchParams),
+    responses(
+        (status = 200, body = Vec<M::User>),
+        (status = 422, description = "Invalid query", body = APIError),
+    )
+)]
+pub async fn search(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidQuery(params): ValidQuery<T::SearchParams>,
+) -> Result<APIResponse<Vec<M::User>>, APIError> {
+    let page = params.p.unwrap_or(1) as i16;
+
+    let users = Q::search(pool, user.id, params.q, page).await?;
+
+    Ok(APIResponse::ok(users))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/profile",
+    responses(
+        (status = 200, body = T::ViewUser),
+        (status = 403, description = "Your own id", body = APIError),
+        (status = 404, description = "No such user", body = APIError),
+    )
+)]
+pub async fn view_user_profile(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APIResponse<T::ViewUser>, APIError> {
+    if user.id == id {
+        return Err(APIError::forbidden());
+    }
+
+    if Q::is_blocked(&pool, user.id, id).await? {
+        return Err(APIError::not_found());
+    }
+    let connection = Q::select_connection(&pool, user.id, id).await?;
+
+    let _user = Q::select_user_profile(&pool, id).await?;
+    let mut profile = T::ViewUser::from(_user);
+    if let Some(c) = connection {
+        profile.connected = c.state == M::ConnectionState::Connected;
+        profile.sent_connection = c.state == M::ConnectionState::Pending && c.actor_id == user.id;
+        profile.received_connection = c.state == M::ConnectionState::Pending && c.actor_id == id;
+    }
+
+    Ok(APIResponse::ok(profile))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/request",
+    responses(
+        (status = 200, description = "Request sent", body = SuccessResponse),
+        (status = 400, description = "Already connected or requested", body = APIError),
+        (status = 403, description = "Your own id, blocked, or not taking requests from you", body = APIError),
+        (status = 409, description = "Changed concurrently, retry", body = APIError),
+        (status = 429, description = "Too many requests sent lately", body = APIError),
+    )
+)]
+pub async fn request_connection(
+    Extension(user): Extension<AuthUser>,
+    Extension(rate_limits): Extension<RateLimits>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APISuccess, APIError> {
+    if user.id == id {
+        return Err(APIError::forbidden());
+    }
+
+    // before the privacy settings, which are not for blocked users to learn
+    if Q::is_blocked(&pool, user.id, id).await? {
+        return Err(APIError::forbidden());
+    }
+    let privacy = Q::select_privacy(&pool, id).await?;
+    if matches!(privacy.request_policy, M::RequestPolicy::FriendsOfFriends)
+        && !Q::is_friend_of_friend(&pool, user.id, id).await?
+    {
+        return Err(APIError::new(
+            StatusCode::FORBIDDEN,
+            "This user only accepts connection requests from friends of friends",
+        ));
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            tracing::error!("Failed to start transaction: {:?}", e);
+            return Err(APIError::server());
+        }
+    };
+
+    if !Q::request_connection_tx(&mut tx, user.id, id).await? {
+        drop(tx);
+        return Err(request_conflict(&pool, user.id, id).await);
+    }
+
+    if !Q::consume_daily_quota_tx(
+        &mut tx,
+        user.id,
+        "connection_request",
+        rate_limits.connection_requests_per_day,
+    )
+    .await?
+    {
+        return Err(APIError::too_many_requests());
+    }
+
+    webhooks::emit_tx(
+        &mut tx,
+        id,
+        WebhookEvent::ConnectionRequested,
+        serde_json::json!({ "user_id": user.id }),
+    )
+    .await?;
+
+    match tx.commit().await {
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            return Err(APIError::server());
+        }
+    }
+
+    Ok(APIResponse::ok_msg("User connection request sent"))
+}
+
+/// Explains why a connection request between `user_id` and `id` was refused.
+async fn request_conflict(pool: &PgPool, user_id: uuid::Uuid, id: uuid::Uuid) -> APIError {
+    match Q::is_blocked(pool, user_id, id).await {
+        Ok(true) => return APIError::forbidden(),
+        Ok(false) => {}
+        Err(e) => return e,
+    }
+    let connection = match Q::select_connection(pool, user_id, id).await {
+        Ok(c) => c,
+        Err(e) => return e,
+    };
+
+    match connection {
+        Some(c) => match c.state {
+            M::ConnectionState::Connected => {
+                APIError::bad("You are already connected with this user")
+            }
+            M::ConnectionState::Pending if c.actor_id == user_id => {
+                APIError::bad("You have already sent a connection request to this user")
+            }
+            M::ConnectionState::Pending => {
+                APIError::bad("This user has already sent you a connection request")
+            }
+            M::ConnectionState::Rejected => {
+                APIError::bad("This user has rejected your connection request")
+            }
+            M::ConnectionState::Blocked => APIError::forbidden(),
+        },
+        None => APIError::new(
+            StatusCode::CONFLICT,
+            "Connection changed while sending the request, please retry",
+        ),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/request",
+    responses(
+        (status = 200, description = "Request withdrawn", body = SuccessResponse),
+        (status = 400, description = "No pending request to this user", body = APIError),
+        (status = 403, description = "Your own id", body = APIError),
+    )
+)]
+pub async fn delete_request_connection(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APISuccess, APIError> {
+    if user.id == id {
+        return Err(APIError::forbidden());
+    }
+
+    if !Q::cancel_request(&pool, user.id, id).await? {
+        return Err(APIError::bad(
+            "You have not sent a connection request to this user",
+        ));
+    }
+
+    Ok(APIResponse::ok_msg("User connection request deleted"))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}/accept",
+    responses(
+        (status = 200, description = "Connected", body = SuccessResponse),
+        (status = 400, description = "No pending request from this user", body = APIError),
+        (status = 403, description = "Your own id", body = APIError),
+    )
+)]
+pub async fn accept_connection(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APISuccess, APIError> {
+    if user.id == id {
+        return Err(APIError::forbidden());
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            tracing::error!("Failed to start transaction: {:?}", e);
+            return Err(APIError::server());
+        }
+    };
+
+    if !Q::accept_request_tx(&mut tx, user.id, id).await? {
+        drop(tx);
+        let connection = Q::select_connection(&pool, user.id, id).await?;
+        if matches!(connection, Some(c) if c.state == M::ConnectionState::Connected) {
+            return Err(APIError::bad("You are already connected with this user"));
+        }
+        return Err(APIError::bad(
+            "This user has not sent you a connection request",
+        ));
+    }
+
+    // both sides gained a connection
+    for (owner, other) in [(id, user.id), (user.id, id)] {
+        webhooks::emit_tx(
+            &mut tx,
+            owner,
+            WebhookEvent::ConnectionAccepted,
+            serde_json::json!({ "user_id": other }),
+        )
+        .await?;
+    }
+
+    match tx.commit().await {
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            return Err(APIError::server());
+        }
+    }
+
+    Ok(APIResponse::ok_msg("User connection request accepted"))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}/reject",
+    responses(
+        (status = 200, description = "Request rejected", body = SuccessResponse),
+        (status = 400, description = "No pending request from this user", body = APIError),
+        (status = 403, description = "Your own id", body = APIError),
+    )
+)]
+pub async fn reject_connection(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APISuccess, APIError> {
+    if user.id == id {
+        return Err(APIError::forbidden());
+    }
+
+    if !Q::reject_request(&pool, user.id, id).await? {
+        return Err(APIError::bad(
+            "This user has not sent you a connection request",
+        ));
+    }
+
+    Ok(APIResponse::ok_msg("User connection request rejected"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/requests/received",
+    responses((status = 200, body = Vec<M::User>))
+)]
+pub async fn get_received_requests(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<M::User>>, APIError> {
+    let users = Q::select_received_requests(&pool, user.id).await?;
+    Ok(APIResponse::ok(users))
+}
+
+#[utoipa::path(
+    get,
+    path = "/requests/sent",
+    responses((status = 200, body = Vec<M::User>))
+)]
+pub async fn get_sent_requests(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<M::User>>, APIError> {
+    let users = Q::select_sent_requests(&pool, user.id).await?;
+    Ok(APIResponse::ok(users))
+}
+
+#[utoipa::path(
+    get,
+    path = "/listers/page/{p}",
+    params(("p" = u16, Path, description = "Page, starting at 1")),
+    responses(
+        (status = 200, body = Vec<M::User>),
+        (status = 422, description = "Invalid page", body = APIError),
+    )
+)]
+pub async fn get_listers(
+    Extension(user): Extension<AuthUser>,
+    Extension(limits): Extension<Limits>,
+    State(pool): State<PgPool>,
+    Path(page): Path<u16>,
+) -> Result<APIResponse<Vec<M::User>>, APIError> {
+    let mut v = Validator::default();
+    v.field("p", &page).range(1, limits.search_max_page);
+    v.finish()?;
+
+    let users = Q::select_listers(&pool, user.id, page as i16).await?;
+    Ok(APIResponse::ok(users))
+}
+
+#[utoipa::path(
+    get,
+    path = "/listers/search",
+    params(T::SearchParams),
+    responses(
+        (status = 200, body = Vec<M::User>),
+        (status = 422, description = "Invalid query", body = APIError),
+    )
+)]
+pub async fn search_listers(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidQuery(params): ValidQuery<T::SearchParams>,
+) -> Result<APIResponse<Vec<M::User>>, APIError> {
+    let page = params.p.unwrap_or(1) as i16;
+
+    let users = Q::search_listers(&pool, user.id, params.q, page).await?;
+
+    Ok(APIResponse::ok(users))
+}
+
+#[utoipa::path(
+    get,
+    path = "/listers/{id}",
+    responses(
+        (status = 200, body = T::ViewUser),
+        (status = 403, description = "Your own id", body = APIError),
+        (status = 404, description = "Not connected with this user", body = APIError),
+    )
+)]
+pub async fn view_lister_profile(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APIResponse<T::ViewUser>, APIError> {
+    if user.id == id {
+        return Err(APIError::forbidden());
+    }
+
+    let connection = Q::select_connection(&pool, user.id, id).await?;
+    if !matches!(connection, Some(c) if c.state == M::ConnectionState::Connected) {
+        return Err(APIError::not_found());
+    }
+
+    let _user = Q::select_user_profile(&pool, id).await?;
+
+    let mut profile = T::ViewUser::from(_user);
+    profile.connected = true;
+    profile.sent_connection = false;
+    profile.received_connection = false;
+
+    Ok(APIResponse::ok(profile))
+}
+
+#[utoipa::path(
+    put,
+    path = "/listers/{id}/disconnect",
+    responses(
+        (status = 200, description = "Disconnected", body = SuccessResponse),
+        (status = 400, description = "Not connected with this user", body = APIError),
+        (status = 403, description = "Your own id", body = APIError),
+    )
+)]
+pub async fn disconnect_lister(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APISuccess, APIError> {
+    if user.id == id {
+        return Err(APIError::forbidden());
+    }
+
+    if !Q::disconnect(&pool, user.id, id).await? {
+        return Err(APIError::bad("You are not connected with this user"));
+    }
+
+    Ok(APIResponse::ok_msg("User disconnected"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/block",
+    responses(
+        (status = 200, description = "Blocked", body = SuccessResponse),
+        (status = 403, description = "Your own id", body = APIError),
+        (status = 404, description = "No such user", body = APIError),
+    )
+)]
+pub async fn block_user(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APISuccess, APIError> {
+    if user.id == id {
+        return Err(APIError::forbidden());
+    }
+
+    // blocking again is not an error; the block stands either way
+    Q::block(&pool, user.id, id).await?;
+
+    Ok(APIResponse::ok_msg("User blocked"))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/block",
+    responses(
+        (status = 200, description = "Unblocked", body = SuccessResponse),
+        (status = 403, description = "Your own id", body = APIError),
+        (status = 404, description = "You have not blocked this user", body = APIError),
+    )
+)]
+pub async fn unblock_user(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<APISuccess, APIError> {
+    if !Q::unblock(&pool, user.id, id).await? {
+        return Err(APIError::not_found());
+    }
+    Ok(APIResponse::ok_msg("User unblocked"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/blocked",
+    responses((status = 200, body = Vec<M::User>))
+)]
+pub async fn get_blocked_users(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<M::User>>, APIError> {
+    let users = Q::select_blocked_users(&pool, user.id).await?;
+    Ok(APIResponse::ok(users))
+}
+
+#[utoipa::path(
+    get,
+    path = "/privacy",
+    responses((status = 200, body = M::Privacy))
+)]
+pub async fn get_privacy(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<M::Privacy>, APIError> {
+    let privacy = Q::select_privacy(&pool, user.id).await?;
+    Ok(APIResponse::ok(privacy))
+}
+
+#[utoipa::path(
+    put,
+    path = "/privacy",
+    request_body = T::UpdatePrivacyRequest,
+    responses(
+        (status = 200, description = "The updated settings", body = M::Privacy),
+        (status = 422, description = "Unknown setting", body = APIError),
+    )
+)]
+pub async fn update_privacy(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::UpdatePrivacyRequest>,
+) -> Result<APIResponse<M::Privacy>, APIError> {
+    let privacy = M::Privacy {
+        search_visibility: req.search_visibility,
+        request_policy: req.request_policy,
+    };
+    Q::update_privacy(&pool, user.id, &privacy).await?;
+    Ok(APIResponse::ok(privacy))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/avatar",
+    responses(
+        (status = 200, description = "The avatar", content_type = "image/png"),
+        (status = 404, description = "No such user or no avatar", body = APIError),
+    )
+)]
+pub async fn get_avatar(
+    Extension(user): Extension<AuthUser>,
+    Extension(storage): Extension<SharedStorage>,
+    State(pool): State<PgPool>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<Response, APIError> {
+    if user.id != id && Q::is_blocked(&pool, user.id, id).await? {
+        return Err(APIError::not_found());
+    }
+
+    let key = match MQ::select_avatar_key(&pool, id).await? {
+        Some(key) => key,
+        None => return Err(APIError::not_found()),
+    };
+
+    match storage.get(&key).await {
+        Ok(Some(bytes)) => Ok((
+            [
+                (header::CONTENT_TYPE, "image/png"),
+                (header::CACHE_CONTROL, "private, max-age=86400"),
+            ],
+            bytes,
+        )
+            .into_response()),
+        Ok(None) => Err(APIError::not_found()),
+        Err(e) => {
+            tracing::error!("Failed to read avatar {}: {:?}", key, e);
+            Err(APIError::server())
+        }
+    }
+}
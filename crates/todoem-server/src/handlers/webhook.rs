@@ -0,0 +1,239 @@
+use crate::config::Webhooks;
+use crate::db::query::webhook as Q;
+use crate::handlers::extract::{ValidJson, ValidQuery};
+use crate::services::webhooks;
+use axum::extract::{Extension, Path, State};
+use http::StatusCode;
+use sqlx::PgPool;
+use todoem_core::models::webhook::{Delivery, Webhook, WebhookEvent, WebhookWithSecret};
+use todoem_core::models::AuthUser;
+use todoem_core::types::webhook as T;
+use todoem_core::types::PageParams;
+
+use todoem_core::errors::APIError;
+
+use super::response::APIResponse;
+
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = T::CreateWebhookRequest,
+    responses(
+        (status = 201, description = "The webhook, with its signing secret", body = WebhookWithSecret),
+        (status = 409, description = "Too many webhooks", body = APIError),
+        (status = 422, description = "Invalid webhook", body = APIError),
+    )
+)]
+pub async fn create_webhook(
+    Extension(user): Extension<AuthUser>,
+    Extension(config): Extension<Webhooks>,
+    State(pool): State<PgPool>,
+    ValidJson(req): ValidJson<T::CreateWebhookRequest>,
+) -> Result<APIResponse<WebhookWithSecret>, APIError> {
+    let secret = webhooks::generate_secret();
+    let webhook = Q::insert_webhook(
+        &pool,
+        user.id,
+        &req.url,
+        &req.description,
+        &T::event_names(&req.events),
+        &secret,
+        config.max_per_user,
+    )
+    .await?
+    .ok_or_else(|| {
+        APIError::new(
+            StatusCode::CONFLICT,
+            &format!("At most {} webhooks can be registered", config.max_per_user),
+        )
+    })?;
+    Ok(APIResponse::created(WebhookWithSecret { webhook, secret }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, body = Vec<Webhook>))
+)]
+pub async fn get_webhooks(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+) -> Result<APIResponse<Vec<Webhook>>, APIError> {
+    let webhooks = Q::select_webhooks(&pool, user.id).await?;
+    Ok(APIResponse::ok(webhooks))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    responses(
+        (status = 200, body = Webhook),
+        (status = 404, description = "No such webhook", body = APIError),
+    )
+)]
+pub async fn get_webhook(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse<Webhook>, APIError> {
+    let webhook = Q::select_webhook(&pool, user.id, id).await?;
+    Ok(APIResponse::ok(webhook))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    request_body = T::UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "The updated webhook", body = Webhook),
+        (status = 404, description = "No such webhook", body = APIError),
+        (status = 422, description = "Invalid webhook", body = APIError),
+    )
+)]
+pub async fn update_webhook(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    ValidJson(req): ValidJson<T::UpdateWebhookRequest>,
+) -> Result<APIResponse<Webhook>, APIError> {
+    let webhook = Q::update_webhook(
+        &pool,
+        user.id,
+        id,
+        &req.url,
+        &req.description,
+        &T::event_names(&req.events),
+        req.active,
+    )
+    .await?;
+    Ok(APIResponse::ok(webhook))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "No such webhook", body = APIError),
+    )
+)]
+pub async fn delete_webhook(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse, APIError> {
+    Q::delete_webhook(&pool, user.id, id).await?;
+    Ok(APIResponse::no_content())
+}
+
+/// Replaces the signing secret; deliveries still queued are signed with the new one.
+#[utoipa::path(
+    post,
+    path = "/{id}/secret",
+    responses(
+        (status = 200, description = "The webhook, with its new signing secret", body = WebhookWithSecret),
+        (status = 404, description = "No such webhook", body = APIError),
+    )
+)]
+pub async fn rotate_secret(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse<WebhookWithSecret>, APIError> {
+    let secret = webhooks::generate_secret();
+    let webhook = Q::update_secret(&pool, user.id, id, &secret).await?;
+    Ok(APIResponse::ok(WebhookWithSecret { webhook, secret }))
+}
+
+/// Queues a `ping` delivery to check that the endpoint is reachable.
+#[utoipa::path(
+    post,
+    path = "/{id}/ping",
+    responses(
+        (status = 202, description = "The queued delivery", body = Delivery),
+        (status = 404, description = "No such webhook", body = APIError),
+    )
+)]
+pub async fn ping_webhook(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+) -> Result<APIResponse<Delivery>, APIError> {
+    let webhook = Q::select_webhook(&pool, user.id, id).await?;
+    let payload = webhooks::envelope(
+        WebhookEvent::Ping,
+        serde_json::json!({ "webhook_id": webhook.id }),
+    );
+    let delivery = Q::enqueue_delivery(
+        &pool,
+        webhook.id,
+        WebhookEvent::Ping.as_str(),
+        &payload,
+        None,
+    )
+    .await?;
+    Ok(APIResponse::accepted(delivery))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/deliveries",
+    params(PageParams),
+    responses(
+        (status = 200, body = Vec<Delivery>),
+        (status = 404, description = "No such webhook", body = APIError),
+    )
+)]
+pub async fn get_deliveries(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    ValidQuery(params): ValidQuery<PageParams>,
+) -> Result<APIResponse<Vec<Delivery>>, APIError> {
+    let page = params.p.unwrap_or(1) as i16;
+    let deliveries = Q::select_deliveries(&pool, user.id, id, page).await?;
+    Ok(APIResponse::ok(deliveries))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/deliveries/{delivery_id}",
+    responses(
+        (status = 200, body = Delivery),
+        (status = 404, description = "No such webhook or delivery", body = APIError),
+    )
+)]
+pub async fn get_delivery(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path((id, delivery_id)): Path<(i64, i64)>,
+) -> Result<APIResponse<Delivery>, APIError> {
+    let delivery = Q::select_delivery(&pool, user.id, id, delivery_id).await?;
+    Ok(APIResponse::ok(delivery))
+}
+
+/// Queues the same payload again as a new delivery; the original stays in the log.
+#[utoipa::path(
+    post,
+    path = "/{id}/deliveries/{delivery_id}/replay",
+    responses(
+        (status = 202, description = "The queued copy", body = Delivery),
+        (status = 404, description = "No such webhook or delivery", body = APIError),
+    )
+)]
+pub async fn replay_delivery(
+    Extension(user): Extension<AuthUser>,
+    State(pool): State<PgPool>,
+    Path((id, delivery_id)): Path<(i64, i64)>,
+) -> Result<APIResponse<Delivery>, APIError> {
+    let original = Q::select_delivery(&pool, user.id, id, delivery_id).await?;
+    let delivery = Q::enqueue_delivery(
+        &pool,
+        id,
+        &original.event,
+        &original.payload,
+        Some(original.id),
+    )
+    .await?;
+    Ok(APIResponse::accepted(delivery))
+}
@@ -0,0 +1,16 @@
+use crate::services::jwt_keys::SharedJwtKeys;
+use axum::{extract::Extension, response::IntoResponse, Json};
+use http::header::CACHE_CONTROL;
+
+/// The public keys that verify our tokens, as a JWK Set (RFC 7517), for other
+/// services to check them. Unwrapped, since clients expect the bare set.
+#[utoipa::path(
+    get,
+    path = "/jwks.json",
+    responses((status = 200, description = "A JWK Set (RFC 7517)", body = Object))
+)]
+pub async fn jwks(Extension(keys): Extension<SharedJwtKeys>) -> impl IntoResponse {
+    // short enough that a key published ahead of a rotation is picked up
+    // well before it signs anything
+    ([(CACHE_CONTROL, "public, max-age=300")], Json(keys.jwks()))
+}
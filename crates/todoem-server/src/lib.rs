@@ -0,0 +1,8 @@
+pub mod config;
+pub mod db;
+pub mod errors;
+pub mod handlers;
+pub mod middlewares;
+pub mod routes;
+pub mod services;
+pub mod telemetry;
@@ -0,0 +1,113 @@
+use clap::Parser;
+use std::{future::IntoFuture, net::SocketAddr, process, sync::Arc, time::Duration};
+use todoem_server::config::{self, Cli, Settings};
+use todoem_server::services::metrics::Metrics;
+use todoem_server::services::shutdown::{self, Shutdown};
+use todoem_server::services::storage::{LocalStorage, SharedStorage};
+use todoem_server::{routes, services, telemetry};
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let settings = match Settings::load(&cli) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+    if cli.check_config {
+        println!("Configuration is valid");
+        return;
+    }
+
+    // initialize tracing and metrics
+    let metrics = Metrics::new();
+    let tracing = match telemetry::init(&settings.log, &settings.telemetry, metrics.clone()) {
+        Ok(tracing) => tracing,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    // initialize our configuration
+    let config = match config::init(settings).await {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    // run our app with hyper
+    let addr = config.server.bind;
+
+    println!("\n\nListening on http://{}\n", addr);
+
+    let storage: SharedStorage = Arc::new(LocalStorage::new(&config.accounts.storage_dir));
+    let shutdown = Shutdown::new();
+
+    services::accounts::spawn_purger(
+        config.pool.clone(),
+        storage.clone(),
+        config.accounts.deletion_grace_days,
+        metrics.clone(),
+        &shutdown,
+    );
+
+    services::webhooks::spawn_dispatcher(
+        config.pool.clone(),
+        config.webhooks.clone(),
+        metrics.clone(),
+        &shutdown,
+    );
+
+    services::mail::queue::spawn_sender(
+        config.pool.clone(),
+        services::mail::from_config(&config.mail),
+        config.mail.clone(),
+        metrics.clone(),
+        &shutdown,
+    );
+
+    let pool = config.pool.clone();
+    let drain_timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+    let router = routes::init(config, storage, shutdown.clone(), metrics);
+
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown.triggered());
+    let mut server = tokio::spawn(server.into_future());
+
+    tokio::select! {
+        _ = shutdown::signal() => {}
+        res = &mut server => {
+            tracing::error!("Server stopped unexpectedly: {:?}", res);
+            process::exit(1);
+        }
+    }
+
+    // stop accepting connections and jobs picking up new work, then wait
+    // for what is in flight
+    tracing::info!("Shutting down ...");
+    shutdown.trigger();
+    let drained = tokio::time::timeout(drain_timeout, async {
+        let _ = server.await;
+        shutdown.drained().await;
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!(
+            "Requests or jobs still running after {:?}, stopping anyway",
+            drain_timeout
+        );
+    }
+
+    pool.close().await;
+    tracing::info!("Shut down");
+    tracing.shutdown();
+}
@@ -0,0 +1,127 @@
+use super::rate_limit::client_ip;
+use crate::{
+    db::query::token as Q,
+    services::{
+        auth::{ACCESS_COOKIE, SESSION_AUDIENCE},
+        jwt_keys::SharedJwtKeys,
+        sessions::SharedSessionCache,
+        tokens,
+    },
+};
+use axum::{
+    extract::{Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::cookie::CookieJar;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::net::IpAddr;
+use todoem_core::{
+    errors::APIError,
+    models::{AuthUser, Credential},
+};
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+/// `last_used_at` is only written once per this interval per token.
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct AuthState {
+    pub keys: SharedJwtKeys,
+    pub pool: PgPool,
+    pub sessions: SharedSessionCache,
+    pub trust_proxy: bool,
+}
+
+#[derive(Deserialize)]
+struct SessionClaims {
+    #[serde(flatten)]
+    user: AuthUser,
+    sid: Uuid,
+}
+
+/// Decodes an access token and checks that its session was not revoked.
+async fn session(
+    state: &AuthState,
+    token: &str,
+    ip: Option<IpAddr>,
+) -> Result<(AuthUser, Credential), APIError> {
+    let claims = match state.keys.verify::<SessionClaims>(token, SESSION_AUDIENCE) {
+        Ok(claims) => claims,
+        Err(e) => {
+            error!("Error decoding token: {:#?}", e);
+            return Err(APIError::auth());
+        }
+    };
+
+    let ip = ip.map(|ip| ip.to_string());
+    let live = state
+        .sessions
+        .is_live(&state.pool, claims.sid, claims.user.id, ip.as_deref())
+        .await?;
+    match live {
+        true => Ok((claims.user, Credential::Session { id: claims.sid })),
+        false => Err(APIError::auth()),
+    }
+}
+
+async fn personal_token(pool: &PgPool, token: &str) -> Result<(AuthUser, Credential), APIError> {
+    let grant = match Q::select_grant(pool, &tokens::hash(token)).await? {
+        Some(grant) => grant,
+        None => return Err(APIError::auth()),
+    };
+
+    let stale = Utc::now() - Duration::seconds(TOUCH_INTERVAL_SECS);
+    if grant.last_used_at.is_none_or(|at| at < stale) {
+        let pool = pool.clone();
+        tokio::spawn(async move { Q::touch_token(&pool, grant.id).await });
+    }
+
+    Ok((
+        AuthUser {
+            id: grant.user_id,
+            email: grant.email,
+        },
+        Credential::Token {
+            scopes: grant.scopes,
+        },
+    ))
+}
+
+/// Authenticates with `Authorization: Bearer` (a personal access token or a
+/// JWT) or else the `access_token` cookie.
+#[instrument(skip_all)]
+pub async fn jwt_auth(
+    State(state): State<AuthState>,
+    jar: CookieJar,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, APIError> {
+    let bearer = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| v.trim().to_string());
+
+    let ip = client_ip(req.headers(), req.extensions(), state.trust_proxy);
+    let (auth_user, credential) = match bearer {
+        Some(token) if token.starts_with(tokens::PREFIX) => {
+            personal_token(&state.pool, &token).await?
+        }
+        Some(token) => session(&state, &token, ip).await?,
+        None => match jar.get(ACCESS_COOKIE) {
+            Some(token) => session(&state, token.value(), ip).await?,
+            None => return Err(APIError::auth()),
+        },
+    };
+
+    req.extensions_mut().insert(auth_user);
+    req.extensions_mut().insert(credential);
+
+    Ok(next.run(req).await)
+}
@@ -0,0 +1,27 @@
+use crate::services::metrics::Metrics;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+
+/// Counts and times requests by matched route. Requests matching no route
+/// share the `unmatched` label.
+pub async fn track_requests(State(metrics): State<Metrics>, req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let method = req.method().clone();
+    let started = Instant::now();
+
+    let res = next.run(req).await;
+    metrics.observe_request(
+        &method,
+        route.as_deref().unwrap_or("unmatched"),
+        res.status(),
+        started.elapsed(),
+    );
+    res
+}
@@ -0,0 +1,6 @@
+pub mod jwt;
+pub mod metrics;
+pub mod rate_limit;
+pub mod request_id;
+pub mod scope;
+pub mod version;
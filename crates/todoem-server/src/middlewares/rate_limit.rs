@@ -0,0 +1,355 @@
+use super::version::{original_uri, unversioned};
+use crate::config::RateLimits;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{Extensions, HeaderMap, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use todoem_core::{errors::APIError, models::AuthUser};
+
+/// Buckets are only swept once the map grows past this many entries.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// A token bucket that holds `capacity` tokens and refills completely every `period`.
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl Budget {
+    pub const fn per_minute(capacity: u32) -> Self {
+        Self {
+            capacity,
+            period: Duration::from_secs(60),
+        }
+    }
+
+    fn rate(&self) -> f64 {
+        self.capacity as f64 / self.period.as_secs_f64()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Subject {
+    User(uuid::Uuid),
+    Ip(IpAddr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct BucketKey {
+    /// Index into `routes`, `None` for the default budget.
+    route: Option<usize>,
+    subject: Subject,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, budget: &Budget, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * budget.rate()).min(budget.capacity as f64);
+        self.updated = now;
+    }
+}
+
+struct Route {
+    method: Method,
+    segments: Vec<String>,
+    budget: Budget,
+    /// Counted per client IP rather than per user, for routes used signed out.
+    by_ip: bool,
+}
+
+impl Route {
+    /// Matches `/api/user/:id/request` style patterns segment by segment.
+    /// Patterns are unversioned and `path` has had its version stripped, so
+    /// that every version of a route shares one budget.
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        if self.method != method {
+            return false;
+        }
+
+        let mut parts = path.trim_matches('/').split('/');
+        for segment in &self.segments {
+            match parts.next() {
+                Some(part) if segment.starts_with(':') || segment == part => {}
+                _ => return false,
+            }
+        }
+        parts.next().is_none()
+    }
+}
+
+/// Outcome of taking a token, rendered as `RateLimit-*` headers.
+struct Decision {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    reset: u64,
+    retry_after: u64,
+}
+
+impl Decision {
+    fn write_headers(&self, headers: &mut HeaderMap) {
+        headers.insert("ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert("ratelimit-remaining", HeaderValue::from(self.remaining));
+        headers.insert("ratelimit-reset", HeaderValue::from(self.reset));
+        if !self.allowed {
+            headers.insert("retry-after", HeaderValue::from(self.retry_after));
+        }
+    }
+
+    fn reject(&self) -> Response {
+        let mut res = APIError::too_many_requests().into_response();
+        self.write_headers(res.headers_mut());
+        res
+    }
+}
+
+struct Inner {
+    user_default: Budget,
+    ip_default: Budget,
+    trust_proxy: bool,
+    routes: Vec<Route>,
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+/// In-memory token-bucket limiter keyed by authenticated user and by client IP.
+#[derive(Clone)]
+pub struct RateLimiter(Arc<Inner>);
+
+impl RateLimiter {
+    pub fn new(config: &RateLimits) -> Self {
+        Self(Arc::new(Inner {
+            user_default: Budget::per_minute(config.user_per_minute),
+            ip_default: Budget::per_minute(config.ip_per_minute),
+            trust_proxy: config.trust_proxy,
+            routes: Vec::new(),
+            buckets: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Gives `method pattern` its own per-user budget instead of the default one.
+    /// Must be called before the limiter is cloned into a layer.
+    pub fn route(self, method: Method, pattern: &str, budget: Budget) -> Self {
+        self.push(method, pattern, budget, false)
+    }
+
+    /// Gives `method pattern` a per-IP budget on top of the default one, for
+    /// sign-in and recovery routes that have no user to count against.
+    pub fn ip_route(self, method: Method, pattern: &str, budget: Budget) -> Self {
+        self.push(method, pattern, budget, true)
+    }
+
+    fn push(mut self, method: Method, pattern: &str, budget: Budget, by_ip: bool) -> Self {
+        let inner = Arc::get_mut(&mut self.0).expect("rate limiter is already shared");
+        inner.routes.push(Route {
+            method,
+            segments: pattern
+                .trim_matches('/')
+                .split('/')
+                .map(str::to_string)
+                .collect(),
+            budget,
+            by_ip,
+        });
+        self
+    }
+
+    fn route_index(&self, method: &Method, path: &str, by_ip: bool) -> Option<usize> {
+        let path = unversioned(path);
+        self.0
+            .routes
+            .iter()
+            .position(|r| r.by_ip == by_ip && r.matches(method, &path))
+    }
+
+    fn user_budget(&self, method: &Method, path: &str) -> (Option<usize>, Budget) {
+        match self.route_index(method, path, false) {
+            Some(i) => (Some(i), self.0.routes[i].budget),
+            None => (None, self.0.user_default),
+        }
+    }
+
+    fn take(&self, key: BucketKey, budget: Budget) -> Decision {
+        let now = Instant::now();
+        let mut buckets = self.0.buckets.lock().unwrap();
+
+        if buckets.len() > SWEEP_THRESHOLD {
+            // full buckets carry no state worth keeping
+            buckets.retain(|k, b| {
+                let budget = match (k.route, k.subject) {
+                    (Some(i), _) => self.0.routes[i].budget,
+                    (None, Subject::User(_)) => self.0.user_default,
+                    (None, Subject::Ip(_)) => self.0.ip_default,
+                };
+                b.refill(&budget, now);
+                b.tokens < budget.capacity as f64
+            });
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: budget.capacity as f64,
+            updated: now,
+        });
+        bucket.refill(&budget, now);
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        let rate = budget.rate();
+        Decision {
+            allowed,
+            limit: budget.capacity,
+            remaining: bucket.tokens.floor() as u32,
+            reset: ((budget.capacity as f64 - bucket.tokens) / rate).ceil() as u64,
+            retry_after: ((1.0 - bucket.tokens).max(0.0) / rate).ceil() as u64,
+        }
+    }
+
+    fn client_ip(&self, req: &Request) -> Option<IpAddr> {
+        client_ip(req.headers(), req.extensions(), self.0.trust_proxy)
+    }
+}
+
+/// The address of the client, from `X-Forwarded-For` when behind a trusted
+/// proxy or else from the connection.
+pub fn client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    trust_proxy: bool,
+) -> Option<IpAddr> {
+    if trust_proxy {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+/// Applies the per-IP budget. Runs before authentication so that anonymous
+/// floods are throttled too.
+pub async fn limit_by_ip(State(limiter): State<RateLimiter>, req: Request, next: Next) -> Response {
+    if let Some(ip) = limiter.client_ip(&req) {
+        let key = BucketKey {
+            route: None,
+            subject: Subject::Ip(ip),
+        };
+        let decision = limiter.take(key, limiter.0.ip_default);
+        if !decision.allowed {
+            tracing::warn!("Rate limited ip {}", ip);
+            return decision.reject();
+        }
+
+        if let Some(i) = limiter.route_index(req.method(), req.uri().path(), true) {
+            let key = BucketKey {
+                route: Some(i),
+                subject: Subject::Ip(ip),
+            };
+            let decision = limiter.take(key, limiter.0.routes[i].budget);
+            if !decision.allowed {
+                tracing::warn!("Rate limited ip {} on {}", ip, req.uri().path());
+                return decision.reject();
+            }
+        }
+    }
+
+    next.run(req).await
+}
+
+/// Applies the per-route budget of the authenticated user and reports it in
+/// `RateLimit-*` headers.
+pub async fn limit_by_user(
+    State(limiter): State<RateLimiter>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let user_id = match req.extensions().get::<AuthUser>() {
+        Some(user) => user.id,
+        None => return next.run(req).await,
+    };
+
+    let path = original_uri(&req).path().to_string();
+    let (route, budget) = limiter.user_budget(req.method(), &path);
+    let key = BucketKey {
+        route,
+        subject: Subject::User(user_id),
+    };
+    let decision = limiter.take(key, budget);
+    if !decision.allowed {
+        tracing::warn!("Rate limited user {} on {}", user_id, path);
+        return decision.reject();
+    }
+
+    let mut res = next.run(req).await;
+    decision.write_headers(res.headers_mut());
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middlewares::version::{ApiVersion, UNVERSIONED_PREFIX};
+    use axum::{body::Body, middleware, routing::get, Extension, Router};
+    use http::StatusCode;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn route_budgets_are_shared_across_versions() {
+        let limiter = RateLimiter::new(&RateLimits::default()).route(
+            Method::GET,
+            "/api/user/search",
+            Budget::per_minute(2),
+        );
+        let api = Router::new()
+            .route("/user/search", get(|| async {}))
+            .route_layer(middleware::from_fn_with_state(limiter, limit_by_user))
+            .layer(Extension(AuthUser {
+                id: uuid::Uuid::new_v4(),
+                email: "nami@example.com".to_string(),
+            }));
+        let app = Router::new()
+            .nest(ApiVersion::V1.prefix(), api.clone())
+            .nest(UNVERSIONED_PREFIX, api);
+
+        let mut statuses = Vec::new();
+        for uri in [
+            "/api/v1/user/search",
+            "/api/user/search",
+            "/api/v1/user/search",
+        ] {
+            let req = Request::get(uri).body(Body::empty()).unwrap();
+            let res = app.clone().oneshot(req).await.unwrap();
+            statuses.push(res.status());
+        }
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::TOO_MANY_REQUESTS
+            ]
+        );
+    }
+}
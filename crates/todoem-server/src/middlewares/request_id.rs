@@ -0,0 +1,82 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use http::{HeaderName, HeaderValue};
+use todoem_core::errors::REQUEST_ID;
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest `X-Request-Id` taken from a client or proxy.
+const MAX_LEN: usize = 128;
+
+/// Ids from upstream are kept when short and plain enough to log safely.
+fn acceptable(id: &str) -> bool {
+    (1..=MAX_LEN).contains(&id.len())
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+/// Keeps the `X-Request-Id` of the request, or gives it a new one, and
+/// echoes it on the response. Must run before the trace layer, which logs it
+/// with every line.
+pub async fn request_id(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| acceptable(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let value = HeaderValue::from_str(&id).expect("request ids are visible ascii");
+    req.headers_mut().insert(X_REQUEST_ID, value.clone());
+
+    let mut res = REQUEST_ID.scope(id, next.run(req)).await;
+    res.headers_mut().insert(X_REQUEST_ID, value);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use todoem_core::errors::APIError;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { Err::<(), _>(APIError::not_found()) }))
+            .layer(middleware::from_fn(request_id))
+    }
+
+    async fn call(id: Option<&str>) -> (String, serde_json::Value) {
+        let mut req = Request::get("/");
+        if let Some(id) = id {
+            req = req.header(X_REQUEST_ID, id);
+        }
+        let res = app()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let header = res.headers()[X_REQUEST_ID].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (header, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn upstream_ids_are_kept() {
+        let (header, body) = call(Some("lb-7f3a.42")).await;
+        assert_eq!(header, "lb-7f3a.42");
+        assert_eq!(body["request_id"], "lb-7f3a.42");
+        assert_eq!(body["error"], "Not found");
+    }
+
+    #[tokio::test]
+    async fn missing_or_odd_ids_are_replaced() {
+        for id in [None, Some("two words"), Some(&"x".repeat(200)[..])] {
+            let (header, body) = call(id).await;
+            assert!(uuid::Uuid::parse_str(&header).is_ok(), "{}", header);
+            assert_eq!(body["request_id"], header.as_str());
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use todoem_core::{errors::APIError, models::token::Scope, models::Credential};
+
+/// The scope a personal access token needs for a group of routes.
+#[derive(Debug, Clone, Copy)]
+pub enum ScopeRule {
+    /// `read` for GET and HEAD, `write` for everything else.
+    ByMethod {
+        read: Scope,
+        write: Scope,
+    },
+    All(Scope),
+    /// Closed to personal access tokens.
+    SessionOnly,
+}
+
+impl ScopeRule {
+    /// The scope a token needs for `method`, `None` when tokens are refused.
+    pub fn needed(&self, method: &Method) -> Option<Scope> {
+        match *self {
+            ScopeRule::ByMethod { read, .. } if matches!(*method, Method::GET | Method::HEAD) => {
+                Some(read)
+            }
+            ScopeRule::ByMethod { write, .. } => Some(write),
+            ScopeRule::All(scope) => Some(scope),
+            ScopeRule::SessionOnly => None,
+        }
+    }
+}
+
+/// Checks the token of the request against `rule`. Cookie and JWT sessions
+/// are not restricted. Must run after `jwt_auth`.
+pub async fn require_scope(
+    State(rule): State<ScopeRule>,
+    req: Request,
+    next: Next,
+) -> Result<Response, APIError> {
+    let scopes = match req.extensions().get::<Credential>() {
+        Some(Credential::Token { scopes, .. }) => scopes,
+        _ => return Ok(next.run(req).await),
+    };
+
+    let needed = match rule.needed(req.method()) {
+        Some(scope) => scope,
+        None => {
+            return Err(APIError::new(
+                StatusCode::FORBIDDEN,
+                "Not available to personal access tokens",
+            ))
+        }
+    };
+
+    if !scopes.iter().any(|s| s == needed.as_str()) {
+        return Err(APIError::new(
+            StatusCode::FORBIDDEN,
+            &format!("Token lacks the {} scope", needed.as_str()),
+        ));
+    }
+
+    Ok(next.run(req).await)
+}
@@ -0,0 +1,208 @@
+use axum::{
+    extract::{OriginalUri, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use http::{header::LINK, HeaderName, HeaderValue, StatusCode, Uri};
+use std::borrow::Cow;
+
+use todoem_core::errors::APIError;
+
+pub const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+pub const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// Where the unversioned routes are mounted, as an alias of
+/// [`ApiVersion::LEGACY`] while clients move to versioned paths.
+pub const UNVERSIONED_PREFIX: &str = "/api";
+
+/// A version of the HTTP API, mounted under its own prefix. v1 is the only
+/// one, and `/api` serves it unchanged until its sunset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+}
+
+impl ApiVersion {
+    pub const ALL: [ApiVersion; 1] = [ApiVersion::V1];
+
+    /// The version the unversioned prefix stands for.
+    pub const LEGACY: ApiVersion = ApiVersion::V1;
+
+    pub const fn prefix(self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/api/v1",
+        }
+    }
+}
+
+/// The URI as the client sent it. Layers on nested routers otherwise see it
+/// without the prefix they are nested under.
+pub fn original_uri(req: &Request) -> &Uri {
+    match req.extensions().get::<OriginalUri>() {
+        Some(OriginalUri(uri)) => uri,
+        None => req.uri(),
+    }
+}
+
+/// `/api/v1/task/5` as `/api/task/5`, so that a route is the same route
+/// whichever version it is requested through.
+pub fn unversioned(path: &str) -> Cow<'_, str> {
+    for version in ApiVersion::ALL {
+        if let Some(rest) = path.strip_prefix(version.prefix()) {
+            if rest.is_empty() || rest.starts_with('/') {
+                return Cow::Owned(format!("{}{}", UNVERSIONED_PREFIX, rest));
+            }
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+/// Announces that routes mounted under `prefix` are going away, per RFC 9745
+/// and RFC 8594, and where they moved to, and takes them away at the sunset.
+/// Dates are days, from midnight UTC.
+#[derive(Clone)]
+pub struct Deprecation {
+    prefix: &'static str,
+    successor: ApiVersion,
+    deprecation: HeaderValue,
+    sunset: Option<(DateTime<Utc>, HeaderValue)>,
+}
+
+impl Deprecation {
+    pub fn new(
+        prefix: &'static str,
+        successor: ApiVersion,
+        since: NaiveDate,
+        sunset: Option<NaiveDate>,
+    ) -> Self {
+        let http_date = |day: NaiveDate| {
+            HeaderValue::from_str(&day.format("%a, %d %b %Y 00:00:00 GMT").to_string())
+                .expect("dates are visible ascii")
+        };
+        let midnight = |day: NaiveDate| day.and_time(NaiveTime::MIN).and_utc();
+        Self {
+            prefix,
+            successor,
+            deprecation: HeaderValue::from_str(&format!("@{}", midnight(since).timestamp()))
+                .expect("timestamps are visible ascii"),
+            sunset: sunset.map(|day| (midnight(day), http_date(day))),
+        }
+    }
+
+    /// The same request under the successor's prefix.
+    fn successor_link(&self, req: &Request) -> Option<HeaderValue> {
+        let target = original_uri(req).path_and_query()?.as_str();
+        let rest = target.strip_prefix(self.prefix)?;
+        HeaderValue::from_str(&format!(
+            "<{}{}>; rel=\"successor-version\"",
+            self.successor.prefix(),
+            rest
+        ))
+        .ok()
+    }
+}
+
+/// Adds the `Deprecation`, `Sunset` and `Link` headers of `deprecation` to
+/// every response. From the sunset on, the response is `410 Gone` instead.
+pub async fn deprecated(
+    State(deprecation): State<Deprecation>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let link = deprecation.successor_link(&req);
+    let mut res = match &deprecation.sunset {
+        Some((at, _)) if Utc::now() >= *at => APIError::new(
+            StatusCode::GONE,
+            "This path is no longer served, see the successor-version link",
+        )
+        .into_response(),
+        _ => next.run(req).await,
+    };
+
+    let headers = res.headers_mut();
+    headers.insert(DEPRECATION, deprecation.deprecation.clone());
+    if let Some((_, sunset)) = &deprecation.sunset {
+        headers.insert(SUNSET, sunset.clone());
+    }
+    if let Some(link) = link {
+        headers.append(LINK, link);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app_until(sunset: NaiveDate) -> Router {
+        let deprecation = Deprecation::new(
+            UNVERSIONED_PREFIX,
+            ApiVersion::V1,
+            NaiveDate::from_ymd_opt(2026, 10, 19).unwrap(),
+            Some(sunset),
+        );
+        let api = Router::new().route("/task/:id", get(|| async { "task" }));
+        Router::new()
+            .nest(ApiVersion::V1.prefix(), api.clone())
+            .nest(
+                UNVERSIONED_PREFIX,
+                api.layer(middleware::from_fn_with_state(deprecation, deprecated)),
+            )
+    }
+
+    async fn call_until(sunset: NaiveDate, uri: &str) -> (StatusCode, http::HeaderMap, String) {
+        let req = Request::get(uri).body(Body::empty()).unwrap();
+        let res = app_until(sunset).oneshot(req).await.unwrap();
+        let (status, headers) = (res.status(), res.headers().clone());
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, headers, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn call(uri: &str) -> (http::HeaderMap, String) {
+        let sunset = NaiveDate::from_ymd_opt(2099, 4, 19).unwrap();
+        let (_, headers, body) = call_until(sunset, uri).await;
+        (headers, body)
+    }
+
+    #[tokio::test]
+    async fn the_alias_serves_v1_and_says_so() {
+        let (headers, body) = call("/api/task/5?x=1").await;
+        assert_eq!(body, "task");
+        assert_eq!(headers[DEPRECATION], "@1792368000");
+        assert_eq!(headers[SUNSET], "Sun, 19 Apr 2099 00:00:00 GMT");
+        assert_eq!(
+            headers[LINK],
+            "</api/v1/task/5?x=1>; rel=\"successor-version\""
+        );
+
+        let (headers, body) = call("/api/v1/task/5").await;
+        assert_eq!(body, "task");
+        assert!(!headers.contains_key(DEPRECATION));
+        assert!(!headers.contains_key(SUNSET));
+    }
+
+    #[tokio::test]
+    async fn the_alias_is_gone_after_the_sunset() {
+        let today = Utc::now().date_naive();
+        let (status, headers, _) = call_until(today, "/api/task/5").await;
+        assert_eq!(status, StatusCode::GONE);
+        assert_eq!(headers[LINK], "</api/v1/task/5>; rel=\"successor-version\"");
+
+        let (status, _, body) = call_until(today, "/api/v1/task/5").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "task");
+    }
+
+    #[test]
+    fn versions_are_stripped_from_paths() {
+        assert_eq!(unversioned("/api/v1/user/search"), "/api/user/search");
+        assert_eq!(unversioned("/api/v1"), "/api");
+        assert_eq!(unversioned("/api/user/search"), "/api/user/search");
+        assert_eq!(unversioned("/api/v10/user"), "/api/v10/user");
+    }
+}
@@ -0,0 +1,34 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::{auth as A, oidc as H};
+
+/// Sign-in routes, reachable without a session.
+pub fn init() -> Routes {
+    Routes::new()
+        .post("/login", A::login)
+        .post("/2fa", A::second_factor)
+        .post("/password/forgot", A::forgot_password)
+        .post("/password/reset", A::reset_password)
+        .post("/email/verify", A::verify_email)
+        .get("/oidc/providers", H::get_providers)
+        .get("/oidc/signup", H::get_signup)
+        .post("/oidc/signup", H::complete_signup)
+        .get("/oidc/:provider/login", H::login)
+        .get("/oidc/:provider/callback", H::callback)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    A::login,
+    A::second_factor,
+    A::forgot_password,
+    A::reset_password,
+    A::verify_email,
+    H::get_providers,
+    H::get_signup,
+    H::complete_signup,
+    H::login,
+    H::callback,
+))]
+pub struct Api;
@@ -0,0 +1,463 @@
+use axum::Router;
+use http::Method;
+use sqlx::PgPool;
+use utoipa::{
+    openapi::{
+        path::{Operation, PathItem},
+        security::{
+            ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme,
+        },
+        ContentBuilder, OpenApi as Document, Ref, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use super::{
+    auth, filter, health, identity, list, me, metrics, session, stats, table::join, task, token,
+    two_factor, user, webhook, well_known, PROFILE, TASKS,
+};
+use crate::middlewares::scope::ScopeRule;
+use crate::middlewares::version::ApiVersion;
+use crate::services::auth::ACCESS_COOKIE;
+use todoem_core::errors::APIError;
+use todoem_core::models::token::Scope;
+
+pub const SPEC_PATH: &str = "/api/v1/openapi.json";
+pub const UI_PATH: &str = "/api/v1/docs";
+/// Where the document was served before versioning, until the sunset of the
+/// unversioned `/api`.
+pub const UNVERSIONED_SPEC_PATH: &str = "/api/openapi.json";
+pub const UNVERSIONED_UI_PATH: &str = "/api/docs";
+
+const SESSION_SCHEME: &str = "session";
+const BEARER_SCHEME: &str = "bearer";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "todoem",
+        description = "Tasks, shared lists and connections between users.\n\n\
+            Sign in with `POST /api/v1/auth/login`, which sets the session cookie, \
+            or send a personal access token as a bearer token. Errors come as an \
+            `APIError` body with the same `X-Request-Id` as the response.\n\n\
+            The same routes are served without the `/v1` until their `Sunset`, \
+            with a `Deprecation` header.",
+        version = "1"
+    ),
+    components(schemas(APIError)),
+    modifiers(&SecuritySchemes)
+)]
+struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, doc: &mut Document) {
+        let components = doc.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            SESSION_SCHEME,
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                ACCESS_COOKIE,
+                "Set by signing in",
+            ))),
+        );
+        components.add_security_scheme(
+            BEARER_SCHEME,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "A session token, or a personal access token limited to the \
+                         listed scopes",
+                    ))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Who may call a group of routes, as enforced by `routes::init`.
+#[derive(Clone, Copy)]
+enum Access {
+    Public,
+    Session(ScopeRule),
+}
+
+fn operations(item: &mut PathItem) -> impl Iterator<Item = (Method, &mut Operation)> {
+    [
+        (Method::GET, &mut item.get),
+        (Method::PUT, &mut item.put),
+        (Method::POST, &mut item.post),
+        (Method::DELETE, &mut item.delete),
+        (Method::PATCH, &mut item.patch),
+    ]
+    .into_iter()
+    .filter_map(|(method, operation)| operation.as_mut().map(|op| (method, op)))
+}
+
+fn error_response(description: &str) -> utoipa::openapi::Response {
+    ResponseBuilder::new()
+        .description(description)
+        .content(
+            "application/json",
+            ContentBuilder::new()
+                .schema(Some(Ref::from_schema_name("APIError")))
+                .build(),
+        )
+        .build()
+}
+
+/// Tags the operations of `api` and documents the credentials they take.
+fn describe(mut api: Document, tag: &str, access: Access) -> Document {
+    for item in api.paths.paths.values_mut() {
+        for (method, operation) in operations(item) {
+            operation.tags = Some(vec![tag.to_string()]);
+            let Access::Session(rule) = access else {
+                continue;
+            };
+
+            let scopes = rule.needed(&method).map(|scope: Scope| scope.as_str());
+            operation.security = Some(vec![
+                SecurityRequirement::new::<_, _, &str>(SESSION_SCHEME, []),
+                SecurityRequirement::new(BEARER_SCHEME, scopes),
+            ]);
+            let responses = &mut operation.responses.responses;
+            responses.insert("401".to_string(), error_response("Not signed in").into());
+            let forbidden = match scopes {
+                Some(_) => "The token lacks the scope",
+                None => "Not available to personal access tokens",
+            };
+            // handlers document their own reasons to refuse, if they have any
+            responses
+                .entry("403".to_string())
+                .or_insert_with(|| error_response(forbidden).into());
+        }
+    }
+    api
+}
+
+/// The OpenAPI document of v1, nested as in `routes::init`. The unversioned
+/// alias of v1 is left out.
+pub fn openapi() -> Document {
+    let versioned = [
+        ("/me", "me", me::Api::openapi(), Access::Session(PROFILE)),
+        (
+            "/me",
+            "me",
+            me::AccountApi::openapi(),
+            Access::Session(ScopeRule::SessionOnly),
+        ),
+        (
+            "/user",
+            "users",
+            user::Api::openapi(),
+            Access::Session(ScopeRule::All(Scope::Connections)),
+        ),
+        (
+            "/task",
+            "tasks",
+            task::Api::openapi(),
+            Access::Session(TASKS),
+        ),
+        (
+            "/list",
+            "lists",
+            list::Api::openapi(),
+            Access::Session(TASKS),
+        ),
+        (
+            "/filters",
+            "filters",
+            filter::Api::openapi(),
+            Access::Session(TASKS),
+        ),
+        (
+            "/stats",
+            "stats",
+            stats::Api::openapi(),
+            Access::Session(TASKS),
+        ),
+        (
+            "/webhooks",
+            "webhooks",
+            webhook::Api::openapi(),
+            Access::Session(ScopeRule::All(Scope::Webhooks)),
+        ),
+        (
+            "/me/2fa",
+            "two-factor",
+            two_factor::Api::openapi(),
+            Access::Session(ScopeRule::SessionOnly),
+        ),
+        (
+            "/me/sessions",
+            "sessions",
+            session::Api::openapi(),
+            Access::Session(ScopeRule::SessionOnly),
+        ),
+        (
+            "/tokens",
+            "tokens",
+            token::Api::openapi(),
+            Access::Session(ScopeRule::SessionOnly),
+        ),
+        (
+            "/identities",
+            "identities",
+            identity::Api::openapi(),
+            Access::Session(ScopeRule::SessionOnly),
+        ),
+        ("/auth", "auth", auth::Api::openapi(), Access::Public),
+    ]
+    .map(|(prefix, tag, api, access)| {
+        (
+            format!("{}{}", ApiVersion::V1.prefix(), prefix),
+            tag,
+            api,
+            access,
+        )
+    });
+    let unversioned = [
+        (
+            "/.well-known",
+            "well-known",
+            well_known::Api::openapi(),
+            Access::Public,
+        ),
+        ("", "operations", health::Api::openapi(), Access::Public),
+        ("", "operations", metrics::Api::openapi(), Access::Public),
+    ]
+    .map(|(prefix, tag, api, access)| (prefix.to_string(), tag, api, access));
+
+    versioned.into_iter().chain(unversioned).fold(
+        ApiDoc::openapi(),
+        |mut doc, (prefix, tag, api, access)| {
+            // merged rather than nested outright, which would replace the
+            // paths `/me` shares between its two rules
+            let nested = Document::default().nest_with_path_composer(
+                prefix,
+                describe(api, tag, access),
+                join,
+            );
+            doc.merge(nested);
+            doc
+        },
+    )
+}
+
+/// The document, and a browsable rendering of it.
+pub fn init() -> Router<PgPool> {
+    SwaggerUi::new(UI_PATH).url(SPEC_PATH, openapi()).into()
+}
+
+/// The same at the paths of before versioning, for `routes::init` to mark
+/// deprecated along with the rest of `/api`.
+pub fn unversioned() -> Router<PgPool> {
+    SwaggerUi::new(UNVERSIONED_UI_PATH)
+        .url(UNVERSIONED_SPEC_PATH, openapi())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, Settings};
+    use crate::handlers::extract::ClientInfo;
+    use crate::middlewares::version::{unversioned, DEPRECATION, SUNSET};
+    use crate::routes;
+    use crate::services::auth::SessionIssuer;
+    use crate::services::{metrics::Metrics, shutdown::Shutdown, storage::LocalStorage};
+    use axum::{body::Body, response::Response};
+    use chrono::NaiveDate;
+    use http::{header::COOKIE, Request, StatusCode};
+    use sqlx::postgres::PgPoolOptions;
+    use std::{collections::BTreeSet, sync::Arc, time::Duration};
+    use todoem_core::models::AuthUser;
+    use tower::ServiceExt;
+
+    /// The real router, over a database that is never reached.
+    fn router() -> Router {
+        let mut settings = Settings::default();
+        settings.jwt.secret_key = "secret".to_string();
+        // the old paths are asked for, whenever the tests run
+        settings.api.unversioned_sunset = NaiveDate::from_ymd_opt(2099, 4, 19).unwrap();
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(50))
+            .connect_lazy("postgres://localhost:1/unused")
+            .unwrap();
+        let config = Config::new(settings, pool).unwrap();
+        let storage = Arc::new(LocalStorage::new(std::env::temp_dir()));
+        routes::init(config, storage, Shutdown::new(), Metrics::new())
+    }
+
+    /// The real router over `pool` and its table of routes, and a way to
+    /// sign in to it.
+    fn served(pool: PgPool) -> (Router, Vec<(Method, String)>, SessionIssuer) {
+        let mut settings = Settings::default();
+        settings.jwt.secret_key = "secret".to_string();
+        // every alias is asked for, all as one user
+        settings.rate_limits.user_per_minute = u32::MAX;
+        settings.rate_limits.ip_per_minute = u32::MAX;
+        settings.api.unversioned_sunset = NaiveDate::from_ymd_opt(2099, 4, 19).unwrap();
+        let config = Config::new(settings, pool).unwrap();
+        let sessions =
+            SessionIssuer::new(config.jwt_keys.clone(), &config.accounts, &config.cookies);
+        let storage = Arc::new(LocalStorage::new(std::env::temp_dir()));
+        let (router, table) =
+            routes::init_with_table(config, storage, Shutdown::new(), Metrics::new());
+        (router, table, sessions)
+    }
+
+    /// `method` on `path` with a new session, as a route sees it. A session
+    /// per request, since some routes end the one they are called with.
+    async fn probe(
+        router: &Router,
+        sessions: &SessionIssuer,
+        pool: &PgPool,
+        method: &Method,
+        path: &str,
+    ) -> Response {
+        let user = AuthUser {
+            id: uuid::uuid!("5d43fc3c-8acb-48f9-9b25-8f8bd6f3d834"),
+            email: "luffy@op.co".to_string(),
+        };
+        let cookie = sessions
+            .start(pool, &user, &ClientInfo::default(), None)
+            .await
+            .unwrap();
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(COOKIE, format!("{}={}", cookie.name(), cookie.value()))
+            .body(Body::empty())
+            .unwrap();
+        router.clone().oneshot(req).await.unwrap()
+    }
+
+    /// Whether a route took the request, whatever it made of it. The router
+    /// answers an empty 404 for paths it has no route for, and a 405 for
+    /// methods it has none for on a routed path; handlers give reasons.
+    async fn routed(res: Response) -> bool {
+        let status = res.status();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        !(status == StatusCode::METHOD_NOT_ALLOWED
+            || status == StatusCode::NOT_FOUND && body.is_empty())
+    }
+
+    /// `/api/v1/task/:id` as a path to request, `/api/v1/task/1`. Values of
+    /// the wrong type are rejected by the route, which still took them.
+    fn concrete(path: &str) -> String {
+        path.split('/')
+            .map(|segment| match segment.starts_with(':') {
+                true => "1",
+                false => segment,
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// An operation with its path parameters unnamed, so that the spec's
+    /// `/task/{id}` and the router's `/task/:id` are the same.
+    fn operation(method: &Method, path: &str) -> String {
+        let path = path
+            .split('/')
+            .map(|segment| match segment.starts_with(['{', ':']) {
+                true => "{}",
+                false => segment,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("{} {}", method, path)
+    }
+
+    /// The spec documents every route of the router's table and nothing
+    /// else, and each v1 route is also served, deprecated, without `/v1`.
+    #[sqlx::test(migrations = "src/db/migrations", fixtures("../db/fixtures/users.sql"))]
+    async fn spec_matches_the_routes(pool: PgPool) {
+        let (router, table, sessions) = served(pool.clone());
+
+        let documented: BTreeSet<_> = openapi()
+            .paths
+            .paths
+            .into_iter()
+            .flat_map(|(path, mut item)| {
+                operations(&mut item)
+                    .map(|(method, _)| operation(&method, &path))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let served: BTreeSet<_> = table
+            .iter()
+            .map(|(method, path)| operation(method, path))
+            .collect();
+
+        let mut unaliased = Vec::new();
+        for (method, path) in &table {
+            if !path.starts_with(ApiVersion::V1.prefix()) {
+                continue;
+            }
+            let uri = concrete(path);
+            let alias = unversioned(&uri);
+            let res = probe(&router, &sessions, &pool, method, &alias).await;
+            if !res.headers().contains_key(DEPRECATION) || !routed(res).await {
+                unaliased.push(format!("{} {}", method, path));
+            }
+        }
+
+        let unserved: Vec<_> = documented.difference(&served).collect();
+        let undocumented: Vec<_> = served.difference(&documented).collect();
+        assert!(
+            unserved.is_empty() && undocumented.is_empty() && unaliased.is_empty(),
+            "spec entries without a route: {:?}\nroutes missing from the spec: {:?}\n\
+             without a deprecated unversioned alias: {:?}",
+            unserved,
+            undocumented,
+            unaliased
+        );
+    }
+
+    #[tokio::test]
+    async fn spec_and_docs_are_served() {
+        let router = router();
+        let req = Request::get(SPEC_PATH).body(Body::empty()).unwrap();
+        let res = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let spec: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(spec["openapi"].as_str().unwrap().starts_with("3.1"));
+        let create = &spec["paths"]["/api/v1/task"]["post"];
+        assert_eq!(
+            create["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/CreateTaskRequest"
+        );
+        assert_eq!(create["security"][1]["bearer"][0], "write:tasks");
+        assert!(spec["components"]["schemas"]["APIError"].is_object());
+
+        let req = Request::get(format!("{}/", UI_PATH))
+            .body(Body::empty())
+            .unwrap();
+        let res = router.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn old_paths_serve_the_docs_until_the_sunset() {
+        let router = router();
+        for path in [UNVERSIONED_SPEC_PATH, &format!("{}/", UNVERSIONED_UI_PATH)] {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            let res = router.clone().oneshot(req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK, "{}", path);
+            assert!(res.headers().contains_key(DEPRECATION), "{}", path);
+            assert!(res.headers().contains_key(SUNSET), "{}", path);
+        }
+
+        let req = Request::get(SPEC_PATH).body(Body::empty()).unwrap();
+        let res = router.oneshot(req).await.unwrap();
+        assert!(!res.headers().contains_key(DEPRECATION));
+    }
+}
@@ -0,0 +1,25 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::filter as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        .post("/", H::create_filter)
+        .get("/", H::get_filters)
+        .get("/:id", H::get_filter)
+        .put("/:id", H::update_filter)
+        .delete("/:id", H::delete_filter)
+        .get("/:id/tasks", H::get_filter_tasks)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    H::create_filter,
+    H::get_filters,
+    H::get_filter,
+    H::update_filter,
+    H::delete_filter,
+    H::get_filter_tasks,
+))]
+pub struct Api;
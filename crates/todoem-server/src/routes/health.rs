@@ -0,0 +1,14 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::health as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        .get("/healthz", H::healthz)
+        .get("/readyz", H::readyz)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(H::healthz, H::readyz,))]
+pub struct Api;
@@ -0,0 +1,15 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::oidc as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        .get("/", H::get_identities)
+        .post("/", H::link_identity)
+        .delete("/:id", H::unlink_identity)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(H::get_identities, H::link_identity, H::unlink_identity,))]
+pub struct Api;
@@ -0,0 +1,16 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::list as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        .post("/", H::create_list)
+        .get("/", H::get_lists)
+        .get("/:id", H::get_list)
+        .post("/:id/send/:user_id", H::send_list)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(H::create_list, H::get_lists, H::get_list, H::send_list))]
+pub struct Api;
@@ -0,0 +1,39 @@
+use axum::{extract::DefaultBodyLimit, handler::Handler};
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::me as H;
+
+pub fn init(avatar_max_bytes: usize) -> Routes {
+    Routes::new()
+        .get("/", H::get_me)
+        .patch("/", H::update_me)
+        .post("/email/verification", H::request_verification)
+        .put(
+            "/avatar",
+            // leave room for the multipart framing around the image
+            H::upload_avatar.layer(DefaultBodyLimit::max(avatar_max_bytes + 64 * 1024)),
+        )
+        .delete("/avatar", H::delete_avatar)
+}
+
+/// Closing and reopening the account, which personal access tokens cannot do.
+pub fn account() -> Routes {
+    Routes::new()
+        .delete("/", H::delete_me)
+        .post("/restore", H::restore_me)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    H::get_me,
+    H::update_me,
+    H::request_verification,
+    H::upload_avatar,
+    H::delete_avatar,
+))]
+pub struct Api;
+
+#[derive(OpenApi)]
+#[openapi(paths(H::delete_me, H::restore_me))]
+pub struct AccountApi;
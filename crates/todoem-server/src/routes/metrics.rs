@@ -0,0 +1,12 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::metrics as H;
+
+pub fn init() -> Routes {
+    Routes::new().get("/metrics", H::metrics)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(H::metrics,))]
+pub struct Api;
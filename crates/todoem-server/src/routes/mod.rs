@@ -0,0 +1,256 @@
+pub mod auth;
+pub mod docs;
+pub mod filter;
+pub mod health;
+pub mod identity;
+pub mod list;
+pub mod me;
+pub mod metrics;
+pub mod session;
+pub mod stats;
+mod table;
+pub mod task;
+pub mod token;
+pub mod two_factor;
+pub mod user;
+pub mod webhook;
+pub mod well_known;
+
+pub use table::Routes;
+
+use crate::config::{Config, Cors};
+use crate::errors;
+use crate::middlewares::jwt::{jwt_auth, AuthState};
+use crate::middlewares::metrics::track_requests;
+use crate::middlewares::rate_limit::{limit_by_ip, limit_by_user, Budget, RateLimiter};
+use crate::middlewares::request_id::{request_id, X_REQUEST_ID};
+use crate::middlewares::scope::{require_scope, ScopeRule};
+use crate::middlewares::version::{
+    deprecated, ApiVersion, Deprecation, DEPRECATION, SUNSET, UNVERSIONED_PREFIX,
+};
+use crate::services::auth::SessionIssuer;
+use crate::services::metrics::Metrics;
+use crate::services::oidc::{OidcClient, SharedOidc};
+use crate::services::sessions::{SessionCache, SharedSessionCache};
+use crate::services::shutdown::Shutdown;
+use crate::services::storage::SharedStorage;
+use crate::telemetry;
+use axum::{error_handling::HandleErrorLayer, middleware, Extension, Router};
+use http::{
+    header::{AUTHORIZATION, CONTENT_TYPE, LINK},
+    Method,
+};
+use std::{sync::Arc, time::Duration};
+use todoem_core::models::token::Scope;
+use tower::ServiceBuilder;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::trace::TraceLayer;
+
+const TASKS: ScopeRule = ScopeRule::ByMethod {
+    read: Scope::ReadTasks,
+    write: Scope::WriteTasks,
+};
+
+const PROFILE: ScopeRule = ScopeRule::ByMethod {
+    read: Scope::ReadProfile,
+    write: Scope::WriteProfile,
+};
+
+/// Limits what personal access tokens may do on `routes`.
+fn scoped(routes: Routes, rule: ScopeRule) -> Routes {
+    routes.layer_with(|router| {
+        router.route_layer(middleware::from_fn_with_state(rule, require_scope))
+    })
+}
+
+fn cors(config: &Cors) -> CorsLayer {
+    let origins = match config.allows_any() {
+        true => AllowOrigin::any(),
+        false => AllowOrigin::list(config.origins()),
+    };
+    CorsLayer::new()
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers([CONTENT_TYPE, AUTHORIZATION, X_REQUEST_ID])
+        .expose_headers([X_REQUEST_ID, DEPRECATION, SUNSET, LINK])
+        .allow_origin(origins)
+        .allow_credentials(config.allow_credentials)
+        .max_age(Duration::from_secs(config.max_age_secs))
+}
+
+pub fn init(
+    config: Config,
+    storage: SharedStorage,
+    shutdown: Shutdown,
+    metrics: Metrics,
+) -> Router {
+    init_with_table(config, storage, shutdown, metrics).0
+}
+
+/// The router of `init`, and the method and path of each route the API docs
+/// cover: those of v1, `/.well-known` and the probes.
+pub fn init_with_table(
+    config: Config,
+    storage: SharedStorage,
+    shutdown: Shutdown,
+    metrics: Metrics,
+) -> (Router, Vec<(Method, String)>) {
+    let limiter = RateLimiter::new(&config.rate_limits)
+        .route(Method::GET, "/api/user/search", Budget::per_minute(30))
+        .route(
+            Method::GET,
+            "/api/user/listers/search",
+            Budget::per_minute(30),
+        )
+        .route(
+            Method::POST,
+            "/api/user/:id/request",
+            Budget::per_minute(10),
+        )
+        .route(
+            Method::POST,
+            "/api/me/2fa/totp/confirm",
+            Budget::per_minute(10),
+        )
+        .route(
+            Method::POST,
+            "/api/me/2fa/recovery-codes",
+            Budget::per_minute(10),
+        )
+        .route(Method::POST, "/api/me/2fa/disable", Budget::per_minute(10))
+        .ip_route(Method::POST, "/api/auth/login", Budget::per_minute(10))
+        .ip_route(Method::POST, "/api/auth/2fa", Budget::per_minute(10))
+        .ip_route(
+            Method::POST,
+            "/api/auth/password/forgot",
+            Budget::per_minute(3),
+        )
+        .ip_route(
+            Method::POST,
+            "/api/auth/password/reset",
+            Budget::per_minute(10),
+        )
+        .ip_route(
+            Method::POST,
+            "/api/auth/email/verify",
+            Budget::per_minute(10),
+        );
+
+    let sessions = SessionIssuer::new(config.jwt_keys.clone(), &config.accounts, &config.cookies);
+    let oidc: SharedOidc = Arc::new(OidcClient::new(config.oidc));
+    let session_cache: SharedSessionCache = Arc::new(SessionCache::new(Duration::from_secs(
+        config.accounts.session_cache_secs,
+    )));
+    let auth = AuthState {
+        keys: config.jwt_keys.clone(),
+        pool: config.pool.clone(),
+        sessions: session_cache.clone(),
+        trust_proxy: config.rate_limits.trust_proxy,
+    };
+
+    let me = me::init(config.accounts.avatar_max_bytes);
+    let apis = Routes::new()
+        .nest(
+            "/me",
+            scoped(me, PROFILE).merge(scoped(me::account(), ScopeRule::SessionOnly)),
+        )
+        .nest(
+            "/user",
+            scoped(user::init(), ScopeRule::All(Scope::Connections)),
+        )
+        .nest("/task", scoped(task::init(), TASKS))
+        .nest("/list", scoped(list::init(), TASKS))
+        .nest("/filters", scoped(filter::init(), TASKS))
+        .nest("/stats", scoped(stats::init(), TASKS))
+        .nest(
+            "/webhooks",
+            scoped(webhook::init(), ScopeRule::All(Scope::Webhooks)),
+        )
+        .nest(
+            "/me/2fa",
+            scoped(two_factor::init(), ScopeRule::SessionOnly),
+        )
+        .nest(
+            "/me/sessions",
+            scoped(session::init(), ScopeRule::SessionOnly),
+        )
+        .nest("/tokens", scoped(token::init(), ScopeRule::SessionOnly))
+        .nest(
+            "/identities",
+            scoped(identity::init(), ScopeRule::SessionOnly),
+        )
+        .layer_with(|router| {
+            router.route_layer(
+                ServiceBuilder::new()
+                    .layer(middleware::from_fn_with_state(auth, jwt_auth))
+                    .layer(middleware::from_fn_with_state(
+                        limiter.clone(),
+                        limit_by_user,
+                    )),
+            )
+        })
+        // added after the layer above, so reachable without a session
+        .nest("/auth", auth::init());
+    let unversioned = Deprecation::new(
+        UNVERSIONED_PREFIX,
+        ApiVersion::LEGACY,
+        config.api.unversioned_deprecated,
+        Some(config.api.unversioned_sunset),
+    );
+
+    let v1 = Routes::new().nest(ApiVersion::V1.prefix(), apis.clone());
+    let well_known = Routes::new().nest("/.well-known", well_known::init());
+    let probes = health::init().merge(metrics::init());
+    let table = [v1.table(), well_known.table(), probes.table()].concat();
+
+    let probes = probes
+        .into_router()
+        .with_state(config.pool.clone())
+        .layer(Extension(shutdown))
+        .layer(Extension(metrics.clone()))
+        .layer(Extension(config.telemetry));
+
+    let router = Router::new()
+        .merge(v1.into_router())
+        // the routes of v1 as they were before versioning, until the sunset
+        .nest(
+            UNVERSIONED_PREFIX,
+            apis.into_router().layer(middleware::from_fn_with_state(
+                unversioned.clone(),
+                deprecated,
+            )),
+        )
+        .merge(well_known.into_router())
+        .merge(docs::init())
+        .merge(docs::unversioned().layer(middleware::from_fn_with_state(unversioned, deprecated)))
+        .with_state(config.pool)
+        .layer(
+            ServiceBuilder::new()
+                .layer(middleware::from_fn_with_state(metrics, track_requests))
+                .layer(middleware::from_fn(request_id))
+                .layer(TraceLayer::new_for_http().make_span_with(telemetry::request_span))
+                .layer(cors(&config.cors))
+                .layer(Extension(config.limits))
+                .layer(Extension(config.rate_limits))
+                .layer(Extension(config.accounts))
+                .layer(Extension(config.webhooks))
+                .layer(Extension(config.mail))
+                .layer(Extension(storage))
+                .layer(Extension(sessions))
+                .layer(Extension(session_cache))
+                .layer(Extension(config.jwt_keys))
+                .layer(Extension(oidc))
+                .layer(middleware::from_fn_with_state(limiter, limit_by_ip))
+                .layer(HandleErrorLayer::new(errors::handle_api_error))
+                .timeout(Duration::from_secs(config.server.request_timeout_secs)),
+        )
+        // merged after the layers above, so probes and scrapes are neither
+        // traced, counted nor rate limited
+        .merge(probes);
+    (router, table)
+}
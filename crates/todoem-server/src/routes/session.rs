@@ -0,0 +1,14 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::session as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        .get("/", H::get_sessions)
+        .delete("/:id", H::revoke_session)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(H::get_sessions, H::revoke_session,))]
+pub struct Api;
@@ -0,0 +1,12 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::stats as H;
+
+pub fn init() -> Routes {
+    Routes::new().get("/", H::get_stats)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(H::get_stats,))]
+pub struct Api;
@@ -0,0 +1,114 @@
+use axum::{
+    handler::Handler,
+    routing::{self, MethodFilter},
+    Router,
+};
+use http::Method;
+use sqlx::PgPool;
+
+/// A router that keeps the method and path of each of its routes, for the
+/// API docs to be checked against what is served. Routes are added one
+/// method at a time, as everywhere in `routes`.
+#[derive(Clone, Default)]
+pub struct Routes {
+    router: Router<PgPool>,
+    table: Vec<(Method, String)>,
+}
+
+/// Joins paths the way `Router::nest` does, so that `/` nested under
+/// `/api/task` is `/api/task`.
+pub fn join(prefix: &str, path: &str) -> String {
+    match path {
+        "/" => prefix.to_string(),
+        _ => format!("{}{}", prefix, path),
+    }
+}
+
+impl Routes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, PgPool>,
+        T: 'static,
+    {
+        self.on(Method::GET, path, handler)
+    }
+
+    pub fn post<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, PgPool>,
+        T: 'static,
+    {
+        self.on(Method::POST, path, handler)
+    }
+
+    pub fn put<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, PgPool>,
+        T: 'static,
+    {
+        self.on(Method::PUT, path, handler)
+    }
+
+    pub fn patch<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, PgPool>,
+        T: 'static,
+    {
+        self.on(Method::PATCH, path, handler)
+    }
+
+    pub fn delete<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, PgPool>,
+        T: 'static,
+    {
+        self.on(Method::DELETE, path, handler)
+    }
+
+    fn on<H, T>(mut self, method: Method, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, PgPool>,
+        T: 'static,
+    {
+        let filter = MethodFilter::try_from(method.clone()).expect("a method axum routes");
+        self.router = self.router.route(path, routing::on(filter, handler));
+        self.table.push((method, path.to_string()));
+        self
+    }
+
+    pub fn nest(mut self, prefix: &str, routes: Routes) -> Self {
+        self.router = self.router.nest(prefix, routes.router);
+        self.table.extend(
+            routes
+                .table
+                .into_iter()
+                .map(|(method, path)| (method, join(prefix, &path))),
+        );
+        self
+    }
+
+    pub fn merge(mut self, routes: Routes) -> Self {
+        self.router = self.router.merge(routes.router);
+        self.table.extend(routes.table);
+        self
+    }
+
+    /// Layers the router with `layer`, which must leave its routes as they are.
+    pub fn layer_with(mut self, layer: impl FnOnce(Router<PgPool>) -> Router<PgPool>) -> Self {
+        self.router = layer(self.router);
+        self
+    }
+
+    /// The method and path of every route, with `:param` segments.
+    pub fn table(&self) -> &[(Method, String)] {
+        &self.table
+    }
+
+    pub fn into_router(self) -> Router<PgPool> {
+        self.router
+    }
+}
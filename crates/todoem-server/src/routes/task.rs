@@ -0,0 +1,49 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::task as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        .post("/", H::create_task)
+        .get("/:id", H::get_task)
+        .put("/:id", H::update_task)
+        .delete("/:id", H::delete_task)
+        .put("/done/:id", H::done_task)
+        .put("/undone/:id", H::undone_task)
+        .get("/due", H::get_due_tasks)
+        .get("/view/today", H::get_today_view)
+        .get("/view/overdue", H::get_overdue_view)
+        .get("/view/upcoming", H::get_upcoming_view)
+        .get("/view/no-due-date", H::get_undated_view)
+        .get("/view/counts", H::get_view_counts)
+        .get("/all", H::get_all_tasks)
+        .get("/all/done", H::get_all_done_tasks)
+        .get("/all/undone", H::get_all_undone_tasks)
+        .delete("/all", H::delete_all_tasks)
+        .delete("/all/done", H::delete_all_done_tasks)
+        .delete("/all/undone", H::delete_all_undone_tasks)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    H::create_task,
+    H::get_task,
+    H::update_task,
+    H::delete_task,
+    H::done_task,
+    H::undone_task,
+    H::get_due_tasks,
+    H::get_today_view,
+    H::get_overdue_view,
+    H::get_upcoming_view,
+    H::get_undated_view,
+    H::get_view_counts,
+    H::get_all_tasks,
+    H::get_all_done_tasks,
+    H::get_all_undone_tasks,
+    H::delete_all_tasks,
+    H::delete_all_done_tasks,
+    H::delete_all_undone_tasks,
+))]
+pub struct Api;
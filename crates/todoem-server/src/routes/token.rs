@@ -0,0 +1,15 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::token as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        .post("/", H::create_token)
+        .get("/", H::get_tokens)
+        .delete("/:id", H::revoke_token)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(H::create_token, H::get_tokens, H::revoke_token,))]
+pub struct Api;
@@ -0,0 +1,23 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::two_factor as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        .get("/", H::get_status)
+        .post("/totp", H::enroll)
+        .post("/totp/confirm", H::confirm)
+        .post("/recovery-codes", H::regenerate_recovery_codes)
+        .post("/disable", H::disable)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    H::get_status,
+    H::enroll,
+    H::confirm,
+    H::regenerate_recovery_codes,
+    H::disable,
+))]
+pub struct Api;
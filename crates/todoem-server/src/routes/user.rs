@@ -0,0 +1,52 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::user as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        //* REQUEST *//
+        .get("/search", H::search)
+        .get("/:id/profile", H::view_user_profile)
+        .get("/:id/avatar", H::get_avatar)
+        .post("/:id/request", H::request_connection)
+        .delete("/:id/request", H::delete_request_connection)
+        .put("/:id/accept", H::accept_connection)
+        .put("/:id/reject", H::reject_connection)
+        .get("/requests/received", H::get_received_requests)
+        .get("/requests/sent", H::get_sent_requests)
+        //* PRIVACY *//
+        .post("/:id/block", H::block_user)
+        .delete("/:id/block", H::unblock_user)
+        .get("/blocked", H::get_blocked_users)
+        .get("/privacy", H::get_privacy)
+        .put("/privacy", H::update_privacy)
+        //* LISTERS *//
+        .get("/listers/page/:p", H::get_listers)
+        .get("/listers/search", H::search_listers)
+        .get("/listers/:id", H::view_lister_profile)
+        .put("/listers/:id/disconnect", H::disconnect_lister)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    H::search,
+    H::view_user_profile,
+    H::get_avatar,
+    H::request_connection,
+    H::delete_request_connection,
+    H::accept_connection,
+    H::reject_connection,
+    H::get_received_requests,
+    H::get_sent_requests,
+    H::block_user,
+    H::unblock_user,
+    H::get_blocked_users,
+    H::get_privacy,
+    H::update_privacy,
+    H::get_listers,
+    H::search_listers,
+    H::view_lister_profile,
+    H::disconnect_lister,
+))]
+pub struct Api;
@@ -0,0 +1,33 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::webhook as H;
+
+pub fn init() -> Routes {
+    Routes::new()
+        .post("/", H::create_webhook)
+        .get("/", H::get_webhooks)
+        .get("/:id", H::get_webhook)
+        .put("/:id", H::update_webhook)
+        .delete("/:id", H::delete_webhook)
+        .post("/:id/secret", H::rotate_secret)
+        .post("/:id/ping", H::ping_webhook)
+        .get("/:id/deliveries", H::get_deliveries)
+        .get("/:id/deliveries/:delivery_id", H::get_delivery)
+        .post("/:id/deliveries/:delivery_id/replay", H::replay_delivery)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    H::create_webhook,
+    H::get_webhooks,
+    H::get_webhook,
+    H::update_webhook,
+    H::delete_webhook,
+    H::rotate_secret,
+    H::ping_webhook,
+    H::get_deliveries,
+    H::get_delivery,
+    H::replay_delivery,
+))]
+pub struct Api;
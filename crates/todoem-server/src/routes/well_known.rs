@@ -0,0 +1,12 @@
+use utoipa::OpenApi;
+
+use super::Routes;
+use crate::handlers::well_known as H;
+
+pub fn init() -> Routes {
+    Routes::new().get("/jwks.json", H::jwks)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(H::jwks,))]
+pub struct Api;
@@ -0,0 +1,65 @@
+use crate::db::query::{me as Q, session as QSession};
+use crate::services::{metrics::Metrics, shutdown::Shutdown, storage::SharedStorage};
+use sqlx::PgPool;
+use std::time::Duration;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Permanently deletes accounts whose deletion grace period has run out,
+/// along with their avatars, and forgets long-ended sessions. Runs until
+/// `shutdown` is triggered.
+pub fn spawn_purger(
+    pool: PgPool,
+    storage: SharedStorage,
+    grace_days: i32,
+    metrics: Metrics,
+    shutdown: &Shutdown,
+) {
+    let stop = shutdown.clone();
+    shutdown.spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = stop.triggered() => break,
+                _ = interval.tick() => {}
+            }
+            metrics.job_ran("account_purge");
+            purge(&pool, &storage, grace_days).await;
+            let _ = QSession::purge_sessions(&pool).await;
+        }
+    });
+}
+
+pub async fn purge(pool: &PgPool, storage: &SharedStorage, grace_days: i32) {
+    let avatars = match Q::purge_deleted_accounts(pool, grace_days).await {
+        Ok(avatars) => avatars,
+        Err(_) => return,
+    };
+
+    for key in avatars {
+        if let Err(e) = storage.delete(&key).await {
+            tracing::error!("Failed to delete avatar {}: {:?}", key, e);
+        }
+    }
+}
+
+/// Upper bound on decoded avatar dimensions, to refuse decompression bombs.
+const AVATAR_MAX_SOURCE_DIMENSION: u32 = 8192;
+
+/// Decodes an uploaded image and crops/scales it to a `size`x`size` PNG.
+/// CPU bound: call from `spawn_blocking`.
+pub fn resize_avatar(bytes: &[u8], size: u32) -> Result<Vec<u8>, image::ImageError> {
+    let mut reader = image::ImageReader::new(std::io::Cursor::new(bytes)).with_guessed_format()?;
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(AVATAR_MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(AVATAR_MAX_SOURCE_DIMENSION);
+    reader.limits(limits);
+
+    let avatar = reader
+        .decode()?
+        .resize_to_fill(size, size, image::imageops::FilterType::Lanczos3);
+
+    let mut out = std::io::Cursor::new(Vec::new());
+    avatar.write_to(&mut out, image::ImageFormat::Png)?;
+    Ok(out.into_inner())
+}
@@ -0,0 +1,163 @@
+use crate::config::{Accounts, Cookies};
+use crate::db::query::session as Q;
+use crate::handlers::extract::ClientInfo;
+use crate::services::{jwt_keys::SharedJwtKeys, sessions};
+use axum_extra::extract::cookie::Cookie;
+use chrono::{Duration, Utc};
+use http::StatusCode;
+use serde::Serialize;
+use sqlx::PgPool;
+use todoem_core::errors::APIError;
+use todoem_core::models::AuthUser;
+use uuid::Uuid;
+
+pub const ACCESS_COOKIE: &str = "access_token";
+/// Carries the step-up token between the password check and the second factor.
+pub const CHALLENGE_COOKIE: &str = "two_factor";
+
+/// Audience of session tokens, the only one `middlewares::jwt` accepts.
+pub const SESSION_AUDIENCE: &str = "todoem";
+/// Audience of step-up tokens, which prove the first factor only.
+const CHALLENGE_AUDIENCE: &str = "todoem:2fa";
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    #[serde(flatten)]
+    user: &'a AuthUser,
+    /// The session an access token belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sid: Option<Uuid>,
+    aud: &'static str,
+    iat: i64,
+    exp: i64,
+}
+
+/// Signs the `access_token` cookie that `middlewares::jwt` accepts, and the
+/// step-up tokens of a sign-in waiting for its second factor.
+#[derive(Clone)]
+pub struct SessionIssuer {
+    keys: SharedJwtKeys,
+    ttl: Duration,
+    cookies: Cookies,
+}
+
+impl SessionIssuer {
+    pub fn new(keys: SharedJwtKeys, accounts: &Accounts, cookies: &Cookies) -> Self {
+        Self {
+            keys,
+            ttl: Duration::hours(accounts.session_ttl_hours),
+            cookies: cookies.clone(),
+        }
+    }
+
+    fn sign(
+        &self,
+        user: &AuthUser,
+        sid: Option<Uuid>,
+        aud: &'static str,
+        ttl: Duration,
+    ) -> Result<String, APIError> {
+        let now = Utc::now();
+        let claims = Claims {
+            user,
+            sid,
+            aud,
+            iat: now.timestamp(),
+            exp: (now + ttl).timestamp(),
+        };
+        self.keys.sign(&claims).map_err(|e| {
+            tracing::error!("Failed to sign token: {:?}", e);
+            APIError::server()
+        })
+    }
+
+    pub fn token(&self, user: &AuthUser, session_id: Uuid) -> Result<String, APIError> {
+        self.sign(user, Some(session_id), SESSION_AUDIENCE, self.ttl)
+    }
+
+    /// Records a new session for `user` and returns its `access_token` cookie.
+    pub async fn start(
+        &self,
+        pool: &PgPool,
+        user: &AuthUser,
+        client: &ClientInfo,
+        device_name: Option<&str>,
+    ) -> Result<Cookie<'static>, APIError> {
+        let id = Uuid::new_v4();
+        let device_name = match device_name {
+            Some(name) => name.to_string(),
+            None => sessions::device_name(client.user_agent.as_deref()),
+        };
+        let ip = client.ip.map(|ip| ip.to_string());
+        Q::insert_session(
+            pool,
+            id,
+            user.id,
+            &device_name,
+            client.user_agent.as_deref(),
+            ip.as_deref(),
+            Utc::now() + self.ttl,
+        )
+        .await?;
+
+        let max_age = time::Duration::seconds(self.ttl.num_seconds());
+        Ok(self.build(ACCESS_COOKIE, self.token(user, id)?, max_age))
+    }
+
+    /// A step-up token for `user`, who passed the first factor.
+    pub fn challenge(&self, user: &AuthUser) -> Result<String, APIError> {
+        self.sign(
+            user,
+            None,
+            CHALLENGE_AUDIENCE,
+            Duration::minutes(CHALLENGE_TTL_MINUTES),
+        )
+    }
+
+    pub fn challenge_ttl_secs(&self) -> i64 {
+        CHALLENGE_TTL_MINUTES * 60
+    }
+
+    pub fn challenge_cookie(&self, challenge: String) -> Cookie<'static> {
+        let max_age = time::Duration::seconds(self.challenge_ttl_secs());
+        self.build(CHALLENGE_COOKIE, challenge, max_age)
+    }
+
+    /// The user a step-up token was issued to.
+    pub fn verify_challenge(&self, challenge: &str) -> Result<AuthUser, APIError> {
+        match self.keys.verify::<AuthUser>(challenge, CHALLENGE_AUDIENCE) {
+            Ok(user) => Ok(user),
+            Err(_) => Err(APIError::new(
+                StatusCode::UNAUTHORIZED,
+                "Sign-in expired, please start again",
+            )),
+        }
+    }
+
+    /// An HTTP-only cookie for the whole site. With the default `SameSite=Lax`
+    /// it is sent on top-level navigations from elsewhere, so that sign-in
+    /// redirects carry it.
+    pub fn build(
+        &self,
+        name: &'static str,
+        value: String,
+        max_age: time::Duration,
+    ) -> Cookie<'static> {
+        let cookie = Cookie::build((name, value))
+            .path("/")
+            .http_only(true)
+            .secure(self.cookies.secure)
+            .same_site(self.cookies.same_site.into())
+            .max_age(max_age);
+        match &self.cookies.domain {
+            Some(domain) => cookie.domain(domain.clone()).build(),
+            None => cookie.build(),
+        }
+    }
+
+    /// Clears a cookie set by [`Self::build`].
+    pub fn removal(&self, name: &'static str) -> Cookie<'static> {
+        self.build(name, String::new(), time::Duration::ZERO)
+    }
+}
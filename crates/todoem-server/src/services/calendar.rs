@@ -0,0 +1,262 @@
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+pub use todoem_core::due::{resolve_local, Due};
+use todoem_core::models::task::Frequency;
+
+/// Upper bound on the occurrences expanded for a single task.
+const MAX_OCCURRENCES: usize = 1_000;
+
+/// Falls back to UTC for names the tz database does not know (anymore).
+pub fn parse_tz(name: &str) -> Tz {
+    name.parse().unwrap_or(Tz::UTC)
+}
+
+pub fn today(tz: Tz) -> NaiveDate {
+    Utc::now().with_timezone(&tz).date_naive()
+}
+
+/// The instant `date` starts in `tz`, which is not always midnight.
+pub fn start_of_day(tz: Tz, date: NaiveDate) -> DateTime<Utc> {
+    resolve_local(tz, date.and_time(NaiveTime::MIN))
+}
+
+/// `[start, end)` of the days `from..=to` in `tz`.
+pub fn day_range(tz: Tz, from: NaiveDate, to: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let end = to.succ_opt().unwrap_or(NaiveDate::MAX);
+    (start_of_day(tz, from), start_of_day(tz, end))
+}
+
+fn nth(anchor: NaiveDate, freq: &Frequency, n: u32) -> Option<NaiveDate> {
+    match freq {
+        Frequency::Daily => anchor.checked_add_days(Days::new(n as u64)),
+        Frequency::Weekly => anchor.checked_add_days(Days::new(7 * n as u64)),
+        Frequency::Monthly => anchor.checked_add_months(Months::new(n)),
+    }
+}
+
+/// A lower bound for the index of the first occurrence on or after `from`.
+fn first_index(anchor: NaiveDate, freq: &Frequency, from: NaiveDate) -> u32 {
+    let n = match freq {
+        Frequency::Daily => (from - anchor).num_days(),
+        Frequency::Weekly => (from - anchor).num_days() / 7,
+        Frequency::Monthly => {
+            (from.year() - anchor.year()) as i64 * 12 + from.month() as i64
+                - anchor.month() as i64
+                - 1
+        }
+    };
+    n.clamp(0, u32::MAX as i64) as u32
+}
+
+/// Occurrences of a task due at `due` that fall on the days `from..=to` in `tz`.
+///
+/// Repeats are computed on the local calendar: a timed task keeps its wall-clock
+/// time across DST changes, and a monthly one due on the 31st falls on the last
+/// day of shorter months.
+pub fn occurrences(
+    due: Due,
+    freq: Option<&Frequency>,
+    tz: Tz,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<Due> {
+    let Some(freq) = freq else {
+        return match (from..=to).contains(&due.date_in(tz)) {
+            true => vec![due],
+            false => vec![],
+        };
+    };
+
+    let (anchor, time) = match due {
+        Due::At(at) => {
+            let local = at.with_timezone(&tz).naive_local();
+            (local.date(), Some(local.time()))
+        }
+        Due::On(date) => (date, None),
+    };
+
+    let mut found = Vec::new();
+    let mut n = first_index(anchor, freq, from);
+    while found.len() < MAX_OCCURRENCES {
+        let Some(date) = nth(anchor, freq, n) else {
+            break;
+        };
+        if date > to {
+            break;
+        }
+        if date >= from {
+            found.push(match time {
+                Some(time) => Due::At(resolve_local(tz, date.and_time(time))),
+                None => Due::On(date),
+            });
+        }
+        n += 1;
+    }
+    found
+}
+
+/// The index of the first occurrence of a series started on `anchor` that
+/// falls after `day`.
+fn index_after(anchor: NaiveDate, freq: &Frequency, day: NaiveDate) -> Option<u32> {
+    let mut n = first_index(anchor, freq, day.succ_opt()?);
+    while nth(anchor, freq, n)? <= day {
+        n = n.checked_add(1)?;
+    }
+    Some(n)
+}
+
+/// Moves a recurring task past a completed occurrence `due`: to the first
+/// occurrence of the series started at `anchor` after both `due` and `today`.
+/// Also returns how many occurrences were passed over on the way, i.e. missed.
+///
+/// The occurrences in between are counted, not expanded, so a task left
+/// overdue for years moves on like any other.
+pub fn advance(
+    due: Due,
+    anchor: Due,
+    freq: &Frequency,
+    tz: Tz,
+    today: NaiveDate,
+) -> Option<(Due, u32)> {
+    let start = anchor.date_in(tz);
+    let due_day = due.date_in(tz);
+    let first = index_after(start, freq, due_day)?;
+    let next = index_after(start, freq, due_day.max(today))?;
+    let day = nth(start, freq, next)?;
+    let found = occurrences(anchor, Some(freq), tz, day, day);
+    found.first().map(|next_due| (*next_due, next - first))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use chrono_tz::Europe::Berlin;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn day_range_spans_dst_days() {
+        let (start, end) = day_range(Berlin, date(2024, 3, 31), date(2024, 3, 31));
+        assert_eq!(start, utc("2024-03-30T23:00:00Z"));
+        assert_eq!(end - start, Duration::hours(23));
+
+        let (start, end) = day_range(Berlin, date(2024, 10, 27), date(2024, 10, 27));
+        assert_eq!(start, utc("2024-10-26T22:00:00Z"));
+        assert_eq!(end - start, Duration::hours(25));
+    }
+
+    #[test]
+    fn daily_keeps_wall_clock_across_dst() {
+        // 09:00 in Berlin, the day before clocks go forward
+        let due = Due::At(utc("2024-03-30T08:00:00Z"));
+        let found = occurrences(
+            due,
+            Some(&Frequency::Daily),
+            Berlin,
+            date(2024, 3, 30),
+            date(2024, 4, 1),
+        );
+        assert_eq!(
+            found,
+            vec![
+                Due::At(utc("2024-03-30T08:00:00Z")),
+                Due::At(utc("2024-03-31T07:00:00Z")),
+                Due::At(utc("2024-04-01T07:00:00Z")),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_clamps_to_month_end() {
+        let found = occurrences(
+            Due::On(date(2024, 1, 31)),
+            Some(&Frequency::Monthly),
+            Berlin,
+            date(2024, 2, 1),
+            date(2024, 4, 30),
+        );
+        assert_eq!(
+            found,
+            vec![
+                Due::On(date(2024, 2, 29)),
+                Due::On(date(2024, 3, 31)),
+                Due::On(date(2024, 4, 30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn advance_skips_missed_occurrences() {
+        let due = Due::On(date(2024, 3, 1));
+        assert_eq!(
+            advance(due, due, &Frequency::Daily, Tz::UTC, date(2024, 3, 1)),
+            Some((Due::On(date(2024, 3, 2)), 0))
+        );
+        assert_eq!(
+            advance(due, due, &Frequency::Daily, Tz::UTC, date(2024, 3, 4)),
+            Some((Due::On(date(2024, 3, 5)), 3))
+        );
+        // completed ahead of time
+        assert_eq!(
+            advance(due, due, &Frequency::Weekly, Tz::UTC, date(2024, 2, 27)),
+            Some((Due::On(date(2024, 3, 8)), 0))
+        );
+    }
+
+    #[test]
+    fn advance_moves_on_from_years_overdue() {
+        let due = Due::At(utc("2019-03-01T08:00:00Z"));
+        let today = date(2024, 3, 1);
+        let (next, missed) = advance(due, due, &Frequency::Daily, Berlin, today).unwrap();
+        assert_eq!(next, Due::At(utc("2024-03-02T08:00:00Z")));
+        assert_eq!(missed as i64, (today - date(2019, 3, 1)).num_days());
+
+        let (next, missed) = advance(due, due, &Frequency::Weekly, Berlin, today).unwrap();
+        assert_eq!(next, Due::At(utc("2024-03-08T08:00:00Z")));
+        assert_eq!(missed, 261);
+    }
+
+    #[test]
+    fn advance_keeps_to_the_anchor_day() {
+        let anchor = Due::On(date(2024, 1, 31));
+        let feb = advance(
+            anchor,
+            anchor,
+            &Frequency::Monthly,
+            Tz::UTC,
+            date(2024, 1, 31),
+        );
+        assert_eq!(feb, Some((Due::On(date(2024, 2, 29)), 0)));
+        let mar = advance(
+            Due::On(date(2024, 2, 29)),
+            anchor,
+            &Frequency::Monthly,
+            Tz::UTC,
+            date(2024, 2, 29),
+        );
+        assert_eq!(mar, Some((Due::On(date(2024, 3, 31)), 0)));
+    }
+
+    #[test]
+    fn weekly_starts_inside_range() {
+        let found = occurrences(
+            Due::On(date(2024, 1, 1)),
+            Some(&Frequency::Weekly),
+            Tz::UTC,
+            date(2024, 6, 1),
+            date(2024, 6, 14),
+        );
+        assert_eq!(
+            found,
+            vec![Due::On(date(2024, 6, 3)), Due::On(date(2024, 6, 10))]
+        );
+    }
+}
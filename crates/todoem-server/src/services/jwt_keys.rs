@@ -0,0 +1,385 @@
+use crate::config::{Jwt, JwtAlgorithm};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::errors::{Error, ErrorKind};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use simple_asn1::{from_der, oid, ASN1Block};
+use std::{fs, path::Path, sync::Arc};
+
+const MIN_RSA_BITS: usize = 2048;
+
+struct VerifyingKey {
+    /// `None` for the HMAC secret, whose tokens carry no `kid`.
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+    /// How the key is published, if it is public.
+    jwk: Option<Jwk>,
+}
+
+/// The keys that sign and verify session and step-up tokens, parsed once at
+/// startup from [`Jwt`].
+///
+/// New tokens are signed with one key; any of the verification keys may have
+/// signed a token presented, picked by its `kid`. The public ones are
+/// published at `/.well-known/jwks.json`.
+pub struct JwtKeys {
+    header: Header,
+    signing: EncodingKey,
+    verifying: Vec<VerifyingKey>,
+}
+
+pub type SharedJwtKeys = Arc<JwtKeys>;
+
+impl std::fmt::Debug for JwtKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let kids: Vec<_> = self.verifying.iter().map(|k| &k.kid).collect();
+        f.debug_struct("JwtKeys")
+            .field("algorithm", &self.header.alg)
+            .field("kid", &self.header.kid)
+            .field("verifying", &kids)
+            .finish_non_exhaustive()
+    }
+}
+
+impl JwtKeys {
+    pub fn load(jwt: &Jwt) -> Result<Self, String> {
+        let mut verifying = Vec::new();
+        if !jwt.secret_key.is_empty() {
+            verifying.push(VerifyingKey {
+                kid: None,
+                algorithm: hmac_algorithm(jwt),
+                key: DecodingKey::from_secret(jwt.secret_key.as_ref()),
+                jwk: None,
+            });
+        }
+
+        let (header, signing) = match jwt.algorithm {
+            JwtAlgorithm::Hs256 | JwtAlgorithm::Hs384 | JwtAlgorithm::Hs512 => (
+                Header::new(jwt.algorithm.into()),
+                EncodingKey::from_secret(jwt.secret_key.as_ref()),
+            ),
+            JwtAlgorithm::Rs256 | JwtAlgorithm::EdDsa => {
+                let (Some(kid), Some(private), Some(public)) =
+                    (&jwt.key_id, &jwt.private_key_file, &jwt.public_key_file)
+                else {
+                    return Err("jwt.key_id, jwt.private_key_file and jwt.public_key_file are required for RS256 and EdDSA".to_string());
+                };
+                let public = VerifyingKey::public(kid, jwt.algorithm, public)?;
+                let pem = read(private)?;
+                let signing = match jwt.algorithm {
+                    JwtAlgorithm::Rs256 => EncodingKey::from_rsa_pem(&pem),
+                    _ => EncodingKey::from_ed_pem(&pem),
+                }
+                .map_err(|e| format!("{}: {}", private.display(), e))?;
+                let mut header = Header::new(jwt.algorithm.into());
+                header.kid = Some(kid.clone());
+                if !public.verifies(&header, &signing) {
+                    return Err(format!(
+                        "{} is not the public key of {}",
+                        jwt.public_key_file.as_ref().unwrap().display(),
+                        private.display()
+                    ));
+                }
+                verifying.push(public);
+                (header, signing)
+            }
+        };
+
+        for key in &jwt.verification_keys {
+            if verifying
+                .iter()
+                .any(|k| k.kid.as_ref() == Some(&key.key_id))
+            {
+                return Err(format!("jwt key id {} is used twice", key.key_id));
+            }
+            verifying.push(VerifyingKey::public(
+                &key.key_id,
+                key.algorithm,
+                &key.public_key_file,
+            )?);
+        }
+
+        Ok(Self {
+            header,
+            signing,
+            verifying,
+        })
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, Error> {
+        encode(&self.header, claims, &self.signing)
+    }
+
+    /// The claims of a token signed by one of the verification keys for
+    /// `audience`.
+    pub fn verify<T: DeserializeOwned>(&self, token: &str, audience: &str) -> Result<T, Error> {
+        let header = decode_header(token)?;
+        let key = self
+            .verifying
+            .iter()
+            .find(|k| k.kid == header.kid && k.algorithm == header.alg)
+            .ok_or(Error::from(ErrorKind::InvalidSignature))?;
+        let mut validation = Validation::new(key.algorithm);
+        validation.set_audience(&[audience]);
+        Ok(decode::<T>(token, &key.key, &validation)?.claims)
+    }
+
+    /// The public verification keys, as served at `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .verifying
+                .iter()
+                .filter_map(|k| k.jwk.clone())
+                .collect(),
+        }
+    }
+}
+
+/// The HMAC secret verifies with the configured HS algorithm, or HS256 when
+/// it is only kept to accept tokens from before a switch to RS256 or EdDSA.
+fn hmac_algorithm(jwt: &Jwt) -> Algorithm {
+    match jwt.algorithm {
+        JwtAlgorithm::Rs256 | JwtAlgorithm::EdDsa => Algorithm::HS256,
+        hmac => hmac.into(),
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))
+}
+
+impl VerifyingKey {
+    /// Reads an RSA or Ed25519 public key from a PEM file.
+    fn public(kid: &str, algorithm: JwtAlgorithm, path: &Path) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("{}: {}", path.display(), reason);
+        let pem = pem::parse(read(path)?).map_err(|e| invalid(&e.to_string()))?;
+        let spki = match pem.tag() {
+            "PUBLIC KEY" => subject_public_key(pem.contents()),
+            // PKCS#1, which holds an RSA key without the algorithm around it
+            "RSA PUBLIC KEY" => Some((PublicKeyKind::Rsa, pem.contents().to_vec())),
+            _ => None,
+        };
+        let (kind, key) = spki.ok_or_else(|| invalid("not a PEM encoded public key"))?;
+
+        let params = match (algorithm, kind) {
+            (JwtAlgorithm::Rs256, PublicKeyKind::Rsa) => {
+                let (n, e) = rsa_components(&key).ok_or_else(|| invalid("malformed RSA key"))?;
+                if n.len() * 8 < MIN_RSA_BITS {
+                    return Err(invalid("RSA keys need at least 2048 bits"));
+                }
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(n),
+                    e: URL_SAFE_NO_PAD.encode(e),
+                })
+            }
+            (JwtAlgorithm::EdDsa, PublicKeyKind::Ed25519) => {
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(key),
+                })
+            }
+            (JwtAlgorithm::Rs256 | JwtAlgorithm::EdDsa, _) => {
+                return Err(invalid(&format!("not a key for {}", algorithm)))
+            }
+            _ => return Err(invalid("HMAC keys are not read from files")),
+        };
+
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(match algorithm {
+                    JwtAlgorithm::Rs256 => KeyAlgorithm::RS256,
+                    _ => KeyAlgorithm::EdDSA,
+                }),
+                key_id: Some(kid.to_string()),
+                ..Default::default()
+            },
+            algorithm: params,
+        };
+        Ok(Self {
+            kid: Some(kid.to_string()),
+            algorithm: algorithm.into(),
+            key: DecodingKey::from_jwk(&jwk).map_err(|e| invalid(&e.to_string()))?,
+            jwk: Some(jwk),
+        })
+    }
+
+    /// Whether a token signed with `signing` checks out against this key.
+    fn verifies(&self, header: &Header, signing: &EncodingKey) -> bool {
+        let mut validation = Validation::new(self.algorithm);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        encode(header, &serde_json::json!({}), signing)
+            .and_then(|token| decode::<serde_json::Value>(&token, &self.key, &validation))
+            .is_ok()
+    }
+}
+
+enum PublicKeyKind {
+    Rsa,
+    Ed25519,
+}
+
+/// The algorithm and key bits of a DER encoded `SubjectPublicKeyInfo`
+/// (RFC 5280), the usual `PUBLIC KEY` PEM.
+fn subject_public_key(der: &[u8]) -> Option<(PublicKeyKind, Vec<u8>)> {
+    let blocks = from_der(der).ok()?;
+    let [ASN1Block::Sequence(_, spki)] = blocks.as_slice() else {
+        return None;
+    };
+    let [ASN1Block::Sequence(_, algorithm), ASN1Block::BitString(_, _, key)] = spki.as_slice()
+    else {
+        return None;
+    };
+    let Some(ASN1Block::ObjectIdentifier(_, oid)) = algorithm.first() else {
+        return None;
+    };
+    let kind = if *oid == oid!(1, 2, 840, 113549, 1, 1, 1) {
+        PublicKeyKind::Rsa
+    } else if *oid == oid!(1, 3, 101, 112) {
+        PublicKeyKind::Ed25519
+    } else {
+        return None;
+    };
+    Some((kind, key.clone()))
+}
+
+/// Modulus and exponent of a DER encoded PKCS#1 `RSAPublicKey`, big-endian.
+fn rsa_components(der: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let blocks = from_der(der).ok()?;
+    let [ASN1Block::Sequence(_, parts)] = blocks.as_slice() else {
+        return None;
+    };
+    let [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] = parts.as_slice() else {
+        return None;
+    };
+    Some((n.to_bytes_be().1, e.to_bytes_be().1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::VerificationKey;
+    use std::path::PathBuf;
+
+    const AUDIENCE: &str = "todoem";
+
+    fn key(name: &str) -> Option<PathBuf> {
+        Some(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/keys")
+                .join(name),
+        )
+    }
+
+    fn rsa(kid: &str, name: &str) -> Jwt {
+        Jwt {
+            algorithm: JwtAlgorithm::Rs256,
+            key_id: Some(kid.to_string()),
+            private_key_file: key(&format!("{}.pem", name)),
+            public_key_file: key(&format!("{}.pub.pem", name)),
+            ..Default::default()
+        }
+    }
+
+    fn claims() -> serde_json::Value {
+        serde_json::json!({ "aud": AUDIENCE, "exp": chrono::Utc::now().timestamp() + 60 })
+    }
+
+    #[test]
+    fn rotated_keys_keep_verifying() {
+        let before = JwtKeys::load(&rsa("2026-04", "rsa-a")).unwrap();
+        let old = before.sign(&claims()).unwrap();
+        assert_eq!(decode_header(&old).unwrap().kid.as_deref(), Some("2026-04"));
+
+        let after = JwtKeys::load(&Jwt {
+            verification_keys: vec![VerificationKey {
+                key_id: "2026-04".to_string(),
+                algorithm: JwtAlgorithm::Rs256,
+                public_key_file: key("rsa-a.pub.pem").unwrap(),
+            }],
+            ..rsa("2026-10", "rsa-b")
+        })
+        .unwrap();
+        let new = after.sign(&claims()).unwrap();
+        assert!(after.verify::<serde_json::Value>(&old, AUDIENCE).is_ok());
+        assert!(after.verify::<serde_json::Value>(&new, AUDIENCE).is_ok());
+        assert!(after.verify::<serde_json::Value>(&new, "other").is_err());
+        // once retired, the old key is gone
+        let retired = JwtKeys::load(&rsa("2026-10", "rsa-b")).unwrap();
+        assert!(retired.verify::<serde_json::Value>(&old, AUDIENCE).is_err());
+
+        let jwks = serde_json::to_value(after.jwks()).unwrap();
+        let kids: Vec<_> = jwks["keys"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|k| &k["kid"])
+            .collect();
+        assert_eq!(kids, ["2026-10", "2026-04"]);
+        assert_eq!(jwks["keys"][0]["kty"], "RSA");
+        assert_eq!(jwks["keys"][0]["e"], "AQAB");
+        assert_eq!(jwks["keys"][0]["use"], "sig");
+    }
+
+    #[test]
+    fn hmac_tokens_outlive_a_switch_to_eddsa() {
+        let secret = "xqcknM1ICw65eHLPB7+0UPgT2sdayTvIm0bRKhA+Oug=".to_string();
+        let hmac = JwtKeys::load(&Jwt {
+            secret_key: secret.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+        let old = hmac.sign(&claims()).unwrap();
+        assert!(hmac.jwks().keys.is_empty());
+
+        let eddsa = JwtKeys::load(&Jwt {
+            algorithm: JwtAlgorithm::EdDsa,
+            secret_key: secret,
+            key_id: Some("ed-1".to_string()),
+            private_key_file: key("ed25519.pem"),
+            public_key_file: key("ed25519.pub.pem"),
+            ..Default::default()
+        })
+        .unwrap();
+        let new = eddsa.sign(&claims()).unwrap();
+        assert!(eddsa.verify::<serde_json::Value>(&old, AUDIENCE).is_ok());
+        assert!(eddsa.verify::<serde_json::Value>(&new, AUDIENCE).is_ok());
+        // not accepted as an HMAC token signed with the public key
+        assert!(hmac.verify::<serde_json::Value>(&new, AUDIENCE).is_err());
+
+        let jwks = serde_json::to_value(eddsa.jwks()).unwrap();
+        assert_eq!(jwks["keys"][0]["kty"], "OKP");
+        assert_eq!(jwks["keys"][0]["crv"], "Ed25519");
+        assert_eq!(jwks["keys"][0]["alg"], "EdDSA");
+    }
+
+    #[test]
+    fn refuses_mismatched_keys() {
+        let err = JwtKeys::load(&Jwt {
+            public_key_file: key("rsa-b.pub.pem"),
+            ..rsa("2026-04", "rsa-a")
+        })
+        .err()
+        .unwrap();
+        assert!(err.contains("is not the public key of"), "{}", err);
+
+        let err = JwtKeys::load(&Jwt {
+            algorithm: JwtAlgorithm::EdDsa,
+            ..rsa("2026-04", "rsa-a")
+        })
+        .err()
+        .unwrap();
+        assert!(err.contains("not a key for EdDSA"), "{}", err);
+    }
+}
@@ -0,0 +1,146 @@
+pub mod queue;
+mod smtp;
+pub mod templates;
+
+use crate::config::{Mail, MailTransport};
+use axum::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use std::{fmt, path::PathBuf, sync::Arc, sync::Mutex};
+
+pub use smtp::SmtpMailer;
+
+/// A plain text message ready to be sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+impl fmt::Display for MailError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<std::io::Error> for MailError {
+    fn from(e: std::io::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: &Message) -> Result<(), MailError>;
+}
+
+pub type SharedMailer = Arc<dyn Mailer>;
+
+pub fn from_config(config: &Mail) -> SharedMailer {
+    match config.transport {
+        MailTransport::Smtp => Arc::new(SmtpMailer::new(config)),
+        MailTransport::File => Arc::new(FileMailer::new(&config.file_dir, &config.from)),
+        MailTransport::Memory => Arc::new(MemoryMailer::default()),
+    }
+}
+
+/// The address part of `Name <addr>`.
+fn address(mailbox: &str) -> &str {
+    match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox.trim(),
+    }
+}
+
+/// RFC 2047 encoding for header values that are not plain ASCII.
+fn encode_header(value: &str) -> String {
+    match value.is_ascii() {
+        true => value.to_string(),
+        false => format!("=?UTF-8?B?{}?=", STANDARD.encode(value)),
+    }
+}
+
+/// Renders `message` as an RFC 5322 document with CRLF line endings and a
+/// base64 body, which any server accepts regardless of its 8BITMIME support.
+fn format_message(from: &str, message: &Message) -> Result<String, MailError> {
+    let headers = [from, &message.to, &message.subject];
+    if headers.iter().any(|h| h.contains(['\r', '\n'])) {
+        return Err(MailError("line break in a header".to_string()));
+    }
+
+    let domain = address(from).rsplit('@').next().unwrap_or("localhost");
+    let body = STANDARD.encode(message.body.replace('\n', "\r\n"));
+    let mut out = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\n\
+         MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\
+         Content-Transfer-Encoding: base64\r\n\r\n",
+        from,
+        message.to,
+        encode_header(&message.subject),
+        Utc::now().to_rfc2822(),
+        uuid::Uuid::new_v4(),
+        domain,
+    );
+    for line in body.as_bytes().chunks(76) {
+        out.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        out.push_str("\r\n");
+    }
+    Ok(out)
+}
+
+/// Writes every message to an `.eml` file for development.
+pub struct FileMailer {
+    dir: PathBuf,
+    from: String,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>, from: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            from: from.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: &Message) -> Result<(), MailError> {
+        let eml = format_message(&self.from, message)?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S"),
+            uuid::Uuid::new_v4()
+        ));
+        tokio::fs::write(&path, eml).await?;
+        tracing::info!("Wrote email {:?} to {}", message.subject, path.display());
+        Ok(())
+    }
+}
+
+/// Keeps sent messages in memory, for tests.
+#[derive(Default)]
+pub struct MemoryMailer {
+    sent: Mutex<Vec<Message>>,
+}
+
+impl MemoryMailer {
+    /// Takes the messages sent so far.
+    #[cfg(test)]
+    pub fn take(&self) -> Vec<Message> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+}
+
+#[async_trait]
+impl Mailer for MemoryMailer {
+    async fn send(&self, message: &Message) -> Result<(), MailError> {
+        self.sent.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
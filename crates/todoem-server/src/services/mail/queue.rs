@@ -0,0 +1,180 @@
+use super::{templates::Template, Message, SharedMailer};
+use crate::config::Mail;
+use crate::db::query::{auth as AQ, mail as Q};
+use crate::services::{metrics::Metrics, shutdown::Shutdown, tokens};
+use rand::Rng;
+use sqlx::PgPool;
+use std::time::Duration;
+use todoem_core::errors::APIError;
+use todoem_core::models::email::{EmailTokenPurpose, OutboundEmail};
+use uuid::Uuid;
+
+/// Emails are sent from the `outbound_emails` table rather than inline, so a
+/// slow or unavailable relay never holds up a request and nothing is lost on
+/// restart.
+pub async fn enqueue(pool: &PgPool, message: &Message) -> Result<(), APIError> {
+    Q::enqueue_email(pool, message).await
+}
+
+/// Emails `user_id` a link that verifies `email` as their address.
+pub async fn send_verification(
+    pool: &PgPool,
+    config: &Mail,
+    user_id: Uuid,
+    username: &str,
+    email: &str,
+) -> Result<(), APIError> {
+    let token = hex::encode(rand::random::<[u8; 32]>());
+    let ttl_secs = (config.verify_ttl_hours * 60 * 60) as f64;
+    AQ::insert_email_token(
+        pool,
+        user_id,
+        EmailTokenPurpose::VerifyEmail,
+        email,
+        &tokens::hash(&token),
+        ttl_secs,
+    )
+    .await?;
+
+    let link = format!("{}/verify-email?token={}", config.link_url, token);
+    let hours = config.verify_ttl_hours.to_string();
+    let message = Template::VerifyEmail.render(
+        email,
+        &[
+            ("username", username),
+            ("email", email),
+            ("link", &link),
+            ("hours", &hours),
+        ],
+    );
+    enqueue(pool, &message).await
+}
+
+/// Emails a single-use link to choose a new password.
+pub async fn send_password_reset(
+    pool: &PgPool,
+    config: &Mail,
+    user_id: Uuid,
+    username: &str,
+    email: &str,
+) -> Result<(), APIError> {
+    let token = hex::encode(rand::random::<[u8; 32]>());
+    let ttl_secs = (config.reset_ttl_minutes * 60) as f64;
+    AQ::insert_email_token(
+        pool,
+        user_id,
+        EmailTokenPurpose::ResetPassword,
+        email,
+        &tokens::hash(&token),
+        ttl_secs,
+    )
+    .await?;
+
+    let link = format!("{}/reset-password?token={}", config.link_url, token);
+    let minutes = config.reset_ttl_minutes.to_string();
+    let message = Template::ResetPassword.render(
+        email,
+        &[
+            ("username", username),
+            ("link", &link),
+            ("minutes", &minutes),
+        ],
+    );
+    enqueue(pool, &message).await
+}
+
+/// Label of the sender in the job metrics.
+const JOB: &str = "mail";
+
+/// Sends due emails from the queue.
+pub struct MailQueue {
+    pool: PgPool,
+    mailer: SharedMailer,
+    config: Mail,
+    metrics: Metrics,
+}
+
+impl MailQueue {
+    pub fn new(pool: PgPool, mailer: SharedMailer, config: Mail, metrics: Metrics) -> Self {
+        Self {
+            pool,
+            mailer,
+            config,
+            metrics,
+        }
+    }
+
+    /// Doubles from a minute up to an hour, with some jitter.
+    fn backoff(&self, attempts: i32) -> f64 {
+        let exp = attempts.saturating_sub(1).clamp(0, 6) as u32;
+        let delay = (60u64 << exp).min(60 * 60);
+        delay as f64 * rand::thread_rng().gen_range(0.9..1.1)
+    }
+
+    /// Sends one batch of due emails and returns how many were attempted.
+    pub async fn send_due(&self) -> usize {
+        self.metrics.job_ran(JOB);
+        // the SMTP conversation times out after 30 seconds
+        let lease = 90.0;
+        let due = match Q::claim_due_emails(&self.pool, self.config.batch_size, lease).await {
+            Ok(due) => due,
+            Err(_) => return 0,
+        };
+
+        let count = due.len();
+        for email in due {
+            self.send(email).await;
+        }
+        count
+    }
+
+    async fn send(&self, email: OutboundEmail) {
+        let message = Message {
+            to: email.to_address,
+            subject: email.subject,
+            body: email.body,
+        };
+        let error = self.mailer.send(&message).await.err().map(|e| e.0);
+
+        let retry_in = match error.is_none() || email.attempts >= self.config.max_attempts {
+            true => None,
+            false => Some(self.backoff(email.attempts)),
+        };
+        if let Some(error) = &error {
+            tracing::warn!(
+                "Email {} failed (attempt {}): {}",
+                email.id,
+                email.attempts,
+                error
+            );
+        }
+
+        self.metrics
+            .job_attempt(JOB, error.is_none(), retry_in.is_some());
+        let _ = Q::record_email_attempt(&self.pool, email.id, error.as_deref(), retry_in).await;
+    }
+}
+
+/// Polls the mail queue until `shutdown` is triggered, finishing the batch
+/// in flight.
+pub fn spawn_sender(
+    pool: PgPool,
+    mailer: SharedMailer,
+    config: Mail,
+    metrics: Metrics,
+    shutdown: &Shutdown,
+) {
+    let idle = Duration::from_millis(config.poll_interval_ms);
+    let queue = MailQueue::new(pool, mailer, config, metrics);
+    let stop = shutdown.clone();
+    shutdown.spawn(async move {
+        while !stop.is_triggered() {
+            if queue.send_due().await == 0 {
+                tokio::select! {
+                    _ = stop.triggered() => {}
+                    _ = tokio::time::sleep(idle) => {}
+                }
+            }
+        }
+    });
+}
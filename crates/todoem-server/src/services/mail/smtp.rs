@@ -0,0 +1,149 @@
+use super::{address, format_message, MailError, Mailer, Message};
+use crate::config::{Mail, SmtpTls};
+use axum::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_native_tls::{native_tls, TlsConnector};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// One SMTP conversation. Replies are read line by line; a reply ends with the
+/// line whose code is followed by a space rather than a dash.
+struct Connection {
+    stream: BufReader<Box<dyn Stream>>,
+}
+
+impl Connection {
+    async fn reply(&mut self, expect: &[u16]) -> Result<Vec<String>, MailError> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err(MailError("connection closed by server".to_string()));
+            }
+            let line = line.trim_end().to_string();
+            let last = line.as_bytes().get(3) != Some(&b'-');
+            lines.push(line);
+            if last {
+                break;
+            }
+        }
+
+        let code = lines
+            .last()
+            .and_then(|l| l.get(..3))
+            .and_then(|c| c.parse::<u16>().ok());
+        match code {
+            Some(code) if expect.contains(&code) => Ok(lines),
+            _ => Err(MailError(format!(
+                "unexpected reply: {}",
+                lines.join(" | ")
+            ))),
+        }
+    }
+
+    async fn command(&mut self, command: &str, expect: &[u16]) -> Result<Vec<String>, MailError> {
+        let stream = self.stream.get_mut();
+        stream.write_all(command.as_bytes()).await?;
+        stream.write_all(b"\r\n").await?;
+        stream.flush().await?;
+        self.reply(expect).await
+    }
+}
+
+/// Sends mail through an SMTP relay, with STARTTLS or implicit TLS and
+/// `AUTH PLAIN` when credentials are configured.
+pub struct SmtpMailer {
+    host: String,
+    port: u16,
+    tls: SmtpTls,
+    credentials: Option<(String, String)>,
+    from: String,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &Mail) -> Self {
+        Self {
+            host: config.smtp_host.clone(),
+            port: config.smtp_port,
+            tls: config.smtp_tls,
+            credentials: config
+                .smtp_username
+                .clone()
+                .zip(config.smtp_password.clone()),
+            from: config.from.clone(),
+        }
+    }
+
+    async fn wrap_tls(&self, stream: Box<dyn Stream>) -> Result<Box<dyn Stream>, MailError> {
+        let connector = native_tls::TlsConnector::new().map_err(|e| MailError(e.to_string()))?;
+        let stream = TlsConnector::from(connector)
+            .connect(&self.host, stream)
+            .await
+            .map_err(|e| MailError(e.to_string()))?;
+        Ok(Box::new(stream))
+    }
+
+    async fn converse(&self, message: &Message) -> Result<(), MailError> {
+        let eml = format_message(&self.from, message)?;
+
+        let mut stream: Box<dyn Stream> =
+            Box::new(TcpStream::connect((self.host.as_str(), self.port)).await?);
+        if self.tls == SmtpTls::Tls {
+            stream = self.wrap_tls(stream).await?;
+        }
+        let mut conn = Connection {
+            stream: BufReader::new(stream),
+        };
+        conn.reply(&[220]).await?;
+        conn.command("EHLO localhost", &[250]).await?;
+
+        if self.tls == SmtpTls::StartTls {
+            conn.command("STARTTLS", &[220]).await?;
+            let stream = self.wrap_tls(conn.stream.into_inner()).await?;
+            conn = Connection {
+                stream: BufReader::new(stream),
+            };
+            conn.command("EHLO localhost", &[250]).await?;
+        }
+
+        if let Some((username, password)) = &self.credentials {
+            let plain = STANDARD.encode(format!("\0{}\0{}", username, password));
+            conn.command(&format!("AUTH PLAIN {}", plain), &[235])
+                .await?;
+        }
+
+        conn.command(&format!("MAIL FROM:<{}>", address(&self.from)), &[250])
+            .await?;
+        conn.command(&format!("RCPT TO:<{}>", address(&message.to)), &[250, 251])
+            .await?;
+        conn.command("DATA", &[354]).await?;
+        // a line starting with a dot would end the data early: double it
+        let data: String = eml
+            .split_inclusive("\r\n")
+            .map(|line| match line.starts_with('.') {
+                true => format!(".{}", line),
+                false => line.to_string(),
+            })
+            .collect();
+        conn.command(&format!("{}.", data), &[250]).await?;
+        // the message is accepted at this point, whatever happens next
+        let _ = conn.command("QUIT", &[221]).await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: &Message) -> Result<(), MailError> {
+        match tokio::time::timeout(TIMEOUT, self.converse(message)).await {
+            Ok(sent) => sent,
+            Err(_) => Err(MailError("timed out".to_string())),
+        }
+    }
+}
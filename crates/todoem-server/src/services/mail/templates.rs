@@ -0,0 +1,58 @@
+use super::Message;
+
+/// The emails todoem sends. Each template is a subject line followed by the
+/// body, with `{{name}}` placeholders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    VerifyEmail,
+    ResetPassword,
+    PasswordChanged,
+}
+
+impl Template {
+    fn source(&self) -> &'static str {
+        match self {
+            Template::VerifyEmail => include_str!("templates/verify_email.txt"),
+            Template::ResetPassword => include_str!("templates/reset_password.txt"),
+            Template::PasswordChanged => include_str!("templates/password_changed.txt"),
+        }
+    }
+
+    /// Fills in the placeholders. Every placeholder must be given a value.
+    pub fn render(&self, to: &str, vars: &[(&str, &str)]) -> Message {
+        let mut text = self.source().to_string();
+        for (name, value) in vars {
+            text = text.replace(&format!("{{{{{}}}}}", name), value);
+        }
+        debug_assert!(!text.contains("{{"), "unfilled placeholder in {:?}", self);
+
+        let (subject, body) = text.split_once('\n').unwrap_or((&text, ""));
+        Message {
+            to: to.to_string(),
+            subject: subject.trim().to_string(),
+            body: body.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_subject_and_body() {
+        let message = Template::ResetPassword.render(
+            "nami@op.co",
+            &[
+                ("username", "nami"),
+                ("link", "https://todoem.test/reset?token=abc"),
+                ("minutes", "60"),
+            ],
+        );
+        assert_eq!(message.to, "nami@op.co");
+        assert_eq!(message.subject, "Reset your password");
+        assert!(message.body.starts_with("Hi nami,"));
+        assert!(message.body.contains("https://todoem.test/reset?token=abc"));
+        assert!(message.body.contains("expires in 60 minutes"));
+    }
+}
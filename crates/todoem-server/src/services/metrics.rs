@@ -0,0 +1,174 @@
+use http::{Method, StatusCode};
+use prometheus::{
+    exponential_buckets, Encoder, Gauge, HistogramOpts, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+
+/// Longest a scrape waits for a database connection when sampling how long
+/// requests wait for one.
+const ACQUIRE_SAMPLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The Prometheus metrics of the process, all prefixed with `todoem_`.
+/// Cheap to clone, clones share the same values.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    db_connections: IntGaugeVec,
+    db_max_connections: IntGauge,
+    db_acquire: Gauge,
+    db_queries: HistogramVec,
+    job_runs: IntCounterVec,
+    job_items: IntCounterVec,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("todoem".to_string()), None).expect("valid metrics prefix");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by matched route"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time to answer HTTP requests, by matched route",
+            ),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let db_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Open database connections"),
+            &["state"],
+        )
+        .unwrap();
+        let db_max_connections = IntGauge::new(
+            "db_pool_max_connections",
+            "Most database connections the pool opens",
+        )
+        .unwrap();
+        let db_acquire = Gauge::new(
+            "db_pool_acquire_seconds",
+            "Time the last scrape waited for a database connection",
+        )
+        .unwrap();
+        let db_queries = HistogramVec::new(
+            HistogramOpts::new(
+                "db_query_duration_seconds",
+                "Time spent in db::query functions",
+            )
+            .buckets(exponential_buckets(0.0005, 2.0, 14).unwrap()),
+            &["query"],
+        )
+        .unwrap();
+        let job_runs = IntCounterVec::new(
+            Opts::new("job_runs_total", "Polls of the background jobs"),
+            &["job"],
+        )
+        .unwrap();
+        let job_items = IntCounterVec::new(
+            Opts::new(
+                "job_items_total",
+                "Items handled by the background jobs, by outcome",
+            ),
+            &["job", "outcome"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry.register(Box::new(http_duration.clone())).unwrap();
+        registry.register(Box::new(db_connections.clone())).unwrap();
+        registry
+            .register(Box::new(db_max_connections.clone()))
+            .unwrap();
+        registry.register(Box::new(db_acquire.clone())).unwrap();
+        registry.register(Box::new(db_queries.clone())).unwrap();
+        registry.register(Box::new(job_runs.clone())).unwrap();
+        registry.register(Box::new(job_items.clone())).unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_duration,
+            db_connections,
+            db_max_connections,
+            db_acquire,
+            db_queries,
+            job_runs,
+            job_items,
+        }
+    }
+
+    /// `route` is the matched path pattern, like `/api/task/:id`, so that
+    /// ids do not each get their own series.
+    pub fn observe_request(
+        &self,
+        method: &Method,
+        route: &str,
+        status: StatusCode,
+        elapsed: Duration,
+    ) {
+        let labels = [method.as_str(), route, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// `query` names the function, like `task::select_task`.
+    pub fn observe_query(&self, query: &str, elapsed: Duration) {
+        self.db_queries
+            .with_label_values(&[query])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn job_ran(&self, job: &str) {
+        self.job_runs.with_label_values(&[job]).inc();
+    }
+
+    /// Counts an attempt at an item, like a webhook delivery: it succeeded,
+    /// failed and will be retried, or failed for the last time.
+    pub fn job_attempt(&self, job: &str, succeeded: bool, retried: bool) {
+        let outcome = match (succeeded, retried) {
+            (true, _) => "succeeded",
+            (false, true) => "retried",
+            (false, false) => "dropped",
+        };
+        self.job_items.with_label_values(&[job, outcome]).inc();
+    }
+
+    /// Samples the pool and renders everything in the Prometheus text format.
+    pub async fn render(&self, pool: &PgPool) -> String {
+        let size = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+        self.db_connections.with_label_values(&["idle"]).set(idle);
+        self.db_connections
+            .with_label_values(&["active"])
+            .set(size - idle);
+        self.db_max_connections
+            .set(pool.options().get_max_connections() as i64);
+
+        let started = Instant::now();
+        let acquired = tokio::time::timeout(ACQUIRE_SAMPLE_TIMEOUT, pool.acquire()).await;
+        self.db_acquire.set(started.elapsed().as_secs_f64());
+        drop(acquired);
+
+        let mut buf = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buf) {
+            tracing::error!("Failed to encode metrics: {:?}", e);
+        }
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
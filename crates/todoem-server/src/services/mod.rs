@@ -0,0 +1,14 @@
+pub mod accounts;
+pub mod auth;
+pub mod calendar;
+pub mod jwt_keys;
+pub mod mail;
+pub mod metrics;
+pub mod oidc;
+pub mod passwords;
+pub mod sessions;
+pub mod shutdown;
+pub mod storage;
+pub mod tokens;
+pub mod two_factor;
+pub mod webhooks;
@@ -0,0 +1,450 @@
+use crate::config::{Limits, Oidc, OidcProvider};
+use crate::db::query::identity as Q;
+use crate::middlewares::version::ApiVersion;
+use crate::services::tokens;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use http::StatusCode;
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use todoem_core::errors::APIError;
+use todoem_core::models::identity::{PendingLogin, Signup};
+use todoem_core::models::AuthUser;
+use uuid::Uuid;
+
+/// Binds a sign-in to the browser that started it.
+pub const STATE_COOKIE: &str = "oidc_state";
+/// Carries a first sign-in to the username choice.
+pub const SIGNUP_COOKIE: &str = "oidc_signup";
+
+/// Signature algorithms accepted on ID tokens. Shared-secret ones are not: the
+/// client secret is no proof that the provider issued a token.
+const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// A provider's keys are refetched for an unknown `kid` at most this often.
+const JWKS_REFRESH: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+struct Discovered {
+    metadata: Metadata,
+    jwks: RwLock<(JwkSet, Instant)>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IdClaims {
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+/// What a verified callback amounts to.
+pub enum SignIn {
+    Existing(AuthUser),
+    /// Linked to the signed-in user who started it.
+    Linked,
+    /// A first sign-in, identified by this token until a username is chosen.
+    Signup(String),
+}
+
+fn random_token() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// The S256 PKCE challenge of `verifier`.
+pub fn challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+fn unreachable(e: impl std::fmt::Debug) -> APIError {
+    tracing::error!("OIDC provider request failed: {:?}", e);
+    APIError::new(
+        StatusCode::BAD_GATEWAY,
+        "The identity provider could not be reached",
+    )
+}
+
+fn rejected(e: impl std::fmt::Debug) -> APIError {
+    tracing::warn!("Rejected OIDC sign-in: {:?}", e);
+    APIError::new(
+        StatusCode::UNAUTHORIZED,
+        "The sign-in could not be verified",
+    )
+}
+
+/// Talks to the configured providers. Their discovery documents and keys are
+/// fetched on first use and kept.
+pub struct OidcClient {
+    http: reqwest::Client,
+    config: Oidc,
+    discovered: RwLock<HashMap<String, Arc<Discovered>>>,
+}
+
+pub type SharedOidc = Arc<OidcClient>;
+
+impl OidcClient {
+    pub fn new(config: Oidc) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(concat!("todoem/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("failed to build OIDC client");
+        Self {
+            http,
+            config,
+            discovered: RwLock::default(),
+        }
+    }
+
+    pub fn provider_names(&self) -> Vec<&str> {
+        self.config
+            .providers
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect()
+    }
+
+    /// Lifetime of pending sign-ins and signups.
+    pub fn flow_ttl_secs(&self) -> f64 {
+        self.config.flow_ttl_secs as f64
+    }
+
+    fn provider(&self, name: &str) -> Result<&OidcProvider, APIError> {
+        self.config.provider(name).ok_or_else(APIError::not_found)
+    }
+
+    /// Registered with the providers; the unversioned one stops working at
+    /// the sunset of `/api`.
+    fn callback_url(&self, provider: &OidcProvider) -> String {
+        format!(
+            "{}{}/auth/oidc/{}/callback",
+            self.config.public_url,
+            ApiVersion::V1.prefix(),
+            provider.name
+        )
+    }
+
+    async fn fetch_jwks(&self, uri: &str) -> Result<JwkSet, APIError> {
+        let res = self.http.get(uri).send().await.map_err(unreachable)?;
+        res.error_for_status()
+            .map_err(unreachable)?
+            .json()
+            .await
+            .map_err(unreachable)
+    }
+
+    async fn discover(&self, provider: &OidcProvider) -> Result<Arc<Discovered>, APIError> {
+        if let Some(found) = self.discovered.read().unwrap().get(&provider.name) {
+            return Ok(found.clone());
+        }
+
+        let url = format!("{}/.well-known/openid-configuration", provider.issuer);
+        let res = self.http.get(&url).send().await.map_err(unreachable)?;
+        let metadata: Metadata = res
+            .error_for_status()
+            .map_err(unreachable)?
+            .json()
+            .await
+            .map_err(unreachable)?;
+        if metadata.issuer.trim_end_matches('/') != provider.issuer {
+            return Err(unreachable(format!(
+                "{} claims to be issuer {}",
+                provider.issuer, metadata.issuer
+            )));
+        }
+
+        let jwks = self.fetch_jwks(&metadata.jwks_uri).await?;
+        let found = Arc::new(Discovered {
+            metadata,
+            jwks: RwLock::new((jwks, Instant::now())),
+        });
+        self.discovered
+            .write()
+            .unwrap()
+            .insert(provider.name.clone(), found.clone());
+        Ok(found)
+    }
+
+    /// The key an ID token was signed with. Keys the provider rotated in since
+    /// they were fetched are picked up by fetching them again.
+    async fn key(
+        &self,
+        discovered: &Discovered,
+        kid: Option<&str>,
+    ) -> Result<DecodingKey, APIError> {
+        let find = |jwks: &JwkSet| -> Option<Jwk> {
+            match kid {
+                Some(kid) => jwks.find(kid).cloned(),
+                None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+                None => None,
+            }
+        };
+
+        let (found, stale) = {
+            let jwks = discovered.jwks.read().unwrap();
+            (find(&jwks.0), jwks.1.elapsed() >= JWKS_REFRESH)
+        };
+        let jwk = match found {
+            Some(jwk) => jwk,
+            None if stale => {
+                let jwks = self.fetch_jwks(&discovered.metadata.jwks_uri).await?;
+                let jwk = find(&jwks);
+                *discovered.jwks.write().unwrap() = (jwks, Instant::now());
+                jwk.ok_or_else(|| rejected(format!("unknown key {:?}", kid)))?
+            }
+            None => return Err(rejected(format!("unknown key {:?}", kid))),
+        };
+        DecodingKey::from_jwk(&jwk).map_err(rejected)
+    }
+
+    /// Starts a sign-in: records its state, nonce and PKCE verifier and returns
+    /// the provider's authorization URL along with the state.
+    pub async fn authorize(
+        &self,
+        pool: &PgPool,
+        provider: &str,
+        redirect_to: &str,
+        link_user_id: Option<Uuid>,
+    ) -> Result<(String, String), APIError> {
+        let provider = self.provider(provider)?;
+        let discovered = self.discover(provider).await?;
+
+        let state = random_token();
+        let nonce = random_token();
+        let verifier = random_token();
+        Q::insert_login(
+            pool,
+            &state,
+            &provider.name,
+            &nonce,
+            &verifier,
+            redirect_to,
+            link_user_id,
+            self.flow_ttl_secs(),
+        )
+        .await?;
+
+        let mut url = reqwest::Url::parse(&discovered.metadata.authorization_endpoint)
+            .map_err(unreachable)?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &provider.client_id)
+            .append_pair("redirect_uri", &self.callback_url(provider))
+            .append_pair("scope", &provider.scopes)
+            .append_pair("state", &state)
+            .append_pair("nonce", &nonce)
+            .append_pair("code_challenge", &challenge(&verifier))
+            .append_pair("code_challenge_method", "S256");
+        Ok((url.into(), state))
+    }
+
+    /// Redeems the code a provider called back with for the verified claims of
+    /// its ID token. Each `state` can only be redeemed once.
+    pub async fn redeem(
+        &self,
+        pool: &PgPool,
+        provider: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<(PendingLogin, IdClaims), APIError> {
+        let provider = self.provider(provider)?;
+        let login = Q::take_login(pool, state, &provider.name, self.flow_ttl_secs())
+            .await?
+            .ok_or_else(|| APIError::bad("The sign-in has expired, please try again"))?;
+        let discovered = self.discover(provider).await?;
+
+        let callback_url = self.callback_url(provider);
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &callback_url),
+            ("code_verifier", &login.code_verifier),
+        ];
+        let mut req = self.http.post(&discovered.metadata.token_endpoint);
+        match &provider.client_secret {
+            Some(secret) => req = req.basic_auth(&provider.client_id, Some(secret)),
+            None => form.push(("client_id", &provider.client_id)),
+        }
+        let res = req.form(&form).send().await.map_err(unreachable)?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(rejected(format!(
+                "token endpoint returned {}: {}",
+                status, body
+            )));
+        }
+        let tokens: TokenResponse = res.json().await.map_err(unreachable)?;
+
+        let claims = self
+            .verify(provider, &discovered, &tokens.id_token, &login.nonce)
+            .await?;
+        Ok((login, claims))
+    }
+
+    async fn verify(
+        &self,
+        provider: &OidcProvider,
+        discovered: &Discovered,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<IdClaims, APIError> {
+        let header = decode_header(id_token).map_err(rejected)?;
+        if !ALGORITHMS.contains(&header.alg) {
+            return Err(rejected(format!("ID token signed with {:?}", header.alg)));
+        }
+        let key = self.key(discovered, header.kid.as_deref()).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&provider.client_id]);
+        validation.set_issuer(&[&discovered.metadata.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = decode::<IdClaims>(id_token, &key, &validation)
+            .map_err(rejected)?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(rejected("nonce mismatch"));
+        }
+        Ok(claims)
+    }
+}
+
+/// A valid username close to what the provider knows the user as.
+pub fn suggest_username(claims: &IdClaims, limits: &Limits) -> String {
+    let base = claims
+        .preferred_username
+        .as_deref()
+        .or_else(|| claims.email.as_deref().and_then(|e| e.split('@').next()))
+        .unwrap_or_default();
+
+    let mut username: String = base
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    if !username.starts_with(|c: char| c.is_ascii_lowercase()) {
+        username.insert_str(0, "user_");
+    }
+    username.truncate(limits.username_max_len);
+    while username.len() < limits.username_min_len {
+        username.push('_');
+    }
+    username
+}
+
+/// Acts on a verified callback: signs in the user the identity is linked to,
+/// links it to the user who asked, or else starts a signup.
+pub async fn sign_in(
+    pool: &PgPool,
+    login: &PendingLogin,
+    claims: IdClaims,
+    limits: &Limits,
+    ttl_secs: f64,
+) -> Result<SignIn, APIError> {
+    if let Some(user_id) = login.link_user_id {
+        let mut tx = begin(pool).await?;
+        Q::insert_identity_tx(
+            &mut tx,
+            user_id,
+            &login.provider,
+            &claims.sub,
+            claims.email.as_deref(),
+        )
+        .await?;
+        commit(tx).await?;
+        return Ok(SignIn::Linked);
+    }
+
+    if let Some(user) = Q::select_identity_user(pool, &login.provider, &claims.sub).await? {
+        return Ok(SignIn::Existing(user));
+    }
+
+    let Some(email) = claims.email.clone() else {
+        return Err(APIError::bad(
+            "The identity provider did not share an email address",
+        ));
+    };
+    let token = random_token();
+    let signup = Signup {
+        provider: login.provider.clone(),
+        username: suggest_username(&claims, limits),
+        subject: claims.sub,
+        email,
+        name: claims.name,
+    };
+    Q::insert_signup(pool, &tokens::hash(&token), &signup, ttl_secs).await?;
+    Ok(SignIn::Signup(token))
+}
+
+/// Creates the account of a first sign-in under the chosen `username`.
+pub async fn complete_signup(
+    pool: &PgPool,
+    token: &str,
+    username: &str,
+    ttl_secs: f64,
+) -> Result<AuthUser, APIError> {
+    let mut tx = begin(pool).await?;
+    let signup = Q::take_signup_tx(&mut tx, &tokens::hash(token), ttl_secs)
+        .await?
+        .ok_or_else(|| APIError::bad("The sign-up has expired, please sign in again"))?;
+    let user = Q::insert_user_tx(&mut tx, username, &signup.email, signup.name.as_deref()).await?;
+    Q::insert_identity_tx(
+        &mut tx,
+        user.id,
+        &signup.provider,
+        &signup.subject,
+        Some(&signup.email),
+    )
+    .await?;
+    commit(tx).await?;
+    Ok(user)
+}
+
+async fn begin(pool: &PgPool) -> Result<sqlx::Transaction<'static, sqlx::Postgres>, APIError> {
+    pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {:?}", e);
+        APIError::server()
+    })
+}
+
+async fn commit(tx: sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(), APIError> {
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        APIError::server()
+    })
+}
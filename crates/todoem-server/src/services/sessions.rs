@@ -0,0 +1,132 @@
+use crate::db::query::session as Q;
+use sqlx::PgPool;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use todoem_core::errors::APIError;
+use uuid::Uuid;
+
+/// Entries are only swept once the map grows past this many.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Remembers whether sessions are live so that `jwt_auth` asks the database
+/// at most once per session every `ttl`. Each of those lookups also records
+/// the session as seen.
+pub struct SessionCache {
+    entries: Mutex<HashMap<Uuid, (bool, Instant)>>,
+    ttl: Duration,
+}
+
+pub type SharedSessionCache = Arc<SessionCache>;
+
+impl std::fmt::Debug for SessionCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SessionCache")
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    fn cached(&self, id: Uuid) -> Option<bool> {
+        let entries = self.entries.lock().unwrap();
+        match entries.get(&id) {
+            Some((live, checked)) if checked.elapsed() < self.ttl => Some(*live),
+            _ => None,
+        }
+    }
+
+    fn store(&self, id: Uuid, live: bool) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() > SWEEP_THRESHOLD {
+            entries.retain(|_, (_, checked)| checked.elapsed() < self.ttl);
+        }
+        entries.insert(id, (live, Instant::now()));
+    }
+
+    pub async fn is_live(
+        &self,
+        pool: &PgPool,
+        id: Uuid,
+        user_id: Uuid,
+        ip: Option<&str>,
+    ) -> Result<bool, APIError> {
+        if let Some(live) = self.cached(id) {
+            return Ok(live);
+        }
+        let live = Q::touch_session(pool, id, user_id, ip).await?;
+        self.store(id, live);
+        Ok(live)
+    }
+
+    /// Takes effect on this instance at once, on others within `ttl`.
+    pub fn revoked(&self, id: Uuid) {
+        self.store(id, false);
+    }
+}
+
+/// A readable name for a session without one, like `Firefox on Linux`.
+pub fn device_name(user_agent: Option<&str>) -> String {
+    let Some(ua) = user_agent else {
+        return "Unknown device".to_string();
+    };
+
+    // order matters: Edge claims to be Chrome, Chrome claims to be Safari
+    // and Android claims to be Linux
+    let browser = [
+        ("Edg/", "Edge"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("curl/", "curl"),
+        ("todoem", "todoem CLI"),
+    ]
+    .iter()
+    .find(|(marker, _)| ua.contains(marker))
+    .map(|(_, name)| *name);
+    let os = [
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ]
+    .iter()
+    .find(|(marker, _)| ua.contains(marker))
+    .map(|(_, name)| *name);
+
+    match (browser, os) {
+        (Some(browser), Some(os)) => format!("{} on {}", browser, os),
+        (Some(name), None) | (None, Some(name)) => name.to_string(),
+        (None, None) => "Unknown device".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_devices_from_user_agents() {
+        let chrome_android = "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 \
+            (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36";
+        assert_eq!(device_name(Some(chrome_android)), "Chrome on Android");
+        let safari_iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) \
+            AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1";
+        assert_eq!(device_name(Some(safari_iphone)), "Safari on iOS");
+        let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0";
+        assert_eq!(device_name(Some(firefox)), "Firefox on Linux");
+        assert_eq!(device_name(Some("curl/8.8.0")), "curl");
+        assert_eq!(device_name(None), "Unknown device");
+    }
+}
@@ -0,0 +1,112 @@
+use std::future::Future;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+/// Coordinates stopping the process: background jobs are spawned through it,
+/// watch for [`Shutdown::triggered`] between units of work, and are waited
+/// for by [`Shutdown::drained`].
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    token: CancellationToken,
+    jobs: TaskTracker,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `job` in the background, to be waited for on shutdown.
+    pub fn spawn<F>(&self, job: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.jobs.spawn(job);
+    }
+
+    pub fn trigger(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Resolves once the shutdown has been triggered.
+    pub fn triggered(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.token.clone().cancelled_owned()
+    }
+
+    /// Resolves once every spawned job has returned.
+    pub async fn drained(&self) {
+        self.jobs.close();
+        self.jobs.wait().await;
+    }
+}
+
+/// Resolves on SIGINT (Ctrl+C) or, on Unix, SIGTERM.
+pub async fn signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for SIGINT: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => tracing::info!("Received SIGINT"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn jobs_finish_their_work_before_draining() {
+        let shutdown = Shutdown::new();
+        let done = Arc::new(AtomicUsize::new(0));
+
+        let (job, count) = (shutdown.clone(), done.clone());
+        shutdown.spawn(async move {
+            while !job.is_triggered() {
+                tokio::select! {
+                    _ = job.triggered() => {}
+                    _ = tokio::time::sleep(Duration::from_millis(5)) => {}
+                }
+            }
+            // a unit of work started before the trigger still completes
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            count.fetch_add(1, Ordering::SeqCst);
+        });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(done.load(Ordering::SeqCst), 0);
+        shutdown.trigger();
+        tokio::time::timeout(Duration::from_secs(1), shutdown.drained())
+            .await
+            .unwrap();
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,19 @@
+use sha2::{Digest, Sha256};
+
+/// Marks a bearer credential as a personal access token rather than a JWT.
+pub const PREFIX: &str = "tdm_";
+
+/// Characters of a token kept in the clear to tell tokens apart.
+const DISPLAY_LEN: usize = PREFIX.len() + 8;
+
+pub fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// A fresh token with its hash and display prefix.
+pub fn generate() -> (String, String, String) {
+    let token = format!("{}{}", PREFIX, hex::encode(rand::random::<[u8; 32]>()));
+    let hash = hash(&token);
+    let prefix = token[..DISPLAY_LEN].to_string();
+    (token, hash, prefix)
+}
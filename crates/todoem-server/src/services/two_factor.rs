@@ -0,0 +1,184 @@
+use crate::db::query::two_factor as Q;
+use crate::services::tokens;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sqlx::PgPool;
+use todoem_core::errors::APIError;
+use uuid::Uuid;
+
+/// RFC 6238 defaults, the only parameters every authenticator app supports.
+const STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+/// Codes of the steps either side of the current one are accepted too, for
+/// clocks that drift and codes typed as they roll over.
+const WINDOW: i64 = 1;
+const SECRET_LEN: usize = 20;
+const RECOVERY_CODES: usize = 10;
+
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Unpadded RFC 4648 base32, as authenticator apps expect secrets.
+pub fn base32(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    for chunk in bytes.chunks(5) {
+        let mut buf = [0u8; 5];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let bits = buf.iter().fold(0u64, |acc, b| acc << 8 | *b as u64);
+        let chars = (chunk.len() * 8).div_ceil(5);
+        for i in 0..chars {
+            out.push(BASE32[(bits >> (35 - i * 5)) as usize & 31] as char);
+        }
+    }
+    out
+}
+
+pub fn generate_secret() -> Vec<u8> {
+    rand::random::<[u8; SECRET_LEN]>().to_vec()
+}
+
+/// RFC 4226 HOTP of one counter value.
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[19] & 0xf) as usize;
+    let value = u32::from_be_bytes(digest[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+    value % 10u32.pow(DIGITS)
+}
+
+/// The time step `code` belongs to, if it is valid around `now`.
+fn matching_step(secret: &[u8], code: &str, now: i64) -> Option<i64> {
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let current = now / STEP_SECS;
+    (current - WINDOW..=current + WINDOW).find(|step| hotp(secret, *step as u64) == code)
+}
+
+/// The step of a valid code at the current time.
+pub fn verify_code(secret: &[u8], code: &str) -> Option<i64> {
+    matching_step(secret, code.trim(), Utc::now().timestamp())
+}
+
+/// The code an authenticator shows right now.
+#[cfg(test)]
+pub fn current_code(secret: &[u8]) -> String {
+    let step = Utc::now().timestamp() / STEP_SECS;
+    format!("{:06}", hotp(secret, step as u64))
+}
+
+/// The `otpauth://` URI that authenticator apps read from a QR code.
+pub fn provisioning_uri(username: &str, secret: &[u8]) -> String {
+    let mut url = reqwest::Url::parse("otpauth://totp/").expect("valid base URL");
+    url.set_path(&format!("todoem:{}", username));
+    url.query_pairs_mut()
+        .append_pair("secret", &base32(secret))
+        .append_pair("issuer", "todoem")
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &DIGITS.to_string())
+        .append_pair("period", &STEP_SECS.to_string());
+    url.to_string()
+}
+
+/// The provisioning URI as an SVG QR code, for clients that cannot draw one.
+pub fn qr_svg(uri: &str) -> Result<String, APIError> {
+    let code = qrcode::QrCode::new(uri.as_bytes()).map_err(|e| {
+        tracing::error!("Failed to encode QR code: {:?}", e);
+        APIError::server()
+    })?;
+    Ok(code
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(200, 200)
+        .build())
+}
+
+/// Recovery codes are compared without dashes, spaces or case.
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Fresh recovery codes like `abcd-efgh-ijkl-mnop` (80 bits each), with
+/// their hashes.
+pub fn generate_recovery_codes() -> (Vec<String>, Vec<String>) {
+    (0..RECOVERY_CODES)
+        .map(|_| {
+            let raw = base32(&rand::random::<[u8; 10]>()).to_ascii_lowercase();
+            let code = raw
+                .as_bytes()
+                .chunks(4)
+                .map(|c| std::str::from_utf8(c).unwrap())
+                .collect::<Vec<_>>()
+                .join("-");
+            let hash = tokens::hash(&normalize_recovery_code(&code));
+            (code, hash)
+        })
+        .unzip()
+}
+
+/// Checks a second factor of a user with two-factor enabled: a current
+/// authenticator code or an unused recovery code. Either works only once.
+pub async fn check(pool: &PgPool, user_id: Uuid, code: &str) -> Result<bool, APIError> {
+    let totp = match Q::select_totp(pool, user_id).await? {
+        Some(totp) if totp.confirmed_at.is_some() => totp,
+        _ => return Ok(false),
+    };
+
+    match verify_code(&totp.secret, code) {
+        Some(step) => Q::use_totp_step(pool, user_id, step).await,
+        None => {
+            let hash = tokens::hash(&normalize_recovery_code(code));
+            Q::consume_recovery_code(pool, user_id, &hash).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn matches_rfc_6238_vectors() {
+        // the 8 digit SHA-1 vectors of RFC 6238, cut to 6 digits
+        for (time, code) in [
+            (59, 94287082),
+            (1111111109, 7081804),
+            (1234567890, 89005924),
+            (2000000000, 69279037),
+        ] {
+            assert_eq!(hotp(RFC_SECRET, time / 30), code % 1_000_000);
+        }
+    }
+
+    #[test]
+    fn accepts_adjacent_steps_only() {
+        let now = 1234567890;
+        let code = format!("{:06}", hotp(RFC_SECRET, (now / 30) as u64 + 1));
+        assert_eq!(matching_step(RFC_SECRET, &code, now), Some(now / 30 + 1));
+        assert_eq!(matching_step(RFC_SECRET, &code, now - 60), None);
+        assert_eq!(matching_step(RFC_SECRET, "12345", now), None);
+    }
+
+    #[test]
+    fn encodes_base32() {
+        assert_eq!(base32(b""), "");
+        assert_eq!(base32(b"f"), "MY");
+        assert_eq!(base32(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32(RFC_SECRET), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    }
+
+    #[test]
+    fn recovery_codes_ignore_formatting() {
+        let (codes, hashes) = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODES);
+        assert_eq!(codes[0].len(), 19);
+        let typed = codes[0].replace('-', " ").to_uppercase();
+        assert_eq!(tokens::hash(&normalize_recovery_code(&typed)), hashes[0]);
+    }
+}
@@ -0,0 +1,346 @@
+use crate::config::Webhooks;
+use crate::db::query::webhook as Q;
+use crate::services::{metrics::Metrics, shutdown::Shutdown};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use todoem_core::errors::APIError;
+use todoem_core::models::webhook::{DueDelivery, WebhookEvent};
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+pub const SIGNATURE_HEADER: &str = "x-todoem-signature";
+pub const EVENT_HEADER: &str = "x-todoem-event";
+pub const DELIVERY_HEADER: &str = "x-todoem-delivery";
+
+/// How much of a receiver's response is kept in the delivery log.
+const RESPONSE_BODY_MAX_CHARS: usize = 1024;
+/// How much of it is read, enough for the characters above in any UTF-8.
+const RESPONSE_BODY_MAX_BYTES: usize = 4 * RESPONSE_BODY_MAX_CHARS;
+
+/// Label of the dispatcher in the job metrics.
+const JOB: &str = "webhooks";
+
+pub fn generate_secret() -> String {
+    format!("whsec_{}", hex::encode(rand::random::<[u8; 32]>()))
+}
+
+fn mac(secret: &str, timestamp: i64, body: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac
+}
+
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">` keyed with the
+/// webhook's secret. Receivers should also reject stale timestamps.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let signature = hex::encode(mac(secret, timestamp, body).finalize().into_bytes());
+    format!("t={},v1={}", timestamp, signature)
+}
+
+/// Checks a signature header the way a receiver would, in constant time.
+#[cfg(test)]
+pub fn verify(secret: &str, header: &str, body: &str) -> bool {
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.split_once('=') {
+            Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+            Some(("v1", v)) => signature = hex::decode(v).ok(),
+            _ => {}
+        }
+    }
+    match (timestamp, signature) {
+        (Some(t), Some(signature)) => mac(secret, t, body).verify_slice(&signature).is_ok(),
+        _ => false,
+    }
+}
+
+/// The JSON body every delivery carries.
+pub fn envelope(event: WebhookEvent, data: impl Serialize) -> serde_json::Value {
+    serde_json::json!({
+        "event": event.as_str(),
+        "occurred_at": Utc::now(),
+        "data": data,
+    })
+}
+
+/// Queues `event` for the user's subscribed webhooks in the transaction of the
+/// action that caused it, so that the two are committed or rolled back together.
+pub async fn emit_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    event: WebhookEvent,
+    data: impl Serialize,
+) -> Result<(), APIError> {
+    let payload = envelope(event, data);
+    Q::enqueue_event_tx(tx, user_id, event.as_str(), &payload).await?;
+    Ok(())
+}
+
+/// Whether `ip` is on the public internet. Loopback, private, link-local,
+/// unique-local and other special-purpose addresses are not.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // "this network", 0.0.0.0/8
+                || a == 0
+                // shared address space of carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && b & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Sends due deliveries from the `webhook_deliveries` queue.
+#[derive(Clone)]
+pub struct Dispatcher {
+    pool: PgPool,
+    config: Webhooks,
+    metrics: Metrics,
+}
+
+impl Dispatcher {
+    pub fn new(pool: PgPool, config: Webhooks, metrics: Metrics) -> Self {
+        Self {
+            pool,
+            config,
+            metrics,
+        }
+    }
+
+    /// Resolves the host of `url` to the one address the delivery may go to.
+    /// Hosts with any non-public address are refused, unless private targets
+    /// are allowed, as the receiver's reply is shown to the webhook's owner.
+    async fn resolve(&self, url: &str) -> Result<(reqwest::Url, SocketAddr), String> {
+        let url = reqwest::Url::parse(url).map_err(|e| format!("invalid url: {}", e))?;
+        let port = url
+            .port_or_known_default()
+            .ok_or("url has no port for its scheme")?;
+        let addrs: Vec<SocketAddr> = match domain(&url) {
+            Some(domain) => tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|e| format!("cannot resolve {}: {}", domain, e))?
+                .collect(),
+            None => match url.host_str().map(|h| h.trim_matches(['[', ']']).parse()) {
+                Some(Ok(ip)) => vec![SocketAddr::new(ip, port)],
+                _ => return Err("url has no host".to_string()),
+            },
+        };
+
+        if !self.config.allow_private_targets {
+            if let Some(addr) = addrs.iter().find(|a| !is_public(a.ip())) {
+                return Err(format!(
+                    "refused to deliver to non-public address {}",
+                    addr.ip()
+                ));
+            }
+        }
+        match addrs.first() {
+            Some(addr) => Ok((url, *addr)),
+            None => Err("host has no addresses".to_string()),
+        }
+    }
+
+    /// A client that connects to `addr` for the url's host, so that the
+    /// address checked above is the one used, whatever DNS says by then.
+    fn client(&self, url: &reqwest::Url, addr: SocketAddr) -> reqwest::Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.config.timeout_secs))
+            // a redirect could point the signed payload anywhere
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent(concat!("todoem-webhooks/", env!("CARGO_PKG_VERSION")));
+        if let Some(domain) = domain(url) {
+            builder = builder.resolve(domain, addr);
+        }
+        builder.build()
+    }
+
+    /// Seconds until the next attempt after `attempts` failed ones: doubling
+    /// from the base delay up to the cap, with some jitter so that retries of a
+    /// burst do not arrive together.
+    fn backoff(&self, attempts: i32) -> f64 {
+        let exp = attempts.saturating_sub(1).clamp(0, 30) as u32;
+        let delay = self
+            .config
+            .backoff_base_secs
+            .saturating_mul(1 << exp)
+            .min(self.config.backoff_max_secs);
+        delay as f64 * rand::thread_rng().gen_range(0.9..1.1)
+    }
+
+    /// Sends one batch of due deliveries and returns how many were attempted.
+    pub async fn dispatch_due(&self) -> usize {
+        self.metrics.job_ran(JOB);
+        let lease = (self.config.timeout_secs + 30) as f64;
+        let due = match Q::claim_due_deliveries(&self.pool, self.config.batch_size, lease).await {
+            Ok(due) => due,
+            Err(_) => return 0,
+        };
+
+        let count = due.len();
+        let mut sending = JoinSet::new();
+        for delivery in due {
+            let this = self.clone();
+            sending.spawn(async move { this.deliver(delivery).await });
+        }
+        while sending.join_next().await.is_some() {}
+        count
+    }
+
+    async fn send(&self, delivery: &DueDelivery) -> Result<reqwest::Response, String> {
+        let (url, addr) = self.resolve(&delivery.url).await?;
+        let client = self.client(&url, addr).map_err(|e| e.to_string())?;
+
+        let body = delivery.payload.to_string();
+        let signature = sign(&delivery.secret, Utc::now().timestamp(), &body);
+        client
+            .post(url)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &delivery.event)
+            .header(DELIVERY_HEADER, delivery.id)
+            .header(SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn deliver(&self, delivery: DueDelivery) {
+        let (status, response, error) = match self.send(&delivery).await {
+            Ok(res) => {
+                let status = res.status();
+                let mut text = read_capped(res).await;
+                if let Some((cut, _)) = text.char_indices().nth(RESPONSE_BODY_MAX_CHARS) {
+                    text.truncate(cut);
+                }
+                let error = (!status.is_success()).then(|| format!("receiver returned {}", status));
+                (Some(status.as_u16() as i32), Some(text), error)
+            }
+            Err(e) => (None, None, Some(e)),
+        };
+
+        let succeeded = error.is_none();
+        let retry_in = match succeeded || delivery.attempts >= self.config.max_attempts {
+            true => None,
+            false => Some(self.backoff(delivery.attempts)),
+        };
+        if !succeeded {
+            tracing::warn!(
+                "Webhook delivery {} failed (attempt {}): {:?}",
+                delivery.id,
+                delivery.attempts,
+                error
+            );
+        }
+        self.metrics.job_attempt(JOB, succeeded, retry_in.is_some());
+
+        let _ = Q::record_attempt(
+            &self.pool,
+            delivery.id,
+            succeeded,
+            status,
+            response.as_deref(),
+            error.as_deref(),
+            retry_in,
+        )
+        .await;
+    }
+}
+
+/// The host of `url` when it is a name rather than an IP address.
+fn domain(url: &reqwest::Url) -> Option<&str> {
+    let host = url.host_str()?;
+    match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(_) => None,
+        Err(_) => Some(host),
+    }
+}
+
+/// The start of a reply, reading no more than `RESPONSE_BODY_MAX_BYTES` of
+/// it however much the receiver sends.
+async fn read_capped(mut res: reqwest::Response) -> String {
+    let mut body = Vec::new();
+    while body.len() < RESPONSE_BODY_MAX_BYTES {
+        match res.chunk().await {
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+            _ => break,
+        }
+    }
+    body.truncate(RESPONSE_BODY_MAX_BYTES);
+    String::from_utf8_lossy(&body).into_owned()
+}
+
+/// Polls the delivery queue until `shutdown` is triggered, finishing the
+/// batch in flight.
+pub fn spawn_dispatcher(pool: PgPool, config: Webhooks, metrics: Metrics, shutdown: &Shutdown) {
+    let idle = Duration::from_millis(config.poll_interval_ms);
+    let dispatcher = Dispatcher::new(pool, config, metrics);
+    let stop = shutdown.clone();
+    shutdown.spawn(async move {
+        while !stop.is_triggered() {
+            if dispatcher.dispatch_due().await == 0 {
+                tokio::select! {
+                    _ = stop.triggered() => {}
+                    _ = tokio::time::sleep(idle) => {}
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public(ip: &str) -> bool {
+        is_public(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn only_public_addresses_are_targets() {
+        assert!(public("93.184.215.14"));
+        assert!(public("2606:2800:21f:cb07:6820:80da:af6b:8b2c"));
+
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!public(ip), "{} should not be public", ip);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+pub mod redact;
+
+use crate::config::{Log, LogFormat, Telemetry};
+use crate::middlewares::request_id::X_REQUEST_ID;
+use crate::services::metrics::Metrics;
+use axum::extract::Request;
+use opentelemetry::{global, trace::TracerProvider as _, KeyValue};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator, runtime, trace::TracerProvider, Resource,
+};
+use redact::Redacting;
+use std::time::Instant;
+use tracing::{span, Level, Span, Subscriber};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    filter::{dynamic_filter_fn, Targets},
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    Layer,
+};
+
+/// Spans of this target are timed as database queries.
+const QUERY_TARGET: &str = "todoem_server::db::query";
+
+/// Keeps the span exporter alive; [`Tracing::shutdown`] flushes what is
+/// still buffered.
+pub struct Tracing {
+    provider: Option<TracerProvider>,
+}
+
+impl Tracing {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber: logs as configured by `log`, query timings
+/// into `metrics` and, with an OTLP endpoint, spans to the collector.
+pub fn init(log: &Log, telemetry: &Telemetry, metrics: Metrics) -> Result<Tracing, String> {
+    let logs = tracing_subscriber::fmt::layer().with_writer(Redacting::new(std::io::stdout));
+    let logs = match log.format {
+        LogFormat::Text => logs.boxed(),
+        LogFormat::Json => logs.json().boxed(),
+    };
+
+    let provider = match telemetry.traces_endpoint() {
+        Some(endpoint) => {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(endpoint)
+                .build()
+                .map_err(|e| format!("Failed to build the OTLP exporter: {}", e))?;
+            let resource = Resource::new([KeyValue::new(
+                "service.name",
+                telemetry.service_name.clone(),
+            )]);
+            Some(
+                TracerProvider::builder()
+                    .with_batch_exporter(exporter, runtime::Tokio)
+                    .with_resource(resource)
+                    .build(),
+            )
+        }
+        None => None,
+    };
+    let spans = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer("todoem"))
+            .with_filter(dynamic_filter_fn(|meta, cx| {
+                // the polls of background jobs would each start a trace
+                meta.target().starts_with("todoem")
+                    && *meta.level() <= Level::INFO
+                    && (meta.name() == "request" || cx.lookup_current().is_some())
+            }))
+    });
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    tracing_subscriber::registry()
+        .with(logs.with_filter(log.filter()))
+        .with(spans)
+        .with(query_timer(metrics))
+        .try_init()
+        .map_err(|e| e.to_string())?;
+    Ok(Tracing { provider })
+}
+
+/// The span of an HTTP request, with its `X-Request-Id`, continuing the
+/// trace of the caller when the request has a W3C `traceparent` header.
+pub fn request_span(req: &Request) -> Span {
+    let request_id = req
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let span = tracing::info_span!(
+        "request",
+        method = %req.method(),
+        uri = %req.uri(),
+        version = ?req.version(),
+        request_id,
+    );
+    let parent = global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(req.headers())));
+    span.set_parent(parent);
+    span
+}
+
+/// Records how long each `db::query` function takes into `metrics`.
+pub fn query_timer<S>(metrics: Metrics) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    QueryTimer { metrics }.with_filter(Targets::new().with_target(QUERY_TARGET, Level::INFO))
+}
+
+struct Started(Instant);
+
+/// Times the `#[instrument]`ed functions of `db::query` from creation to
+/// close, awaits included.
+struct QueryTimer {
+    metrics: Metrics,
+}
+
+impl<S> Layer<S> for QueryTimer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Started(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(Started(started)) = span.extensions().get::<Started>().map(|s| Started(s.0))
+        else {
+            return;
+        };
+        let module = span
+            .metadata()
+            .target()
+            .strip_prefix(QUERY_TARGET)
+            .unwrap_or_default()
+            .trim_start_matches("::");
+        self.metrics
+            .observe_query(&format!("{}::{}", module, span.name()), started.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use opentelemetry::trace::TraceContextExt;
+
+    #[test]
+    fn requests_continue_the_callers_trace() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let req = Request::get("/api/me")
+            .header(
+                "traceparent",
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+            .body(Body::empty())
+            .unwrap();
+        let cx = request_span(&req).context();
+        let span = cx.span();
+        assert_eq!(
+            span.span_context().trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+
+        let req = Request::get("/api/me").body(Body::empty()).unwrap();
+        let cx = request_span(&req).context();
+        assert_ne!(
+            cx.span().span_context().trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+    }
+}
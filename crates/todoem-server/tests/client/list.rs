@@ -0,0 +1,121 @@
+use super::{TestApp, LUFFY, NAMI, ZORO};
+use http::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn lists_are_only_sent_to_connections(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let list = luffy
+        .post("/list", &json!({ "name": "crew", "tasks": ["meat"] }))
+        .await
+        .expect(StatusCode::CREATED);
+    let id = list["id"].as_str().unwrap();
+
+    let e = luffy
+        .post(&format!("/list/{}/send/{}", id, NAMI), &json!({}))
+        .await
+        .error(StatusCode::FORBIDDEN);
+    assert_eq!(e, "Lists can only be sent to connections");
+    let e = luffy
+        .post(&format!("/list/{}/send/{}", id, LUFFY), &json!({}))
+        .await
+        .error(StatusCode::FORBIDDEN);
+    assert_eq!(e, "Lists can only be sent to connections");
+
+    // someone else's list is not found
+    let zoro = app.sign_in(ZORO).await;
+    zoro.get(&format!("/list/{}", id))
+        .await
+        .expect(StatusCode::NOT_FOUND);
+    zoro.post(&format!("/list/{}/send/{}", id, LUFFY), &json!({}))
+        .await
+        .expect(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn lists_are_checked(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    luffy
+        .post("/list", &json!({ "name": "crew", "tasks": [" "] }))
+        .await
+        .expect(StatusCode::UNPROCESSABLE_ENTITY);
+    let tasks = vec!["meat"; 101];
+    luffy
+        .post("/list", &json!({ "name": "crew", "tasks": tasks }))
+        .await
+        .expect(StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn list_sizes_follow_the_limits(pool: PgPool) {
+    let app = TestApp::with_settings(pool, |s| s.limits.list_max_tasks = 1);
+    let luffy = app.sign_in(LUFFY).await;
+    luffy
+        .post("/list", &json!({ "name": "crew", "tasks": ["meat"] }))
+        .await
+        .expect(StatusCode::CREATED);
+    luffy
+        .post(
+            "/list",
+            &json!({ "name": "crew", "tasks": ["meat", "sake"] }),
+        )
+        .await
+        .expect(StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn recipients_hear_of_sent_lists(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    luffy
+        .post(&format!("/user/{}/request", ZORO), &json!({}))
+        .await
+        .expect(StatusCode::OK);
+    zoro.put_empty(&format!("/user/{}/accept", LUFFY))
+        .await
+        .expect(StatusCode::OK);
+    let webhook = zoro
+        .post(
+            "/webhooks",
+            &json!({ "url": "https://example.com/hook", "events": ["list.received"] }),
+        )
+        .await
+        .expect(StatusCode::CREATED);
+    let deliveries = format!("/webhooks/{}/deliveries", webhook["id"]);
+
+    let list = luffy
+        .post("/list", &json!({ "name": "crew", "tasks": ["meat"] }))
+        .await
+        .expect(StatusCode::CREATED);
+    luffy
+        .post(
+            &format!("/list/{}/send/{}", list["id"].as_str().unwrap(), ZORO),
+            &json!({}),
+        )
+        .await
+        .expect(StatusCode::OK);
+
+    let sent = zoro.get(&deliveries).await.expect(StatusCode::OK);
+    assert_eq!(sent.as_array().unwrap().len(), 1);
+    assert_eq!(sent[0]["event"], "list.received");
+    let data = &sent[0]["payload"]["data"];
+    assert_eq!(data["name"], "crew");
+    assert_eq!(data["user_id"], ZORO.to_string());
+    assert_eq!(data["sent_by_id"], LUFFY.to_string());
+}
@@ -0,0 +1,237 @@
+//! Tests of the whole router, driven in process the way clients see it:
+//! through the middlewares, without binding a port. `#[sqlx::test]` creates
+//! a fresh database per test on the server behind `DATABASE_URL` and applies
+//! `src/db/migrations`, then the users of `src/db/fixtures/users.sql`.
+
+mod list;
+mod task;
+mod user;
+
+use axum::{body::Body, Router};
+use http::{
+    header::{AUTHORIZATION, CONTENT_TYPE, COOKIE},
+    HeaderMap, Method, Request, StatusCode,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::sync::Arc;
+use todoem_core::models::AuthUser;
+use todoem_server::config::{Config, Settings};
+use todoem_server::handlers::extract::ClientInfo;
+use todoem_server::middlewares::version::ApiVersion;
+use todoem_server::routes;
+use todoem_server::services::{
+    auth::SessionIssuer, metrics::Metrics, shutdown::Shutdown, storage::LocalStorage,
+    storage::SharedStorage,
+};
+use tower::ServiceExt;
+use uuid::{uuid, Uuid};
+
+pub const LUFFY: Uuid = uuid!("5d43fc3c-8acb-48f9-9b25-8f8bd6f3d834");
+pub const ZORO: Uuid = uuid!("4157ee44-1de0-4168-a1f3-7ad6a5fd09b6");
+pub const NAMI: Uuid = uuid!("f6d1dabe-7766-4a6c-b34e-75e444cc3cbd");
+
+/// The router of a test, over its own database and avatar directory.
+pub struct TestApp {
+    router: Router,
+    pub pool: PgPool,
+    pub storage: SharedStorage,
+    sessions: SessionIssuer,
+}
+
+impl TestApp {
+    pub fn new(pool: PgPool) -> Self {
+        Self::with_settings(pool, |_| {})
+    }
+
+    /// With the default settings as changed by `configure`.
+    pub fn with_settings(pool: PgPool, configure: impl FnOnce(&mut Settings)) -> Self {
+        let mut settings = Settings::default();
+        settings.jwt.secret_key = "secret".to_string();
+        configure(&mut settings);
+        let config = Config::new(settings, pool.clone()).unwrap();
+        let sessions =
+            SessionIssuer::new(config.jwt_keys.clone(), &config.accounts, &config.cookies);
+
+        let dir = std::env::temp_dir().join(format!("todoem-test-{}", Uuid::new_v4()));
+        let storage: SharedStorage = Arc::new(LocalStorage::new(dir));
+        let router = routes::init(config, storage.clone(), Shutdown::new(), Metrics::new());
+
+        Self {
+            router,
+            pool,
+            storage,
+            sessions,
+        }
+    }
+
+    /// A client without credentials.
+    pub fn anonymous(&self) -> Client {
+        Client {
+            router: self.router.clone(),
+            auth: None,
+        }
+    }
+
+    /// A client signed in as the fixture user `id`, with a session cookie
+    /// minted the way signing in does.
+    pub async fn sign_in(&self, id: Uuid) -> Client {
+        let email = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+            .unwrap();
+        let user = AuthUser { id, email };
+        let client = ClientInfo {
+            user_agent: Some("todoem-tests".to_string()),
+            ip: None,
+        };
+        let cookie = self
+            .sessions
+            .start(&self.pool, &user, &client, None)
+            .await
+            .unwrap();
+        Client {
+            router: self.router.clone(),
+            auth: Some((COOKIE, format!("{}={}", cookie.name(), cookie.value()))),
+        }
+    }
+
+    /// A client using a personal access token of `id` limited to `scopes`.
+    pub async fn with_token(&self, id: Uuid, scopes: &[&str]) -> Client {
+        let res = self
+            .sign_in(id)
+            .await
+            .post("/tokens", &json!({ "name": "tests", "scopes": scopes }))
+            .await;
+        assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+        let token: Value = res.json();
+        Client {
+            router: self.router.clone(),
+            auth: Some((
+                AUTHORIZATION,
+                format!("Bearer {}", token["value"].as_str().unwrap()),
+            )),
+        }
+    }
+}
+
+/// Calls the API as one user. Paths are relative to `/api/v1`.
+#[derive(Clone)]
+pub struct Client {
+    router: Router,
+    auth: Option<(http::HeaderName, String)>,
+}
+
+impl Client {
+    pub async fn get(&self, path: &str) -> Response {
+        self.send(Method::GET, path, None).await
+    }
+
+    pub async fn delete(&self, path: &str) -> Response {
+        self.send(Method::DELETE, path, None).await
+    }
+
+    pub async fn post<T: Serialize>(&self, path: &str, body: &T) -> Response {
+        self.send(Method::POST, path, Some(serde_json::to_vec(body).unwrap()))
+            .await
+    }
+
+    pub async fn put<T: Serialize>(&self, path: &str, body: &T) -> Response {
+        self.send(Method::PUT, path, Some(serde_json::to_vec(body).unwrap()))
+            .await
+    }
+
+    /// For the routes that take no body.
+    pub async fn put_empty(&self, path: &str) -> Response {
+        self.send(Method::PUT, path, None).await
+    }
+
+    async fn send(&self, method: Method, path: &str, body: Option<Vec<u8>>) -> Response {
+        let mut req =
+            Request::builder()
+                .method(method)
+                .uri(format!("{}{}", ApiVersion::V1.prefix(), path));
+        if let Some((name, value)) = &self.auth {
+            req = req.header(name, value);
+        }
+        let req = match body {
+            Some(body) => req
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body)),
+            None => req.body(Body::empty()),
+        };
+
+        let res = self.router.clone().oneshot(req.unwrap()).await.unwrap();
+        let status = res.status();
+        let headers = res.headers().clone();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec();
+        Response {
+            status,
+            headers,
+            body,
+        }
+    }
+}
+
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| panic!("{}: {}", e, self.text()))
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Asserts the status and returns the body as JSON.
+    pub fn expect(&self, status: StatusCode) -> Value {
+        assert_eq!(self.status, status, "{}", self.text());
+        if self.body.is_empty() {
+            return Value::Null;
+        }
+        self.json()
+    }
+
+    /// Asserts an error status and returns its message.
+    pub fn error(&self, status: StatusCode) -> String {
+        self.expect(status)["error"].as_str().unwrap().to_string()
+    }
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn probes_answer(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let req = Request::get("/healthz").body(Body::empty()).unwrap();
+    let res = app.router.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn routes_need_a_session(pool: PgPool) {
+    let app = TestApp::new(pool);
+    app.anonymous()
+        .get("/task/all")
+        .await
+        .error(StatusCode::UNAUTHORIZED);
+    app.sign_in(LUFFY)
+        .await
+        .get("/task/all")
+        .await
+        .expect(StatusCode::OK);
+}
@@ -0,0 +1,642 @@
+use super::{Client, TestApp, LUFFY, ZORO};
+use chrono::{Datelike, Days, NaiveDate, Utc};
+use http::StatusCode;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+fn today() -> NaiveDate {
+    // the fixture users are in UTC
+    Utc::now().date_naive()
+}
+
+async fn create(client: &Client, body: Value) -> Value {
+    client
+        .post("/task", &body)
+        .await
+        .expect(StatusCode::CREATED)
+}
+
+async fn titled(client: &Client, title: &str) -> i64 {
+    create(client, json!({ "task": title, "description": "" })).await["id"]
+        .as_i64()
+        .unwrap()
+}
+
+async fn due_on(client: &Client, title: &str, day: NaiveDate) -> i64 {
+    create(
+        client,
+        json!({ "task": title, "description": "", "due_on": day }),
+    )
+    .await["id"]
+        .as_i64()
+        .unwrap()
+}
+
+fn titles(tasks: &Value) -> Vec<&str> {
+    let mut titles: Vec<_> = tasks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["task"].as_str().unwrap())
+        .collect();
+    titles.sort_unstable();
+    titles
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn tasks_are_created_and_read_by_their_owner(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+
+    let task = create(
+        &luffy,
+        json!({
+            "task": "Find the One Piece",
+            "description": "Grand Line",
+            "due_on": today(),
+            "repeat_frequency": "weekly",
+        }),
+    )
+    .await;
+    assert_eq!(task["user_id"], LUFFY.to_string());
+    assert_eq!(task["done"], false);
+    assert_eq!(task["repeat_frequency"], "weekly");
+
+    let id = task["id"].as_i64().unwrap();
+    let read = luffy
+        .get(&format!("/task/{}", id))
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(read, task);
+
+    let zoro = app.sign_in(ZORO).await;
+    zoro.get(&format!("/task/{}", id))
+        .await
+        .error(StatusCode::NOT_FOUND);
+    luffy.get("/task/0").await.error(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn invalid_tasks_are_refused(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+
+    let blank = luffy
+        .post("/task", &json!({ "task": "  ", "description": "" }))
+        .await
+        .expect(StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(blank["details"]["fields"]["task"].is_array(), "{}", blank);
+
+    let both = json!({
+        "task": "Nap",
+        "description": "",
+        "due_date": Utc::now(),
+        "due_on": today(),
+    });
+    luffy
+        .post("/task", &both)
+        .await
+        .error(StatusCode::UNPROCESSABLE_ENTITY);
+    luffy
+        .post("/task", &json!({ "description": "no title" }))
+        .await
+        .error(StatusCode::UNPROCESSABLE_ENTITY);
+
+    app.anonymous()
+        .post("/task", &json!({ "task": "Nap", "description": "" }))
+        .await
+        .error(StatusCode::UNAUTHORIZED);
+    let all = luffy.get("/task/all").await.expect(StatusCode::OK);
+    assert_eq!(all, json!([]));
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn tasks_are_updated_by_their_owner(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let id = titled(&luffy, "Eat meat").await;
+
+    let update = json!({ "task": "Eat more meat", "description": "Lots", "due_on": today() });
+    luffy
+        .put(&format!("/task/{}", id), &update)
+        .await
+        .expect(StatusCode::NO_CONTENT);
+    let task = luffy
+        .get(&format!("/task/{}", id))
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(task["task"], "Eat more meat");
+    assert_eq!(task["description"], "Lots");
+    assert_eq!(task["due_on"], json!(today()));
+
+    let zoro = app.sign_in(ZORO).await;
+    zoro.put(&format!("/task/{}", id), &update)
+        .await
+        .error(StatusCode::NOT_FOUND);
+    luffy
+        .put("/task/0", &update)
+        .await
+        .error(StatusCode::NOT_FOUND);
+    luffy
+        .put(
+            &format!("/task/{}", id),
+            &json!({ "task": "", "description": "" }),
+        )
+        .await
+        .error(StatusCode::UNPROCESSABLE_ENTITY);
+    let task = luffy
+        .get(&format!("/task/{}", id))
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(task["task"], "Eat more meat");
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn tasks_are_deleted_by_their_owner(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let id = titled(&luffy, "Leave Windmill Village").await;
+    let path = format!("/task/{}", id);
+
+    let zoro = app.sign_in(ZORO).await;
+    zoro.delete(&path).await.error(StatusCode::NOT_FOUND);
+    luffy.get(&path).await.expect(StatusCode::OK);
+
+    luffy.delete(&path).await.expect(StatusCode::NO_CONTENT);
+    luffy.get(&path).await.error(StatusCode::NOT_FOUND);
+    luffy.delete(&path).await.error(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn tasks_are_completed_and_reopened(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let id = titled(&luffy, "Recruit a swordsman").await;
+    let path = format!("/task/{}", id);
+
+    luffy
+        .put_empty(&format!("/task/done/{}", id))
+        .await
+        .expect(StatusCode::NO_CONTENT);
+    let task = luffy.get(&path).await.expect(StatusCode::OK);
+    assert_eq!(task["done"], true);
+    assert!(task["done_at"].is_string());
+    // completing twice is not an error
+    luffy
+        .put_empty(&format!("/task/done/{}", id))
+        .await
+        .expect(StatusCode::NO_CONTENT);
+
+    luffy
+        .put_empty(&format!("/task/undone/{}", id))
+        .await
+        .expect(StatusCode::NO_CONTENT);
+    let task = luffy.get(&path).await.expect(StatusCode::OK);
+    assert_eq!(task["done"], false);
+    assert_eq!(task["done_at"], Value::Null);
+
+    let zoro = app.sign_in(ZORO).await;
+    zoro.put_empty(&format!("/task/done/{}", id))
+        .await
+        .error(StatusCode::NOT_FOUND);
+    zoro.put_empty(&format!("/task/undone/{}", id))
+        .await
+        .error(StatusCode::NOT_FOUND);
+    luffy
+        .put_empty("/task/done/0")
+        .await
+        .error(StatusCode::NOT_FOUND);
+    luffy
+        .put_empty("/task/undone/0")
+        .await
+        .error(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn completing_a_recurring_task_moves_it_on(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let task = create(
+        &luffy,
+        json!({
+            "task": "Train",
+            "description": "",
+            "due_on": today(),
+            "repeat_frequency": "daily",
+        }),
+    )
+    .await;
+    let id = task["id"].as_i64().unwrap();
+
+    luffy
+        .put_empty(&format!("/task/done/{}", id))
+        .await
+        .expect(StatusCode::NO_CONTENT);
+    let task = luffy
+        .get(&format!("/task/{}", id))
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(task["done"], false);
+    assert_eq!(task["due_on"], json!(today() + Days::new(1)));
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn recurring_tasks_years_overdue_move_on(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let task = create(
+        &luffy,
+        json!({
+            "task": "Stretch",
+            "description": "",
+            "due_on": today(),
+            "repeat_frequency": "daily",
+        }),
+    )
+    .await;
+    let id = task["id"].as_i64().unwrap();
+    // far longer ago than a due date can be set, and than occurrences are expanded
+    sqlx::query("UPDATE tasks SET due_on = $1, repeat_anchor_on = $1 WHERE id = $2")
+        .bind(today() - Days::new(3 * 365))
+        .bind(id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    luffy
+        .put_empty(&format!("/task/done/{}", id))
+        .await
+        .expect(StatusCode::NO_CONTENT);
+    let task = luffy
+        .get(&format!("/task/{}", id))
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(task["done"], false);
+    assert_eq!(task["due_on"], json!(today() + Days::new(1)));
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn monthly_tasks_come_back_to_their_day(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let year = today().year() + 1;
+    let day = |m, d| NaiveDate::from_ymd_opt(year, m, d).unwrap();
+    let task = create(
+        &luffy,
+        json!({
+            "task": "Pay rent",
+            "description": "",
+            "due_on": day(1, 31),
+            "repeat_frequency": "monthly",
+        }),
+    )
+    .await;
+    let id = task["id"].as_i64().unwrap();
+    let done = || async {
+        luffy
+            .put_empty(&format!("/task/done/{}", id))
+            .await
+            .expect(StatusCode::NO_CONTENT);
+        luffy
+            .get(&format!("/task/{}", id))
+            .await
+            .expect(StatusCode::OK)
+    };
+
+    let task = done().await;
+    let end_of_feb = day(3, 1).pred_opt().unwrap();
+    assert_eq!(task["due_on"], json!(end_of_feb));
+
+    // editing it with the date it is at now keeps the series on the 31st
+    luffy
+        .put(
+            &format!("/task/{}", id),
+            &json!({
+                "task": "Pay the rent",
+                "description": "",
+                "due_on": end_of_feb,
+                "repeat_frequency": "monthly",
+            }),
+        )
+        .await
+        .expect(StatusCode::NO_CONTENT);
+
+    let task = done().await;
+    assert_eq!(task["due_on"], json!(day(3, 31)));
+    let task = done().await;
+    assert_eq!(task["due_on"], json!(day(4, 30)));
+
+    let due = luffy
+        .get(&format!("/task/due?from={}&to={}", day(4, 1), day(5, 31)))
+        .await
+        .expect(StatusCode::OK);
+    let dates: Vec<_> = due.as_array().unwrap().iter().map(|o| &o["date"]).collect();
+    assert_eq!(dates, [&json!(day(4, 30)), &json!(day(5, 31))]);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn due_tasks_are_listed_per_occurrence(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let from = today();
+    let to = from + Days::new(2);
+    create(
+        &luffy,
+        json!({
+            "task": "Drink",
+            "description": "",
+            "due_on": from,
+            "repeat_frequency": "daily",
+        }),
+    )
+    .await;
+    due_on(&luffy, "Sail", from + Days::new(1)).await;
+    due_on(&luffy, "Later", from + Days::new(30)).await;
+
+    let due = luffy
+        .get(&format!("/task/due?from={}&to={}", from, to))
+        .await
+        .expect(StatusCode::OK);
+    let listed: Vec<_> = due
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|o| {
+            (
+                o["date"].as_str().unwrap(),
+                o["task"]["task"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    let (d0, d1, d2) = (
+        from.to_string(),
+        (from + Days::new(1)).to_string(),
+        to.to_string(),
+    );
+    assert_eq!(
+        listed,
+        [
+            (d0.as_str(), "Drink"),
+            (d1.as_str(), "Drink"),
+            (d1.as_str(), "Sail"),
+            (d2.as_str(), "Drink"),
+        ]
+    );
+
+    luffy
+        .get(&format!("/task/due?from={}&to={}", to, from))
+        .await
+        .error(StatusCode::UNPROCESSABLE_ENTITY);
+    luffy
+        .get(&format!(
+            "/task/due?from={}&to={}",
+            from,
+            from + Days::new(400)
+        ))
+        .await
+        .error(StatusCode::UNPROCESSABLE_ENTITY);
+    luffy
+        .get(&format!("/task/due?from={}", from))
+        .await
+        .error(StatusCode::BAD_REQUEST);
+    luffy
+        .get("/task/due?from=tomorrow&to=later")
+        .await
+        .error(StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn views_sort_tasks_by_due_date(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    due_on(&luffy, "Today", today()).await;
+    due_on(&luffy, "Yesterday", today() - Days::new(1)).await;
+    due_on(&luffy, "Tomorrow", today() + Days::new(1)).await;
+    due_on(&luffy, "Next month", today() + Days::new(30)).await;
+    titled(&luffy, "Someday").await;
+    let done = due_on(&luffy, "Done yesterday", today() - Days::new(1)).await;
+    luffy
+        .put_empty(&format!("/task/done/{}", done))
+        .await
+        .expect(StatusCode::NO_CONTENT);
+
+    let view = luffy.get("/task/view/today").await.expect(StatusCode::OK);
+    assert_eq!(view["date"], json!(today()));
+    assert_eq!(view["count"], 1);
+    assert_eq!(view["tasks"][0]["task"]["task"], "Today");
+
+    let view = luffy.get("/task/view/overdue").await.expect(StatusCode::OK);
+    assert_eq!(view["count"], 1);
+    assert_eq!(titles(&view["tasks"]), ["Yesterday"]);
+
+    let view = luffy
+        .get("/task/view/upcoming")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(view["count"], 1);
+    let days = view["days"].as_array().unwrap();
+    assert_eq!(days.len(), 7);
+    assert_eq!(days[0]["date"], json!(today() + Days::new(1)));
+    assert_eq!(days[0]["tasks"][0]["task"]["task"], "Tomorrow");
+    assert!(days[1..].iter().all(|d| d["count"] == 0));
+
+    let view = luffy
+        .get("/task/view/no-due-date")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(view["count"], 1);
+    assert_eq!(titles(&view["tasks"]), ["Someday"]);
+
+    let counts = luffy.get("/task/view/counts").await.expect(StatusCode::OK);
+    assert_eq!(
+        counts,
+        json!({ "today": 1, "overdue": 1, "upcoming": 1, "no_due_date": 1 })
+    );
+
+    // nothing of luffy's shows up for zoro
+    let zoro = app.sign_in(ZORO).await;
+    let counts = zoro.get("/task/view/counts").await.expect(StatusCode::OK);
+    assert_eq!(
+        counts,
+        json!({ "today": 0, "overdue": 0, "upcoming": 0, "no_due_date": 0 })
+    );
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn overdue_view_falls_back_to_utc_for_unknown_zones(pool: PgPool) {
+    sqlx::query("UPDATE users SET timezone = 'Mars/Olympus_Mons' WHERE id = $1")
+        .bind(LUFFY)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    due_on(&luffy, "Yesterday", today() - Days::new(1)).await;
+
+    let view = luffy.get("/task/view/overdue").await.expect(StatusCode::OK);
+    assert_eq!(titles(&view["tasks"]), ["Yesterday"]);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn views_need_the_read_scope(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let profile_only = app.with_token(LUFFY, &["read:profile"]).await;
+    let anonymous = app.anonymous();
+    for view in [
+        "/task/view/today",
+        "/task/view/overdue",
+        "/task/view/upcoming",
+        "/task/view/no-due-date",
+        "/task/view/counts",
+        "/task/all",
+        "/task/all/done",
+        "/task/all/undone",
+    ] {
+        profile_only.get(view).await.error(StatusCode::FORBIDDEN);
+        anonymous.get(view).await.error(StatusCode::UNAUTHORIZED);
+    }
+
+    let reader = app.with_token(LUFFY, &["read:tasks"]).await;
+    reader.get("/task/view/counts").await.expect(StatusCode::OK);
+    reader
+        .post("/task", &json!({ "task": "Nap", "description": "" }))
+        .await
+        .error(StatusCode::FORBIDDEN);
+    let writer = app.with_token(LUFFY, &["write:tasks"]).await;
+    create(&writer, json!({ "task": "Nap", "description": "" })).await;
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn all_tasks_are_listed_by_status(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    titled(&luffy, "Gum").await;
+    titled(&luffy, "Gum").await;
+    let done = titled(&luffy, "Gear").await;
+    luffy
+        .put_empty(&format!("/task/done/{}", done))
+        .await
+        .expect(StatusCode::NO_CONTENT);
+
+    let all = luffy.get("/task/all").await.expect(StatusCode::OK);
+    assert_eq!(titles(&all), ["Gear", "Gum", "Gum"]);
+    let done = luffy.get("/task/all/done").await.expect(StatusCode::OK);
+    assert_eq!(titles(&done), ["Gear"]);
+    let undone = luffy.get("/task/all/undone").await.expect(StatusCode::OK);
+    assert_eq!(titles(&undone), ["Gum", "Gum"]);
+
+    let zoro = app.sign_in(ZORO).await;
+    let all = zoro.get("/task/all").await.expect(StatusCode::OK);
+    assert_eq!(all, json!([]));
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn all_tasks_are_paged_on_request(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    for _ in 0..12 {
+        titled(&luffy, "Gum").await;
+    }
+
+    let first = luffy.get("/task/all?p=1").await.expect(StatusCode::OK);
+    assert_eq!(first.as_array().unwrap().len(), 10);
+    let second = luffy.get("/task/all?p=2").await.expect(StatusCode::OK);
+    assert_eq!(second.as_array().unwrap().len(), 2);
+    assert!(first[9]["id"].as_i64() > second[0]["id"].as_i64());
+    luffy
+        .get("/task/all?p=0")
+        .await
+        .expect(StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn tasks_are_deleted_in_bulk_by_status(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    for title in ["Rubber", "Band"] {
+        titled(&luffy, title).await;
+    }
+    let done = titled(&luffy, "Pistol").await;
+    luffy
+        .put_empty(&format!("/task/done/{}", done))
+        .await
+        .expect(StatusCode::NO_CONTENT);
+    titled(&zoro, "Get lost").await;
+
+    let reader = app.with_token(LUFFY, &["read:tasks"]).await;
+    for path in ["/task/all", "/task/all/done", "/task/all/undone"] {
+        reader.delete(path).await.error(StatusCode::FORBIDDEN);
+    }
+
+    luffy
+        .delete("/task/all/done")
+        .await
+        .expect(StatusCode::NO_CONTENT);
+    let all = luffy.get("/task/all").await.expect(StatusCode::OK);
+    assert_eq!(titles(&all), ["Band", "Rubber"]);
+
+    luffy
+        .delete("/task/all/undone")
+        .await
+        .expect(StatusCode::NO_CONTENT);
+    let all = luffy.get("/task/all").await.expect(StatusCode::OK);
+    assert_eq!(all, json!([]));
+
+    titled(&luffy, "Again").await;
+    luffy
+        .delete("/task/all")
+        .await
+        .expect(StatusCode::NO_CONTENT);
+    let all = luffy.get("/task/all").await.expect(StatusCode::OK);
+    assert_eq!(all, json!([]));
+
+    // zoro's tasks are untouched
+    let all = zoro.get("/task/all").await.expect(StatusCode::OK);
+    assert_eq!(titles(&all), ["Get lost"]);
+}
@@ -0,0 +1,649 @@
+use super::{Client, TestApp, LUFFY, NAMI, ZORO};
+use http::{header::CONTENT_TYPE, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use todoem_server::db::query::me as MQ;
+use uuid::Uuid;
+
+const NOBODY: Uuid = Uuid::nil();
+
+async fn request(from: &Client, to: Uuid) {
+    from.post(&format!("/user/{}/request", to), &json!({}))
+        .await
+        .expect(StatusCode::OK);
+}
+
+async fn accept(by: &Client, from: Uuid) {
+    by.put_empty(&format!("/user/{}/accept", from))
+        .await
+        .expect(StatusCode::OK);
+}
+
+fn usernames(users: &Value) -> Vec<&str> {
+    let mut names: Vec<_> = users
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|u| u["username"].as_str().unwrap())
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn users_are_found_by_name(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+
+    let found = luffy.get("/user/search?q=zor").await.expect(StatusCode::OK);
+    assert_eq!(usernames(&found), ["zoro"]);
+    let found = luffy
+        .get("/user/search?q=japan")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(usernames(&found), ["zoro"]);
+    let found = luffy
+        .get("/user/search?q=zor&p=2")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(found, json!([]));
+
+    // hidden by their privacy settings
+    let zoro = app.sign_in(ZORO).await;
+    zoro.put(
+        "/user/privacy",
+        &json!({ "search_visibility": "username", "request_policy": "everyone" }),
+    )
+    .await
+    .expect(StatusCode::OK);
+    let found = luffy.get("/user/search?q=zor").await.expect(StatusCode::OK);
+    assert_eq!(found, json!([]));
+    let found = luffy
+        .get("/user/search?q=ZORO")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(usernames(&found), ["zoro"]);
+
+    // and by blocks
+    zoro.post(&format!("/user/{}/block", LUFFY), &json!({}))
+        .await
+        .expect(StatusCode::OK);
+    let found = luffy
+        .get("/user/search?q=zoro")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(found, json!([]));
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn searches_are_validated(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    for query in ["q=z", "q=%20%20", "q=zoro&p=0", "q=zoro&p=101"] {
+        for path in ["/user/search", "/user/listers/search"] {
+            luffy
+                .get(&format!("{}?{}", path, query))
+                .await
+                .error(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+    luffy
+        .get("/user/search")
+        .await
+        .error(StatusCode::BAD_REQUEST);
+
+    let tasks_only = app.with_token(LUFFY, &["read:tasks", "write:tasks"]).await;
+    tasks_only
+        .get("/user/search?q=zoro")
+        .await
+        .error(StatusCode::FORBIDDEN);
+    let connections = app.with_token(LUFFY, &["connections"]).await;
+    connections
+        .get("/user/search?q=zoro")
+        .await
+        .expect(StatusCode::OK);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn profiles_show_the_connection(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+
+    let profile = luffy
+        .get(&format!("/user/{}/profile", ZORO))
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(profile["username"], "zoro");
+    assert_eq!(profile["name"], "zoro japan");
+    assert_eq!(profile["connected"], false);
+
+    request(&luffy, ZORO).await;
+    let profile = luffy
+        .get(&format!("/user/{}/profile", ZORO))
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(profile["sent_connection"], true);
+    let profile = zoro
+        .get(&format!("/user/{}/profile", LUFFY))
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(profile["received_connection"], true);
+
+    accept(&zoro, LUFFY).await;
+    let profile = luffy
+        .get(&format!("/user/{}/profile", ZORO))
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(profile["connected"], true);
+    assert_eq!(profile["sent_connection"], false);
+
+    luffy
+        .get(&format!("/user/{}/profile", LUFFY))
+        .await
+        .error(StatusCode::FORBIDDEN);
+    luffy
+        .get(&format!("/user/{}/profile", NOBODY))
+        .await
+        .error(StatusCode::NOT_FOUND);
+    let res = luffy.get("/user/not-a-uuid/profile").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    zoro.post(&format!("/user/{}/block", LUFFY), &json!({}))
+        .await
+        .expect(StatusCode::OK);
+    luffy
+        .get(&format!("/user/{}/profile", ZORO))
+        .await
+        .error(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn avatars_are_served_unless_blocked(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    let path = format!("/user/{}/avatar", ZORO);
+
+    luffy.get(&path).await.error(StatusCode::NOT_FOUND);
+
+    let key = format!("avatars/{}.png", Uuid::new_v4());
+    app.storage.put(&key, b"png".to_vec()).await.unwrap();
+    MQ::update_avatar_key(&app.pool, ZORO, Some(&key))
+        .await
+        .unwrap();
+
+    let res = luffy.get(&path).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers[CONTENT_TYPE], "image/png");
+    assert_eq!(res.body, b"png");
+    // their own, too
+    assert_eq!(zoro.get(&path).await.status, StatusCode::OK);
+
+    zoro.post(&format!("/user/{}/block", LUFFY), &json!({}))
+        .await
+        .expect(StatusCode::OK);
+    luffy.get(&path).await.error(StatusCode::NOT_FOUND);
+    luffy
+        .get(&format!("/user/{}/avatar", NOBODY))
+        .await
+        .error(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn connection_requests_are_sent_once(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+
+    request(&luffy, ZORO).await;
+    let sent = luffy
+        .get("/user/requests/sent")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(usernames(&sent), ["zoro"]);
+    let received = zoro
+        .get("/user/requests/received")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(usernames(&received), ["luffy"]);
+    let received = luffy
+        .get("/user/requests/received")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(received, json!([]));
+
+    let again = luffy
+        .post(&format!("/user/{}/request", ZORO), &json!({}))
+        .await
+        .error(StatusCode::BAD_REQUEST);
+    assert_eq!(
+        again,
+        "You have already sent a connection request to this user"
+    );
+    let back = zoro
+        .post(&format!("/user/{}/request", LUFFY), &json!({}))
+        .await
+        .error(StatusCode::BAD_REQUEST);
+    assert_eq!(back, "This user has already sent you a connection request");
+
+    luffy
+        .post(&format!("/user/{}/request", LUFFY), &json!({}))
+        .await
+        .error(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn connection_requests_follow_the_request_policy(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    let nami = app.sign_in(NAMI).await;
+    nami.put(
+        "/user/privacy",
+        &json!({ "search_visibility": "everyone", "request_policy": "friends_of_friends" }),
+    )
+    .await
+    .expect(StatusCode::OK);
+
+    luffy
+        .post(&format!("/user/{}/request", NAMI), &json!({}))
+        .await
+        .error(StatusCode::FORBIDDEN);
+
+    // luffy - zoro - nami
+    request(&luffy, ZORO).await;
+    accept(&zoro, LUFFY).await;
+    request(&nami, ZORO).await;
+    accept(&zoro, NAMI).await;
+    request(&luffy, NAMI).await;
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn blocked_requests_say_nothing_of_the_policy(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    zoro.post(&format!("/user/{}/block", LUFFY), &json!({}))
+        .await
+        .expect(StatusCode::OK);
+
+    let mut refusals = Vec::new();
+    for policy in ["everyone", "friends_of_friends"] {
+        zoro.put(
+            "/user/privacy",
+            &json!({ "search_visibility": "everyone", "request_policy": policy }),
+        )
+        .await
+        .expect(StatusCode::OK);
+        let refusal = luffy
+            .post(&format!("/user/{}/request", ZORO), &json!({}))
+            .await
+            .error(StatusCode::FORBIDDEN);
+        refusals.push(refusal);
+    }
+    assert_eq!(refusals[0], refusals[1]);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn connection_requests_are_limited_per_day(pool: PgPool) {
+    let app = TestApp::with_settings(pool, |s| s.rate_limits.connection_requests_per_day = 1);
+    let luffy = app.sign_in(LUFFY).await;
+
+    request(&luffy, ZORO).await;
+    luffy
+        .post(&format!("/user/{}/request", NAMI), &json!({}))
+        .await
+        .error(StatusCode::TOO_MANY_REQUESTS);
+    let sent = luffy
+        .get("/user/requests/sent")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(usernames(&sent), ["zoro"]);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn connection_requests_are_withdrawn(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let path = format!("/user/{}/request", ZORO);
+
+    luffy.delete(&path).await.error(StatusCode::BAD_REQUEST);
+    request(&luffy, ZORO).await;
+    luffy.delete(&path).await.expect(StatusCode::OK);
+    let sent = luffy
+        .get("/user/requests/sent")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(sent, json!([]));
+    luffy.delete(&path).await.error(StatusCode::BAD_REQUEST);
+
+    luffy
+        .delete(&format!("/user/{}/request", LUFFY))
+        .await
+        .error(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn connection_requests_are_accepted(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    let path = format!("/user/{}/accept", LUFFY);
+
+    let error = zoro.put_empty(&path).await.error(StatusCode::BAD_REQUEST);
+    assert_eq!(error, "This user has not sent you a connection request");
+    request(&luffy, ZORO).await;
+    // only by whoever received it
+    luffy
+        .put_empty(&format!("/user/{}/accept", ZORO))
+        .await
+        .error(StatusCode::BAD_REQUEST);
+
+    zoro.put_empty(&path).await.expect(StatusCode::OK);
+    let error = zoro.put_empty(&path).await.error(StatusCode::BAD_REQUEST);
+    assert_eq!(error, "You are already connected with this user");
+    let received = zoro
+        .get("/user/requests/received")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(received, json!([]));
+    let again = luffy
+        .post(&format!("/user/{}/request", ZORO), &json!({}))
+        .await
+        .error(StatusCode::BAD_REQUEST);
+    assert_eq!(again, "You are already connected with this user");
+
+    zoro.put_empty(&format!("/user/{}/accept", ZORO))
+        .await
+        .error(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn connection_requests_are_rejected(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    let path = format!("/user/{}/reject", LUFFY);
+
+    zoro.put_empty(&path).await.error(StatusCode::BAD_REQUEST);
+    request(&luffy, ZORO).await;
+    zoro.put_empty(&path).await.expect(StatusCode::OK);
+    zoro.put_empty(&path).await.error(StatusCode::BAD_REQUEST);
+
+    let again = luffy
+        .post(&format!("/user/{}/request", ZORO), &json!({}))
+        .await
+        .error(StatusCode::BAD_REQUEST);
+    assert_eq!(again, "This user has rejected your connection request");
+
+    zoro.put_empty(&format!("/user/{}/reject", ZORO))
+        .await
+        .error(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn users_are_blocked_and_unblocked(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    request(&luffy, ZORO).await;
+    accept(&zoro, LUFFY).await;
+
+    zoro.post(&format!("/user/{}/block", LUFFY), &json!({}))
+        .await
+        .expect(StatusCode::OK);
+    let blocked = zoro.get("/user/blocked").await.expect(StatusCode::OK);
+    assert_eq!(usernames(&blocked), ["luffy"]);
+    // the block is not shown to whoever was blocked
+    let blocked = luffy.get("/user/blocked").await.expect(StatusCode::OK);
+    assert_eq!(blocked, json!([]));
+    let listers = zoro
+        .get("/user/listers/page/1")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(listers, json!([]));
+    luffy
+        .post(&format!("/user/{}/request", ZORO), &json!({}))
+        .await
+        .error(StatusCode::FORBIDDEN);
+    luffy
+        .post(&format!("/user/{}/block", LUFFY), &json!({}))
+        .await
+        .error(StatusCode::FORBIDDEN);
+
+    // blocking back is kept as luffy's own block
+    luffy
+        .post(&format!("/user/{}/block", ZORO), &json!({}))
+        .await
+        .expect(StatusCode::OK);
+    let blocked = luffy.get("/user/blocked").await.expect(StatusCode::OK);
+    assert_eq!(usernames(&blocked), ["zoro"]);
+
+    // and lifting one side leaves the other in place
+    zoro.delete(&format!("/user/{}/block", LUFFY))
+        .await
+        .expect(StatusCode::OK);
+    let blocked = zoro.get("/user/blocked").await.expect(StatusCode::OK);
+    assert_eq!(blocked, json!([]));
+    zoro.delete(&format!("/user/{}/block", LUFFY))
+        .await
+        .error(StatusCode::NOT_FOUND);
+    zoro.post(&format!("/user/{}/request", LUFFY), &json!({}))
+        .await
+        .error(StatusCode::FORBIDDEN);
+    let blocked = luffy.get("/user/blocked").await.expect(StatusCode::OK);
+    assert_eq!(usernames(&blocked), ["zoro"]);
+
+    luffy
+        .delete(&format!("/user/{}/block", ZORO))
+        .await
+        .expect(StatusCode::OK);
+    request(&luffy, ZORO).await;
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn privacy_settings_are_updated(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+
+    let privacy = luffy.get("/user/privacy").await.expect(StatusCode::OK);
+    assert_eq!(
+        privacy,
+        json!({ "search_visibility": "everyone", "request_policy": "everyone" })
+    );
+
+    let update = json!({ "search_visibility": "nobody", "request_policy": "friends_of_friends" });
+    let privacy = luffy
+        .put("/user/privacy", &update)
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(privacy, update);
+    let privacy = luffy.get("/user/privacy").await.expect(StatusCode::OK);
+    assert_eq!(privacy, update);
+
+    luffy
+        .put(
+            "/user/privacy",
+            &json!({ "search_visibility": "friends", "request_policy": "everyone" }),
+        )
+        .await
+        .error(StatusCode::UNPROCESSABLE_ENTITY);
+    luffy
+        .put("/user/privacy", &json!({ "search_visibility": "everyone" }))
+        .await
+        .error(StatusCode::UNPROCESSABLE_ENTITY);
+    let privacy = luffy.get("/user/privacy").await.expect(StatusCode::OK);
+    assert_eq!(privacy, update);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn listers_are_the_connections(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    let nami = app.sign_in(NAMI).await;
+    for (user, id) in [(&zoro, ZORO), (&nami, NAMI)] {
+        request(&luffy, id).await;
+        accept(user, LUFFY).await;
+    }
+
+    let listers = luffy
+        .get("/user/listers/page/1")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(usernames(&listers), ["nami", "zoro"]);
+    let listers = luffy
+        .get("/user/listers/page/2")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(listers, json!([]));
+    let listers = zoro
+        .get("/user/listers/page/1")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(usernames(&listers), ["luffy"]);
+
+    luffy
+        .get("/user/listers/page/0")
+        .await
+        .error(StatusCode::UNPROCESSABLE_ENTITY);
+    let res = luffy.get("/user/listers/page/first").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let found = luffy
+        .get("/user/listers/search?q=na")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(usernames(&found), ["nami"]);
+    let found = zoro
+        .get("/user/listers/search?q=na")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(found, json!([]));
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn lister_profiles_need_a_connection(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    let path = format!("/user/listers/{}", ZORO);
+
+    luffy.get(&path).await.error(StatusCode::NOT_FOUND);
+    request(&luffy, ZORO).await;
+    luffy.get(&path).await.error(StatusCode::NOT_FOUND);
+    accept(&zoro, LUFFY).await;
+
+    let profile = luffy.get(&path).await.expect(StatusCode::OK);
+    assert_eq!(profile["username"], "zoro");
+    assert_eq!(profile["connected"], true);
+
+    luffy
+        .get(&format!("/user/listers/{}", LUFFY))
+        .await
+        .error(StatusCode::FORBIDDEN);
+    luffy
+        .get(&format!("/user/listers/{}", NOBODY))
+        .await
+        .error(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn listers_are_disconnected(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let luffy = app.sign_in(LUFFY).await;
+    let zoro = app.sign_in(ZORO).await;
+    let path = format!("/user/listers/{}/disconnect", ZORO);
+
+    luffy.put_empty(&path).await.error(StatusCode::BAD_REQUEST);
+    request(&luffy, ZORO).await;
+    accept(&zoro, LUFFY).await;
+
+    luffy.put_empty(&path).await.expect(StatusCode::OK);
+    let listers = zoro
+        .get("/user/listers/page/1")
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(listers, json!([]));
+    luffy.put_empty(&path).await.error(StatusCode::BAD_REQUEST);
+    // either side may ask again
+    request(&zoro, LUFFY).await;
+
+    luffy
+        .put_empty(&format!("/user/listers/{}/disconnect", LUFFY))
+        .await
+        .error(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(
+    migrations = "src/db/migrations",
+    fixtures("../../src/db/fixtures/users.sql")
+)]
+async fn accounts_are_closed_only_from_a_session(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app
+        .with_token(LUFFY, &["read:profile", "write:profile"])
+        .await;
+    token.get("/me").await.expect(StatusCode::OK);
+    token.delete("/me").await.error(StatusCode::FORBIDDEN);
+    token
+        .post("/me/restore", &json!({}))
+        .await
+        .error(StatusCode::FORBIDDEN);
+
+    let luffy = app.sign_in(LUFFY).await;
+    luffy.delete("/me").await.expect(StatusCode::ACCEPTED);
+    luffy
+        .post("/me/restore", &json!({}))
+        .await
+        .expect(StatusCode::OK);
+}
@@ -1,9 +0,0 @@
-use serde::Deserialize
-
-
-
-#[derive(Deserialize)]
-pub struct CreateListRequest {
-    pub name: String,
-    pub description: Option<String>,
-}
\ No newline at end of file